@@ -0,0 +1,88 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+use std::time::{Duration, Instant};
+
+use front_end::lexer;
+
+// 词法分析器的性能测试
+//
+// 运行：`cargo bench -p front-end --bench lexer`
+//
+// 生成一个数 MB 的源文件（模拟自动生成的源代码），然后分别测量
+// 一次性 tokenize 以及逐个读取 token 所需的时间。
+
+const TARGET_SIZE: usize = 8 * 1024 * 1024;
+const ROUNDS: usize = 5;
+
+fn generate_source(target_size: usize) -> String {
+    let mut source = String::with_capacity(target_size + 1024);
+    let mut index: usize = 0;
+
+    while source.len() < target_size {
+        source.push_str(&format!(
+            "// 自动生成的函数 {index}\n\
+             function calculate_{index}(Int left, Int right, String name) type Int {{\n    \
+                 let sum_{index} = left + right * {index} - (left / 2)\n    \
+                 let message = `{{name}}: {{sum_{index}}}`\n    \
+                 let values = [1, 2.5, 3_000, 6.626e-34, \"文字 {index}\", #tag_{index}]\n    \
+                 if sum_{index} >= 100 && left != right then\n        \
+                     writeLine(message)\n    \
+                 else\n        \
+                     values.length\n    \
+                 /* 块注释 */\n    \
+                 sum_{index} :add: 1\n\
+             }}\n\n"
+        ));
+        index += 1;
+    }
+
+    source
+}
+
+fn measure<F: FnMut() -> usize>(name: &str, source_size: usize, mut f: F) {
+    let mut best = Duration::MAX;
+    let mut count = 0;
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        count = f();
+        best = best.min(start.elapsed());
+    }
+
+    let megabytes = source_size as f64 / (1024.0 * 1024.0);
+    println!(
+        "{:<12} {:>9} tokens  {:>8.2} ms  {:>7.1} MB/s",
+        name,
+        count,
+        best.as_secs_f64() * 1000.0,
+        megabytes / best.as_secs_f64()
+    );
+}
+
+fn main() {
+    let source = generate_source(TARGET_SIZE);
+    println!(
+        "source size: {:.2} MB, best of {} rounds",
+        source.len() as f64 / (1024.0 * 1024.0),
+        ROUNDS
+    );
+
+    measure("tokenize", source.len(), || {
+        lexer::tokenize(&source).unwrap().len()
+    });
+
+    // 流式读取，不保存 token
+    measure("streaming", source.len(), || {
+        let mut count = 0;
+        for result in lexer::Lexer::new(&source, 0) {
+            result.unwrap();
+            count += 1;
+        }
+        count
+    });
+}
@@ -5,11 +5,15 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
-use std::fmt::{Display, Write};
+use std::fmt::Display;
 
-use crate::token::Token;
+use crate::token::{
+    escape_char, escape_string, format_complex_part, format_float, BitLiteral, IntegerLiteral,
+    Location, Token, TokenDetail,
+};
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Node {
     Program(Program),
     Statement(Statement),
@@ -19,15 +23,72 @@ pub enum Node {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub body: Vec<Statement>,
+    pub disabled: Vec<DisabledCode>, // 被结构注释 `#disable` 注释掉的代码
+    pub regions: Vec<Region>,        // 范围注释
     pub range: Range,
 }
 
+// 被结构注释 `#disable` 注释掉的一个语句或者表达式
+//
+// 被注释掉的代码不会出现在 AST 里，但其 token（包括 `#disable` 本身）
+// 原样保留在 token_details，编辑器可以根据 location 把这段代码显示为灰色。
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisabledCode {
+    pub location: Location,
+    pub token_details: Vec<TokenDetail>,
+}
+
+// 范围注释，用于描述一段代码（若干个语句或者表达式）的作用
+//
+// ```
+// // 标题
+// // 描述
+// // ===
+// ...
+// // ---
+// ```
+//
+// range 为范围之内的代码的位置（不包括首尾的空行），编辑器可以据此折叠代码，
+// 文档生成器则可以据此生成文档。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub title: String,
+    pub description: String,
+    pub range: Range,
+}
+
+// 输出程序的语句
+//
+// 被注释掉的代码（disabled）以及范围注释（regions）记录的是源文本的位置，
+// 而语句没有源文本的位置，无法确定它们在输出的文本中的位置，所以不输出。
+// 因此输出的文本重新解析之后 body 相同，但 disabled 和 regions 均为空。
 impl Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", format_statements(&self.body))
     }
 }
 
+impl Program {
+    // 在当前程序定义的常量以及常量组里查找标识符所指的常量，返回常量的值
+    //
+    // e.g.
+    // `Code` 指常量 `const Int Code = 123`
+    // `Code::Ok` 指常量组 `const Int Code {...}` 的成员 `Ok`
+    pub fn resolve_const(&self, identifier: &Identifier) -> Option<&Expression> {
+        self.body.iter().find_map(|statement| match statement {
+            Statement::ConstDeclaration(c)
+                if identifier.dirs.is_empty()
+                    && identifier.generics.is_empty()
+                    && c.name == identifier.name =>
+            {
+                Some(&c.value)
+            }
+            Statement::ConstGroupDeclaration(g) => g.resolve(identifier).map(|m| &m.value),
+            _ => None,
+        })
+    }
+}
+
 impl Display for Node {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -41,11 +102,12 @@ impl Display for Node {
 // Statement
 //  | FunctionDeclaration
 //  | EmptyFunctionDeclaration
-//  | PatternFunctionDeclarationzs
+//  | PatternFunctionGroup
 //
 //  | NamespaceStatement
 //  | UseStatement
 //  | ConstDeclaration
+//  | ConstGroupDeclaration
 //
 //  | MemberStructDeclaration
 //  | TupleStructDeclaration
@@ -62,11 +124,12 @@ impl Display for Node {
 pub enum Statement {
     FunctionDeclaration(FunctionDeclaration),
     EmptyFunctionDeclaration(EmptyFunctionDeclaration), // 空函数
-    PatternFunctionDeclaration(PatternFunctionDeclaration), // 模式函数
+    PatternFunctionGroup(PatternFunctionGroup),         // 模式函数
 
     // NamespaceStatement(NamespaceStatement),
     UseStatement(UseStatement),
     ConstDeclaration(ConstDeclaration),
+    ConstGroupDeclaration(ConstGroupDeclaration), // 常量组
 
     MemberStructDeclaration(MemberStructDeclaration),
     TupleStructDeclaration(TupleStructDeclaration),
@@ -88,6 +151,7 @@ pub struct FunctionDeclaration {
     pub return_data_type: Option<DataType>,
     pub whiches: Vec<WhichEntry>,
     pub body: Expression,
+    pub docs: Vec<String>, // 文档注释
     pub range: Range,
 }
 
@@ -110,6 +174,7 @@ pub struct EmptyFunctionDeclaration {
     pub parameters: Vec<EmptyFunctionParameter>,
     pub return_data_type: Option<DataType>,
     pub whiches: Vec<WhichEntry>,
+    pub docs: Vec<String>, // 文档注释
     pub range: Range,
 }
 
@@ -121,6 +186,17 @@ pub struct EmptyFunctionParameter {
     pub range: Range,
 }
 
+// 同名的模式函数
+//
+// 同一个程序里的同名模式函数组合为一个逻辑上的函数，
+// 各个模式函数按照定义的先后顺序排列（即匹配的先后顺序）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternFunctionGroup {
+    pub name: String,
+    pub declarations: Vec<PatternFunctionDeclaration>,
+    pub range: Range,
+}
+
 // 模式函数的定义语句
 #[derive(Debug, Clone, PartialEq)]
 pub struct PatternFunctionDeclaration {
@@ -130,6 +206,8 @@ pub struct PatternFunctionDeclaration {
     pub return_data_type: Option<DataType>,
     pub only: Option<Expression>, // 在各个参数匹配后，模式函数的最后一道防线
     pub whiches: Vec<WhichEntry>,
+    pub body: Expression,
+    pub docs: Vec<String>, // 文档注释
     pub range: Range,
 }
 
@@ -162,40 +240,82 @@ pub struct UseStatement {
 #[derive(Debug, Clone, PartialEq)]
 pub struct NamePath {
     pub directories: Vec<NamePathItem>,
+    pub alias: Option<String>, // `as` 别名，以子路径列表结尾的名称路径没有别名
 }
 
+// 名称路径的项目，`Children` 只能是路径的最后一项
 #[derive(Debug, Clone, PartialEq)]
 pub enum NamePathItem {
-    Name(String),
-    Children(String, Vec<NamePath>),
+    Name(String),                    // `name`
+    Children(String, Vec<NamePath>), // `name::{one, two::three}`
 }
 
+// const Int Code = 123
+// const Code = 123   // 数据类型可以省略
 #[derive(Debug, Clone, PartialEq)]
 pub struct ConstDeclaration {
+    pub data_type: Option<DataType>,
+    pub name: String,
+    pub value: Expression,
+    pub range: Range,
+}
+
+// 常量组
+//
+// const Int Code {
+//     Ok = 200
+//     Moved = 301
+// }
+//
+// 常量组会产生同名的命名空间，组的成员通过 `组名::成员名` 访问，比如 `Code::Ok`，
+// 组的数据类型即每个成员的数据类型。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstGroupDeclaration {
+    pub data_type: Option<DataType>,
+    pub name: String,
+    pub members: Vec<ConstMember>,
+    pub range: Range,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstMember {
     pub name: String,
     pub value: Expression,
     pub range: Range,
 }
 
+// @validate(id > 0)
+// struct User {
+//     Int id
+//     @validate(name != "")
+//     String name = "foo"
+// }
 #[derive(Debug, Clone, PartialEq)]
 pub struct MemberStructDeclaration {
     pub name: String,
     pub members: Vec<StructMember>,
-    pub generics: Vec<DataType>, // 泛型类型列表
+    pub generics: Vec<DataType>,    // 泛型类型列表
+    pub validates: Vec<Expression>, // 数据验证约束，即 `@validate(...)` 标注
+    pub docs: Vec<String>,          // 文档注释
     pub range: Range,
 }
 
+// struct Point (Int, Int)
 #[derive(Debug, Clone, PartialEq)]
 pub struct TupleStructDeclaration {
     pub name: String,
     pub members: Vec<DataType>,
     pub generics: Vec<DataType>, // 泛型类型列表
+    pub docs: Vec<String>,       // 文档注释
     pub range: Range,
 }
 
+// struct Writer {}
 #[derive(Debug, Clone, PartialEq)]
 pub struct EmptyStructDeclaration {
     pub name: String,
+    pub generics: Vec<DataType>, // 泛型类型列表
+    pub docs: Vec<String>,       // 文档注释
     pub range: Range,
 }
 
@@ -203,6 +323,9 @@ pub struct EmptyStructDeclaration {
 pub struct StructMember {
     pub data_type: DataType,
     pub name: String,
+    pub value: Option<Expression>,  // 默认值
+    pub validates: Vec<Expression>, // 数据验证约束，只能验证当前成员的值
+    pub docs: Vec<String>,          // 文档注释
     pub range: Range,
 }
 
@@ -210,6 +333,7 @@ pub struct StructMember {
 pub struct UnionDeclaration {
     pub members: Vec<UnionMember>,
     pub generics: Vec<DataType>, // 泛型类型列表
+    pub docs: Vec<String>,       // 文档注释
     pub range: Range,
 }
 
@@ -225,6 +349,7 @@ pub struct TraitDeclaration {
     pub name: String,
     pub associated_types: Vec<AssociatedType>, // 关联类型
     pub function_items: Vec<TraitFunctionItem>,
+    pub docs: Vec<String>, // 文档注释
     pub range: Range,
 }
 
@@ -277,7 +402,7 @@ impl Display for FunctionDeclaration {
         segments.push("function".to_string());
         segments.push(self.name.clone());
 
-        if self.generics.len() > 0 {
+        if !self.generics.is_empty() {
             segments.push(format!("<{}>", format_generics(&self.generics)));
         }
 
@@ -290,7 +415,7 @@ impl Display for FunctionDeclaration {
             segments.push(format!("type {}", d));
         }
 
-        if self.whiches.len() > 0 {
+        if !self.whiches.is_empty() {
             segments.push(format!(
                 "which {{\n{}\n}}",
                 format_which_entries(&self.whiches)
@@ -305,19 +430,112 @@ impl Display for FunctionDeclaration {
             _ => segments.push(format!("= {}", self.body)),
         }
 
-        write!(f, "{}", segments.join(" ") + "\n")
+        write!(
+            f,
+            "{}{}",
+            format_docs(&self.docs),
+            segments.join(" ") + "\n"
+        )
     }
 }
 
 impl Display for EmptyFunctionDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        let mut segments = Vec::<String>::new();
+
+        segments.push("empty function".to_string());
+        segments.push(self.name.clone());
+
+        if !self.generics.is_empty() {
+            segments.push(format!("<{}>", format_generics(&self.generics)));
+        }
+
+        segments.push(format!(
+            "({})",
+            format_empty_function_parameters(&self.parameters)
+        ));
+
+        if let Some(d) = &self.return_data_type {
+            segments.push(format!("type {}", d));
+        }
+
+        if !self.whiches.is_empty() {
+            segments.push(format!(
+                "which {{\n{}\n}}",
+                format_which_entries(&self.whiches)
+            ));
+        }
+
+        write!(
+            f,
+            "{}{}",
+            format_docs(&self.docs),
+            segments.join(" ") + "\n"
+        )
+    }
+}
+
+impl Display for PatternFunctionGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for d in &self.declarations {
+            write!(f, "{}", d)?;
+        }
+        Ok(())
     }
 }
 
 impl Display for PatternFunctionDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        let mut segments = Vec::<String>::new();
+
+        segments.push("pattern function".to_string());
+        segments.push(self.name.clone());
+
+        if !self.generics.is_empty() {
+            segments.push(format!("<{}>", format_generics(&self.generics)));
+        }
+
+        segments.push(format!(
+            "({})",
+            format_pattern_function_parameters(&self.parameters)
+        ));
+
+        if let Some(d) = &self.return_data_type {
+            segments.push(format!("type {}", d));
+        }
+
+        if let Some(e) = &self.only {
+            match e {
+                // 函数范围的 only 从属表达式里的结构体实例化表达式需要加上括号，
+                // 否则其中的花括号会被解析为函数主体
+                Expression::ConstructorExpression(_) | Expression::UnaryExpression(_) => {
+                    segments.push(format!("only ({})", e))
+                }
+                _ => segments.push(format!("only {}", e)),
+            }
+        }
+
+        if !self.whiches.is_empty() {
+            segments.push(format!(
+                "which {{\n{}\n}}",
+                format_which_entries(&self.whiches)
+            ));
+        }
+
+        match &self.body {
+            Expression::BlockExpression(b) if !(b.is_explicit) => {
+                // 函数主体是隠式 do 表达式
+                segments.push(format!("{}", b))
+            }
+            _ => segments.push(format!("= {}", self.body)),
+        }
+
+        write!(
+            f,
+            "{}{}",
+            format_docs(&self.docs),
+            segments.join(" ") + "\n"
+        )
     }
 }
 
@@ -328,55 +546,235 @@ impl Display for PatternFunctionDeclaration {
 // }
 
 impl Display for UseStatement {
+    // e.g.
+    // `use std::List`
+    // `use foo::{bar, sub::{one, two}}`
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        writeln!(f, "use {}", self.name_path)
+    }
+}
+
+impl Display for NamePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self
+            .directories
+            .iter()
+            .map(|item| match item {
+                NamePathItem::Name(n) => n.clone(),
+                NamePathItem::Children(n, children) => format!(
+                    "{}::{{{}}}",
+                    n,
+                    children
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            })
+            .collect::<Vec<String>>();
+
+        match &self.alias {
+            Some(alias) => write!(f, "{} as {}", items.join("::"), alias),
+            None => write!(f, "{}", items.join("::")),
+        }
+    }
+}
+
+// use 语句引入的一个标识符
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseItem {
+    pub path: Vec<String>,     // 完整路径，以名称列表表示
+    pub alias: Option<String>, // 别名
+}
+
+impl UseItem {
+    // 在当前源文件里使用的名称，即别名或者完整路径的最后一个名称
+    pub fn name(&self) -> &str {
+        match &self.alias {
+            Some(alias) => alias,
+            None => self.path.last().map(|n| n.as_str()).unwrap_or_default(),
+        }
+    }
+}
+
+impl UseStatement {
+    // 展开为各个被引入的标识符
+    //
+    // e.g.
+    // `use foo::{bar, sub::{one as two}}` 展开为
+    // `["foo", "bar"]` 以及 `["foo", "sub", "one"]`（别名为 `two`）
+    pub fn flatten(&self) -> Vec<UseItem> {
+        flatten_name_path(&[], &self.name_path)
+    }
+}
+
+fn flatten_name_path(prefix: &[String], name_path: &NamePath) -> Vec<UseItem> {
+    let mut names = prefix.to_vec();
+
+    for item in &name_path.directories {
+        match item {
+            NamePathItem::Name(n) => names.push(n.clone()),
+            NamePathItem::Children(n, children) => {
+                names.push(n.clone());
+                return children
+                    .iter()
+                    .flat_map(|c| flatten_name_path(&names, c))
+                    .collect();
+            }
+        }
     }
+
+    vec![UseItem {
+        path: names,
+        alias: name_path.alias.clone(),
+    }]
 }
 
 impl Display for ConstDeclaration {
+    // e.g.
+    // `const Int Code = 123`
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        match &self.data_type {
+            Some(t) => writeln!(f, "const {} {} = {}", t, self.name, self.value),
+            None => writeln!(f, "const {} = {}", self.name, self.value),
+        }
+    }
+}
+
+impl Display for ConstGroupDeclaration {
+    // e.g.
+    // `const Int Code {\nOk = 200\nMoved = 301\n}`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut segments = Vec::<String>::new();
+
+        segments.push("const".to_string());
+
+        if let Some(t) = &self.data_type {
+            segments.push(format!("{}", t));
+        }
+
+        segments.push(self.name.clone());
+
+        let members = self
+            .members
+            .iter()
+            .map(|m| format!("{} = {}\n", m.name, m.value))
+            .collect::<Vec<String>>()
+            .join("");
+
+        writeln!(f, "{} {{\n{}}}", segments.join(" "), members)
+    }
+}
+
+impl ConstGroupDeclaration {
+    pub fn get_member(&self, name: &str) -> Option<&ConstMember> {
+        self.members.iter().find(|m| m.name == name)
+    }
+
+    // 根据 `组名::成员名` 格式的标识符查找成员
+    pub fn resolve(&self, identifier: &Identifier) -> Option<&ConstMember> {
+        match identifier.dirs.as_slice() {
+            [group_name] if *group_name == self.name && identifier.generics.is_empty() => {
+                self.get_member(&identifier.name)
+            }
+            _ => None,
+        }
     }
 }
 
 impl Display for MemberStructDeclaration {
+    // e.g.
+    // `struct User {\nInt id\nString name = "foo"\n}`
+    // `@validate(id > 0)\nstruct User <T> {\nT id\n}`
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        let members = self
+            .members
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<String>>()
+            .join("");
+
+        writeln!(
+            f,
+            "{}{}{} {{\n{}}}",
+            format_docs(&self.docs),
+            format_validates(&self.validates),
+            format_struct_name(&self.name, &self.generics),
+            members
+        )
+    }
+}
+
+impl Display for StructMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{} {}",
+            format_docs(&self.docs),
+            format_validates(&self.validates),
+            self.data_type,
+            self.name
+        )?;
+
+        if let Some(v) = &self.value {
+            write!(f, " = {}", v)?;
+        }
+
+        writeln!(f)
     }
 }
 
 impl Display for TupleStructDeclaration {
+    // e.g.
+    // `struct Point (Int, Int)`
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        writeln!(
+            f,
+            "{}{} ({})",
+            format_docs(&self.docs),
+            format_struct_name(&self.name, &self.generics),
+            self.members
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
     }
 }
 
 impl Display for EmptyStructDeclaration {
+    // e.g.
+    // `struct Writer {}`
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        writeln!(
+            f,
+            "{}{} {{}}",
+            format_docs(&self.docs),
+            format_struct_name(&self.name, &self.generics)
+        )
     }
 }
 
 impl Display for UnionDeclaration {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         todo!()
     }
 }
 
 impl Display for TraitDeclaration {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         todo!()
     }
 }
 
 impl Display for ImplStatement {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         todo!()
     }
 }
 
 impl Display for AliasStatement {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         todo!()
     }
 }
@@ -386,10 +784,11 @@ impl Display for Statement {
         match self {
             Statement::FunctionDeclaration(v) => write!(f, "{}", v),
             Statement::EmptyFunctionDeclaration(v) => write!(f, "{}", v),
-            Statement::PatternFunctionDeclaration(v) => write!(f, "{}", v),
+            Statement::PatternFunctionGroup(v) => write!(f, "{}", v),
             // Statement::NamespaceStatement(v) => write!(f, "{}", v),
             Statement::UseStatement(v) => write!(f, "{}", v),
             Statement::ConstDeclaration(v) => write!(f, "{}", v),
+            Statement::ConstGroupDeclaration(v) => write!(f, "{}", v),
             Statement::MemberStructDeclaration(v) => write!(f, "{}", v),
             Statement::TupleStructDeclaration(v) => write!(f, "{}", v),
             Statement::EmptyStructDeclaration(v) => write!(f, "{}", v),
@@ -398,7 +797,7 @@ impl Display for Statement {
             Statement::ImplStatement(v) => write!(f, "{}", v),
             Statement::AliasStatement(v) => write!(f, "{}", v),
             Statement::Expression(expression) => {
-                write!(f, "{}\n", expression)
+                writeln!(f, "{}", expression)
             }
         }
     }
@@ -437,6 +836,8 @@ pub enum Expression {
     BlockExpression(BlockExpression), // `表达式块` 本身也是 `表达式` 其中的一种
     JoinExpression(JoinExpression),
     LetExpression(LetExpression),
+    PropertySetExpression(PropertySetExpression), // `a.b := c`
+    PropertyGetExpression(PropertyGetExpression), // `let a := b.c`
 
     IfExpression(IfExpression),
     ForExpression(ForExpression),
@@ -492,6 +893,28 @@ pub struct LetExpression {
     pub range: Range,
 }
 
+// 属性设置表达式
+//
+// `window.title := "hello"` 是函数调用 `!set(window, #title, "hello")` 的语法糖，
+// 见 docs/reference/mutable.md
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertySetExpression {
+    pub member: MemberProperty, // 属性名称只允许标识符
+    pub value: Box<Expression>,
+    pub range: Range,
+}
+
+// 属性读取表达式
+//
+// `let String title := window.title` 是 `let String title = !get(window, #title)` 的语法糖
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyGetExpression {
+    pub data_type: Option<DataType>, // 数据类型是可选的
+    pub object: Box<Expression>,
+    pub member: MemberProperty, // 属性名称只允许标识符
+    pub range: Range,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct IfExpression {
     pub testing: Box<Expression>,
@@ -774,7 +1197,7 @@ impl Display for BlockExpression {
             segments.push("do ".to_string());
         }
         segments.push("{".to_string());
-        if self.body.len() > 0 {
+        if !self.body.is_empty() {
             segments.push("\n".to_string());
             segments.push(format_expressions_with_new_line(&self.body));
             segments.push("\n".to_string());
@@ -791,7 +1214,7 @@ impl Display for JoinExpression {
         segments.push("join ".to_string());
 
         segments.push("{".to_string());
-        if self.body.len() > 0 {
+        if !self.body.is_empty() {
             segments.push("\n".to_string());
             segments.push(format_expressions_with_new_line(&self.body));
             segments.push("\n".to_string());
@@ -812,13 +1235,120 @@ impl Display for LetExpression {
     }
 }
 
+impl Display for PropertySetExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} := {}", self.member, self.value)
+    }
+}
+
+impl Display for PropertyGetExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(d) = &self.data_type {
+            write!(f, "let {} {} := {}", d, self.object, self.member)
+        } else {
+            write!(f, "let {} := {}", self.object, self.member)
+        }
+    }
+}
+
+impl PropertySetExpression {
+    // 去除语法糖，转换为函数调用 `!set(object, #property, value)`
+    //
+    // 属性链当中的中间属性使用 `!get` 读取，比如 `frame.rect.left := 10`
+    // 转换为 `!set(!get(frame, #rect), #left, 10)`。
+    pub fn desugar(&self) -> Expression {
+        new_property_function_call(
+            "set",
+            vec![
+                desugar_property_object(&self.member.object),
+                new_property_name(&self.member.property),
+                (*self.value).clone(),
+            ],
+            &self.range,
+        )
+    }
+}
+
+impl PropertyGetExpression {
+    // 去除语法糖，转换为 `let` 表达式 `let data_type object = !get(object, #property)`
+    pub fn desugar(&self) -> Expression {
+        Expression::LetExpression(LetExpression {
+            data_type: self.data_type.clone(),
+            object: self.object.clone(),
+            value: Box::new(desugar_property_get(&self.member)),
+            range: self.range.clone(),
+        })
+    }
+}
+
+fn desugar_property_get(member: &MemberProperty) -> Expression {
+    new_property_function_call(
+        "get",
+        vec![
+            desugar_property_object(&member.object),
+            new_property_name(&member.property),
+        ],
+        &member.range,
+    )
+}
+
+// 属性链当中的中间属性（即属性名称为标识符的 MemberProperty）转换为 `!get`，
+// 其他表达式保持不变
+fn desugar_property_object(object: &Expression) -> Expression {
+    match object {
+        Expression::MemberExpression(MemberExpression::Property(member))
+            if matches!(*member.property, Expression::Identifier(_)) =>
+        {
+            desugar_property_get(member)
+        }
+        _ => object.clone(),
+    }
+}
+
+// 属性名称转换为哈希字符串，比如 `title` 转换为 `#title`
+fn new_property_name(property: &Expression) -> Expression {
+    match property {
+        Expression::Identifier(identifier) => {
+            Expression::Literal(Literal::HashString(HashString {
+                value: identifier.name.clone(),
+                range: identifier.range.clone(),
+            }))
+        }
+        _ => property.clone(),
+    }
+}
+
+fn new_property_function_call(name: &str, arguments: Vec<Expression>, range: &Range) -> Expression {
+    Expression::FunctionCallExpression(FunctionCallExpression {
+        callee: Box::new(Expression::PrefixIdentifier(PrefixIdentifier {
+            identifier: Identifier {
+                dirs: vec![],
+                name: name.to_string(),
+                generics: vec![],
+                range: range.clone(),
+            },
+            range: range.clone(),
+        })),
+        arguments: arguments
+            .into_iter()
+            .map(|value| Argument {
+                name: None,
+                value: Box::new(value),
+                range: range.clone(),
+            })
+            .collect(),
+        range: range.clone(),
+    })
+}
+
 impl Display for IfExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // 当 if 的各子表达式为另一个 if 表达式时，加上
-        // 一对花括号包围起来，防止嵌套 if 表达式结构错乱
+        // 一对括号包围起来，防止嵌套 if 表达式结构错乱
+        // 注：不能使用花括号，花括号会被解析为表达式块
         let escape = |e: &Expression| match e {
             Expression::IfExpression(i) => {
-                format!("{{{}}}", i)
+                format!("({})", i)
             }
             _ => format!("{}", e),
         };
@@ -982,7 +1512,7 @@ impl Display for Sign {
 
         segments.push("sign".to_string());
 
-        if self.generics.len() > 0 {
+        if !self.generics.is_empty() {
             segments.push(format!("<{}>", format_generics(&self.generics)));
         }
 
@@ -992,7 +1522,7 @@ impl Display for Sign {
             segments.push(format!("type {}", dt));
         }
 
-        if self.whiches.len() > 0 {
+        if !self.whiches.is_empty() {
             segments.push(format!(
                 "which {{\n{}\n}}",
                 format_which_entries(&self.whiches)
@@ -1051,7 +1581,13 @@ impl Display for WhichEntryLimit {
 
 impl Display for BinaryExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({} {} {})", self.left, self.operator, self.right)
+        write!(
+            f,
+            "({} {} {})",
+            format_operand(&self.left),
+            self.operator,
+            format_operand(&self.right)
+        )
     }
 }
 
@@ -1059,13 +1595,18 @@ impl Display for UnaryExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.operator {
             Token::Cast => {
-                write!(f, "{}^", self.operand)
-            }
-            Token::Minus => {
-                write!(f, "-{}", self.operand)
+                write!(f, "{}^", format_operand(&self.operand))
             }
+            Token::Minus => match self.operand.as_ref() {
+                // 连续的负号需要加上括号，否则会被输出为 `--`
+                Expression::UnaryExpression(UnaryExpression {
+                    operator: Token::Minus,
+                    ..
+                }) => write!(f, "-({})", self.operand),
+                _ => write!(f, "-{}", format_operand(&self.operand)),
+            },
             Token::Unwrap => {
-                write!(f, "{}?", self.operand)
+                write!(f, "{}?", format_operand(&self.operand))
             }
             _ => {
                 panic!("invalid unary operator")
@@ -1132,7 +1673,7 @@ impl Display for AnonymousFunction {
             segments.push(format!("type {}", dt));
         }
 
-        if self.whiches.len() > 0 {
+        if !self.whiches.is_empty() {
             segments.push(format!(
                 "which {{\n{}\n}}",
                 format_which_entries(&self.whiches)
@@ -1144,9 +1685,7 @@ impl Display for AnonymousFunction {
         // }
 
         match self.body.as_ref() {
-            Expression::BlockExpression(e) if e.is_explicit == false => {
-                segments.push(format!("{}", e))
-            }
+            Expression::BlockExpression(e) if !e.is_explicit => segments.push(format!("{}", e)),
             _ => {
                 segments.push("=".to_string());
                 segments.push(format!("{}", &self.body));
@@ -1163,7 +1702,7 @@ impl Display for Identifier {
         let mut segments = Vec::<String>::new();
 
         // 命名空间路径
-        if self.dirs.len() > 0 {
+        if !self.dirs.is_empty() {
             segments.push(self.dirs.join("::"));
             segments.push("::".to_string());
         }
@@ -1172,7 +1711,7 @@ impl Display for Identifier {
         segments.push(self.name.clone());
 
         // 泛型
-        if self.generics.len() > 0 {
+        if !self.generics.is_empty() {
             segments.push(format!("<{}>", format_generics(&self.generics)));
         }
 
@@ -1208,7 +1747,7 @@ impl Display for Interval {
 
 impl Display for Tuple {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.elements.len() == 0 {
+        if self.elements.is_empty() {
             write!(f, "()")
         } else {
             let text = format_expressions_with_comma(&self.elements);
@@ -1219,7 +1758,7 @@ impl Display for Tuple {
 
 impl Display for List {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.elements.len() == 0 {
+        if self.elements.is_empty() {
             write!(f, "[]")
         } else {
             let text = format_expressions_with_comma(&self.elements);
@@ -1260,6 +1799,8 @@ impl Display for Expression {
             Expression::BlockExpression(v) => write!(f, "{}", v),
             Expression::JoinExpression(v) => write!(f, "{}", v),
             Expression::LetExpression(v) => write!(f, "{}", v),
+            Expression::PropertySetExpression(v) => write!(f, "{}", v),
+            Expression::PropertyGetExpression(v) => write!(f, "{}", v),
             Expression::IfExpression(v) => write!(f, "{}", v),
             Expression::ForExpression(v) => write!(f, "{}", v),
             Expression::NextExpression(v) => write!(f, "{}", v),
@@ -1302,7 +1843,7 @@ pub enum Literal {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Integer {
-    pub value: i64,
+    pub value: IntegerLiteral,
     pub range: Range,
 }
 
@@ -1321,8 +1862,7 @@ pub struct Complex {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Bit {
-    pub width: usize,
-    pub bytes: Vec<u8>,
+    pub value: BitLiteral,
     pub range: Range,
 }
 
@@ -1371,23 +1911,42 @@ impl Display for Integer {
 
 impl Display for Float {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+        write!(f, "{}", format_float(self.value))
     }
 }
 
 impl Display for Complex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}+{}i", self.real, self.imaginary)
+        write!(
+            f,
+            "{}+{}i",
+            format_complex_part(self.real),
+            format_complex_part(self.imaginary)
+        )
+    }
+}
+
+impl Bit {
+    // 比特数
+    pub fn bit_length(&self) -> usize {
+        self.value.bit_length()
+    }
+
+    // 无符号整数值，数值超出 u128 范围时返回 None
+    pub fn to_u128(&self) -> Option<u128> {
+        self.value.to_u128()
+    }
+
+    // 以补码解释的有符号整数值，位宽超过 128 时返回 None
+    pub fn to_i128(&self) -> Option<i128> {
+        self.value.to_i128()
     }
 }
 
+// 按源代码里的进制输出，并按位宽补齐前导 0
 impl Display for Bit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut hex = String::new();
-        for byte in &self.bytes {
-            write!(hex, "{:02x}", byte)?;
-        }
-        write!(f, "{}'x{}", self.width, hex)
+        write!(f, "{}", self.value)
     }
 }
 
@@ -1399,13 +1958,13 @@ impl Display for Boolean {
 
 impl Display for Char {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "'{}'", self.value)
+        write!(f, "'{}'", escape_char(self.value))
     }
 }
 
 impl Display for GeneralString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "\"{}\"", self.value)
+        write!(f, "\"{}\"", escape_string(&self.value))
     }
 }
 
@@ -1416,9 +1975,9 @@ impl Display for TemplateString {
         // 交叉合并两个列表
         let mut expression_iter = self.expressions.iter();
         for fragment in &self.fragments {
-            cross_combined_items.push(fragment.to_string());
+            cross_combined_items.push(escape_template_fragment(fragment));
             if let Some(expression) = expression_iter.next() {
-                cross_combined_items.push(format!("{{{{{}}}}}", expression));
+                cross_combined_items.push(format!("{{{}}}", expression));
             }
         }
 
@@ -1426,6 +1985,18 @@ impl Display for TemplateString {
     }
 }
 
+// 转义模板字符串片段里的 '\\'、'`'、'{' 以及 '}'
+fn escape_template_fragment(fragment: &str) -> String {
+    let mut escaped = String::with_capacity(fragment.len());
+    for c in fragment.chars() {
+        if matches!(c, '\\' | '`' | '{' | '}') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 impl Display for HashString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "#{}", self.value)
@@ -1482,14 +2053,75 @@ fn format_function_parameters(parameters: &[FunctionParameter]) -> String {
         .join(", ")
 }
 
+// 返回结构体的关键字、名称以及泛型，比如 `struct User <T>`
+fn format_struct_name(name: &str, generics: &[DataType]) -> String {
+    if generics.is_empty() {
+        format!("struct {}", name)
+    } else {
+        format!("struct {} <{}>", name, format_generics(generics))
+    }
+}
+
+// 返回数据验证约束，每一个约束占一行
+fn format_validates(validates: &[Expression]) -> String {
+    validates
+        .iter()
+        .map(|v| format!("@validate({})\n", v))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+// 返回文档注释，每一段文档注释占一行
+fn format_docs(docs: &[String]) -> String {
+    docs.iter()
+        .map(|d| format!("'''{}'''\n", d))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
 // 返回函数签名的所有参数以逗号 ", " 的拼接，不包含括号
 // 注：空函数的参数不支持默认值
 fn format_empty_function_parameters(parameters: &[EmptyFunctionParameter]) -> String {
-    todo!()
+    parameters
+        .iter()
+        .map(|p| format!("{} {}", p.data_type, p.name))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
+// 返回模式函数的所有参数以逗号 ", " 的拼接，不包含括号
+// 注：模式函数的参数可省略数据类型、变量和模式表达式，且不支持默认值
 fn format_pattern_function_parameters(parameters: &[PatternFunctionParameter]) -> String {
-    todo!()
+    parameters
+        .iter()
+        .map(|p| {
+            let mut segments = Vec::<String>::new();
+
+            if let Some(t) = &p.data_type {
+                segments.push(format!("{}", t));
+            }
+
+            if let Some(v) = &p.variable {
+                segments.push(format!("{} @", v));
+            }
+
+            if let Some(e) = &p.pattern {
+                segments.push(format!("{}", e));
+            }
+
+            // 先 only，后 where
+            if let Some(e) = &p.only {
+                segments.push(format!("only {}", e));
+            }
+
+            if let Some(e) = &p.where_exp {
+                segments.push(format!("where {}", e));
+            }
+
+            segments.join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
 // 返回函数签名的所有参数以逗号 ", " 的拼接，不包含括号
@@ -1519,13 +2151,33 @@ fn format_anonymous_parameters(parameters: &[AnonymousParameter]) -> String {
                 format!("{} {}", d, p.name)
             } else {
                 // 省略了参数数据类型
-                format!("{}", p.name)
+                p.name.to_string()
             }
         })
         .collect::<Vec<String>>()
         .join(", ")
 }
 
+// 返回运算符的操作数
+// 注：以关键字开始的表达式（比如 `if`、`let`）以及匿名函数不是基本表达式，
+// 作为操作数时需要加上括号，否则会被解析为语句或者吞掉后面的运算。
+fn format_operand(expression: &Expression) -> String {
+    match expression {
+        Expression::BlockExpression(_)
+        | Expression::JoinExpression(_)
+        | Expression::LetExpression(_)
+        | Expression::PropertySetExpression(_)
+        | Expression::IfExpression(_)
+        | Expression::ForExpression(_)
+        | Expression::NextExpression(_)
+        | Expression::EachExpression(_)
+        | Expression::BranchExpression(_)
+        | Expression::MatchExpression(_)
+        | Expression::AnonymousFunction(_) => format!("({})", expression),
+        _ => expression.to_string(),
+    }
+}
+
 // 返回函数调用时所有参数（实参）以逗号 ", " 的拼接，不包含括号
 // 注：参数有 "按位置" 和 "按名称" 两种方式。
 fn format_arguments(arguments: &[Argument]) -> String {
@@ -1588,7 +2240,9 @@ pub struct Range {
     pub end: usize,     // 结束位置（不包括）
 }
 
+// 测试代码保留原来的写法（比如 `value: value` 以及 `&vec![...]`）
 #[cfg(test)]
+#[allow(clippy::redundant_field_names, clippy::useless_vec)]
 mod tests {
     use crate::{
         ast::{
@@ -1597,15 +2251,19 @@ mod tests {
             MemberProperty, NamedOperator, NextExpression, PatternExpression, PrefixIdentifier,
             SignParameter, UnaryExpression, WhichEntry, WhichEntryLimit, WhichEntryType,
         },
-        token::Token,
+        token::{BitLiteral, IntegerLiteral, Token},
     };
 
     use super::{
         AnonymousFunction, AnonymousParameter, BinaryExpression, BlockExpression, Boolean,
-        BranchCase, BranchExpression, Char, ConstructorExpression, DataType, EachExpression, Float,
-        ForExpression, FunctionDeclaration, FunctionParameter, IfExpression, Integer, Interval,
-        LetExpression, Literal, Map, MapEntry, MemberExpression, Range, Sign, SliceExpression,
-        Statement, TemplateString, Tuple,
+        BranchCase, BranchExpression, Char, ConstDeclaration, ConstGroupDeclaration, ConstMember,
+        ConstructorExpression, DataType, EachExpression, EmptyFunctionDeclaration,
+        EmptyFunctionParameter, EmptyStructDeclaration, Float, ForExpression, FunctionDeclaration,
+        FunctionParameter, IfExpression, Integer, Interval, LetExpression, Literal, Map, MapEntry,
+        MemberExpression, MemberStructDeclaration, NamePath, NamePathItem,
+        PatternFunctionDeclaration, PatternFunctionGroup, PatternFunctionParameter, Range, Sign,
+        SliceExpression, Statement, StructMember, TemplateString, Tuple, TupleStructDeclaration,
+        UseItem, UseStatement,
     };
 
     // 辅助函数
@@ -1629,7 +2287,7 @@ mod tests {
 
     fn new_literal_integer(value: i64) -> Literal {
         Literal::Integer(Integer {
-            value: value,
+            value: IntegerLiteral::new(&value.to_string(), None),
             range: new_range(),
         })
     }
@@ -1707,7 +2365,7 @@ mod tests {
     #[test]
     fn test_display_integer() {
         let i1 = Integer {
-            value: 123,
+            value: IntegerLiteral::new("123", None),
             range: new_range(),
         };
         assert_eq!(i1.to_string(), "123");
@@ -1735,11 +2393,19 @@ mod tests {
     #[test]
     fn test_display_bit() {
         let i1 = Bit {
-            width: 12,
-            bytes: vec![0xab, 0x8, 0x12],
+            value: BitLiteral::new(24, 16, vec![0xab, 0x8, 0x12]),
+            range: new_range(),
+        };
+        assert_eq!(i1.to_string(), "24'xab0812");
+        assert_eq!(i1.bit_length(), 24);
+        assert_eq!(i1.to_u128(), Some(0xab0812));
+
+        let i2 = Bit {
+            value: BitLiteral::new(6, 2, vec![0b101]),
             range: new_range(),
         };
-        assert_eq!(i1.to_string(), "12'xab0812");
+        assert_eq!(i2.to_string(), "6'b000101");
+        assert_eq!(i2.to_i128(), Some(5));
     }
 
     #[test]
@@ -1814,7 +2480,21 @@ mod tests {
             range: new_range(),
         };
 
-        assert_eq!(l1.to_string(), "`foo{{name}}bar{{number}}baz`");
+        assert_eq!(l1.to_string(), "`foo{name}bar{number}baz`");
+
+        // 片段里的特殊字符需要转义
+        let l2 = TemplateString {
+            fragments: vec!["a{b}".to_string(), "c`\\".to_string()],
+            expressions: vec![Expression::Identifier(Identifier {
+                dirs: vec![],
+                generics: vec![],
+                name: "x".to_string(),
+                range: new_range(),
+            })],
+            range: new_range(),
+        };
+
+        assert_eq!(l2.to_string(), "`a\\{b\\}{x}c\\`\\\\`");
     }
 
     #[test]
@@ -2838,7 +3518,7 @@ mod tests {
             alternate: Some(Box::new(Expression::Literal(new_literal_integer(3)))),
             range: new_range(),
         };
-        assert_eq!(e3.to_string(), "if true then {if (1 > 2) then 3} else 3");
+        assert_eq!(e3.to_string(), "if true then (if (1 > 2) then 3) else 3");
 
         // 测试空子表达式
         let e4 = IfExpression {
@@ -3173,7 +3853,7 @@ mod tests {
                 MatchCase {
                     variable: None,
                     pattern: Some(Box::new(PatternExpression::Regular(
-                        Token::Regexp("(\\d+),(\\w+)".to_string()),
+                        Token::Regexp("(\\d+),(\\w+)".to_string(), "".to_string(), 2),
                         Tuple {
                             elements: vec![
                                 Expression::Identifier(new_identifier("id")),
@@ -3505,6 +4185,7 @@ mod tests {
     #[test]
     fn test_function_declaration() {
         let s1 = FunctionDeclaration {
+            docs: vec![],
             name: "test".to_string(),
             generics: vec![],
             parameters: vec![
@@ -3539,6 +4220,7 @@ mod tests {
 
         // 测试泛型和 which 从属表达式
         let s2 = FunctionDeclaration {
+            docs: vec![],
             name: "writeLine".to_string(),
             generics: vec![
                 DataType::Identifier(new_identifier("D")),
@@ -3584,7 +4266,7 @@ mod tests {
                         value: Box::new(Expression::BinaryExpression(BinaryExpression {
                             operator: Token::Concat,
                             left: Box::new(Expression::Identifier(new_identifier("data"))),
-                            right: Box::new(Expression::Literal(new_literal_string("\\n"))),
+                            right: Box::new(Expression::Literal(new_literal_string("\n"))),
                             range: new_range(),
                         })),
                         range: new_range(),
@@ -3601,13 +4283,14 @@ mod tests {
                 "function writeLine <D, W> (D data, W output) which {
                 D: limit Display
                 W: limit Writer
-            } = (write)(output, (data ++ \"\\n\"))
+            } = (write)(output, (data ++ \"\n\"))
             "
             )
         );
 
         // 测试默认值和 where 从属表达式
         let s3 = FunctionDeclaration {
+            docs: vec![],
             name: "test".to_string(),
             generics: vec![],
             parameters: vec![
@@ -3655,50 +4338,377 @@ mod tests {
         );
     }
 
+    #[test]
     fn test_empty_function_declaration() {
-        // todo::
+        let s1 = EmptyFunctionDeclaration {
+            name: "get".to_string(),
+            generics: vec![],
+            parameters: vec![
+                EmptyFunctionParameter {
+                    data_type: DataType::Identifier(new_identifier("Self")),
+                    name: "self".to_string(),
+                    range: new_range(),
+                },
+                EmptyFunctionParameter {
+                    data_type: DataType::Identifier(new_identifier("Int")),
+                    name: "id".to_string(),
+                    range: new_range(),
+                },
+            ],
+            return_data_type: Some(DataType::Identifier(new_identifier("Ticket"))),
+            whiches: vec![],
+            docs: vec!["get a ticket".to_string()],
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            "'''get a ticket'''\nempty function get (Self self, Int id) type Ticket\n"
+        );
+
+        // 泛型以及 which 从属表达式
+        let s2 = EmptyFunctionDeclaration {
+            name: "show".to_string(),
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            parameters: vec![],
+            return_data_type: None,
+            whiches: vec![WhichEntry::Limit(WhichEntryLimit {
+                name: "T".to_string(),
+                data_types: vec![DataType::Identifier(new_identifier("Display"))],
+                range: new_range(),
+            })],
+            docs: vec![],
+            range: new_range(),
+        };
+        assert_eq!(
+            s2.to_string(),
+            "empty function show <T> () which {\nT: limit Display\n}\n"
+        );
     }
 
-    fn test_pattern_function_declarationzs() {
-        // todo::
+    #[test]
+    fn test_pattern_function_group() {
+        let d1 = PatternFunctionDeclaration {
+            name: "check".to_string(),
+            generics: vec![],
+            parameters: vec![PatternFunctionParameter {
+                data_type: Some(DataType::Identifier(new_identifier("Int"))),
+                variable: Some("i".to_string()),
+                pattern: Some(Box::new(PatternExpression::In(Expression::Identifier(
+                    new_identifier("ids"),
+                )))),
+                where_exp: Some(Box::new(Expression::Identifier(new_identifier("c")))),
+                only: Some(Box::new(Expression::Identifier(new_identifier("valid")))),
+                range: new_range(),
+            }],
+            return_data_type: Some(DataType::Identifier(new_identifier("Int"))),
+            only: None,
+            whiches: vec![],
+            body: Expression::Identifier(new_identifier("i")),
+            docs: vec!["check id".to_string()],
+            range: new_range(),
+        };
+        assert_eq!(
+            d1.to_string(),
+            "'''check id'''\npattern function check (Int i @ in ids only valid where c) type Int = i\n"
+        );
+
+        // 省略了数据类型的参数，以及函数范围的 only 从属表达式
+        let d2 = PatternFunctionDeclaration {
+            name: "check".to_string(),
+            generics: vec![],
+            parameters: vec![PatternFunctionParameter {
+                data_type: None,
+                variable: Some("s".to_string()),
+                pattern: Some(Box::new(PatternExpression::Template("id={id}".to_string()))),
+                where_exp: None,
+                only: None,
+                range: new_range(),
+            }],
+            return_data_type: Some(DataType::Identifier(new_identifier("Int"))),
+            only: Some(Expression::Identifier(new_identifier("flag"))),
+            whiches: vec![],
+            body: Expression::BlockExpression(BlockExpression {
+                is_explicit: false,
+                body: vec![Expression::Literal(new_literal_integer(0))],
+                range: new_range(),
+            }),
+            docs: vec![],
+            range: new_range(),
+        };
+        assert_eq!(
+            d2.to_string(),
+            trim_left_margin(
+                "pattern function check (s @ template \"id={id}\") type Int only flag {
+                0
+                }
+                "
+            )
+        );
+
+        let s1 = Statement::PatternFunctionGroup(PatternFunctionGroup {
+            name: "check".to_string(),
+            declarations: vec![d1.clone(), d2.clone()],
+            range: new_range(),
+        });
+        assert_eq!(s1.to_string(), format!("{}{}", d1, d2));
     }
 
     // fn test_namespace_statement() {
     //     // todo::
     // }
 
+    #[test]
     fn test_use_statement() {
-        // todo::
+        let new_use_item = |path: &[&str], alias: Option<&str>| UseItem {
+            path: path.iter().map(|n| n.to_string()).collect(),
+            alias: alias.map(|n| n.to_string()),
+        };
+
+        let s1 = UseStatement {
+            name_path: NamePath {
+                directories: vec![
+                    NamePathItem::Name("std".to_string()),
+                    NamePathItem::Name("List".to_string()),
+                ],
+                alias: None,
+            },
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "use std::List\n");
+        assert_eq!(s1.flatten(), vec![new_use_item(&["std", "List"], None)]);
+        assert_eq!(s1.flatten()[0].name(), "List");
+
+        let s2 = UseStatement {
+            name_path: NamePath {
+                directories: vec![NamePathItem::Children(
+                    "foo".to_string(),
+                    vec![
+                        NamePath {
+                            directories: vec![NamePathItem::Name("bar".to_string())],
+                            alias: None,
+                        },
+                        NamePath {
+                            directories: vec![NamePathItem::Children(
+                                "sub".to_string(),
+                                vec![
+                                    NamePath {
+                                        directories: vec![NamePathItem::Name("one".to_string())],
+                                        alias: None,
+                                    },
+                                    NamePath {
+                                        directories: vec![NamePathItem::Name("two".to_string())],
+                                        alias: Some("three".to_string()),
+                                    },
+                                ],
+                            )],
+                            alias: None,
+                        },
+                    ],
+                )],
+                alias: None,
+            },
+            range: new_range(),
+        };
+        assert_eq!(s2.to_string(), "use foo::{bar, sub::{one, two as three}}\n");
+        assert_eq!(
+            s2.flatten(),
+            vec![
+                new_use_item(&["foo", "bar"], None),
+                new_use_item(&["foo", "sub", "one"], None),
+                new_use_item(&["foo", "sub", "two"], Some("three")),
+            ]
+        );
+        assert_eq!(s2.flatten()[2].name(), "three");
     }
 
+    #[test]
     fn test_const_declaration() {
-        // todo::
+        let s1 = ConstDeclaration {
+            data_type: Some(DataType::Identifier(new_identifier("Int"))),
+            name: "Code".to_string(),
+            value: Expression::Literal(new_literal_integer(123)),
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "const Int Code = 123\n");
+
+        let s2 = ConstDeclaration {
+            data_type: None,
+            name: "Name".to_string(),
+            value: Expression::Literal(new_literal_string("foo")),
+            range: new_range(),
+        };
+        assert_eq!(s2.to_string(), "const Name = \"foo\"\n");
+    }
+
+    #[test]
+    fn test_const_group_declaration() {
+        let s1 = ConstGroupDeclaration {
+            data_type: Some(DataType::Identifier(new_identifier("Int"))),
+            name: "Code".to_string(),
+            members: vec![
+                ConstMember {
+                    name: "Ok".to_string(),
+                    value: Expression::Literal(new_literal_integer(200)),
+                    range: new_range(),
+                },
+                ConstMember {
+                    name: "Moved".to_string(),
+                    value: Expression::Literal(new_literal_integer(301)),
+                    range: new_range(),
+                },
+            ],
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            "const Int Code {\nOk = 200\nMoved = 301\n}\n"
+        );
+
+        // `组名::成员名`
+        let mut identifier = new_identifier("Moved");
+        identifier.dirs = vec!["Code".to_string()];
+        assert_eq!(
+            s1.resolve(&identifier).map(|m| m.value.to_string()),
+            Some("301".to_string())
+        );
+
+        identifier.dirs = vec!["Other".to_string()];
+        assert_eq!(s1.resolve(&identifier), None);
+        assert_eq!(s1.resolve(&new_identifier("Moved")), None);
+        assert_eq!(s1.get_member("Found"), None);
     }
 
+    #[test]
     fn test_member_struct_declaration() {
-        // todo::
+        let s1 = MemberStructDeclaration {
+            name: "User".to_string(),
+            members: vec![
+                StructMember {
+                    data_type: DataType::Identifier(new_identifier("Int")),
+                    name: "id".to_string(),
+                    value: None,
+                    validates: vec![Expression::BinaryExpression(BinaryExpression {
+                        operator: Token::GreaterThan,
+                        left: Box::new(Expression::Identifier(new_identifier("id"))),
+                        right: Box::new(Expression::Literal(new_literal_integer(0))),
+                        range: new_range(),
+                    })],
+                    docs: vec!["the id".to_string()],
+                    range: new_range(),
+                },
+                StructMember {
+                    data_type: DataType::Identifier(new_identifier("String")),
+                    name: "name".to_string(),
+                    value: Some(Expression::Literal(new_literal_string("foo"))),
+                    validates: vec![],
+                    docs: vec![],
+                    range: new_range(),
+                },
+            ],
+            generics: vec![],
+            validates: vec![Expression::Identifier(new_identifier("valid"))],
+            docs: vec!["user".to_string()],
+            range: new_range(),
+        };
+        assert_eq!(
+            s1.to_string(),
+            trim_left_margin(
+                "'''user'''
+                @validate(valid)
+                struct User {
+                '''the id'''
+                @validate((id > 0))
+                Int id
+                String name = \"foo\"
+                }
+                "
+            )
+        );
+
+        // 泛型
+        let s2 = MemberStructDeclaration {
+            name: "Box".to_string(),
+            members: vec![StructMember {
+                data_type: DataType::Identifier(new_identifier("T")),
+                name: "value".to_string(),
+                value: None,
+                validates: vec![],
+                docs: vec![],
+                range: new_range(),
+            }],
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            validates: vec![],
+            docs: vec![],
+            range: new_range(),
+        };
+        assert_eq!(s2.to_string(), "struct Box <T> {\nT value\n}\n");
     }
 
+    #[test]
     fn test_tuple_struct_declaration() {
-        // todo::
+        let s1 = TupleStructDeclaration {
+            name: "Point".to_string(),
+            members: vec![
+                DataType::Identifier(new_identifier("Int")),
+                DataType::Identifier(new_identifier("Int")),
+            ],
+            generics: vec![],
+            docs: vec![],
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "struct Point (Int, Int)\n");
+
+        let s2 = TupleStructDeclaration {
+            name: "Pair".to_string(),
+            members: vec![
+                DataType::Identifier(new_identifier("T")),
+                DataType::Identifier(new_identifier("E")),
+            ],
+            generics: vec![
+                DataType::Identifier(new_identifier("T")),
+                DataType::Identifier(new_identifier("E")),
+            ],
+            docs: vec!["a pair".to_string()],
+            range: new_range(),
+        };
+        assert_eq!(s2.to_string(), "'''a pair'''\nstruct Pair <T, E> (T, E)\n");
     }
 
+    #[test]
     fn test_empty_struct_declaration() {
-        // todo::
+        let s1 = EmptyStructDeclaration {
+            name: "Writer".to_string(),
+            generics: vec![],
+            docs: vec![],
+            range: new_range(),
+        };
+        assert_eq!(s1.to_string(), "struct Writer {}\n");
+
+        let s2 = EmptyStructDeclaration {
+            name: "Marker".to_string(),
+            generics: vec![DataType::Identifier(new_identifier("T"))],
+            docs: vec!["marker".to_string()],
+            range: new_range(),
+        };
+        assert_eq!(s2.to_string(), "'''marker'''\nstruct Marker <T> {}\n");
     }
 
+    #[test]
     fn test_union_declaration() {
         // todo::
     }
 
+    #[test]
     fn test_trait_declaration() {
         // todo::
     }
 
+    #[test]
     fn test_impl_statement() {
         // todo::
     }
 
+    #[test]
     fn test_alias_statement() {
         // todo::
     }
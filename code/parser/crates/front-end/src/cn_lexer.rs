@@ -0,0 +1,820 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::error::{Diagnostic, DiagnosticCode};
+use crate::identifier::{is_identifier_continue, is_identifier_start, normalize_identifier};
+use crate::keyword::get_locale_header_length;
+use crate::keyword::KeywordTable;
+use crate::lexer::{
+    check_integer_range, get_error_range, lex_token, new_error, new_hash_string_token,
+};
+use crate::token::IntegerLiteral;
+use crate::token::Location;
+use crate::token::Token;
+use crate::token::TokenDetail;
+
+// 中文文法的词法分析
+//
+// 详细的规则见 docs/reference/cn-spec.md，简单来说：
+//
+// - 词语之间不使用空格分隔，而是依据字面量、关键字以及导入的名称来分词；
+// - 在同一个位置同时匹配多个关键字或者名称时，选择最长的一个，
+//   长度相同时关键字优先，因此分词结果是确定的；
+// - 包含关键字或者导入名称的标识符，可以使用一对花括号 `{用户甲}`
+//   或者一对单引号 `'用户甲'` 包围起来。
+//
+// - 代码块可以省略开始的 `以下`，比如函数、结构体的声明以及 `那么`、`否则`
+//   所在的行之后直到 `以上` 的内容，见 insert_implicit_braces 函数。
+//
+// 分析的结果跟 lexer::tokenize 的一样是 Token 序列，可以直接交给 parser 解析。
+pub fn tokenize_chinese(text: &str, file_id: usize) -> Result<Vec<TokenDetail>, Diagnostic> {
+    let token_details = ChineseLexer::new(text, file_id).collect::<Result<Vec<_>, _>>()?;
+    Ok(insert_implicit_braces(token_details))
+}
+
+// 代码块的开始位置
+enum BlockOpener {
+    Explicit,           // `以下`（或者 `{`）
+    Then(usize),        // 以 `那么` 结尾的行，参数为行尾换行符的索引
+    Other(usize),       // 以 `否则`、`执行` 或者 `汇合` 结尾的行
+    Declaration(usize), // 函数、结构体等声明语句的首行
+}
+
+// 为省略了 `以下` 的代码块补上开始符号 `{`
+//
+// 中文文法的代码块通常不写开始的 `以下`，只写结束的 `以上`，比如：
+//
+// 函数整数最大值(整数左手值, 整数右手值)
+//     如果左手值:大于:右手值那么
+//         左手值
+//     否则
+//         右手值
+//     以上
+// 以上
+//
+// 规则：
+// - 以 `那么`、`否则`、`执行` 或者 `汇合` 结尾的行，以及函数、结构体等声明语句
+//   （不包括以 `=` 开始函数主体的）的首行，都有可能是代码块的开始，
+//   当后面出现跟它匹配的 `以上` 时，在行尾补上 `{`；
+// - 位于行首的 `否则` 同时结束 `那么` 开始的代码块，即 if 表达式只需要一个 `以上`；
+// - 声明语句不能嵌套，所以遇到下一个声明语句时，前一个未匹配的声明语句没有代码块，
+//   比如元组结构体 `结构点(整数,整数)`。
+//
+// 补上的符号的位置是行尾（或者 `否则` 之前）的空范围。
+fn insert_implicit_braces(token_details: Vec<TokenDetail>) -> Vec<TokenDetail> {
+    let mut openers: Vec<BlockOpener> = vec![];
+    let mut left_brace_indices: HashSet<usize> = HashSet::new();
+    let mut right_brace_indices: HashSet<usize> = HashSet::new();
+    let mut line_start = 0;
+
+    for (index, token_detail) in token_details.iter().enumerate() {
+        match token_detail.token {
+            Token::LeftBrace => openers.push(BlockOpener::Explicit),
+            Token::RightBrace => match openers.pop() {
+                Some(BlockOpener::Then(line_end))
+                | Some(BlockOpener::Other(line_end))
+                | Some(BlockOpener::Declaration(line_end)) => {
+                    left_brace_indices.insert(line_end);
+                }
+                _ => {}
+            },
+            Token::Else if index == line_start => {
+                if let Some(BlockOpener::Then(line_end)) = openers.last() {
+                    left_brace_indices.insert(*line_end);
+                    right_brace_indices.insert(index);
+                    openers.pop();
+                }
+            }
+            Token::NewLine => {
+                let line = &token_details[line_start..index];
+                match line.last().map(|t| &t.token) {
+                    Some(Token::LeftBrace) => {}
+                    Some(Token::Then) => openers.push(BlockOpener::Then(index)),
+                    Some(Token::Else | Token::Do | Token::Join) => {
+                        openers.push(BlockOpener::Other(index))
+                    }
+                    _ if is_declaration_header(line) => {
+                        if let Some(BlockOpener::Declaration(_)) = openers.last() {
+                            openers.pop();
+                        }
+                        openers.push(BlockOpener::Declaration(index));
+                    }
+                    _ => {}
+                }
+                line_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if left_brace_indices.is_empty() {
+        return token_details;
+    }
+
+    let mut result = Vec::with_capacity(token_details.len() + left_brace_indices.len() * 2);
+    for (index, token_detail) in token_details.into_iter().enumerate() {
+        let position = token_detail.location.start;
+        if right_brace_indices.contains(&index) {
+            result.push(new_implicit_token(
+                Token::RightBrace,
+                &token_detail.location,
+                position,
+            ));
+        }
+        if left_brace_indices.contains(&index) {
+            result.push(new_implicit_token(
+                Token::LeftBrace,
+                &token_detail.location,
+                position,
+            ));
+        }
+        result.push(token_detail);
+    }
+    result
+}
+
+// 声明语句的首行，并且函数主体不以 `=` 开始（`=` 不在括号之内）
+fn is_declaration_header(line: &[TokenDetail]) -> bool {
+    let is_declaration = matches!(
+        line.first().map(|t| &t.token),
+        Some(
+            Token::Function
+                | Token::Pattern
+                | Token::Struct
+                | Token::Union
+                | Token::Trait
+                | Token::Impl
+        )
+    );
+
+    let mut depth = 0;
+    let has_assignment = line.iter().any(|t| {
+        match t.token {
+            Token::LeftParen | Token::LeftBracket | Token::LeftBrace => depth += 1,
+            Token::RightParen | Token::RightBracket | Token::RightBrace => depth -= 1,
+            Token::Assign if depth == 0 => return true,
+            _ => {}
+        }
+        false
+    });
+
+    is_declaration && !has_assignment
+}
+
+fn new_implicit_token(token: Token, location: &Location, position: usize) -> TokenDetail {
+    TokenDetail {
+        token,
+        location: Location {
+            file_id: location.file_id,
+            start: position,
+            end: position,
+        },
+        trivia: None,
+    }
+}
+
+// 预导入的名称（主要是数据类型的名称）
+const PRELUDE_NAMES: [&str; 6] = ["整数", "浮点数", "字符", "字符串", "逻辑", "序列"];
+
+// 中文文法的流式词法分析器
+//
+// 除了预导入的名称，使用语句（`使用数学::{平方,平方根}`）里出现的名称
+// 也会自动加入导入名称列表，作用于该语句之后的源文本。
+pub struct ChineseLexer<'a> {
+    text: &'a str,
+    rest: &'a str, // 尚未分析的源文本
+    file_id: usize,
+    is_failed: bool,
+    is_in_use_statement: bool,
+    names: HashSet<String>, // 导入的名称
+    max_name_length: usize, // 导入的名称的最大长度（字符数）
+    symbols: HashMap<&'a str, Rc<str>>,
+    keywords: Rc<KeywordTable>,
+}
+
+impl<'a> ChineseLexer<'a> {
+    pub fn new(text: &'a str, file_id: usize) -> Self {
+        let lexer = ChineseLexer {
+            text,
+            rest: text,
+            file_id,
+            is_failed: false,
+            is_in_use_statement: false,
+            names: HashSet::new(),
+            max_name_length: 0,
+            symbols: HashMap::new(),
+            keywords: Rc::new(KeywordTable::chinese()),
+        };
+
+        lexer.with_imported_names(PRELUDE_NAMES)
+    }
+
+    // 使用指定的关键字表，默认为简体中文关键字表
+    pub fn with_keywords(mut self, keywords: Rc<KeywordTable>) -> Self {
+        self.keywords = keywords;
+        self
+    }
+
+    // 添加导入的名称，比如由其他源文件或者包导入的函数名称、数据类型名称
+    pub fn with_imported_names<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for name in names {
+            self.add_imported_name(name.as_ref());
+        }
+        self
+    }
+
+    fn add_imported_name(&mut self, name: &str) {
+        self.max_name_length = self.max_name_length.max(name.chars().count());
+        self.names.insert(name.to_string());
+    }
+
+    // 解析一个 token，或者跳过一段空白或注释（此时 token 为 None）
+    fn next_lexeme(&mut self) -> Option<Result<(Option<Token>, Location), Diagnostic>> {
+        if self.rest.is_empty() || self.is_failed {
+            return None;
+        }
+
+        let start = self.text.len() - self.rest.len();
+
+        if start == 0 {
+            if let Some(length) = get_locale_header_length(self.text) {
+                // 跳过源文件首行的语言标记
+                self.rest = &self.rest[length..];
+                return Some(Ok((None, self.new_location(0, length))));
+            }
+        }
+
+        let (token, post_rest) = match self.lex_chinese_token(self.rest) {
+            Ok(v) => v,
+            Err(mut diagnostic) => {
+                let (error_start, error_end) = get_error_range(&diagnostic, self.rest);
+                diagnostic.location = self.new_location(start + error_start, start + error_end);
+                self.is_failed = true;
+                return Some(Err(diagnostic));
+            }
+        };
+
+        self.rest = post_rest;
+        let end = self.text.len() - self.rest.len();
+
+        Some(Ok((token, self.new_location(start, end))))
+    }
+
+    fn lex_chinese_token(
+        &mut self,
+        source: &'a str,
+    ) -> Result<(Option<Token>, &'a str), Diagnostic> {
+        let first = match source.chars().next() {
+            Some(c) => c,
+            None => return Ok((None, source)),
+        };
+
+        if let Some(half_width) = to_half_width_symbol(first) {
+            // 中文文法不使用全角标点符号
+            return Err(new_error(
+                DiagnosticCode::InvalidChar,
+                &format!("invalid char '{}'", first),
+            )
+            .with_help(&format!(
+                "use the half-width symbol '{}' instead of the full-width one",
+                half_width
+            )));
+        }
+
+        let rest = &source[first.len_utf8()..];
+
+        match first {
+            '{' if !self.is_in_use_statement => {
+                // `{用户甲}`
+                // 注：使用语句里的花括号总是表示名称列表
+                if let Some((name, post_rest)) = lex_quoted_word(rest, '}') {
+                    return Ok((Some(self.new_identifier(name)), post_rest));
+                }
+            }
+            '\'' if !rest.starts_with("''") => {
+                // `'用户甲'`
+                // 注：只有一个字符时仍然是字符字面量，比如 `'a'` 和 `'甲'`
+                match lex_quoted_word(rest, '\'') {
+                    Some((name, post_rest)) if name.chars().count() > 1 => {
+                        return Ok((Some(self.new_identifier(name)), post_rest));
+                    }
+                    _ => {}
+                }
+            }
+            ':' => {
+                // `:大于:`
+                if let Some((name, post_rest)) = lex_quoted_word(rest, ':') {
+                    let name = normalize_identifier(name).into_owned();
+                    return Ok((Some(Token::NamedOperator(name)), post_rest));
+                }
+            }
+            '#' => {
+                // `#中文`
+                let end_pos = find_end_of_word(rest);
+                if end_pos > 0 {
+                    let token = new_hash_string_token(&normalize_identifier(&rest[..end_pos]));
+                    return Ok((Some(token), &rest[end_pos..]));
+                }
+            }
+            _ if is_word_start_char(first) => {
+                let (token, post_rest) = self.lex_word(source)?;
+                return Ok((Some(token), post_rest));
+            }
+            _ => {}
+        }
+
+        // 其余的字面量、符号、空白以及注释跟 lexer 的一样
+        lex_token(source, &mut self.symbols, &self.keywords)
+    }
+
+    fn lex_word(&mut self, source: &'a str) -> Result<(Token, &'a str), Diagnostic> {
+        // 使用语句里的名称由符号分隔，整个词语作为一个名称，
+        // 以免后面的名称被前面刚导入的名称拆分，比如 `{平方,平方根}`
+        if self.is_in_use_statement {
+            let end_pos = find_end_of_word(source);
+            if self.keywords.lookup(&source[..end_pos]).is_none() {
+                let (name, rest) = source.split_at(end_pos);
+                return Ok((self.new_identifier(name), rest));
+            }
+        }
+
+        // 关键字或者导入的名称
+        if let Some((length, keyword)) = self.find_longest_word(source) {
+            let (word, rest) = source.split_at(length);
+            let token = match keyword {
+                Some(token) => token,
+                None => self.new_identifier(word),
+            };
+            return Ok((token, rest));
+        }
+
+        // 中文数字，比如 `一二三`
+        let end_pos = source
+            .find(|c| to_chinese_digit(c).is_none())
+            .unwrap_or(source.len());
+        if end_pos > 0 {
+            let digits: String = source[..end_pos]
+                .chars()
+                .filter_map(to_chinese_digit)
+                .collect();
+            let literal = IntegerLiteral::new(&digits, None);
+            check_integer_range(&literal, end_pos)?;
+            return Ok((Token::Integer(literal), &source[end_pos..]));
+        }
+
+        // 标识符
+        // 直到遇到非文字字符，或者遇到关键字以及导入的名称为止，
+        // 注：标识符中间的数字（包括中文数字）属于标识符的一部分，比如 `第一个`
+        let mut end_pos = source.len();
+        for (index, c) in source.char_indices().skip(1) {
+            if !is_word_char(c) || self.find_longest_word(&source[index..]).is_some() {
+                end_pos = index;
+                break;
+            }
+        }
+
+        let (name, rest) = source.split_at(end_pos);
+        Ok((self.new_identifier(name), rest))
+    }
+
+    // 查找源文本开头的最长的关键字或者导入的名称
+    //
+    // 返回匹配的长度（字节数），以及关键字对应的 token（导入的名称则为 None）
+    fn find_longest_word(&self, source: &str) -> Option<(usize, Option<Token>)> {
+        let max_length = self.keywords.max_keyword_length().max(self.max_name_length);
+
+        let mut ends: Vec<usize> = source
+            .char_indices()
+            .take(max_length)
+            .map(|(index, c)| index + c.len_utf8())
+            .collect();
+
+        while let Some(end) = ends.pop() {
+            let word = &source[..end];
+            if let Some(token) = self.keywords.lookup(word) {
+                return Some((end, Some(token)));
+            }
+            if self.names.contains(word) {
+                return Some((end, None));
+            }
+        }
+
+        None
+    }
+
+    fn new_identifier(&mut self, name: &'a str) -> Token {
+        if self.is_in_use_statement {
+            self.add_imported_name(name);
+        }
+
+        // 同名的标识符共享同一个 Rc<str>，
+        // 标识符按 NFC 规范化，以便组合形式与预组合形式的同一个名称相等
+        let name = self
+            .symbols
+            .entry(name)
+            .or_insert_with(|| Rc::from(normalize_identifier(name).as_ref()))
+            .clone();
+        Token::Identifier(name)
+    }
+
+    fn new_location(&self, start: usize, end: usize) -> Location {
+        Location {
+            file_id: self.file_id,
+            start,
+            end,
+        }
+    }
+}
+
+impl<'a> Iterator for ChineseLexer<'a> {
+    type Item = Result<TokenDetail, Diagnostic>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_lexeme()? {
+                Ok((Some(token), location)) => {
+                    match token {
+                        Token::Use => self.is_in_use_statement = true,
+                        Token::NewLine => self.is_in_use_statement = false,
+                        _ => {}
+                    }
+
+                    return Some(Ok(TokenDetail {
+                        location,
+                        token,
+                        trivia: None,
+                    }));
+                }
+                Ok((None, _)) => {
+                    // 跳过空白和注释
+                }
+                Err(diagnostic) => return Some(Err(diagnostic)),
+            }
+        }
+    }
+}
+
+// 解析被一对符号包围的文字，比如 `{用户甲}` 和 `'用户甲'`
+//
+// e.g.
+// {用户甲}
+//  ^-------- 当前所在的位置
+fn lex_quoted_word(source: &str, closing: char) -> Option<(&str, &str)> {
+    // 跟标识符一样，不能以数字或者组合符号开头
+    if !source.starts_with(is_word_start_char) {
+        return None;
+    }
+
+    let end_pos = find_end_of_word(source);
+    if end_pos > 0 && source[end_pos..].starts_with(closing) {
+        Some((&source[..end_pos], &source[end_pos + closing.len_utf8()..]))
+    } else {
+        None
+    }
+}
+
+fn find_end_of_word(source: &str) -> usize {
+    source.find(|c| !is_word_char(c)).unwrap_or(source.len())
+}
+
+// 可以组成词语的文字（中文文字、字母、数字、下划线以及组合符号等），
+// 跟 lexer 的标识符一样遵循 UAX#31 的 XID_Continue，但不包括全角符号
+fn is_word_char(c: char) -> bool {
+    is_identifier_continue(c) && to_half_width_symbol(c).is_none()
+}
+
+// 可以作为词语首个字符的文字，即 XID_Start 或者下划线
+fn is_word_start_char(c: char) -> bool {
+    is_identifier_start(c) && to_half_width_symbol(c).is_none()
+}
+
+fn to_chinese_digit(c: char) -> Option<char> {
+    match c {
+        '〇' | '零' => Some('0'),
+        '一' => Some('1'),
+        '二' => Some('2'),
+        '三' => Some('3'),
+        '四' => Some('4'),
+        '五' => Some('5'),
+        '六' => Some('6'),
+        '七' => Some('7'),
+        '八' => Some('8'),
+        '九' => Some('9'),
+        _ => None,
+    }
+}
+
+// 全角标点符号（以及全角字母、数字）所对应的半角符号
+fn to_half_width_symbol(c: char) -> Option<char> {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0),
+        '\u{3000}' => Some(' '),
+        '。' => Some('.'),
+        '、' => Some(','),
+        '“' | '”' => Some('"'),
+        '‘' | '’' => Some('\''),
+        '【' | '「' => Some('['),
+        '】' | '」' => Some(']'),
+        '《' => Some('<'),
+        '》' => Some('>'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::DiagnosticCode,
+        lexer::tokenize,
+        parser::parse,
+        token::{Location, Token, TokenDetail},
+    };
+
+    use super::{tokenize_chinese, ChineseLexer};
+
+    // 辅助函数
+
+    fn token_details_to_string(token_details: &[TokenDetail]) -> Vec<String> {
+        token_details.iter().map(|t| t.token.to_string()).collect()
+    }
+
+    fn tokenize_to_string(text: &str) -> Vec<String> {
+        token_details_to_string(&tokenize_chinese(text, 0).unwrap())
+    }
+
+    fn tokens_of(token_details: Vec<TokenDetail>) -> Vec<Token> {
+        token_details.into_iter().map(|t| t.token).collect()
+    }
+
+    #[test]
+    fn test_word_segmentation() {
+        // 依据关键字分词
+        assert_eq!(
+            tokenize_to_string("令勾等于三;令股等于四"),
+            vec!["let", "勾", "=", "3", "\n", "let", "股", "=", "4"]
+        );
+
+        // 依据预导入的名称分词
+        assert_eq!(
+            tokenize_to_string("赋值整数累加值予一二三"),
+            vec!["let", "整数", "累加值", "=", "123"]
+        );
+
+        // 选择最长的名称
+        assert_eq!(tokenize_to_string("字符串姓名"), vec!["字符串", "姓名"]);
+
+        // 标识符中间的数字属于标识符
+        assert_eq!(
+            tokenize_to_string("列表.第一个()"),
+            vec!["列表", ".", "第一个", "(", ")"]
+        );
+
+        // 命名操作符
+        assert_eq!(
+            tokenize_to_string("如果左手值:大于:右手值那么左手值否则右手值"),
+            vec![
+                "if",
+                "左手值",
+                ":大于:",
+                "右手值",
+                "then",
+                "左手值",
+                "else",
+                "右手值"
+            ]
+        );
+
+        // 其他字面量和符号
+        assert_eq!(
+            tokenize_to_string("让字符串变量甲=\"你好\"\n书写行(变量甲, 123, 真)"),
+            vec![
+                "let",
+                "字符串",
+                "变量甲",
+                "=",
+                "\"你好\"",
+                "\n",
+                "书写行",
+                "(",
+                "变量甲",
+                ",",
+                "123",
+                ",",
+                "true",
+                ")"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_imported_names() {
+        // 使用语句里的名称
+        assert_eq!(
+            tokenize_to_string("使用数学::{平方,平方根}\n令面积等于平方根平方"),
+            vec![
+                "use",
+                "数学",
+                "::",
+                "{",
+                "平方",
+                ",",
+                "平方根",
+                "}",
+                "\n",
+                "let",
+                "面积",
+                "=",
+                "平方根",
+                "平方"
+            ]
+        );
+
+        // 由外部添加的名称
+        let t1 = ChineseLexer::new("用户甲", 0)
+            .with_imported_names(["用户"])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(token_details_to_string(&t1), vec!["用户", "甲"]);
+    }
+
+    #[test]
+    fn test_quoted_identifier() {
+        let t1 = ChineseLexer::new("赋值用户{用户甲}予用户::新建(100)", 0)
+            .with_imported_names(["用户"])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            token_details_to_string(&t1),
+            vec![
+                "let",
+                "用户",
+                "用户甲",
+                "=",
+                "用户",
+                "::",
+                "新建",
+                "(",
+                "100",
+                ")"
+            ]
+        );
+
+        let t2 = ChineseLexer::new("赋值用户'用户甲'予用户::新建(100)", 0)
+            .with_imported_names(["用户"])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(tokens_of(t1), tokens_of(t2));
+
+        // 非文字内容以及只有一个字符的内容仍然是字符字面量，花括号也一样
+        assert_eq!(tokenize_to_string("'+'"), vec!["'+'"]);
+        assert_eq!(
+            tokens_of(tokenize_chinese("令a等于'a'", 0).unwrap()),
+            tokens_of(tokenize("let a = 'a'").unwrap())
+        );
+        assert_eq!(
+            tokenize_chinese("'甲'", 0).unwrap()[0].token,
+            Token::Char('甲')
+        );
+        assert_eq!(tokenize_to_string("{1}"), vec!["{", "1", "}"]);
+    }
+
+    #[test]
+    fn test_same_tokens_as_lexer() {
+        let t1 = tokenize_chinese("函数a(整数b)以下\n如果b>一那么b否则真\n以上", 0).unwrap();
+
+        let t2 = tokenize("function a(整数 b){\nif b>1 then b else true\n}").unwrap();
+        assert_eq!(tokens_of(t1), tokens_of(t2));
+
+        // 标识符遵循 UAX#31 并且按 NFC 规范化，结果跟 lexer 的一样
+        let t3 = tokenize_chinese("令cafe\u{301}等于1", 0).unwrap();
+        let t4 = tokenize("let cafe\u{301} = 1").unwrap();
+        assert_eq!(t3[1].token, Token::Identifier("caf\u{e9}".into()));
+        assert_eq!(tokens_of(t3), tokens_of(t4));
+        assert_eq!(tokenize_to_string("#cafe\u{301}"), vec!["#caf\u{e9}"]);
+
+        // 可以直接交给 parser 解析
+        let n1 = parse(&tokenize_chinese("令勾等于三", 0).unwrap()).unwrap();
+        assert_eq!(n1.to_string(), "let 勾 = 3\n");
+    }
+
+    #[test]
+    fn test_implicit_block() {
+        // cn-spec 里的示例：代码块省略了开始的 `以下`
+        let t1 = tokenize_chinese(
+            "函数整数最大值(整数左手值, 整数右手值)
+    如果左手值:大于:右手值那么
+        左手值
+    否则
+        右手值
+    以上
+以上",
+            0,
+        )
+        .unwrap();
+        let t2 = tokenize(
+            "function 整数 最大值(整数 左手值, 整数 右手值){
+    if 左手值 :大于: 右手值 then{
+        左手值
+    }else{
+        右手值
+    }
+}",
+        )
+        .unwrap();
+        assert_eq!(tokens_of(t1.clone()), tokens_of(t2));
+        parse(&t1).unwrap();
+
+        // 补上的符号位于行尾，范围为空
+        assert_eq!(t1[10].token, Token::LeftBrace);
+        assert_eq!((t1[10].location.start, t1[10].location.end), (55, 55));
+
+        let t3 = tokenize_chinese("结构用户\n    整数编号\n    字符串姓名\n以上", 0).unwrap();
+        assert_eq!(
+            tokens_of(t3.clone()),
+            tokens_of(tokenize("struct 用户{\n    整数 编号\n    字符串 姓名\n}").unwrap())
+        );
+        parse(&t3).unwrap();
+
+        // 以 `=` 开始函数主体的声明、元组结构体以及行内的 if 表达式都没有代码块
+        let t4 = tokenize_chinese(
+            "结构点(整数,整数)\n函数加一(整数甲)=甲+一\n函数乙()\n如果真那么一否则二\n以上",
+            0,
+        )
+        .unwrap();
+        let t5 = tokenize(
+            "struct 点(整数,整数)\nfunction 加一(整数 甲)=甲+1\nfunction 乙(){\nif true then 1 else 2\n}",
+        )
+        .unwrap();
+        assert_eq!(tokens_of(t4), tokens_of(t5));
+
+        // 明确写出 `以下` 时不再补上符号；`否则` 之前已经有 `以上` 时也一样
+        let t6 = tokenize_chinese("如果真那么以下\n一\n以上\n否则\n二\n以上", 0).unwrap();
+        assert_eq!(
+            tokens_of(t6),
+            tokens_of(tokenize("if true then {\n1\n}\nelse{\n2\n}").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_location() {
+        let t1 = tokenize_chinese("令勾 = 三", 0).unwrap();
+        assert_eq!(
+            t1.iter()
+                .map(|t| t.location.clone())
+                .collect::<Vec<Location>>(),
+            vec![
+                Location {
+                    file_id: 0,
+                    start: 0,
+                    end: 3
+                },
+                Location {
+                    file_id: 0,
+                    start: 3,
+                    end: 6
+                },
+                Location {
+                    file_id: 0,
+                    start: 7,
+                    end: 8
+                },
+                Location {
+                    file_id: 0,
+                    start: 9,
+                    end: 12
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        // 全角标点符号
+        let e1 = tokenize_chinese("令勾等于（三）", 0).unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidChar);
+        assert_eq!(e1.location.start, 12);
+        assert_eq!(
+            e1.helps,
+            vec!["use the half-width symbol '(' instead of the full-width one".to_string()]
+        );
+
+        // 中文数字超出范围
+        let e2 =
+            tokenize_chinese("令勾等于九九九九九九九九九九九九九九九九九九九九", 0).unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::IntegerOverflow);
+        assert_eq!((e2.location.start, e2.location.end), (12, 72));
+    }
+}
@@ -5,9 +5,259 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
+use std::fmt;
 
-#[derive(Debug)]
-pub enum Error {
-    LexerError(String),
-    ParserError(String),
+use crate::token::Location;
+
+// 诊断信息（编译错误、警告等）
+//
+// - location 为主要位置，即问题所在的地方；
+// - labels 为次要位置，用于补充说明相关的代码，比如 "括号从这里开始"；
+// - notes 为补充说明；
+// - helps 为修改建议。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub severity: Severity,
+    pub message: Box<str>, // 使用 Box<str> 以减小体积，Diagnostic 是所有解析函数的 Err 值
+    pub location: Location,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub helps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub location: Location,
+    pub message: String,
+}
+
+// 诊断代码
+//
+// 每个代码对应一个固定的字符串（见 DiagnosticCode::as_str），
+// 以便外部工具根据代码而不是根据消息文本来区分不同的问题，
+// 已发布的代码不应该再修改。
+//
+// - E01xx 词法分析错误
+// - E02xx 语法分析错误
+// - W01xx 词法分析警告
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    // 词法分析
+    InvalidChar,                 // 无效的字符
+    InvalidIdentifier,           // 无效的标识符，比如以数字 0 开头
+    UnterminatedBlockComment,    // 缺少块注释的结束符号 `*/`
+    UnterminatedDocumentComment, // 缺少文档注释的结束符号 `'''`
+    UnterminatedChar,            // 缺少字符字面量的结束符号 `'`
+    UnterminatedString,          // 缺少字符串字面量的结束符号 `"` 或者 `"""`
+    UnterminatedTemplateString,  // 缺少模板字符串字面量的结束符号 '`'
+    UnterminatedRegexp,          // 缺少正则表达式字面量的结束符号 `/`
+    UnterminatedNamedOperator,   // 缺少命名操作符的结束符号 `:`
+    UnterminatedAttribute,       // 缺少属性的结束符号 `]`
+    InvalidNumber,               // 无效的数字字面量
+    InvalidEscape,               // 无效的转义字符
+    InvalidTemplateString,       // 无效的模板字符串，比如占位符未闭合
+    IntegerOverflow,             // 整数字面量超出其数据类型的范围
+    UnknownLocale,               // 未知的本地语言，比如 `#lang=xx`
+    InvalidRegexp,               // 无效的正则表达式字面量，比如分组未闭合
+
+    // 语法分析
+    UnexpectedToken,           // 不是期望的 token
+    UnexpectedEndOfFile,       // 源文件意外结束
+    UnclosedDelimiter,         // 缺少右括号（包括圆括号、方括号、花括号以及尖括号）
+    ExpectedExpression,        // 缺少表达式
+    ExpectedIdentifier,        // 缺少标识符
+    ExpectedNewLine,           // 缺少换行符
+    InvalidLeftHandSide,       // 无效的左手边值
+    InvalidParameter,          // 无效的函数参数或者调用参数
+    InvalidPattern,            // 无效的模式表达式
+    InvalidCase,               // 无效的 branch 或者 match 的分支
+    InvalidWhichEntry,         // 无效的 which 项目
+    InvalidPropertyName,       // 无效的属性名称
+    InvalidLiteral,            // 无效的字面量
+    InvalidDataType,           // 无效的数据类型
+    MismatchedPatternFunction, // 模式函数的签名跟同名的模式函数不一致
+    DuplicateMember,           // 重复的成员名称，比如常量组、结构体的成员
+
+    // 词法分析警告
+    MixedScriptIdentifier, // 标识符混合了多种文字，比如拉丁字母和西里尔字母
+    ConfusableIdentifier,  // 标识符容易跟另一个标识符混淆，或者包含全角 ASCII 字符
+}
+
+impl DiagnosticCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticCode::InvalidChar => "E0101",
+            DiagnosticCode::InvalidIdentifier => "E0102",
+            DiagnosticCode::UnterminatedBlockComment => "E0103",
+            DiagnosticCode::UnterminatedDocumentComment => "E0104",
+            DiagnosticCode::UnterminatedChar => "E0105",
+            DiagnosticCode::UnterminatedString => "E0106",
+            DiagnosticCode::UnterminatedTemplateString => "E0107",
+            DiagnosticCode::UnterminatedRegexp => "E0108",
+            DiagnosticCode::UnterminatedNamedOperator => "E0109",
+            DiagnosticCode::UnterminatedAttribute => "E0110",
+            DiagnosticCode::InvalidNumber => "E0111",
+            DiagnosticCode::InvalidEscape => "E0112",
+            DiagnosticCode::InvalidTemplateString => "E0113",
+            DiagnosticCode::IntegerOverflow => "E0114",
+            DiagnosticCode::UnknownLocale => "E0115",
+            DiagnosticCode::InvalidRegexp => "E0116",
+
+            DiagnosticCode::UnexpectedToken => "E0201",
+            DiagnosticCode::UnexpectedEndOfFile => "E0202",
+            DiagnosticCode::UnclosedDelimiter => "E0203",
+            DiagnosticCode::ExpectedExpression => "E0204",
+            DiagnosticCode::ExpectedIdentifier => "E0205",
+            DiagnosticCode::ExpectedNewLine => "E0206",
+            DiagnosticCode::InvalidLeftHandSide => "E0207",
+            DiagnosticCode::InvalidParameter => "E0208",
+            DiagnosticCode::InvalidPattern => "E0209",
+            DiagnosticCode::InvalidCase => "E0210",
+            DiagnosticCode::InvalidWhichEntry => "E0211",
+            DiagnosticCode::InvalidPropertyName => "E0212",
+            DiagnosticCode::InvalidLiteral => "E0213",
+            DiagnosticCode::InvalidDataType => "E0214",
+            DiagnosticCode::MismatchedPatternFunction => "E0215",
+            DiagnosticCode::DuplicateMember => "E0216",
+
+            DiagnosticCode::MixedScriptIdentifier => "W0101",
+            DiagnosticCode::ConfusableIdentifier => "W0102",
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn new(
+        code: DiagnosticCode,
+        severity: Severity,
+        message: &str,
+        location: Location,
+    ) -> Self {
+        Diagnostic {
+            code,
+            severity,
+            message: message.into(),
+            location,
+            labels: vec![],
+            notes: vec![],
+            helps: vec![],
+        }
+    }
+
+    pub fn error(code: DiagnosticCode, message: &str, location: Location) -> Self {
+        Diagnostic::new(code, Severity::Error, message, location)
+    }
+
+    pub fn warning(code: DiagnosticCode, message: &str, location: Location) -> Self {
+        Diagnostic::new(code, Severity::Warning, message, location)
+    }
+
+    pub fn with_label(mut self, location: Location, message: &str) -> Self {
+        self.labels.push(Label {
+            location,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.helps.push(help.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Information => write!(f, "info"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.severity,
+            self.code.as_str(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use crate::token::Location;
+
+    use super::{Diagnostic, DiagnosticCode, Label, Severity};
+
+    fn new_location(start: usize, end: usize) -> Location {
+        Location {
+            file_id: 0,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let d1 = Diagnostic::error(
+            DiagnosticCode::UnterminatedString,
+            "unterminated string literal",
+            new_location(0, 1),
+        );
+        assert_eq!(d1.to_string(), "error[E0106]: unterminated string literal");
+
+        let d2 = Diagnostic::warning(DiagnosticCode::UnexpectedToken, "foo", new_location(0, 1));
+        assert_eq!(d2.to_string(), "warning[E0201]: foo");
+    }
+
+    #[test]
+    fn test_diagnostic_builder() {
+        let d1 = Diagnostic::error(
+            DiagnosticCode::UnclosedDelimiter,
+            "expected the right paren symbol \")\"",
+            new_location(10, 11),
+        )
+        .with_label(new_location(2, 3), "unclosed delimiter")
+        .with_note("foo")
+        .with_help("bar");
+
+        assert_eq!(d1.severity, Severity::Error);
+        assert_eq!(d1.code.as_str(), "E0203");
+        assert_eq!(
+            d1.labels,
+            vec![Label {
+                location: new_location(2, 3),
+                message: "unclosed delimiter".to_string()
+            }]
+        );
+        assert_eq!(d1.notes, vec!["foo".to_string()]);
+        assert_eq!(d1.helps, vec!["bar".to_string()]);
+    }
+
+    #[test]
+    fn test_diagnostic_size() {
+        // 所有解析函数均以 Diagnostic 作为 Err 值，其体积需要小于
+        // clippy 的 result_large_err 检查的阈值（128 字节）
+        assert!(std::mem::size_of::<Diagnostic>() < 128);
+    }
 }
@@ -0,0 +1,599 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+use std::fmt::Write;
+
+use crate::{
+    error::Diagnostic,
+    lexer::tokenize_lossless,
+    source::get_line_starts,
+    token::{Location, Token, TokenDetail, Trivia, TriviaKind},
+};
+
+// 语法高亮
+//
+// 根据词法分析的结果（而不是手工维护的正则表达式语法）把源文本划分为
+// 若干个高亮片段（HighlightSpan），然后输出为 LSP 的语义 token 数组、
+// HTML 或者带 ANSI 颜色的文本。
+//
+// 高亮片段是一个普通的列表，语义分析之后可以修改其中标识符的类别
+// （比如改为函数或者数据类型），然后再输出。
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightClass {
+    Keyword,
+    Operator,
+    Punctuation, // 括号、逗号等符号
+    Identifier,
+    Number, // 整数、浮点数、虚数以及比特数
+    Boolean,
+    Char,
+    String,
+    Template,
+    HashString,
+    Regexp,
+    Attribute, // `#[...]` 以及源文件首行的语言标记
+    DocComment,
+    Comment,
+    NamedOperator,
+    StructuralComment, // `#disable`
+    Region,            // 范围注释
+    Unknown,           // 恢复模式下无法识别的源文本
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub class: HighlightClass,
+    pub location: Location,
+}
+
+// LSP 语义 token 的类型列表（legend），输出的类型值为其中的索引
+pub const SEMANTIC_TOKEN_TYPES: [&str; 11] = [
+    "keyword",
+    "operator",
+    "variable",
+    "number",
+    "string",
+    "regexp",
+    "comment",
+    "decorator",
+    "function",
+    "enumMember",
+    "macro",
+];
+
+// LSP 语义 token 的修饰符列表，输出的修饰符值为位集合
+pub const SEMANTIC_TOKEN_MODIFIERS: [&str; 1] = ["documentation"];
+
+impl HighlightClass {
+    pub fn of_token(token: &Token) -> Option<HighlightClass> {
+        let class = match token {
+            Token::NewLine => return None,
+            Token::Identifier(_) => HighlightClass::Identifier,
+            Token::Unknown(_) => HighlightClass::Unknown,
+
+            Token::Integer(_) | Token::Float(_) | Token::Imaginary(_) | Token::Bit(_) => {
+                HighlightClass::Number
+            }
+            Token::Boolean(_) => HighlightClass::Boolean,
+            Token::Char(_) => HighlightClass::Char,
+            Token::GeneralString(_) => HighlightClass::String,
+            Token::TemplateString(_) => HighlightClass::Template,
+            Token::HashString(_) => HighlightClass::HashString,
+            Token::Regexp(..) => HighlightClass::Regexp,
+            Token::Attribute(_) => HighlightClass::Attribute,
+            Token::DocComment(_) => HighlightClass::DocComment,
+            Token::Disable => HighlightClass::StructuralComment,
+            Token::RegionStart(_) | Token::RegionEnd => HighlightClass::Region,
+            Token::NamedOperator(_) => HighlightClass::NamedOperator,
+
+            Token::LeftBrace
+            | Token::RightBrace
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::LeftParen
+            | Token::RightParen
+            | Token::Separator
+            | Token::Colon
+            | Token::Comma => HighlightClass::Punctuation,
+
+            Token::Assign
+            | Token::Pipe
+            | Token::LogicOr
+            | Token::LogicAnd
+            | Token::Equal
+            | Token::NotEqual
+            | Token::GreaterThan
+            | Token::GreaterThanOrEqual
+            | Token::LessThan
+            | Token::LessThanOrEqual
+            | Token::Concat
+            | Token::Plus
+            | Token::Minus
+            | Token::Asterisk
+            | Token::Slash
+            | Token::OptionalOr
+            | Token::OptionalAnd
+            | Token::Combine
+            | Token::Cast
+            | Token::Unwrap
+            | Token::Dot
+            | Token::Exclamation
+            | Token::At
+            | Token::Interval
+            | Token::IntervalInclusive
+            | Token::Ellipsis
+            | Token::ColonAssign => HighlightClass::Operator,
+
+            Token::Do
+            | Token::Join
+            | Token::Let
+            | Token::Fn
+            | Token::Sign
+            | Token::If
+            | Token::Then
+            | Token::Else
+            | Token::For
+            | Token::Next
+            | Token::Each
+            | Token::In
+            | Token::Branch
+            | Token::Match
+            | Token::Case
+            | Token::Default
+            | Token::Where
+            | Token::Only
+            | Token::Into
+            | Token::Regular
+            | Token::Template
+            | Token::Function
+            | Token::Type
+            | Token::Which
+            | Token::Empty
+            | Token::Pattern
+            | Token::Limit
+            | Token::Use
+            | Token::Const
+            | Token::Enum
+            | Token::Struct
+            | Token::Union
+            | Token::Trait
+            | Token::Impl
+            | Token::Alias => HighlightClass::Keyword,
+        };
+
+        Some(class)
+    }
+
+    pub fn of_trivia(kind: TriviaKind) -> Option<HighlightClass> {
+        match kind {
+            TriviaKind::Whitespace => None,
+            TriviaKind::LineComment | TriviaKind::BlockComment => Some(HighlightClass::Comment),
+            TriviaKind::LanguageHeader => Some(HighlightClass::Attribute),
+        }
+    }
+
+    // 类别的名称，同时用作 HTML 的 class 名称（加上前缀 `hl-`）
+    pub fn name(&self) -> &'static str {
+        match self {
+            HighlightClass::Keyword => "keyword",
+            HighlightClass::Operator => "operator",
+            HighlightClass::Punctuation => "punctuation",
+            HighlightClass::Identifier => "identifier",
+            HighlightClass::Number => "number",
+            HighlightClass::Boolean => "boolean",
+            HighlightClass::Char => "char",
+            HighlightClass::String => "string",
+            HighlightClass::Template => "template",
+            HighlightClass::HashString => "hash-string",
+            HighlightClass::Regexp => "regexp",
+            HighlightClass::Attribute => "attribute",
+            HighlightClass::DocComment => "doc-comment",
+            HighlightClass::Comment => "comment",
+            HighlightClass::NamedOperator => "named-operator",
+            HighlightClass::StructuralComment => "structural-comment",
+            HighlightClass::Region => "region",
+            HighlightClass::Unknown => "unknown",
+        }
+    }
+
+    // LSP 语义 token 的类型（SEMANTIC_TOKEN_TYPES 的索引）以及修饰符，
+    // 符号以及无法识别的文本不输出语义 token，返回 None
+    pub fn semantic_token_type(&self) -> Option<(u32, u32)> {
+        let token_type = match self {
+            HighlightClass::Keyword | HighlightClass::Boolean => 0,
+            HighlightClass::Operator => 1,
+            HighlightClass::Identifier => 2,
+            HighlightClass::Number => 3,
+            HighlightClass::Char | HighlightClass::String | HighlightClass::Template => 4,
+            HighlightClass::Regexp => 5,
+            HighlightClass::Comment | HighlightClass::Region => 6,
+            HighlightClass::DocComment => return Some((6, 1)),
+            HighlightClass::Attribute => 7,
+            HighlightClass::NamedOperator => 8,
+            HighlightClass::HashString => 9,
+            HighlightClass::StructuralComment => 10,
+            HighlightClass::Punctuation | HighlightClass::Unknown => return None,
+        };
+        Some((token_type, 0))
+    }
+
+    // ANSI 终端的颜色代码（SGR 参数）
+    pub fn ansi_style(&self) -> Option<&'static str> {
+        let style = match self {
+            HighlightClass::Keyword => "35",
+            HighlightClass::Operator | HighlightClass::NamedOperator => "36",
+            HighlightClass::Number | HighlightClass::Boolean => "33",
+            HighlightClass::Char | HighlightClass::String | HighlightClass::Template => "32",
+            HighlightClass::HashString => "34",
+            HighlightClass::Regexp => "31",
+            HighlightClass::Attribute => "36;1",
+            HighlightClass::DocComment => "32;3",
+            HighlightClass::Comment => "90",
+            HighlightClass::StructuralComment => "90;9",
+            HighlightClass::Region => "90;1",
+            HighlightClass::Unknown => "31;4",
+            HighlightClass::Punctuation | HighlightClass::Identifier => return None,
+        };
+        Some(style)
+    }
+}
+
+// 对源文本进行词法分析并划分高亮片段，包括注释
+pub fn highlight(text: &str, file_id: usize) -> Result<Vec<HighlightSpan>, Diagnostic> {
+    let lossless_tokens = tokenize_lossless(text, file_id)?;
+    let mut spans = vec![];
+
+    let push_trivias = |spans: &mut Vec<HighlightSpan>, trivias: &[Trivia]| {
+        for trivia in trivias {
+            if let Some(class) = HighlightClass::of_trivia(trivia.kind) {
+                spans.push(HighlightSpan {
+                    class,
+                    location: trivia.location.clone(),
+                });
+            }
+        }
+    };
+
+    for token_detail in &lossless_tokens.token_details {
+        let trivia = token_detail.trivia.as_ref();
+        if let Some(trivia) = trivia {
+            push_trivias(&mut spans, &trivia.leading);
+        }
+        spans.extend(highlight_token(token_detail));
+        if let Some(trivia) = trivia {
+            push_trivias(&mut spans, &trivia.trailing);
+        }
+    }
+    push_trivias(&mut spans, &lossless_tokens.end_of_file_trivia);
+
+    Ok(spans)
+}
+
+// 根据 token 划分高亮片段，普通模式的 token 不包括注释
+pub fn highlight_tokens(token_details: &[TokenDetail]) -> Vec<HighlightSpan> {
+    token_details.iter().filter_map(highlight_token).collect()
+}
+
+fn highlight_token(token_detail: &TokenDetail) -> Option<HighlightSpan> {
+    HighlightClass::of_token(&token_detail.token).map(|class| HighlightSpan {
+        class,
+        location: token_detail.location.clone(),
+    })
+}
+
+// 输出 LSP 的语义 token 数组
+//
+// 每个 token 由 5 个整数组成：与上一个 token 的行差、列差（同一行时）或者列号、
+// 长度、类型以及修饰符，其中列号和长度以 UTF-16 代码单元计算。
+// 跨越多行的片段（比如多行字符串和文档注释）按行拆分为多个 token。
+pub fn to_semantic_tokens(text: &str, spans: &[HighlightSpan]) -> Vec<u32> {
+    let mut data = vec![];
+    let mut previous_line = 0;
+    let mut previous_column = 0;
+
+    // 各行的开始位置只计算一次，片段按位置排序，
+    // 所以列号可以从上一个片段的位置继续累加，整个转换过程是线性的。
+    let line_starts = get_line_starts(text);
+    let mut cursor = ColumnCursor::default();
+
+    for span in spans {
+        let (token_type, modifiers) = match span.class.semantic_token_type() {
+            Some(v) => v,
+            None => continue,
+        };
+
+        for (line, start, end) in split_lines(text, &line_starts, &span.location) {
+            let column = cursor.move_to(text, line_starts[line], start);
+            let length = utf16_length(&text[start..end]);
+            if length == 0 {
+                continue;
+            }
+
+            let line = line as u32;
+            let (delta_line, delta_column) = if line == previous_line {
+                (0, column - previous_column)
+            } else {
+                (line - previous_line, column)
+            };
+
+            data.extend([delta_line, delta_column, length, token_type, modifiers]);
+            previous_line = line;
+            previous_column = column;
+        }
+    }
+
+    data
+}
+
+// 记录上一次计算列号的位置，以便在同一行里继续累加 UTF-16 列号
+#[derive(Default)]
+struct ColumnCursor {
+    line_start: usize,
+    offset: usize,
+    column: u32,
+}
+
+impl ColumnCursor {
+    // 返回 offset 在其所在行（从 line_start 开始）的 UTF-16 列号
+    fn move_to(&mut self, text: &str, line_start: usize, offset: usize) -> u32 {
+        if self.line_start != line_start || self.offset > offset {
+            self.line_start = line_start;
+            self.offset = line_start;
+            self.column = 0;
+        }
+        self.column += utf16_length(&text[self.offset..offset]);
+        self.offset = offset;
+        self.column
+    }
+}
+
+// 输出 HTML，每个片段为一个 `<span class="hl-xxx">` 元素，其他文本原样（转义之后）输出
+pub fn to_html(text: &str, spans: &[HighlightSpan]) -> String {
+    let mut html = String::new();
+    let mut pos = 0;
+
+    for span in spans {
+        escape_html(&mut html, &text[pos..span.location.start]);
+        write!(html, "<span class=\"hl-{}\">", span.class.name()).unwrap();
+        escape_html(&mut html, &text[span.location.start..span.location.end]);
+        html.push_str("</span>");
+        pos = span.location.end;
+    }
+    escape_html(&mut html, &text[pos..]);
+
+    html
+}
+
+// 输出带 ANSI 颜色的文本，跨越多行的片段每行单独设置颜色，以免影响行首的其他输出
+pub fn to_ansi(text: &str, spans: &[HighlightSpan]) -> String {
+    let mut output = String::new();
+    let mut pos = 0;
+
+    for span in spans {
+        output.push_str(&text[pos..span.location.start]);
+        let span_text = &text[span.location.start..span.location.end];
+
+        match span.class.ansi_style() {
+            Some(style) => {
+                for (index, line) in span_text.split('\n').enumerate() {
+                    if index > 0 {
+                        output.push('\n');
+                    }
+                    if !line.is_empty() {
+                        write!(output, "\x1b[{}m{}\x1b[0m", style, line).unwrap();
+                    }
+                }
+            }
+            None => output.push_str(span_text),
+        }
+
+        pos = span.location.end;
+    }
+    output.push_str(&text[pos..]);
+
+    output
+}
+
+// 把片段按行拆分，返回每一行的（行号，开始位置，结束位置），结束位置不包括行尾的换行符
+fn split_lines(
+    text: &str,
+    line_starts: &[usize],
+    location: &Location,
+) -> Vec<(usize, usize, usize)> {
+    let mut line = match line_starts.binary_search(&location.start) {
+        Ok(index) => index,
+        Err(index) => index - 1,
+    };
+
+    let mut segments = vec![];
+    let mut start = location.start;
+
+    while let Some(&next_line_start) = line_starts.get(line + 1) {
+        if location.end < next_line_start {
+            break;
+        }
+
+        // 换行符可能是 `\n`、`\r\n` 或者单独的 `\r`
+        let line_end = if text[..next_line_start].ends_with("\r\n") {
+            next_line_start - 2
+        } else {
+            next_line_start - 1
+        };
+        segments.push((line, start, line_end.max(start)));
+
+        line += 1;
+        start = next_line_start;
+    }
+    segments.push((line, start, location.end));
+
+    segments
+}
+
+fn utf16_length(text: &str) -> u32 {
+    text.chars().map(|c| c.len_utf16() as u32).sum()
+}
+
+fn escape_html(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '&' => html.push_str("&amp;"),
+            '"' => html.push_str("&quot;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::tokenize, source::SourceMap, token::Token};
+
+    use super::{
+        highlight, highlight_tokens, to_ansi, to_html, to_semantic_tokens, HighlightClass,
+        SEMANTIC_TOKEN_TYPES,
+    };
+
+    fn classes(text: &str) -> Vec<(String, &'static str)> {
+        highlight(text, 0)
+            .unwrap()
+            .iter()
+            .map(|span| {
+                (
+                    text[span.location.start..span.location.end].to_string(),
+                    span.class.name(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_class() {
+        assert_eq!(
+            HighlightClass::of_token(&Token::Let),
+            Some(HighlightClass::Keyword)
+        );
+        assert_eq!(HighlightClass::of_token(&Token::NewLine), None);
+
+        // 每个类型值都在类型列表的范围之内
+        for class in [
+            HighlightClass::Keyword,
+            HighlightClass::StructuralComment,
+            HighlightClass::DocComment,
+        ] {
+            let (token_type, _) = class.semantic_token_type().unwrap();
+            assert!((token_type as usize) < SEMANTIC_TOKEN_TYPES.len());
+        }
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            classes("let a = foo(1, \"s\") // c"),
+            vec![
+                ("let".to_string(), "keyword"),
+                ("a".to_string(), "identifier"),
+                ("=".to_string(), "operator"),
+                ("foo".to_string(), "identifier"),
+                ("(".to_string(), "punctuation"),
+                ("1".to_string(), "number"),
+                (",".to_string(), "punctuation"),
+                ("\"s\"".to_string(), "string"),
+                (")".to_string(), "punctuation"),
+                ("// c".to_string(), "comment"),
+            ]
+        );
+
+        assert_eq!(
+            classes("#[test]\n#lang ~/a+/i `t` 4'b1010 :op: /* b */ #disable"),
+            vec![
+                ("#[test]".to_string(), "attribute"),
+                ("#lang".to_string(), "hash-string"),
+                ("~/a+/i".to_string(), "regexp"),
+                ("`t`".to_string(), "template"),
+                ("4'b1010".to_string(), "number"),
+                (":op:".to_string(), "named-operator"),
+                ("/* b */".to_string(), "comment"),
+                ("#disable".to_string(), "structural-comment"),
+            ]
+        );
+
+        // 普通模式的 token 不包括注释
+        let tokens = tokenize("a // b").unwrap();
+        assert_eq!(highlight_tokens(&tokens).len(), 1);
+    }
+
+    #[test]
+    fn test_to_semantic_tokens() {
+        let text = "let 中 = \"a\nbc\"\n  '''\n  doc\n  '''";
+        let spans = highlight(text, 0).unwrap();
+        assert_eq!(
+            to_semantic_tokens(text, &spans),
+            vec![
+                0, 0, 3, 0, 0, // let
+                0, 4, 1, 2, 0, // 中
+                0, 2, 1, 1, 0, // =
+                0, 2, 2, 4, 0, // "a
+                1, 0, 3, 4, 0, // bc"
+                1, 2, 3, 6, 1, // '''
+                1, 0, 5, 6, 1, //   doc
+                1, 0, 5, 6, 1, //   '''
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_semantic_tokens_line_breaks() {
+        // 单独的 `\r` 以及 `\r\n` 都是换行符，行号跟 SourceMap 保持一致
+        let text = "let a\rlet b\r\n\"x\ry\"";
+        let spans = highlight(text, 0).unwrap();
+        assert_eq!(
+            to_semantic_tokens(text, &spans),
+            vec![
+                0, 0, 3, 0, 0, // let
+                0, 4, 1, 2, 0, // a
+                1, 0, 3, 0, 0, // let
+                0, 4, 1, 2, 0, // b
+                1, 0, 2, 4, 0, // "x
+                1, 0, 2, 4, 0, // y"
+            ]
+        );
+
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("a.xuan", text);
+        let b = spans
+            .iter()
+            .find(|s| &text[s.location.start..s.location.end] == "b");
+        assert_eq!(
+            source_map
+                .line_column(file_id, b.unwrap().location.start)
+                .unwrap()
+                .line,
+            1
+        );
+    }
+
+    #[test]
+    fn test_to_html_and_ansi() {
+        let text = "if a<b then \"x&y\"";
+        let spans = highlight(text, 0).unwrap();
+        assert_eq!(
+            to_html(text, &spans),
+            "<span class=\"hl-keyword\">if</span> <span class=\"hl-identifier\">a</span>\
+            <span class=\"hl-operator\">&lt;</span><span class=\"hl-identifier\">b</span> \
+            <span class=\"hl-keyword\">then</span> <span class=\"hl-string\">&quot;x&amp;y&quot;</span>"
+        );
+
+        let text2 = "a = 1 // c";
+        let spans2 = highlight(text2, 0).unwrap();
+        assert_eq!(
+            to_ansi(text2, &spans2),
+            "a \x1b[36m=\x1b[0m \x1b[33m1\x1b[0m \x1b[90m// c\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_security::{skeleton, RestrictionLevel, RestrictionLevelDetection};
+use unicode_xid::UnicodeXID;
+
+use crate::{
+    error::{Diagnostic, DiagnosticCode},
+    token::{Token, TokenDetail},
+};
+
+// Unicode 标识符
+//
+// 标识符的语法遵循 UAX#31（https://www.unicode.org/reports/tr31/）：
+//
+// - 首个字符为 XID_Start 或者下划线；
+// - 其余字符为 XID_Continue。
+//
+// 标识符按 NFC 规范化，以便看起来相同的名称（比如 `é` 的组合形式 `e\u{301}`
+// 以及预组合形式 `\u{e9}`）是同一个标识符。
+//
+// 另外根据 UTS#39（https://www.unicode.org/reports/tr39/）检查混合了多种文字的标识符
+// 以及容易混淆的标识符（比如拉丁字母 `a` 和西里尔字母 `а`、全角 ASCII 字符），
+// 这些检查只产生警告。
+
+// 可以作为标识符或者关键字的首位的文字
+pub fn is_identifier_start(c: char) -> bool {
+    if c.is_ascii() {
+        matches!(c, 'a'..='z' | 'A'..='Z' | '_')
+    } else {
+        c.is_xid_start()
+    }
+}
+
+// 可以作为标识符或者关键字的文字（数字、字母、中文文字、组合符号等）
+pub fn is_identifier_continue(c: char) -> bool {
+    if c.is_ascii() {
+        matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9')
+    } else {
+        c.is_xid_continue()
+    }
+}
+
+// NFC 规范化，ASCII 文本以及已经规范化的文本不需要复制
+pub fn normalize_identifier(name: &str) -> Cow<'_, str> {
+    if name.is_ascii() || is_nfc_quick(name.chars()) == IsNormalized::Yes {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(name.nfc().collect())
+    }
+}
+
+// 检查源文件里的标识符，返回警告
+//
+// - 包含全角 ASCII 字符的标识符；
+// - 混合了多种文字的标识符，中文、日文、韩文跟拉丁字母的组合是允许的（即 UTS#39 的 Highly Restrictive 级别）；
+// - 跟另一个标识符容易混淆（即两者的 skeleton 相同）的标识符。
+//
+// 每个标识符只检查第一次出现的位置，lexer::tokenize_recovering 会把这些警告
+// 跟词法错误一起返回。
+pub fn check_identifiers(token_details: &[TokenDetail]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut skeletons: HashMap<String, &TokenDetail> = HashMap::new();
+    let mut checked_names: HashSet<&str> = HashSet::new();
+
+    for token_detail in token_details {
+        let name = match &token_detail.token {
+            Token::Identifier(name) => name.as_ref(),
+            _ => continue,
+        };
+
+        if !checked_names.insert(name) {
+            continue;
+        }
+
+        // 全角 ASCII 字符不在 UTS#39 的易混淆字符表里，所以先转换为半角字符再计算 skeleton
+        let half_width: String = name.chars().map(to_half_width_ascii).collect();
+
+        if name.is_ascii() {
+            // ASCII 标识符只需检查是否跟其他非 ASCII 标识符混淆
+        } else if half_width != name {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::ConfusableIdentifier,
+                    &format!("identifier `{}` contains full-width characters", name),
+                    token_detail.location.clone(),
+                )
+                .with_help(&format!(
+                    "use the half-width characters `{}` instead",
+                    half_width
+                )),
+            );
+        } else if name.detect_restriction_level() > RestrictionLevel::HighlyRestrictive {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::MixedScriptIdentifier,
+                    &format!("identifier `{}` mixes characters from different scripts", name),
+                    token_detail.location.clone(),
+                )
+                .with_note("letters from different scripts may look the same, e.g. Latin `a` and Cyrillic `а`"),
+            );
+        }
+
+        let name_skeleton: String = skeleton(&half_width).collect();
+        match skeletons.get(&name_skeleton) {
+            // 两个 ASCII 标识符的 skeleton 可能相同（比如 `rn` 与 `m`），但它们不视为容易混淆
+            Some(other) if name.is_ascii() && other.token.to_string().is_ascii() => {}
+            Some(other) => {
+                let other_name = other.token.to_string();
+                diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticCode::ConfusableIdentifier,
+                        &format!("identifier `{}` is confusable with `{}`", name, other_name),
+                        token_detail.location.clone(),
+                    )
+                    .with_label(
+                        other.location.clone(),
+                        &format!("`{}` is defined or used here", other_name),
+                    ),
+                );
+            }
+            None => {
+                skeletons.insert(name_skeleton, token_detail);
+            }
+        }
+    }
+
+    diagnostics
+}
+
+// 全角 ASCII 字符，即 `！` 到 `～`
+fn is_full_width_ascii(c: char) -> bool {
+    matches!(c, '\u{ff01}'..='\u{ff5e}')
+}
+
+fn to_half_width_ascii(c: char) -> char {
+    if is_full_width_ascii(c) {
+        char::from_u32(c as u32 - 0xfee0).unwrap()
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::DiagnosticCode, lexer::tokenize};
+
+    use super::{
+        check_identifiers, is_identifier_continue, is_identifier_start, normalize_identifier,
+    };
+
+    fn check(text: &str) -> Vec<(DiagnosticCode, String)> {
+        check_identifiers(&tokenize(text).unwrap())
+            .into_iter()
+            .map(|d| (d.code, d.message.into()))
+            .collect()
+    }
+
+    #[test]
+    fn test_identifier_chars() {
+        assert!(is_identifier_start('a'));
+        assert!(is_identifier_start('_'));
+        assert!(is_identifier_start('中'));
+        assert!(is_identifier_start('α'));
+        assert!(!is_identifier_start('1'));
+        assert!(!is_identifier_start('\u{301}')); // 组合符号不能作为首个字符
+        assert!(!is_identifier_start('🐱'));
+
+        assert!(is_identifier_continue('1'));
+        assert!(is_identifier_continue('\u{301}'));
+        assert!(is_identifier_continue('·')); // U+00B7 属于 XID_Continue
+        assert!(!is_identifier_continue('¢'));
+        assert!(!is_identifier_continue('、'));
+    }
+
+    #[test]
+    fn test_normalize_identifier() {
+        assert_eq!(normalize_identifier("foo"), "foo");
+        assert_eq!(normalize_identifier("caf\u{e9}"), "caf\u{e9}");
+        assert_eq!(normalize_identifier("cafe\u{301}"), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_check_identifiers() {
+        assert_eq!(check("let 数量 = count + 名称Id"), vec![]);
+        assert_eq!(check("let café = 1"), vec![]);
+        assert_eq!(check("rn + m"), vec![]);
+
+        // 拉丁字母 `a` 和西里尔字母 `а`
+        assert_eq!(
+            check("let a = 1\nlet \u{430} = 2"),
+            vec![(
+                DiagnosticCode::ConfusableIdentifier,
+                "identifier `\u{430}` is confusable with `a`".to_string()
+            )]
+        );
+
+        // 混合了拉丁字母和西里尔字母
+        assert_eq!(
+            check("p\u{430}y p\u{430}y"),
+            vec![(
+                DiagnosticCode::MixedScriptIdentifier,
+                "identifier `p\u{430}y` mixes characters from different scripts".to_string()
+            )]
+        );
+
+        // 全角 ASCII 字符
+        let diagnostics = check_identifiers(&tokenize("ｆｏｏ + foo").unwrap());
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            &*diagnostics[0].message,
+            "identifier `ｆｏｏ` contains full-width characters"
+        );
+        assert_eq!(
+            diagnostics[0].helps,
+            vec!["use the half-width characters `foo` instead"]
+        );
+        assert_eq!(
+            &*diagnostics[1].message,
+            "identifier `foo` is confusable with `ｆｏｏ`"
+        );
+    }
+}
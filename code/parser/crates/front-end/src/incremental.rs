@@ -0,0 +1,333 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+use std::ops::Range;
+
+use crate::{
+    error::Diagnostic,
+    keyword::{get_locale_header_length, KeywordTables},
+    lexer::{tokenize_with_locale, Lexer},
+    token::{Token, TokenDetail},
+};
+
+// 一个 token 在其结束位置之后最多还会读取的字节数（比如数字后面的 `..` 以及 `'''`），
+// 编辑位置在这个范围之内的 token 也需要重新分析
+const MAX_LOOKAHEAD: usize = 4;
+
+// 编辑器对源文本的一次修改
+//
+// 把旧源文本 range 范围（字节偏移量）之内的文本替换为 new_text，
+// 插入时 range 为空，删除时 new_text 为空。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, new_text: &str) -> Self {
+        TextEdit {
+            range,
+            new_text: new_text.to_string(),
+        }
+    }
+
+    // 返回修改之后的源文本，range 超出范围或者不在字符边界时会 panic
+    pub fn apply(&self, text: &str) -> String {
+        let mut new_text = text.to_string();
+        new_text.replace_range(self.range.clone(), &self.new_text);
+        new_text
+    }
+}
+
+// 增量词法分析
+//
+// 根据旧源文本的 token（即 tokenize_with_locale 的结果）以及一次修改，
+// 只重新分析受影响的那一段源文本，返回修改之后的源文本的所有 token，
+// 结果跟使用相同的 keyword_tables 和 package_locale 对修改之后的源文本
+// 重新调用 tokenize_with_locale 完全相同。
+//
+// 因为词法分析器在每个 token 的开始位置除了剩余的源文本之外没有其他状态，所以：
+//
+// - 从编辑位置之前的某个 token（锚点）开始重新分析即可得到正确的结果；
+// - 当重新分析得到的 token 越过了编辑的范围，并且跟某个旧 token 的开始位置以及内容相同时，
+//   后面的 token 必然跟旧 token 相同，只需移动它们的位置即可。
+//
+// 所以打开或者关闭字符串、注释的修改会一直重新分析到两者重新同步的位置（或者源文本末尾）。
+//
+// 修改了首行的语言标记时关键字表可能改变，此时重新分析整个源文本。
+pub fn retokenize(
+    old_tokens: &[TokenDetail],
+    old_text: &str,
+    edit: &TextEdit,
+    file_id: usize,
+    keyword_tables: &KeywordTables,
+    package_locale: Option<&str>,
+) -> Result<Vec<TokenDetail>, Diagnostic> {
+    let new_text = edit.apply(old_text);
+
+    let header_length = get_locale_header_length(old_text)
+        .unwrap_or(0)
+        .max(get_locale_header_length(&new_text).unwrap_or(0));
+    if edit.range.start <= header_length {
+        return tokenize_with_locale(&new_text, file_id, keyword_tables, package_locale);
+    }
+
+    let anchor_index = find_anchor_index(old_tokens, edit.range.start);
+    let anchor_offset = anchor_index.map_or(0, |index| old_tokens[index].location.start);
+    let reused_length = anchor_index.unwrap_or(0);
+
+    // 旧源文本当中编辑范围之后的文本，在新源文本中的位置的偏移量
+    let old_edit_end = edit.range.end;
+    let new_edit_end = edit.range.start + edit.new_text.len();
+
+    let mut token_details: Vec<TokenDetail> = old_tokens[..reused_length].to_vec();
+
+    // 锚点之后的源文本不包含语言标记，所以需要明确指定关键字表
+    let keywords = keyword_tables.select(&new_text, file_id, package_locale)?;
+    let lexer = Lexer::with_base_offset(&new_text[anchor_offset..], file_id, anchor_offset)
+        .with_keywords(keywords);
+
+    for result in lexer {
+        let token_detail = result?;
+
+        if token_detail.location.start >= new_edit_end {
+            let old_start = token_detail.location.start - new_edit_end + old_edit_end;
+            if let Some(sync_index) = find_sync_index(old_tokens, old_start, &token_detail.token) {
+                // 重新同步，剩余的 token 跟旧 token 相同
+                token_details.extend(old_tokens[sync_index..].iter().map(|old| {
+                    let mut shifted = old.clone();
+                    shifted.location.start = old.location.start - old_edit_end + new_edit_end;
+                    shifted.location.end = old.location.end - old_edit_end + new_edit_end;
+                    shifted
+                }));
+                return Ok(token_details);
+            }
+        }
+
+        token_details.push(token_detail);
+    }
+
+    Ok(token_details)
+}
+
+// 查找重新分析的锚点，即开始重新分析的 token 的索引，返回 None 表示从头开始分析
+//
+// 锚点是第一个受编辑影响的 token 之前的一个 token，不能是换行符以及范围注释，
+// 因为它们的位置可能处于连续的行注释之中，而连续的行注释是否组成范围注释
+// 取决于后面的行注释。
+fn find_anchor_index(old_tokens: &[TokenDetail], edit_start: usize) -> Option<usize> {
+    let first_affected_index =
+        old_tokens.partition_point(|detail| detail.location.end + MAX_LOOKAHEAD < edit_start);
+
+    old_tokens[..first_affected_index]
+        .iter()
+        .rposition(|detail| {
+            !matches!(
+                detail.token,
+                Token::NewLine | Token::RegionStart(_) | Token::RegionEnd
+            )
+        })
+}
+
+// 查找开始位置为 old_start 且内容相同的旧 token 的索引
+fn find_sync_index(old_tokens: &[TokenDetail], old_start: usize, token: &Token) -> Option<usize> {
+    let index = old_tokens.partition_point(|detail| detail.location.start < old_start);
+    match old_tokens.get(index) {
+        Some(detail) if detail.location.start == old_start && &detail.token == token => Some(index),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::Diagnostic,
+        keyword::{KeywordTable, KeywordTables},
+        lexer::tokenize_with_locale,
+        testing::Random,
+        token::{Token, TokenDetail},
+    };
+
+    use super::{retokenize, TextEdit};
+
+    fn retokenize_text(old_text: &str, edit: &TextEdit) -> Result<Vec<TokenDetail>, Diagnostic> {
+        let keyword_tables = KeywordTables::new();
+        let old_tokens = tokenize_with_locale(old_text, 1, &keyword_tables, None).unwrap();
+        retokenize(&old_tokens, old_text, edit, 1, &keyword_tables, None)
+    }
+
+    fn assert_same_as_full_tokenize(old_text: &str, edit: &TextEdit) {
+        let new_text = edit.apply(old_text);
+        assert_eq!(
+            retokenize_text(old_text, edit),
+            tokenize_with_locale(&new_text, 1, &KeywordTables::new(), None),
+            "old text: {:?}, edit: {:?}",
+            old_text,
+            edit
+        );
+    }
+
+    #[test]
+    fn test_text_edit() {
+        assert_eq!(TextEdit::new(4..4, "b + ").apply("let a"), "let b + a");
+        assert_eq!(TextEdit::new(0..4, "").apply("let a"), "a");
+        assert_eq!(
+            TextEdit::new(4..5, "中文").apply("let a = 1"),
+            "let 中文 = 1"
+        );
+    }
+
+    #[test]
+    fn test_retokenize() {
+        // 修改标识符，后面的 token 移动位置
+        let t1 = retokenize_text("let a = 1\nlet b = 2", &TextEdit::new(4..5, "abc")).unwrap();
+        assert_eq!(t1[1].token.to_string(), "abc");
+        assert_eq!((t1[6].location.start, t1[6].location.end), (16, 17));
+
+        assert_same_as_full_tokenize("let a = 1\nlet b = 2", &TextEdit::new(4..5, "abc"));
+        assert_same_as_full_tokenize("foo(1, 2)", &TextEdit::new(9..9, " + 3"));
+        assert_same_as_full_tokenize("foo(1, 2)", &TextEdit::new(0..3, ""));
+
+        // 合并以及拆分 token
+        assert_same_as_full_tokenize("a b", &TextEdit::new(1..2, ""));
+        assert_same_as_full_tokenize("ab", &TextEdit::new(1..1, " "));
+        assert_same_as_full_tokenize("1 .. 2", &TextEdit::new(1..2, ""));
+        assert_same_as_full_tokenize(":foo bar:", &TextEdit::new(4..5, ""));
+        assert_same_as_full_tokenize("x - > y", &TextEdit::new(3..4, ""));
+
+        // 语言标记
+        assert_same_as_full_tokenize("#lang=zh_CN\n令 a\n令 b", &TextEdit::new(16..17, "c"));
+        assert_same_as_full_tokenize("#lang=en\n令 a\nlet b", &TextEdit::new(6..8, "zh_CN"));
+        assert_same_as_full_tokenize("令 a\n令 b", &TextEdit::new(0..0, "#lang=zh_CN\n"));
+    }
+
+    #[test]
+    fn test_retokenize_strings_and_comments() {
+        // 打开以及关闭字符串
+        assert_same_as_full_tokenize("a \"b\" c \"d\" e", &TextEdit::new(2..3, ""));
+        assert_same_as_full_tokenize("a b\" c \"d", &TextEdit::new(2..2, "\""));
+        assert_same_as_full_tokenize("let s = \"foo bar\"\nx", &TextEdit::new(16..17, ""));
+
+        // 打开以及关闭块注释
+        assert_same_as_full_tokenize("a /* b */ c d", &TextEdit::new(7..9, ""));
+        assert_same_as_full_tokenize("a /* b c */ d", &TextEdit::new(2..4, ""));
+        assert_same_as_full_tokenize("a b c d", &TextEdit::new(2..2, "/*"));
+        assert_same_as_full_tokenize("a b c d */ e", &TextEdit::new(2..2, "/*"));
+
+        // 行注释以及范围注释
+        assert_same_as_full_tokenize("x\n// a\n// b\ny", &TextEdit::new(10..11, "==="));
+        assert_same_as_full_tokenize("x\n// a\n// ===\ny", &TextEdit::new(10..13, "b"));
+        assert_same_as_full_tokenize("x\n// a\ny\nz", &TextEdit::new(7..7, "// "));
+
+        // 文档注释
+        assert_same_as_full_tokenize("a\n'''\ndoc\n'''\nb", &TextEdit::new(2..5, ""));
+
+        // 编辑之后出错
+        let e1 = retokenize_text("a \"b\" c", &TextEdit::new(4..5, "")).unwrap_err();
+        assert_eq!(e1.location.start, 2);
+    }
+
+    const FRAGMENTS: [&str; 40] = [
+        " ", " ", "\n", "\r\n", "\t", "a", "foo", "let", "if", "1", "23", ".", "..", "1.5", "e",
+        "\"", "\"\"\"", "'", "'''", "`", "{", "}", "(", ")", "/", "*", "/*", "*/", "//", "// ===",
+        "// ---", "=", "-", ">", ":", "#", "~/", "\\", "中", "x'b1",
+    ];
+
+    fn random_text(random: &mut Random, fragments: &[&str], max_fragments: usize) -> String {
+        let count = random.below(max_fragments + 1);
+        (0..count)
+            .map(|_| fragments[random.below(fragments.len())])
+            .collect()
+    }
+
+    fn random_char_boundary(random: &mut Random, text: &str) -> usize {
+        let boundaries: Vec<usize> = text
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain(std::iter::once(text.len()))
+            .collect();
+        boundaries[random.below(boundaries.len())]
+    }
+
+    // 对随机的源文本作随机的修改，增量分析的结果应该跟完整分析的结果相同
+    fn assert_retokenize_property(
+        random: &mut Random,
+        fragments: &[&str],
+        keyword_tables: &KeywordTables,
+        package_locale: Option<&str>,
+    ) {
+        let mut checked_count = 0;
+
+        while checked_count < 3000 {
+            let old_text = random_text(random, fragments, 24);
+            let old_tokens =
+                match tokenize_with_locale(&old_text, 1, keyword_tables, package_locale) {
+                    Ok(tokens) => tokens,
+                    Err(_) => continue, // 只有成功的分析结果才有 token 可以复用
+                };
+
+            let start = random_char_boundary(random, &old_text);
+            let end = start + random_char_boundary(random, &old_text[start..]);
+            let edit = TextEdit::new(start..end, &random_text(random, fragments, 3));
+            let new_text = edit.apply(&old_text);
+
+            assert_eq!(
+                retokenize(
+                    &old_tokens,
+                    &old_text,
+                    &edit,
+                    1,
+                    keyword_tables,
+                    package_locale
+                ),
+                tokenize_with_locale(&new_text, 1, keyword_tables, package_locale),
+                "old text: {:?}, edit: {:?}",
+                old_text,
+                edit
+            );
+            checked_count += 1;
+        }
+    }
+
+    #[test]
+    fn test_retokenize_property() {
+        let mut random = Random::new(0x2545_f491_4f6c_dd1d);
+        assert_retokenize_property(&mut random, &FRAGMENTS, &KeywordTables::new(), None);
+    }
+
+    #[test]
+    fn test_retokenize_property_with_locale() {
+        // 包的语言为中文，并且注册了一种自定义的语言，
+        // 源文本可以通过语言标记切换到其他语言
+        let mut keyword_tables = KeywordTables::new();
+        keyword_tables.register(
+            KeywordTable::new("xx")
+                .with_keyword("lat", Token::Let)
+                .with_keyword("iff", Token::If),
+        );
+
+        let mut fragments = FRAGMENTS.to_vec();
+        fragments.extend([
+            "令",
+            "如果",
+            "那么",
+            "否则",
+            "等于",
+            "以下",
+            "以上",
+            "甲",
+            "lat",
+            "iff",
+            "#lang=en\n",
+            "#lang=xx\n",
+        ]);
+
+        let mut random = Random::new(0x9e37_79b9_7f4a_7c15);
+        assert_retokenize_property(&mut random, &fragments, &keyword_tables, Some("zh_CN"));
+    }
+}
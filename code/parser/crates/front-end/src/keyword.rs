@@ -0,0 +1,339 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+use std::{collections::HashMap, rc::Rc};
+
+use crate::error::{Diagnostic, DiagnosticCode};
+use crate::token::Location;
+use crate::token::Token;
+
+// 默认的本地语言代码（locale code）
+pub const DEFAULT_LOCALE: &str = "en";
+
+// 源文件首行的语言标记，比如 `#lang=zh_CN`
+const LOCALE_HEADER_PREFIX: &str = "#lang=";
+
+// 关键字表
+//
+// 每种本地语言（locale）有各自的关键字表，词法分析器根据关键字表
+// 把词语转换为关键字 token，不在表里的词语则作为标识符。
+//
+// 关键字表也可以包含字面量（比如 `true`）以及符号（比如中文的 `以上`），
+// 只要它们由标识符文字组成。
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordTable {
+    locale: String,
+    keywords: HashMap<String, Token>,
+    max_keyword_length: usize, // 关键字的最大长度（字符数）
+}
+
+impl KeywordTable {
+    pub fn new(locale: &str) -> Self {
+        KeywordTable {
+            locale: locale.to_string(),
+            keywords: HashMap::new(),
+            max_keyword_length: 0,
+        }
+    }
+
+    pub fn with_keyword(mut self, word: &str, token: Token) -> Self {
+        self.max_keyword_length = self.max_keyword_length.max(word.chars().count());
+        self.keywords.insert(word.to_string(), token);
+        self
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn max_keyword_length(&self) -> usize {
+        self.max_keyword_length
+    }
+
+    // 用于检测词语是关键字还是标识符
+    pub fn lookup(&self, word: &str) -> Option<Token> {
+        self.keywords.get(word).cloned()
+    }
+
+    // 英文关键字表
+    pub fn english() -> Self {
+        KeywordTable::new("en")
+            // 字面量
+            .with_keyword("true", Token::Boolean(true))
+            .with_keyword("false", Token::Boolean(false))
+            // 关键字
+            .with_keyword("do", Token::Do)
+            .with_keyword("join", Token::Join)
+            .with_keyword("let", Token::Let)
+            .with_keyword("fn", Token::Fn)
+            .with_keyword("sign", Token::Sign)
+            .with_keyword("if", Token::If)
+            .with_keyword("then", Token::Then)
+            .with_keyword("else", Token::Else)
+            .with_keyword("for", Token::For)
+            .with_keyword("next", Token::Next)
+            .with_keyword("each", Token::Each)
+            .with_keyword("in", Token::In)
+            .with_keyword("branch", Token::Branch)
+            .with_keyword("match", Token::Match)
+            .with_keyword("case", Token::Case)
+            .with_keyword("default", Token::Default)
+            .with_keyword("where", Token::Where)
+            .with_keyword("only", Token::Only)
+            .with_keyword("into", Token::Into)
+            .with_keyword("regular", Token::Regular)
+            .with_keyword("template", Token::Template)
+            .with_keyword("function", Token::Function)
+            .with_keyword("type", Token::Type)
+            .with_keyword("which", Token::Which)
+            .with_keyword("empty", Token::Empty)
+            .with_keyword("pattern", Token::Pattern)
+            .with_keyword("limit", Token::Limit)
+            .with_keyword("use", Token::Use)
+            .with_keyword("const", Token::Const)
+            .with_keyword("enum", Token::Enum)
+            .with_keyword("struct", Token::Struct)
+            .with_keyword("union", Token::Union)
+            .with_keyword("trait", Token::Trait)
+            .with_keyword("impl", Token::Impl)
+            .with_keyword("alias", Token::Alias)
+    }
+
+    // 简体中文关键字表，见 docs/reference/cn-spec.md
+    //
+    // 注：因为 parser 需要明确的代码块开始和结束符号，
+    // 所以 `以下` 和 `以上` 分别对应 `{` 和 `}`。
+    pub fn chinese() -> Self {
+        KeywordTable::new("zh_CN")
+            // 字面量
+            .with_keyword("真", Token::Boolean(true))
+            .with_keyword("假", Token::Boolean(false))
+            // 符号
+            .with_keyword("以下", Token::LeftBrace)
+            .with_keyword("以上", Token::RightBrace)
+            .with_keyword("予", Token::Assign)
+            .with_keyword("等于", Token::Assign)
+            // 关键字
+            .with_keyword("执行", Token::Do)
+            .with_keyword("汇合", Token::Join)
+            .with_keyword("令", Token::Let)
+            .with_keyword("让", Token::Let)
+            .with_keyword("赋值", Token::Let)
+            .with_keyword("如果", Token::If)
+            .with_keyword("那么", Token::Then)
+            .with_keyword("否则", Token::Else)
+            .with_keyword("循环", Token::For)
+            .with_keyword("下一轮", Token::Next)
+            .with_keyword("遍历", Token::Each)
+            .with_keyword("属于", Token::In)
+            .with_keyword("分支", Token::Branch)
+            .with_keyword("匹配", Token::Match)
+            .with_keyword("情况", Token::Case)
+            .with_keyword("默认", Token::Default)
+            .with_keyword("其中", Token::Where)
+            .with_keyword("仅当", Token::Only)
+            .with_keyword("转为", Token::Into)
+            .with_keyword("正则", Token::Regular)
+            .with_keyword("模板", Token::Template)
+            .with_keyword("函数", Token::Function)
+            .with_keyword("类型", Token::Type)
+            .with_keyword("限制", Token::Limit)
+            .with_keyword("模式", Token::Pattern)
+            .with_keyword("使用", Token::Use)
+            .with_keyword("常量", Token::Const)
+            .with_keyword("枚举", Token::Enum)
+            .with_keyword("结构", Token::Struct)
+            .with_keyword("联合", Token::Union)
+            .with_keyword("特性", Token::Trait)
+            .with_keyword("实现", Token::Impl)
+            .with_keyword("别名", Token::Alias)
+    }
+}
+
+thread_local! {
+    // 默认的（英文）关键字表，由所有未指定关键字表的词法分析器共享
+    static DEFAULT_KEYWORD_TABLE: Rc<KeywordTable> = Rc::new(KeywordTable::english());
+
+    // 内置的关键字表集合（`en` 和 `zh_CN`）
+    static BUILTIN_KEYWORD_TABLES: KeywordTables = KeywordTables::new();
+}
+
+pub fn default_keyword_table() -> Rc<KeywordTable> {
+    DEFAULT_KEYWORD_TABLE.with(Rc::clone)
+}
+
+// 根据源文件首行的语言标记从内置的关键字表里选择关键字表，
+// 源文件没有语言标记时返回默认的英文关键字表。
+pub fn select_builtin_keyword_table(
+    text: &str,
+    file_id: usize,
+) -> Result<Rc<KeywordTable>, Diagnostic> {
+    BUILTIN_KEYWORD_TABLES.with(|tables| tables.select(text, file_id, None))
+}
+
+// 关键字表的集合
+//
+// 默认包含 `en` 和 `zh_CN` 两种语言，可以通过 register 方法添加其他语言
+// 或者替换已有的语言。
+#[derive(Debug, Clone)]
+pub struct KeywordTables {
+    tables: HashMap<String, Rc<KeywordTable>>,
+}
+
+impl KeywordTables {
+    pub fn new() -> Self {
+        let mut tables = KeywordTables {
+            tables: HashMap::new(),
+        };
+
+        tables
+            .tables
+            .insert(DEFAULT_LOCALE.to_string(), default_keyword_table());
+        tables.register(KeywordTable::chinese());
+        tables
+    }
+
+    pub fn register(&mut self, table: KeywordTable) {
+        self.tables
+            .insert(table.locale().to_string(), Rc::new(table));
+    }
+
+    pub fn get(&self, locale: &str) -> Option<Rc<KeywordTable>> {
+        self.tables.get(locale).cloned()
+    }
+
+    // 为源文件选择关键字表
+    //
+    // 源文件首行的语言标记（`#lang=zh_CN`）优先，其次是源文件所在的包的语言，
+    // 参数 package_locale 为 None 时使用默认语言 `en`。
+    pub fn select(
+        &self,
+        text: &str,
+        file_id: usize,
+        package_locale: Option<&str>,
+    ) -> Result<Rc<KeywordTable>, Diagnostic> {
+        let (locale, location) = match read_locale_header(text) {
+            Some(locale) => {
+                let start = LOCALE_HEADER_PREFIX.len();
+                (
+                    locale,
+                    Location {
+                        file_id,
+                        start,
+                        end: start + locale.len(),
+                    },
+                )
+            }
+            None => (
+                package_locale.unwrap_or(DEFAULT_LOCALE),
+                Location {
+                    file_id,
+                    start: 0,
+                    end: 0,
+                },
+            ),
+        };
+
+        self.get(locale).ok_or_else(|| {
+            let mut locales: Vec<&str> = self.tables.keys().map(String::as_str).collect();
+            locales.sort_unstable();
+
+            Diagnostic::error(
+                DiagnosticCode::UnknownLocale,
+                &format!("unknown locale \"{}\"", locale),
+                location,
+            )
+            .with_note(&format!("available locales: {}", locales.join(", ")))
+        })
+    }
+}
+
+impl Default for KeywordTables {
+    fn default() -> Self {
+        KeywordTables::new()
+    }
+}
+
+// 读取源文件首行的语言标记，返回语言代码
+pub fn read_locale_header(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix(LOCALE_HEADER_PREFIX)?;
+    let end_pos = rest.find(['\r', '\n']).unwrap_or(rest.len());
+    Some(rest[..end_pos].trim_end())
+}
+
+// 语言标记的长度（字节数），不包括行尾的换行符，源文本没有语言标记时返回 None
+pub fn get_locale_header_length(text: &str) -> Option<usize> {
+    read_locale_header(text)?;
+    Some(text.find(['\r', '\n']).unwrap_or(text.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::DiagnosticCode, token::Token};
+
+    use super::{get_locale_header_length, read_locale_header, KeywordTable, KeywordTables};
+
+    #[test]
+    fn test_keyword_table() {
+        let en = KeywordTable::english();
+        assert_eq!(en.locale(), "en");
+        assert_eq!(en.lookup("let"), Some(Token::Let));
+        assert_eq!(en.lookup("true"), Some(Token::Boolean(true)));
+        assert_eq!(en.lookup("令"), None);
+        assert_eq!(en.max_keyword_length(), 8);
+
+        let zh = KeywordTable::chinese();
+        assert_eq!(zh.lookup("令"), Some(Token::Let));
+        assert_eq!(zh.lookup("以上"), Some(Token::RightBrace));
+        assert_eq!(zh.lookup("let"), None);
+        assert_eq!(zh.max_keyword_length(), 3);
+    }
+
+    #[test]
+    fn test_keyword_tables() {
+        let mut tables = KeywordTables::new();
+        assert_eq!(tables.get("en").unwrap().lookup("if"), Some(Token::If));
+        assert_eq!(tables.get("zh_CN").unwrap().lookup("如果"), Some(Token::If));
+        assert!(tables.get("fr").is_none());
+
+        // 注册新的语言
+        tables.register(KeywordTable::new("fr").with_keyword("si", Token::If));
+        assert_eq!(tables.get("fr").unwrap().lookup("si"), Some(Token::If));
+    }
+
+    #[test]
+    fn test_select_keyword_table() {
+        let tables = KeywordTables::new();
+
+        // 首行的语言标记
+        assert_eq!(read_locale_header("#lang=zh_CN\n令"), Some("zh_CN"));
+        assert_eq!(get_locale_header_length("#lang=zh_CN\r\n令"), Some(11));
+        assert_eq!(read_locale_header("let a = 1"), None);
+
+        let t1 = tables.select("#lang=zh_CN\n令", 0, None).unwrap();
+        assert_eq!(t1.locale(), "zh_CN");
+
+        // 语言标记优先于包的语言
+        let t2 = tables.select("#lang=en\nlet", 0, Some("zh_CN")).unwrap();
+        assert_eq!(t2.locale(), "en");
+
+        // 包的语言
+        let t3 = tables.select("令", 0, Some("zh_CN")).unwrap();
+        assert_eq!(t3.locale(), "zh_CN");
+
+        // 默认语言
+        let t4 = tables.select("let", 0, None).unwrap();
+        assert_eq!(t4.locale(), "en");
+
+        // 未知的语言
+        let e1 = tables.select("#lang=xx\n", 0, None).unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnknownLocale);
+        assert_eq!(e1.location.start, 6);
+        assert_eq!(e1.location.end, 8);
+        assert_eq!(e1.notes, vec!["available locales: en, zh_CN".to_string()]);
+    }
+}
@@ -5,503 +5,823 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
-use std::char;
-
-use crate::error::Error;
+use std::{borrow::Cow, collections::HashMap, rc::Rc, str::FromStr};
+
+use crate::error::{Diagnostic, DiagnosticCode};
+use crate::identifier::{
+    check_identifiers, is_identifier_continue, is_identifier_start, normalize_identifier,
+};
+use crate::keyword::default_keyword_table;
+use crate::keyword::get_locale_header_length;
+use crate::keyword::select_builtin_keyword_table;
+use crate::keyword::KeywordTable;
+use crate::keyword::KeywordTables;
+use crate::regexp::{check_regexp_flags, parse_regexp, REGEXP_FLAGS};
+use crate::token::BitLiteral;
+use crate::token::IntegerLiteral;
+use crate::token::IntegerType;
 use crate::token::Location;
+use crate::token::LosslessTokens;
 use crate::token::Token;
 use crate::token::TokenDetail;
+use crate::token::TokenTrivia;
+use crate::token::Trivia;
+use crate::token::TriviaKind;
+
+pub fn tokenize(text: &str) -> Result<Vec<TokenDetail>, Diagnostic> {
+    tokenize_with_file_id(text, 0)
+}
+
+// 对指定文件的源文本进行词法分析
+//
+// token 的位置信息（Location）的 start 和 end 均为字节偏移量，
+// file_id 一般由 SourceMap::add_file 分配。
+//
+// 源文件首行有语言标记（比如 `#lang=zh_CN`）时使用对应的内置关键字表，
+// 否则使用英文关键字表。
+pub fn tokenize_with_file_id(text: &str, file_id: usize) -> Result<Vec<TokenDetail>, Diagnostic> {
+    Lexer::new(text, file_id).collect()
+}
+
+// 根据源文件的语言选择关键字表，然后进行词法分析
+//
+// 源文件首行的语言标记（比如 `#lang=zh_CN`）优先于包的语言（package_locale），
+// 两者都没有时使用默认的英文关键字表，语言标记所在的行不产生 token。
+pub fn tokenize_with_locale(
+    text: &str,
+    file_id: usize,
+    keyword_tables: &KeywordTables,
+    package_locale: Option<&str>,
+) -> Result<Vec<TokenDetail>, Diagnostic> {
+    let keywords = keyword_tables.select(text, file_id, package_locale)?;
+    Lexer::new(text, file_id).with_keywords(keywords).collect()
+}
+
+// 对嵌入在源文件中的一段源文本进行词法分析，比如模板字符串的占位符表达式
+//
+// 参数 base_offset 为该段文本在源文件中的开始位置（字节偏移量），
+// token 的位置信息均为在源文件中的位置。
+pub fn tokenize_embedded(
+    text: &str,
+    file_id: usize,
+    base_offset: usize,
+) -> Result<Vec<TokenDetail>, Diagnostic> {
+    Lexer::with_base_offset(text, file_id, base_offset).collect()
+}
 
-pub fn tokenize(text: &str) -> Result<Vec<TokenDetail>, Error> {
-    let vec_char: Vec<char> = text.chars().collect();
+// 恢复模式的词法分析
+//
+// 遇到词法错误时不会提前返回，而是把出错的源文本作为 Token::Unknown，
+// 记录诊断信息之后继续分析，最后返回所有 token 以及所有诊断信息。
+//
+// 诊断信息还包括标识符检查（混合了多种文字、容易混淆的标识符等）的警告，
+// 见 identifier::check_identifiers。
+pub fn tokenize_recovering(text: &str, file_id: usize) -> (Vec<TokenDetail>, Vec<Diagnostic>) {
+    let mut lexer = Lexer::new(text, file_id).recovering();
+    let token_details: Vec<TokenDetail> = lexer.by_ref().flatten().collect();
+
+    let mut diagnostics = lexer.take_diagnostics();
+    diagnostics.extend(check_identifiers(&token_details));
+    (token_details, diagnostics)
+}
 
-    let mut chars = &vec_char[..];
+// 无损模式的词法分析
+//
+// 每个 token 都附带其原始文本以及前后的空白和注释（trivia），
+// 通过 LosslessTokens::to_source_text 可以逐字节还原源文本。
+pub fn tokenize_lossless(text: &str, file_id: usize) -> Result<LosslessTokens, Diagnostic> {
+    let mut lexer = Lexer::new(text, file_id);
     let mut token_details: Vec<TokenDetail> = vec![];
 
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    ' ' | '\t' => {
-                        // whitespace
-                        rest
-                    }
+    // 尚未附着到 token 的 trivia
+    let mut pending_trivias: Vec<Trivia> = vec![];
+
+    while let Some(result) = lexer.next_lexeme() {
+        let (token, location) = result?;
+        let source_text = &text[location.start..location.end];
+
+        match token {
+            Some(token) => {
+                token_details.push(TokenDetail {
+                    location,
+                    token,
+                    trivia: None,
+                });
+                attach_trivias(
+                    &mut token_details,
+                    &mut pending_trivias,
+                    source_text.to_string(),
+                );
+            }
+            None => {
+                // 跳过了空白或者注释
+                let kind = if location.start == 0 && get_locale_header_length(text).is_some() {
+                    TriviaKind::LanguageHeader
+                } else if source_text.starts_with("//") {
+                    TriviaKind::LineComment
+                } else if source_text.starts_with("/*") {
+                    TriviaKind::BlockComment
+                } else {
+                    TriviaKind::Whitespace
+                };
 
-                    '\r' => {
-                        // new line
-                        if is_char('\n', rest) {
-                            add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
-                            move_forword(rest, 1)
-                        } else {
-                            add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
-                            rest
-                        }
-                    }
+                match pending_trivias.last_mut() {
+                    // 合并连续的空白
+                    Some(last) if kind == TriviaKind::Whitespace && last.kind == kind => {
+                        last.text.push_str(source_text);
+                        last.location.end = location.end;
+                    }
+                    _ => pending_trivias.push(Trivia {
+                        kind,
+                        text: source_text.to_string(),
+                        location,
+                    }),
+                }
+            }
+        }
+    }
 
-                    '\n' | ';' => {
-                        // new line
-                        add_token_detail(&mut token_details, new_token_detail(Token::NewLine));
-                        rest
-                    }
+    // 源文件末尾的 trivia
+    let mut end_of_file_trivia = vec![];
+    if !pending_trivias.is_empty() {
+        match token_details.last_mut() {
+            Some(TokenDetail {
+                token,
+                trivia: Some(trivia),
+                ..
+            }) if *token != Token::NewLine => {
+                trivia.trailing.append(&mut pending_trivias);
+            }
+            _ => {
+                end_of_file_trivia = pending_trivias;
+            }
+        }
+    }
 
-                    '/' => {
-                        if is_char('/', rest) {
-                            // line comment
-                            let post_rest = skip_line_comment(rest);
-                            post_rest
-                        } else if is_char('*', rest) {
-                            /* comment */
-                            let post_rest = skip_comment(rest)?;
-                            post_rest
-                        } else {
-                            // `/`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Slash));
-                            rest
-                        }
-                    }
+    Ok(LosslessTokens {
+        token_details,
+        end_of_file_trivia,
+    })
+}
 
-                    '{' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::LeftBrace));
-                        rest
-                    }
-                    '}' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::RightBrace));
-                        rest
-                    }
-                    '=' => {
-                        if is_char('=', rest) {
-                            // `==`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Equal));
-                            move_forword(rest, 1)
-                        } else {
-                            // `=`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Assign));
-                            rest
-                        }
-                    }
-                    '>' => {
-                        if is_char('=', rest) {
-                            // `>=`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::GreaterThanOrEqual),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            // `>`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::GreaterThan),
-                            );
-                            rest
-                        }
-                    }
-                    '|' => {
-                        if is_char('|', rest) {
-                            // `||`
-                            add_token_detail(&mut token_details, new_token_detail(Token::LogicOr));
-                            move_forword(rest, 1)
-                        } else {
-                            // `|`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Pipe));
-                            rest
-                        }
-                    }
-                    '&' => {
-                        if is_char('&', rest) {
-                            // `&&`
-                            add_token_detail(&mut token_details, new_token_detail(Token::LogicAnd));
-                            move_forword(rest, 1)
-                        } else {
-                            // `&`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Combine));
-                            rest
-                        }
-                    }
-                    '!' => {
-                        if is_char('=', rest) {
-                            // `!=`
-                            add_token_detail(&mut token_details, new_token_detail(Token::NotEqual));
-                            move_forword(rest, 1)
-                        } else {
-                            // `!`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::Exclamation),
-                            );
-                            rest
-                        }
-                    }
-                    '<' => {
-                        if is_char('=', rest) {
-                            // `<=`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::LessThanOrEqual),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            // `<`
-                            add_token_detail(&mut token_details, new_token_detail(Token::LessThan));
-                            rest
-                        }
-                    }
-                    '+' => {
-                        if is_char('+', rest) {
-                            // `++`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Concat));
-                            move_forword(rest, 1)
-                        } else {
-                            // `+`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Plus));
-                            rest
-                        }
-                    }
-                    '-' => {
-                        if is_char('>', rest) {
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::OptionalAnd),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            add_token_detail(&mut token_details, new_token_detail(Token::Minus));
-                            rest
-                        }
-                    }
-                    '*' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::Asterisk));
-                        rest
-                    }
-                    '?' => {
-                        if is_char('?', rest) {
-                            // `??`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::OptionalOr),
-                            );
-                            move_forword(rest, 1)
-                        } else {
-                            // `?`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Unwrap));
-                            rest
-                        }
-                    }
-                    '^' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::Cast));
-                        rest
-                    }
-                    '@' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::At));
-                        rest
-                    }
-                    '.' => {
-                        if is_chars(['.', '.'], rest) {
-                            // `...`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Ellipsis));
-                            move_forword(rest, 2)
-                        } else if is_chars(['.', '='], rest) {
-                            // `..=`
-                            add_token_detail(
-                                &mut token_details,
-                                new_token_detail(Token::IntervalInclusive),
-                            );
-                            move_forword(rest, 2)
-                        } else if is_char('.', rest) {
-                            // `..`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Interval));
-                            move_forword(rest, 1)
-                        } else {
-                            // `.`
-                            add_token_detail(&mut token_details, new_token_detail(Token::Dot));
-                            rest
-                        }
-                    }
-                    '[' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::LeftBracket));
-                        rest
-                    }
-                    ']' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::RightBracket));
-                        rest
-                    }
-                    '(' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::LeftParen));
-                        rest
-                    }
-                    ')' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::RightParen));
-                        rest
-                    }
+// 把尚未附着的 trivia 附着到前一个 token（作为 trailing trivia）
+// 或者最后一个 token（即新添加的 token，作为 leading trivia）
+fn attach_trivias(
+    token_details: &mut [TokenDetail],
+    pending_trivias: &mut Vec<Trivia>,
+    lexeme: String,
+) {
+    let (last, previous) = token_details.split_last_mut().unwrap();
+
+    let leading = match previous.last_mut() {
+        Some(TokenDetail {
+            token,
+            trivia: Some(trivia),
+            ..
+        }) if *token != Token::NewLine => {
+            trivia.trailing.append(pending_trivias);
+            vec![]
+        }
+        _ => std::mem::take(pending_trivias),
+    };
 
-                    ',' => {
-                        add_token_detail(&mut token_details, new_token_detail(Token::Comma));
-                        rest
-                    }
+    last.trivia = Some(Box::new(TokenTrivia {
+        leading,
+        lexeme,
+        trailing: vec![],
+    }));
+}
 
-                    '\'' => {
-                        if is_chars(['\'', '\''], rest) {
-                            // `'''...'''` 文档注释
-                            let (_, post_rest) = lex_document_comment(rest)?;
-                            post_rest
-                        } else {
-                            // `'char'`
-                            let (token_detail, post_rest) = lex_char(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        }
-                    }
+// 流式词法分析器
+//
+// 直接在源文本（&str）上扫描，不需要事先把源文本转换为字符数组，
+// 每次调用 next 方法返回一个 token，遇到错误之后不再返回 token。
+//
+// 标识符的文本会被驻留（intern），即同名的标识符共享同一个 Rc<str>，
+// 以减少大型源文件的内存分配。
+//
+// 在恢复模式下，出错的源文本会作为 Token::Unknown 返回，诊断信息则
+// 记录在 Lexer 里，可以通过 take_diagnostics 方法取出。
+pub struct Lexer<'a> {
+    text: &'a str,
+    rest: &'a str, // 尚未分析的源文本
+    file_id: usize,
+    base_offset: usize,
+    is_failed: bool,
+    is_recovering: bool,
+    diagnostics: Vec<Diagnostic>, // 恢复模式下记录的诊断信息
+    symbols: HashMap<&'a str, Rc<str>>,
+    keywords: Rc<KeywordTable>,
+    is_keywords_specified: bool, // 是否通过 with_keywords 指定了关键字表
+}
 
-                    '"' => {
-                        if is_chars(['"', '"'], rest) {
-                            // `"""..."""`
-                            let (token_detail, post_rest) = lex_raw_string(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else {
-                            // `"string"`
-                            let (token_detail, post_rest) = lex_string(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        }
-                    }
+impl<'a> Lexer<'a> {
+    pub fn new(text: &'a str, file_id: usize) -> Self {
+        Lexer::with_base_offset(text, file_id, 0)
+    }
 
-                    '`' => {
-                        // `template string`
-                        let (token_detail, post_rest) = lex_template_string(rest)?;
-                        add_token_detail(&mut token_details, token_detail);
-                        post_rest
-                    }
+    // 参数 base_offset 的作用同 tokenize_embedded 函数
+    pub fn with_base_offset(text: &'a str, file_id: usize, base_offset: usize) -> Self {
+        Lexer {
+            text,
+            rest: text,
+            file_id,
+            base_offset,
+            is_failed: false,
+            is_recovering: false,
+            diagnostics: vec![],
+            symbols: HashMap::new(),
+            keywords: default_keyword_table(),
+            is_keywords_specified: false,
+        }
+    }
 
-                    '~' if is_char('/', rest) => {
-                        // ~/regexp string/
-                        let (token_detail, post_rest) = lex_regexp_string(rest)?;
-                        add_token_detail(&mut token_details, token_detail);
-                        post_rest
-                    }
+    // 使用指定的关键字表
+    //
+    // 默认根据源文件首行的语言标记从内置的关键字表里选择，
+    // 没有语言标记时为英文关键字表。
+    pub fn with_keywords(mut self, keywords: Rc<KeywordTable>) -> Self {
+        self.keywords = keywords;
+        self.is_keywords_specified = true;
+        self
+    }
 
-                    '0' => {
-                        if is_char('x', rest) {
-                            // `0x...`， 十六进制整数
-                            let (token_detail, post_rest) = lex_16_radix_integer(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else if is_char('b', rest) {
-                            // `0b...`， 二进制整数
-                            let (token_detail, post_rest) = lex_2_radix_integer(rest)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else if is_char('.', rest) {
-                            if is_char('.', rest) {
-                                // 遇到范围符号，此时的 `0` 作为普通整数
-                                add_token_detail(
-                                    &mut token_details,
-                                    new_token_detail(Token::Integer(0)),
-                                );
-                                rest
-                            } else {
-                                // `0.xx`， 整数部分为 0 的浮点数
-                                let (token_detail, post_rest) = lex_zero_point_float(rest)?;
-                                add_token_detail(&mut token_details, token_detail);
-                                post_rest
-                            }
-                        } else {
-                            match rest.first() {
-                                Some(second_char)
-                                    if is_valid_letter_of_identifier_or_keyword(*second_char) =>
-                                {
-                                    // 数字 0 开头的符号（不是合法的标识符，所以抛出错误）
-                                    return Err(Error::LexerError(
-                                        "invalid identifier".to_string(),
-                                    ));
-                                }
-                                _ => {
-                                    // 普通整数 0
-                                    add_token_detail(
-                                        &mut token_details,
-                                        new_token_detail(Token::Integer(0)),
-                                    );
-                                    rest
-                                }
-                            }
-                        }
-                    }
+    // 切换到恢复模式
+    pub fn recovering(mut self) -> Self {
+        self.is_recovering = true;
+        self
+    }
 
-                    '#' => {
-                        match rest.first() {
-                            Some(second_char)
-                                if is_valid_first_letter_of_identifier_or_keyword(*second_char) =>
-                            {
-                                // `#hash_string`
-                                let (token_detail, post_rest) = lex_hash_string(rest)?;
-                                add_token_detail(&mut token_details, token_detail);
-                                post_rest
-                            }
-                            Some(second_char) if *second_char == '[' => {
-                                // `#[...]`
-                                let (token_detail, post_rest) = lex_attribute(rest)?;
-                                add_token_detail(&mut token_details, token_detail);
-                                post_rest
-                            }
-                            _ => return Err(Error::LexerError("invalid char '#'".to_string())),
-                        }
-                    }
+    // 取出恢复模式下记录的诊断信息
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
 
-                    ':' => {
-                        match rest.first() {
-                            Some(second_char) => {
-                                if *second_char == ':' {
-                                    // `::`
-                                    add_token_detail(
-                                        &mut token_details,
-                                        new_token_detail(Token::Separator),
-                                    );
-                                    move_forword(rest, 1)
-                                } else if is_valid_first_letter_of_identifier_or_keyword(
-                                    *second_char,
-                                ) {
-                                    match lex_named_operator(rest) {
-                                        Ok((token_detail, post_rest)) => {
-                                            // `:name_operator:`
-                                            add_token_detail(&mut token_details, token_detail);
-                                            post_rest
-                                        }
-                                        Err(_) => {
-                                            // `:`
-                                            add_token_detail(
-                                                &mut token_details,
-                                                new_token_detail(Token::Colon),
-                                            ); // ":"
-                                            rest
-                                        }
-                                    }
-                                } else {
-                                    // `:`
-                                    add_token_detail(
-                                        &mut token_details,
-                                        new_token_detail(Token::Colon),
-                                    );
-                                    rest
-                                }
-                            }
-                            None => {
-                                // `:`
-                                add_token_detail(
-                                    &mut token_details,
-                                    new_token_detail(Token::Colon),
-                                );
-                                rest
+    // 解析一个 token，或者跳过一段空白或注释（此时 token 为 None），
+    // 同时返回其在源文本中的位置。
+    //
+    // 注意返回的位置不包括 base_offset。
+    fn next_lexeme(&mut self) -> Option<Result<(Option<Token>, Location), Diagnostic>> {
+        if self.rest.is_empty() || self.is_failed {
+            return None;
+        }
+
+        let start = self.text.len() - self.rest.len();
+
+        if start == 0 && self.base_offset == 0 {
+            if let Some(length) = get_locale_header_length(self.text) {
+                // 未指定关键字表时，根据语言标记选择关键字表
+                if !self.is_keywords_specified {
+                    match select_builtin_keyword_table(self.text, self.file_id) {
+                        Ok(keywords) => self.keywords = keywords,
+                        Err(diagnostic) => {
+                            if !self.is_recovering {
+                                self.is_failed = true;
+                                return Some(Err(diagnostic));
                             }
+                            self.diagnostics.push(diagnostic);
                         }
                     }
+                }
 
-                    _ => {
-                        if is_none_zero_number(*first) {
-                            // 整数、浮点数或者比特数
-                            let (token_detail, post_rest) = lex_number(chars)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else if is_valid_first_letter_of_identifier_or_keyword(*first) {
-                            // 标识符或者关键字
-                            let (token_detail, post_rest) = lex_identifier_or_keyword(chars)?;
-                            add_token_detail(&mut token_details, token_detail);
-                            post_rest
-                        } else {
-                            // 未预料的符号
-                            return Err(Error::LexerError(format!("invalid char '{}'", first)));
-                        }
-                    }
-                };
+                // 跳过源文件首行的语言标记
+                self.rest = move_forword(self.rest, length);
+                return Some(Ok((None, self.new_location(0, length))));
+            }
+        }
+
+        let (token, post_rest) = match lex_token(self.rest, &mut self.symbols, &self.keywords) {
+            Ok(v) => v,
+            Err(mut diagnostic) => {
+                let (error_start, error_end) = get_error_range(&diagnostic, self.rest);
+                diagnostic.location = self.new_location(start + error_start, start + error_end);
+
+                if !self.is_recovering {
+                    self.is_failed = true;
+                    return Some(Err(diagnostic));
+                }
+
+                // 跳过出错的源文本，然后继续分析
+                let length = get_recovery_length(self.rest, diagnostic.code, error_end);
+                self.diagnostics.push(diagnostic);
+                let (unknown, post_rest) = self.rest.split_at(length);
+                (Some(Token::Unknown(unknown.to_string())), post_rest)
             }
-            None => break,
         };
+
+        self.rest = post_rest;
+        let end = self.text.len() - self.rest.len();
+
+        Some(Ok((
+            token,
+            Location {
+                file_id: self.file_id,
+                start,
+                end,
+            },
+        )))
     }
 
-    Ok(token_details)
+    fn new_location(&self, start: usize, end: usize) -> Location {
+        Location {
+            file_id: self.file_id,
+            start: self.base_offset + start,
+            end: self.base_offset + end,
+        }
+    }
 }
 
-fn skip_line_comment(source_chars: &[char]) -> &[char] {
-    // 行注释
-    // 跳过所有字符直到：
-    // - 行尾（`\n`、`\r\n` 或者 `\r`）
-    // - 程序末尾
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<TokenDetail, Diagnostic>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_lexeme()? {
+                Ok((Some(token), location)) => {
+                    return Some(Ok(TokenDetail {
+                        location: self.new_location(location.start, location.end),
+                        token,
+                        trivia: None,
+                    }))
+                }
+                Ok((None, _)) => {
+                    // 跳过空白和注释
+                }
+                Err(diagnostic) => return Some(Err(diagnostic)),
+            }
+        }
+    }
+}
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
+// 恢复模式下需要跳过的出错的源文本的长度（字节数）
+//
+// - 未闭合的块注释和文档注释：直到源文件末尾；
+// - 未闭合的字符串、字符等字面量：直到行尾，以便从下一行开始继续分析；
+// - 字面量内部的错误（比如无效的转义字符）：整个字面量；
+// - 无效的数字和标识符：连续的字母、数字、下划线以及小数点；
+// - 其他错误：至少到错误范围的结束位置（参数 error_end），并且至少一个字符，
+//   以免一个错误引起后续一连串的错误。
+fn get_recovery_length(source: &str, code: DiagnosticCode, error_end: usize) -> usize {
+    let first_char_length = source.chars().next().map_or(0, char::len_utf8);
+
+    let length = match code {
+        DiagnosticCode::UnterminatedBlockComment | DiagnosticCode::UnterminatedDocumentComment => {
+            source.len()
+        }
+        DiagnosticCode::UnterminatedChar
+        | DiagnosticCode::UnterminatedString
+        | DiagnosticCode::UnterminatedTemplateString
+        | DiagnosticCode::UnterminatedRegexp
+        | DiagnosticCode::UnterminatedNamedOperator
+        | DiagnosticCode::UnterminatedAttribute => {
+            source.find(['\r', '\n']).unwrap_or(source.len())
+        }
+        DiagnosticCode::InvalidNumber
+        | DiagnosticCode::IntegerOverflow
+        | DiagnosticCode::InvalidIdentifier => source
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(source.len()),
+        DiagnosticCode::InvalidChar
+        | DiagnosticCode::InvalidEscape
+        | DiagnosticCode::InvalidRegexp => get_literal_length(source).unwrap_or(0),
+        _ => 0,
+    };
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) => match *first {
-                '\r' => {
-                    if is_char('\n', rest) {
-                        // `\r\n`
-                        end_pos += 1;
-                        break;
-                    } else {
-                        // `\r`
-                        break;
+    length.max(error_end).max(first_char_length)
+}
+
+// 获取以引号开始的（已闭合的）字面量的长度，包括两端的引号以及正则表达式的标志
+fn get_literal_length(source: &str) -> Option<usize> {
+    match source.as_bytes().first()? {
+        b'\'' => find_closing_quote(&source[1..], b'\'').map(|pos| pos + 2),
+        b'"' => find_closing_quote(&source[1..], b'"').map(|pos| pos + 2),
+        b'~' if source[1..].starts_with('/') => {
+            let flags_pos = find_closing_quote(&source[2..], b'/')? + 3;
+            let flags_length = source[flags_pos..]
+                .bytes()
+                .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+                .count();
+            Some(flags_pos + flags_length)
+        }
+        _ => None,
+    }
+}
+
+// 解析一个 token，或者跳过空白和注释（返回 None），同时返回剩余的源文本
+pub(crate) fn lex_token<'a>(
+    source: &'a str,
+    symbols: &mut HashMap<&'a str, Rc<str>>,
+    keywords: &KeywordTable,
+) -> Result<(Option<Token>, &'a str), Diagnostic> {
+    let (first, rest) = match split_first_char(source) {
+        Some(v) => v,
+        None => return Ok((None, source)),
+    };
+
+    let (token, post_rest) = match first {
+        ' ' | '\t' => {
+            // whitespace
+            // 连续的空白作为一段
+            (None, rest.trim_start_matches([' ', '\t']))
+        }
+
+        '\r' => {
+            // new line
+            if is_char('\n', rest) {
+                (Some(Token::NewLine), move_forword(rest, 1))
+            } else {
+                (Some(Token::NewLine), rest)
+            }
+        }
+
+        '\n' | ';' => {
+            // new line
+            (Some(Token::NewLine), rest)
+        }
+
+        '/' => {
+            if is_char('/', rest) {
+                // line comment 或者范围注释
+                lex_line_comment(rest)
+            } else if is_char('*', rest) {
+                /* comment */
+                (None, skip_comment(rest)?)
+            } else {
+                // `/`
+                (Some(Token::Slash), rest)
+            }
+        }
+
+        '{' => (Some(Token::LeftBrace), rest),
+        '}' => (Some(Token::RightBrace), rest),
+        '=' => {
+            if is_char('=', rest) {
+                // `==`
+                (Some(Token::Equal), move_forword(rest, 1))
+            } else {
+                // `=`
+                (Some(Token::Assign), rest)
+            }
+        }
+        '>' => {
+            if is_char('=', rest) {
+                // `>=`
+                (Some(Token::GreaterThanOrEqual), move_forword(rest, 1))
+            } else {
+                // `>`
+                (Some(Token::GreaterThan), rest)
+            }
+        }
+        '|' => {
+            if is_char('|', rest) {
+                // `||`
+                (Some(Token::LogicOr), move_forword(rest, 1))
+            } else {
+                // `|`
+                (Some(Token::Pipe), rest)
+            }
+        }
+        '&' => {
+            if is_char('&', rest) {
+                // `&&`
+                (Some(Token::LogicAnd), move_forword(rest, 1))
+            } else {
+                // `&`
+                (Some(Token::Combine), rest)
+            }
+        }
+        '!' => {
+            if is_char('=', rest) {
+                // `!=`
+                (Some(Token::NotEqual), move_forword(rest, 1))
+            } else {
+                // `!`
+                (Some(Token::Exclamation), rest)
+            }
+        }
+        '<' => {
+            if is_char('=', rest) {
+                // `<=`
+                (Some(Token::LessThanOrEqual), move_forword(rest, 1))
+            } else {
+                // `<`
+                (Some(Token::LessThan), rest)
+            }
+        }
+        '+' => {
+            if is_char('+', rest) {
+                // `++`
+                (Some(Token::Concat), move_forword(rest, 1))
+            } else {
+                // `+`
+                (Some(Token::Plus), rest)
+            }
+        }
+        '-' => {
+            if is_char('>', rest) {
+                (Some(Token::OptionalAnd), move_forword(rest, 1))
+            } else {
+                (Some(Token::Minus), rest)
+            }
+        }
+        '*' => (Some(Token::Asterisk), rest),
+        '?' => {
+            if is_char('?', rest) {
+                // `??`
+                (Some(Token::OptionalOr), move_forword(rest, 1))
+            } else {
+                // `?`
+                (Some(Token::Unwrap), rest)
+            }
+        }
+        '^' => (Some(Token::Cast), rest),
+        '@' => (Some(Token::At), rest),
+        '.' => {
+            if is_chars(['.', '.'], rest) {
+                // `...`
+                (Some(Token::Ellipsis), move_forword(rest, 2))
+            } else if is_chars(['.', '='], rest) {
+                // `..=`
+                (Some(Token::IntervalInclusive), move_forword(rest, 2))
+            } else if is_char('.', rest) {
+                // `..`
+                (Some(Token::Interval), move_forword(rest, 1))
+            } else {
+                // `.`
+                (Some(Token::Dot), rest)
+            }
+        }
+        '[' => (Some(Token::LeftBracket), rest),
+        ']' => (Some(Token::RightBracket), rest),
+        '(' => (Some(Token::LeftParen), rest),
+        ')' => (Some(Token::RightParen), rest),
+
+        ',' => (Some(Token::Comma), rest),
+
+        '\'' => {
+            if is_chars(['\'', '\''], rest) {
+                // `'''...'''` 文档注释
+                let (value, post_rest) = lex_document_comment(rest)?;
+                (Some(Token::DocComment(value)), post_rest)
+            } else {
+                // `'char'`
+                let (token, post_rest) = lex_char(rest)?;
+                (Some(token), post_rest)
+            }
+        }
+
+        '"' => {
+            let (token, post_rest) = if is_chars(['"', '"'], rest) {
+                // `"""..."""`
+                lex_raw_string(rest)?
+            } else {
+                // `"string"`
+                lex_string(rest)?
+            };
+            (Some(token), post_rest)
+        }
+
+        '`' => {
+            // `template string`
+            let (token, post_rest) = lex_template_string(rest)?;
+            (Some(token), post_rest)
+        }
+
+        '~' if is_char('/', rest) => {
+            // ~/regexp string/
+            let (token, post_rest) = lex_regexp_string(rest)?;
+            (Some(token), post_rest)
+        }
+
+        '0' => {
+            if is_char('x', rest) {
+                // `0x...`， 十六进制整数
+                let (token, post_rest) = lex_16_radix_integer(source)?;
+                (Some(token), post_rest)
+            } else if is_char('b', rest) {
+                // `0b...`， 二进制整数
+                let (token, post_rest) = lex_2_radix_integer(source)?;
+                (Some(token), post_rest)
+            } else {
+                match rest.chars().next() {
+                    Some(second_char)
+                        if is_valid_first_letter_of_identifier_or_keyword(second_char)
+                            && !matches!(second_char, '_' | 'e' | 'i' | 'u' | 'n') =>
+                    {
+                        // 数字 0 开头的符号（不是合法的标识符，所以抛出错误）
+                        return Err(new_error(
+                            DiagnosticCode::InvalidIdentifier,
+                            "invalid identifier",
+                        )
+                        .with_note("identifiers can not start with a number"));
+                    }
+                    _ => {
+                        // 整数 0、`0.xx` 以及 `0e...` 等
+                        let (token, post_rest) = lex_number(source)?;
+                        (Some(token), post_rest)
                     }
                 }
-                '\n' => {
-                    break;
+            }
+        }
+
+        '#' => {
+            match rest.chars().next() {
+                Some(second_char)
+                    if is_valid_first_letter_of_identifier_or_keyword(second_char) =>
+                {
+                    // `#hash_string`
+                    let (token, post_rest) = lex_hash_string(rest)?;
+                    (Some(token), post_rest)
+                }
+                Some('[') => {
+                    // `#[...]`
+                    let (token, post_rest) = lex_attribute(rest)?;
+                    (Some(token), post_rest)
+                }
+                _ => return Err(new_error(DiagnosticCode::InvalidChar, "invalid char '#'")),
+            }
+        }
+
+        ':' => {
+            match rest.chars().next() {
+                Some(':') => {
+                    // `::`
+                    (Some(Token::Separator), move_forword(rest, 1))
+                }
+                Some('=') => {
+                    // `:=`
+                    (Some(Token::ColonAssign), move_forword(rest, 1))
+                }
+                Some(second_char)
+                    if is_valid_first_letter_of_identifier_or_keyword(second_char) =>
+                {
+                    match lex_named_operator(rest) {
+                        Ok((token, post_rest)) => {
+                            // `:name_operator:`
+                            (Some(token), post_rest)
+                        }
+                        Err(_) => {
+                            // `:`
+                            (Some(Token::Colon), rest)
+                        }
+                    }
                 }
                 _ => {
-                    end_pos += 1;
-                    rest
+                    // `:`
+                    (Some(Token::Colon), rest)
                 }
-            },
-            None => {
-                break;
             }
         }
+
+        _ => {
+            if is_none_zero_number(first) {
+                // 整数、浮点数或者比特数
+                let (token, post_rest) = lex_number(source)?;
+                (Some(token), post_rest)
+            } else if is_valid_first_letter_of_identifier_or_keyword(first) {
+                // 标识符或者关键字
+                let (token, post_rest) = lex_identifier_or_keyword(source, symbols, keywords);
+                (Some(token), post_rest)
+            } else {
+                // 未预料的符号
+                return Err(new_error(
+                    DiagnosticCode::InvalidChar,
+                    &format!("invalid char '{}'", first),
+                ));
+            }
+        }
+    };
+
+    Ok((token, post_rest))
+}
+
+fn lex_line_comment(source: &str) -> (Option<Token>, &str) {
+    // 行注释，或者由连续的行注释组成的范围注释
+    //
+    // e.g.
+    // // foo
+    //  ^-------- 当前所在的位置
+    //
+    // - 包含一行粗横线（至少 3 个 `=`）的连续行注释为范围注释的开始，
+    //   粗横线上方或下方的其他行注释为该范围的标题和描述；
+    // - 一行细横线（至少 3 个 `-`）为范围注释的结束；
+    // - 其他行注释跟普通的行注释一样被跳过。
+    //
+    // 注意要保留换行符到返回的剩余文本（rest）中，以便产生一个 Token::NewLine
+
+    let first_line_end = source.find(['\r', '\n']).unwrap_or(source.len());
+    if get_comment_rule(&source[1..first_line_end]) == Some('-') {
+        return (Some(Token::RegionEnd), &source[first_line_end..]);
+    }
+
+    let mut lines: Vec<&str> = vec![];
+    let mut is_region = false;
+    let mut line_start = 0;
+    let mut end_pos = first_line_end;
+
+    loop {
+        let line_end = line_start
+            + source[line_start..]
+                .find(['\r', '\n'])
+                .unwrap_or(source.len() - line_start);
+
+        match get_comment_rule(&source[line_start + 1..line_end]) {
+            Some('=') => is_region = true,
+            Some(_) => break, // 细横线不属于当前的范围注释
+            None => lines.push(source[line_start + 1..line_end].trim()),
+        }
+        end_pos = line_end;
+
+        // 检查下一行是否仍然是行注释（允许有前导的空白）
+        let post_line = &source[line_end..];
+        let next_line = match post_line
+            .strip_prefix("\r\n")
+            .or_else(|| post_line.strip_prefix(['\r', '\n']))
+        {
+            Some(post_new_line) => post_new_line.trim_start_matches([' ', '\t']),
+            None => break,
+        };
+
+        if !next_line.starts_with("//") {
+            break;
+        }
+        line_start = source.len() - next_line.len() + 1;
     }
 
-    // 注意要保留换行符到返回的字符数组（rest）中，以便产生一个 Token::NewLine
-    &source_chars[end_pos..]
+    if is_region {
+        (
+            Some(Token::RegionStart(lines.join("\n"))),
+            &source[end_pos..],
+        )
+    } else {
+        (None, &source[first_line_end..])
+    }
 }
 
-fn skip_comment(source_chars: &[char]) -> Result<&[char], Error> {
+// 检查行注释是否为横线，即至少 3 个 `=` 或者 `-`，返回横线的字符
+fn get_comment_rule(text: &str) -> Option<char> {
+    let text = text.trim();
+    let first = text.chars().next()?;
+    if text.len() >= 3 && matches!(first, '=' | '-') && text.chars().all(|c| c == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+fn skip_comment(source: &str) -> Result<&str, Diagnostic> {
     // 区域注释
-    // 跳过所有字符直到找到结束符 `*/`
+    // 跳过所有字符直到找到跟开始符号配对的结束符 `*/`，区域注释可以嵌套
+    //
+    // /*foo /*bar*/ baz*/
+    //  ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
+    let bytes = source.as_bytes();
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) => match *first {
-                '*' => {
-                    if is_char('/', rest) {
-                        // `*/`
-                        end_pos += 1;
-                        break;
-                    } else {
-                        // 找到了星号，但不是结束符
-                        end_pos += 1;
-                        rest
-                    }
-                }
-                _ => {
-                    end_pos += 1;
-                    rest
+    // 尚未闭合的开始符号 `/*` 的位置（相对于第一个 `/` 的偏移量）
+    let mut openings: Vec<usize> = vec![0];
+
+    // 跳过开始符号的 `*`，以免把 `/*/` 当作完整的注释
+    let mut pos = 1;
+
+    while pos < bytes.len() {
+        match (bytes[pos], bytes.get(pos + 1)) {
+            (b'/', Some(b'*')) => {
+                openings.push(pos + 1);
+                pos += 2;
+            }
+            (b'*', Some(b'/')) => {
+                openings.pop();
+                pos += 2;
+
+                if openings.is_empty() {
+                    return Ok(move_forword(source, pos));
                 }
-            },
-            None => {
-                // 到了末尾仍未找到结束符
-                return Err(Error::LexerError(
-                    "expected comment ending symbol".to_string(),
-                ));
             }
+            _ => pos += 1,
         }
     }
 
-    // /*foo bar*/
-    //           ^-------- 当前所在的位置
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok(rest)
+    // 到了末尾仍未找到结束符，错误的位置为最内层的未闭合的开始符号
+    let innermost = openings[openings.len() - 1];
+    let mut diagnostic = new_error_at(
+        DiagnosticCode::UnterminatedBlockComment,
+        "expected comment ending symbol",
+        innermost,
+        innermost + 2,
+    );
+
+    if openings.len() > 1 {
+        diagnostic = diagnostic.with_note(&format!(
+            "block comments can be nested, {} comments are not closed",
+            openings.len()
+        ));
+    }
+
+    Err(diagnostic)
 }
 
-fn lex_document_comment(source_chars: &[char]) -> Result<(String, &[char]), Error> {
+fn lex_document_comment(source: &str) -> Result<(String, &str), Diagnostic> {
     // 文档注释
     // 查找 `文档注释` 的结束字符 `‘’‘`
     //
@@ -509,49 +829,26 @@ fn lex_document_comment(source_chars: &[char]) -> Result<(String, &[char]), Erro
     // ’‘’foo bar‘’‘
     //  ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
-
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    '\'' => {
-                        if is_chars(['\'', '\''], rest) {
-                            // 找到了 `'''`
-                            end_pos += 2;
-                            break;
-                        } else {
-                            // 找到了单引号，但不是三个连续的单引号
-                            end_pos += 1;
-                            rest
-                        }
-                    }
-                    _ => {
-                        end_pos += 1;
-                        rest
-                    }
-                }
-            }
-            None => {
-                // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected document comment ending symbol".to_string(),
-                ));
-            }
+    let end_pos = match source[2..].find("'''") {
+        Some(pos) => pos + 2,
+        None => {
+            // 到了末尾仍未找到结束字符
+            return Err(new_error(
+                DiagnosticCode::UnterminatedDocumentComment,
+                "expected document comment ending symbol",
+            ));
         }
-    }
+    };
 
-    let value_chars = &source_chars[2..end_pos - 2];
-    let value = value_chars.iter().collect::<String>();
+    let value = source[2..end_pos].to_string();
 
     // '''foo bar'''
-    //             ^-------- 当前所在的位置
-    let rest = move_forword(source_chars, end_pos + 1);
+    //           ^-------- end_pos 所在的位置
+    let rest = move_forword(source, end_pos + 3);
     Ok((value, rest))
 }
 
-fn lex_char(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_char(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 字符字面量
     // 查找 `字符字面量` 的结束字符 `'`，但不包括 `\'`
     //
@@ -563,56 +860,42 @@ fn lex_char(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
     // '\omega'
     //  ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
-
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    '\\' => {
-                        if is_char('\'', rest) {
-                            // 找到了 '\''
-                            end_pos += 2;
-                            move_forword(rest, 1)
-                        } else {
-                            // 找到了其他转义字符
-                            // todo::
-                            end_pos += 1;
-                            rest
-                        }
-                    }
-                    '\'' => {
-                        break;
-                    }
-                    _ => {
-                        end_pos += 1;
-                        rest
-                    }
-                }
-            }
-            None => {
-                // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected char literal ending symbol".to_string(),
-                ));
-            }
+    let end_pos = match find_closing_quote(source, b'\'') {
+        Some(pos) => pos,
+        None => {
+            // 到了末尾仍未找到结束字符
+            return Err(new_error(
+                DiagnosticCode::UnterminatedChar,
+                "expected char literal ending symbol",
+            ));
         }
-    }
-
-    let value_chars = &source_chars[..end_pos];
+    };
 
-    // todo:: 处理转义字符
-    // todo:: 验证字符的有效性
+    // 处理转义字符，错误的位置需要加上开始符号 `'` 的长度
+    let text = unescape_literal(&source[..end_pos], 1)?;
+
+    let mut chars = text.chars();
+    let value = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        (None, _) => return Err(new_error(DiagnosticCode::InvalidChar, "empty char literal")),
+        (Some(_), Some(_)) => {
+            return Err(new_error_at(
+                DiagnosticCode::InvalidChar,
+                "char literal must contain exactly one character",
+                0,
+                end_pos + 2,
+            )
+            .with_help("use double quotes for a string literal"))
+        }
+    };
 
     // 当前 end_pos 处于字符 `'` 位置
     // 剩余的字符应该从 `'` 位置之后开始
-
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok((new_token_detail(Token::Char(value_chars[0])), rest))
+    let rest = move_forword(source, end_pos + 1);
+    Ok((Token::Char(value), rest))
 }
 
-fn lex_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_string(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 字符串字面量
     // 查找 `字符串字面量` 的结束字符 `"`，但不包括 `\"`
     //
@@ -620,54 +903,151 @@ fn lex_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
     // "foo bar"
     //  ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
+    let end_pos = match find_closing_quote(source, b'"') {
+        Some(pos) => pos,
+        None => {
+            // 到了末尾仍未找到结束字符
+            return Err(new_error(
+                DiagnosticCode::UnterminatedString,
+                "expected string literal ending symbol",
+            ));
+        }
+    };
 
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    '\\' => {
-                        if is_char('"', rest) {
-                            // 找到了 '\"'
-                            end_pos += 2;
-                            move_forword(rest, 1)
-                        } else {
-                            // 找到了其他转义字符
-                            end_pos += 1;
-                            rest
-                        }
+    // 处理转义字符，错误的位置需要加上开始符号 `"` 的长度
+    let value = unescape_literal(&source[..end_pos], 1)?;
+
+    // 当前 end_pos 处于字符 `"` 位置
+    // 剩余的字符应该从 `"` 位置之后开始
+    let rest = move_forword(source, end_pos + 1);
+    Ok((Token::GeneralString(value), rest))
+}
+
+// 查找结束符（比如 `"`）的位置，跳过转义字符（比如 `\"` 以及 `\\`）
+fn find_closing_quote(source: &str, quote: u8) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut pos: usize = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos] == quote {
+            return Some(pos);
+        } else if bytes[pos] == b'\\' {
+            // 结束符是 ASCII 字符，所以跳过多字节字符的一个字节不影响查找
+            pos += 2;
+        } else {
+            pos += 1;
+        }
+    }
+
+    None
+}
+
+// 处理字符串以及字符字面量里的转义字符
+//
+// 转义字符有 `\t`、`\n`、`\r`、`\0`、`\\`、`\"`、`\'`、`\xHH`（ASCII 字符，最大为 `\x7f`）
+// 以及 `\u{H}` ~ `\u{HHHHHH}`（Unicode 字符）。
+//
+// 参数 base_offset 为 text 相对于 token 开始位置的偏移量，用于计算错误的位置。
+fn unescape_literal(text: &str, base_offset: usize) -> Result<String, Diagnostic> {
+    if !text.contains('\\') {
+        return Ok(text.to_string());
+    }
+
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        let new_escape_error = |message: &str, length: usize| {
+            new_error_at(
+                DiagnosticCode::InvalidEscape,
+                message,
+                base_offset + pos,
+                base_offset + pos + length,
+            )
+        };
+
+        let unescaped = match chars.next() {
+            Some((_, 't')) => '\t',
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, '0')) => '\0',
+            Some((_, v @ ('\\' | '"' | '\''))) => v,
+            Some((_, 'x')) => {
+                // `\xHH`
+                let digits = text.get(pos + 2..pos + 4).unwrap_or("-");
+                match u8::from_str_radix(digits, 16) {
+                    Ok(code) if digits.bytes().all(|b| b.is_ascii_hexdigit()) && code <= 0x7f => {
+                        chars.nth(1);
+                        code as char
+                    }
+                    _ => {
+                        return Err(new_escape_error("invalid hex escape", 2)
+                            .with_help("hex escapes must be two digits from `\\x00` to `\\x7f`"))
+                    }
+                }
+            }
+            Some((_, 'u')) => {
+                // `\u{H}` ~ `\u{HHHHHH}`
+                let rest = &text[pos + 2..];
+                let closing = match (rest.starts_with('{'), rest.find('}')) {
+                    (true, Some(closing)) => closing,
+                    _ => {
+                        return Err(new_escape_error("invalid unicode escape", 2)
+                            .with_help("unicode escapes must be like `\\u{6587}`"))
                     }
-                    '\"' => {
-                        break;
+                };
+
+                let digits = &rest[1..closing];
+                let length = 2 + closing + 1;
+                let code = match u32::from_str_radix(digits, 16) {
+                    Ok(code)
+                        if (1..=6).contains(&digits.len())
+                            && digits.bytes().all(|b| b.is_ascii_hexdigit()) =>
+                    {
+                        code
                     }
                     _ => {
-                        end_pos += 1;
-                        rest
+                        return Err(new_escape_error("invalid unicode escape", length)
+                            .with_help("unicode escapes must be 1 to 6 hex digits"))
+                    }
+                };
+
+                match char::from_u32(code) {
+                    Some(v) => {
+                        for _ in 0..closing + 1 {
+                            chars.next();
+                        }
+                        v
+                    }
+                    None => {
+                        return Err(new_escape_error(
+                            "invalid unicode escape, it is not a valid unicode character",
+                            length,
+                        ))
                     }
                 }
             }
-            None => {
-                // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected string literal ending symbol".to_string(),
-                ));
+            Some((_, v)) => {
+                return Err(new_escape_error(
+                    "unknown escape character",
+                    1 + v.len_utf8(),
+                ))
             }
-        }
-    }
-
-    let value_chars = &source_chars[..end_pos];
-    let value = value_chars.iter().collect::<String>();
+            None => return Err(new_escape_error("unknown escape character", 1)),
+        };
 
-    // todo:: 处理转义字符
+        value.push(unescaped);
+    }
 
-    // 当前 end_pos 处于字符 `"` 位置
-    // 剩余的字符应该从 `"` 位置之后开始
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok((new_token_detail(Token::GeneralString(value)), rest))
+    Ok(value)
 }
 
-fn lex_raw_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_raw_string(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 原始字符串字面量
     // 查找 `原始字符串字面量` 的结束字符 `"""`
     //
@@ -675,108 +1055,290 @@ fn lex_raw_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error
     // """foo bar"""
     //  ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
+    let end_pos = match source[2..].find("\"\"\"") {
+        Some(pos) => pos + 2,
+        None => {
+            // 到了末尾仍未找到结束字符
+            return Err(new_error(
+                DiagnosticCode::UnterminatedString,
+                "expected raw string literal ending symbol",
+            ));
+        }
+    };
 
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    '"' => {
-                        if is_chars(['"', '"'], rest) {
-                            // 找到了 '"""'
-                            end_pos += 2;
-                            break;
-                        } else {
-                            // 找到了双引号，但不是三个连续的双引号
-                            end_pos += 1;
-                            rest
+    // todo:: 截去每行的共同前缀空白
+
+    let value = source[2..end_pos].to_string();
+
+    // """foo bar"""
+    //           ^-------- end_pos 所在的位置
+    let rest = move_forword(source, end_pos + 3);
+    Ok((Token::GeneralString(value), rest))
+}
+
+fn lex_template_string(source: &str) -> Result<(Token, &str), Diagnostic> {
+    // 模板字符串字面量
+    // 查找 `模板字符串字面量` 的结束字符 '`'，但不包括 '\`' 以及占位符里面的 '`'
+    //
+    // e.g.
+    // `foo bar`
+    // `foo {bar}`
+    // `foo {`bar {baz}`}`
+    //  ^-------- 当前所在的位置
+    //
+    // token 的值为两个 '`' 之间的原始文本，占位符的拆分以及转义字符的处理
+    // 由 split_template_string 函数完成。
+
+    let end_pos = match scan_template_string(source, false).end_pos {
+        Some(pos) => pos,
+        None => {
+            // 到了末尾仍未找到结束字符
+            return Err(new_error(
+                DiagnosticCode::UnterminatedTemplateString,
+                "expected template string literal ending symbol",
+            )
+            .with_note("placeholders `{...}` must be closed before the ending symbol"));
+        }
+    };
+
+    let value = source[..end_pos].to_string();
+
+    // todo:: 截去每行的共同前缀空白
+
+    // 当前 end_pos 处于字符 '`' 位置
+    // 剩余的字符应该从 '`' 位置之后开始
+    let rest = move_forword(source, end_pos + 1);
+    Ok((Token::TemplateString(value), rest))
+}
+
+// 模板字符串的内容拆分之后的结果
+#[derive(Debug, PartialEq)]
+pub struct TemplateStringParts {
+    pub fragments: Vec<String>, // 已处理转义字符的字面量片段，数量总是比占位符多 1
+    pub placeholders: Vec<TemplatePlaceholder>,
+}
+
+// 模板字符串的占位符
+#[derive(Debug, PartialEq)]
+pub struct TemplatePlaceholder {
+    pub text: String,       // 花括号之间的原始文本
+    pub location: Location, // 原始文本在源文件中的位置
+}
+
+// 拆分模板字符串的内容（即 Token::TemplateString 的值）
+//
+// 参数 base_offset 为内容在源文件中的开始位置（即符号 '`' 之后的位置）。
+//
+// 规则：
+// - 字面量片段里的转义字符有 `\t`、`\n`、`\r`、`\\`、'\`'、`\{`、`\}`、`\"` 以及 `\'`；
+// - 花括号 `{...}` 为占位符，里面是一个表达式，可以包含嵌套的花括号（比如映射表）、
+//   字符串、字符以及嵌套的模板字符串；
+// - 字面量片段里不成对的 `}` 需要写成 `\}`。
+pub fn split_template_string(
+    content: &str,
+    file_id: usize,
+    base_offset: usize,
+) -> Result<TemplateStringParts, Diagnostic> {
+    let new_location = |start: usize, end: usize| Location {
+        file_id,
+        start: base_offset + start,
+        end: base_offset + end,
+    };
+
+    let scan = scan_template_string(content, true);
+
+    if let Some((pos, code, message)) = scan.problems.first() {
+        // 错误的位置为出问题的字符，对于转义字符则包括反斜杠之后的字符
+        let length = content[*pos..]
+            .chars()
+            .take(2)
+            .map(char::len_utf8)
+            .sum::<usize>();
+        return Err(Diagnostic::error(
+            *code,
+            message,
+            new_location(*pos, *pos + length),
+        ));
+    }
+
+    let placeholders = scan
+        .placeholders
+        .iter()
+        .map(|(start, end)| TemplatePlaceholder {
+            text: content[*start..*end].to_string(),
+            location: new_location(*start, *end),
+        })
+        .collect::<Vec<TemplatePlaceholder>>();
+
+    Ok(TemplateStringParts {
+        fragments: scan.fragments,
+        placeholders,
+    })
+}
+
+// 模板字符串的扫描结果，位置均为字节偏移量
+struct TemplateScan {
+    fragments: Vec<String>,
+    placeholders: Vec<(usize, usize)>, // 占位符花括号之间的文本的开始和结束位置
+    problems: Vec<(usize, DiagnosticCode, &'static str)>,
+    end_pos: Option<usize>, // 结束符 '`' 的位置，None 表示未找到结束符
+}
+
+// 扫描模板字符串
+//
+// 词法分析阶段只需要找到结束符，所以当参数 collect_fragments 为 false 时
+// 不构建字面量片段（fragments 为空）。
+fn scan_template_string(source: &str, collect_fragments: bool) -> TemplateScan {
+    let bytes = source.as_bytes();
+
+    let mut fragments: Vec<String> = vec![];
+    let mut placeholders: Vec<(usize, usize)> = vec![];
+    let mut problems: Vec<(usize, DiagnosticCode, &'static str)> = vec![];
+
+    let mut fragment = String::new();
+    let mut pos: usize = 0;
+    let mut end_pos: Option<usize> = None;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'`' => {
+                end_pos = Some(pos);
+                break;
+            }
+            b'\\' => match source[pos + 1..].chars().next() {
+                Some(c) => {
+                    match unescape_template_char(c) {
+                        Some(v) => fragment.push(v),
+                        None => {
+                            problems.push((
+                                pos,
+                                DiagnosticCode::InvalidEscape,
+                                "unknown escape character",
+                            ));
+                            fragment.push('\\');
+                            fragment.push(c);
                         }
                     }
-                    _ => {
-                        end_pos += 1;
-                        rest
-                    }
+                    pos += 1 + c.len_utf8();
+                }
+                None => {
+                    fragment.push('\\');
+                    pos += 1;
+                }
+            },
+            b'{' => match skip_template_placeholder(source, pos + 1) {
+                Some(right_brace_pos) => {
+                    fragments.push(std::mem::take(&mut fragment));
+                    placeholders.push((pos + 1, right_brace_pos));
+                    pos = right_brace_pos + 1;
                 }
-            }
-            None => {
-                // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected raw string literal ending symbol".to_string(),
+                None => {
+                    // 占位符未闭合
+                    problems.push((
+                        pos,
+                        DiagnosticCode::InvalidTemplateString,
+                        "unclosed placeholder in template string",
+                    ));
+                    break;
+                }
+            },
+            b'}' => {
+                problems.push((
+                    pos,
+                    DiagnosticCode::InvalidTemplateString,
+                    "unmatched \"}\" in template string, use \"\\}\" instead",
                 ));
+                fragment.push('}');
+                pos += 1;
+            }
+            _ => {
+                // 连续的普通字符作为一段
+                let length = bytes[pos..]
+                    .iter()
+                    .position(|b| matches!(b, b'`' | b'\\' | b'{' | b'}'))
+                    .unwrap_or(bytes.len() - pos);
+                if collect_fragments {
+                    fragment.push_str(&source[pos..pos + length]);
+                }
+                pos += length;
             }
         }
     }
 
-    // todo:: 截去每行的共同前缀空白
-
-    let value_chars = &source_chars[2..end_pos - 2];
-    let value = value_chars.iter().collect::<String>();
+    fragments.push(fragment);
 
-    // """foo bar"""
-    //             ^-------- 当前所在的位置
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok((new_token_detail(Token::GeneralString(value)), rest))
+    TemplateScan {
+        fragments,
+        placeholders,
+        problems,
+        end_pos,
+    }
 }
 
-fn lex_template_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
-    // 模板字符串字面量
-    // 查找 `模板字符串字面量` 的结束字符 '`'，但不包括 '`'
-    //
-    // e.g.
-    // `foo bar`
-    //  ^-------- 当前所在的位置
-
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
-
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    '\\' => {
-                        if is_char('`', rest) {
-                            // 找到了 '`'
-                            end_pos += 2;
-                            move_forword(rest, 1)
-                        } else {
-                            // 找到了其他转义字符
-                            end_pos += 1;
-                            rest
-                        }
-                    }
-                    '`' => {
-                        break;
-                    }
-                    _ => {
-                        end_pos += 1;
-                        rest
-                    }
+// 查找占位符的结束符 `}`，参数 start 为占位符开始符号 `{` 之后的位置
+fn skip_template_placeholder(source: &str, start: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth: usize = 0;
+    let mut pos = start;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'{' => depth += 1,
+            b'}' => {
+                if depth == 0 {
+                    return Some(pos);
                 }
+                depth -= 1;
             }
-            None => {
-                // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected template string literal ending symbol".to_string(),
-                ));
+            b'\\' => pos += 1,
+            b'"' => {
+                // 跳过字符串
+                pos += 1 + find_closing_quote(&source[pos + 1..], b'"')?;
+            }
+            b'\'' => {
+                // 跳过字符字面量，比如 '}' 和 '\''
+                let mut chars = source[pos + 1..].chars();
+                match (chars.next(), chars.next(), chars.next()) {
+                    (Some('\\'), Some(c), Some('\'')) => pos += 2 + c.len_utf8(),
+                    (Some(c), Some('\''), _) => pos += 1 + c.len_utf8(),
+                    _ => {}
+                }
+            }
+            b'`' => {
+                // 跳过嵌套的模板字符串
+                let scan = scan_template_string(&source[pos + 1..], false);
+                pos += 1 + scan.end_pos?;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                // 跳过区域注释，比如 `{a /* } */}`
+                let rest = skip_comment(&source[pos..]).ok()?;
+                pos = source.len() - rest.len();
+                continue;
             }
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                // 跳过行注释
+                pos += source[pos..].find('\n')?;
+                continue;
+            }
+            _ => {}
         }
+        pos += 1;
     }
 
-    let value_chars = &source_chars[..end_pos];
-    let value = value_chars.iter().collect::<String>();
-
-    // todo:: 处理转义字符
-
-    // todo:: 截去每行的共同前缀空白
+    None
+}
 
-    // 当前 end_pos 处于字符 '`' 位置
-    // 剩余的字符应该从 '`' 位置之后开始
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok((new_token_detail(Token::TemplateString(value)), rest))
+fn unescape_template_char(c: char) -> Option<char> {
+    match c {
+        't' => Some('\t'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        '\\' | '`' | '{' | '}' | '"' | '\'' => Some(c),
+        _ => None,
+    }
 }
 
-fn lex_regexp_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_regexp_string(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 正则表达式字符串
     // 查找结束符 `/` 但不包括 `\/`
     //
@@ -784,58 +1346,60 @@ fn lex_regexp_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Er
     // ~/foo_bar/
     //  ^-------- 当前所在的位置
 
-    let mut chars = &source_chars[1..]; // 跳过第一个字符 '/'
-    let mut end_pos: usize = 0;
-
-    // 注：第一个字符已经验证过是合法的标识符首个字符，无需再检查
-
-    loop {
-        match chars.split_first() {
-            Some((first, rest)) => {
-                chars = match *first {
-                    '\\' => {
-                        if is_char('/', rest) {
-                            // 找到了 "\/"
-                            end_pos += 2;
-                            move_forword(rest, 1)
-                        } else {
-                            // 找到了其他转义字符
-                            end_pos += 1;
-                            rest
-                        }
-                    }
-                    '/' => {
-                        break;
-                    }
-                    _ => {
-                        end_pos += 1;
-                        rest
-                    }
-                }
-            }
-            None => {
-                // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected regular expression literal ending symbol".to_string(),
-                ));
-            }
+    // 跳过第一个字符 '/'
+    let end_pos = match find_closing_quote(&source[1..], b'/') {
+        Some(pos) => pos + 1,
+        None => {
+            // 到了末尾仍未找到结束字符
+            return Err(new_error(
+                DiagnosticCode::UnterminatedRegexp,
+                "expected regular expression literal ending symbol",
+            ));
         }
-    }
-
-    end_pos += 1; // 因为 chars 跳过了第一个字符 `/`，所以这里补回偏移值
+    };
 
-    let value_chars = &source_chars[1..end_pos]; // source_chars 从字符 `/` 开始
-    let value = value_chars.iter().collect::<String>();
+    let value = &source[1..end_pos]; // source 从字符 `/` 开始
+
+    // 检查正则表达式的语法，错误的位置需要加上 `~/` 的长度
+    let group_count = match parse_regexp(value) {
+        Ok(regexp) => regexp.group_count,
+        Err(e) => {
+            return Err(new_error_at(
+                DiagnosticCode::InvalidRegexp,
+                &e.message,
+                e.start + 2,
+                e.end + 2,
+            ));
+        }
+    };
 
-    // todo:: 处理转义字符
+    // 当前 end_pos 处于字符 `/` 位置，结束符 `/` 之后紧随的字母是正则表达式的标志
+    let flags_pos = end_pos + 1;
+    let flags_length = source[flags_pos..]
+        .bytes()
+        .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        .count();
+    let flags = &source[flags_pos..flags_pos + flags_length];
+
+    if let Err(e) = check_regexp_flags(flags) {
+        return Err(new_error_at(
+            DiagnosticCode::InvalidRegexp,
+            &e.message,
+            e.start + flags_pos + 1,
+            e.end + flags_pos + 1,
+        )
+        .with_note(&format!("available flags: {}", REGEXP_FLAGS)));
+    }
 
-    // 当前 end_pos 处于字符 `/` 位置
-    // 剩余的字符应该从字符 `/` 位置之后开始
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok((new_token_detail(Token::Regexp(value)), rest))
+    // 剩余的字符应该从标志之后开始
+    let rest = move_forword(source, flags_pos + flags_length);
+    Ok((
+        Token::Regexp(value.to_string(), flags.to_string(), group_count),
+        rest,
+    ))
 }
 
-fn lex_hash_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_hash_string(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 哈希字符串
     // 查找连续的字符
     //
@@ -843,36 +1407,26 @@ fn lex_hash_string(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Erro
     // #foo_bar
     //  ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
-
     // 注：第一个字符已经验证过是合法的标识符首个字符，无需再检查
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) if is_valid_letter_of_identifier_or_keyword(*first) => {
-                end_pos += 1;
-                rest
-            }
-            Some(_) => {
-                break;
-            }
-            None => {
-                break;
-            }
-        }
-    }
+    let end_pos = find_end_of_identifier(source);
+    let token = new_hash_string_token(&normalize_identifier(&source[..end_pos]));
 
-    let value_chars = &source_chars[..end_pos];
-    let value = value_chars.iter().collect::<String>();
+    // 剩余的字符应该从标识符位置之后开始，即跳过 end_pos 个字节即可。
+    let rest = move_forword(source, end_pos);
+    Ok((token, rest))
+}
 
-    // 当前 end_pos 处于标识符的最后一个字符位置
-    // 剩余的字符应该从标识符位置之后开始，即跳过 end_pos 个字符即可。
-    let rest = move_forword(source_chars, end_pos);
-    Ok((new_token_detail(Token::HashString(value)), rest))
+// 结构注释 `#disable`（以及 `#禁用`）看起来跟哈希字符串一样，
+// 但它用于注释掉紧随其后的一个语句或者表达式，所以单独作为一种 token。
+pub(crate) fn new_hash_string_token(value: &str) -> Token {
+    match value {
+        "disable" | "禁用" => Token::Disable,
+        _ => Token::HashString(value.to_string()),
+    }
 }
 
-fn lex_named_operator(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_named_operator(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 命名操作符
     // 查找连续的字符，以及结束的 `:` 符号
     //
@@ -880,45 +1434,39 @@ fn lex_named_operator(source_chars: &[char]) -> Result<(TokenDetail, &[char]), E
     // :foo_bar:
     //  ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
-
     // 注：第一个字符已经验证过是合法的标识符首个字符，无需再检查
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) => {
-                if *first == ':' {
-                    // 已找到结束符
-                    break;
-                } else if is_valid_letter_of_identifier_or_keyword(*first) {
-                    // 仍在有效标识符字符之中
-                    end_pos += 1;
-                    rest
-                } else {
-                    // 遇到无效的标识符字符
-                    return Err(Error::LexerError("invalid identifier letter".to_string()));
-                }
-            }
-            None => {
-                // 到了末尾仍未找到结束字符
-                return Err(Error::LexerError(
-                    "expected named operator ending symbol".to_string(),
-                ));
-            }
+    let end_pos = find_end_of_identifier(source);
+
+    match source[end_pos..].chars().next() {
+        Some(':') => {
+            // 已找到结束符
+        }
+        Some(_) => {
+            // 遇到无效的标识符字符
+            return Err(new_error(
+                DiagnosticCode::InvalidIdentifier,
+                "invalid identifier letter",
+            ));
+        }
+        None => {
+            // 到了末尾仍未找到结束字符
+            return Err(new_error(
+                DiagnosticCode::UnterminatedNamedOperator,
+                "expected named operator ending symbol",
+            ));
         }
     }
 
-    let value_chars = &source_chars[..end_pos];
-    let value = value_chars.iter().collect::<String>();
+    let value = normalize_identifier(&source[..end_pos]).into_owned();
 
     // 当前 end_pos 处于字符 `:` 位置
     // 剩余的字符应该从 `:` 位置之后开始
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok((new_token_detail(Token::NamedOperator(value)), rest))
+    let rest = move_forword(source, end_pos + 1);
+    Ok((Token::NamedOperator(value), rest))
 }
 
-fn lex_attribute(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_attribute(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 解析属性（attribute，类似 Java 的 annotation）
     // 查找 `属性` 的结束字符 `]`
     //
@@ -926,41 +1474,70 @@ fn lex_attribute(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error>
     // "#[test]"
     //   ^-------- 当前所在的位置
 
-    // let mut chars = &source_chars[1..];
-    // let mut end_pos: usize = 1;
-
-    let end_pos = match source_chars.iter().position(|c| *c == ']') {
+    let end_pos = match source.find(']') {
         Some(pos) => pos,
         None => {
             // 到了末尾仍未找到结束字符
-            return Err(Error::LexerError(
-                "expected attribute ending symbol".to_string(),
+            return Err(new_error(
+                DiagnosticCode::UnterminatedAttribute,
+                "expected attribute ending symbol",
             ));
         }
     };
 
-    let value_chars = &source_chars[1..end_pos];
-    let value = value_chars.iter().collect::<String>();
+    let value = source[1..end_pos].to_string();
 
     // 当前 end_pos 处于字符 `]` 位置
     // 剩余的字符应该从 `]` 位置之后开始
-    let rest = move_forword(source_chars, end_pos + 1);
-    Ok((new_token_detail(Token::Attribute(value)), rest))
+    let rest = move_forword(source, end_pos + 1);
+    Ok((Token::Attribute(value), rest))
 }
 
-fn lex_16_radix_integer(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+fn lex_16_radix_integer(source: &str) -> Result<(Token, &str), Diagnostic> {
+    // 十六进制整数
+    //
+    // e.g.
+    // 0xff
+    // 0x_a__b___c
+    // 0xffff_ffffu32
+    // ^-------- 当前所在的位置
+
+    lex_radix_integer(source, |b| b.is_ascii_hexdigit(), "hexadecimal")
 }
 
-fn lex_2_radix_integer(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+fn lex_2_radix_integer(source: &str) -> Result<(Token, &str), Diagnostic> {
+    // 二进制整数
+    //
+    // e.g.
+    // 0b1010
+    // 0b0000_1111u8
+    // ^-------- 当前所在的位置
+
+    lex_radix_integer(source, |b| matches!(b, b'0' | b'1'), "binary")
 }
 
-fn lex_zero_point_float(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+fn lex_radix_integer<'a>(
+    source: &'a str,
+    is_digit: fn(u8) -> bool,
+    radix_name: &str,
+) -> Result<(Token, &'a str), Diagnostic> {
+    // 跳过前缀 `0x` 或者 `0b`
+    let end_pos = 2 + source.as_bytes()[2..]
+        .iter()
+        .position(|b| !(is_digit(*b) || *b == b'_'))
+        .unwrap_or(source.len() - 2);
+
+    if !source[2..end_pos].bytes().any(is_digit) {
+        return Err(new_error(
+            DiagnosticCode::InvalidNumber,
+            &format!("expected {} digits", radix_name),
+        ));
+    }
+
+    continue_lex_integer_suffix(source, end_pos)
 }
 
-fn lex_number(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_number(source: &str) -> Result<(Token, &str), Diagnostic> {
     // 整数、浮点数或者比特数
     // 查找连续的数字
     //
@@ -974,284 +1551,432 @@ fn lex_number(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
     // 2.71828
     // 6.626e-34
     // ^-------- 当前所在的位置
-
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
+    //
+    // 后续的 continue_lex_* 函数均从 source 的开头（即数字的开头）截取数字的文本，
+    // 参数 pos 为继续解析的开始位置。
 
     // 注：第一个字符已经验证过是合法的标识符首个数字，无需再检查
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) => {
-                match *first {
-                    '0'..='9' | '_' => {
-                        // 仍在有效的数字之中
-                        end_pos += 1;
-                        rest
-                    }
-                    '.' => {
-                        if is_char('.', rest) {
-                            // 遇到了范围表达式符号 `..`
-                            break;
-                        } else {
-                            // 遇到了浮点数
-                            return continue_lex_float_number(
-                                source_chars[..end_pos].to_vec(),
-                                rest,
-                            );
-                        }
-                    }
-                    '\'' => {
-                        if is_chars(['\'', '\''], rest) {
-                            // 遇到了文档注释
-                            break;
-                        } else {
-                            // 遇到了比特数
-                            return continue_lex_bit_number(source_chars[..end_pos].to_vec(), rest);
-                        }
-                    }
-                    'i' => {
-                        return continue_lex_imaginary_number(
-                            source_chars[..end_pos].to_vec(),
-                            rest,
-                        );
-                    }
-                    'e' => {
-                        return continue_lex_float_number_exponent(
-                            source_chars[..end_pos].to_vec(),
-                            rest,
-                        );
-                    }
-                    _ => {
-                        // 遇到了一个非数字
-                        break;
-                    }
+    let bytes = source.as_bytes();
+    let mut end_pos: usize = 0;
+
+    while end_pos < bytes.len() {
+        match bytes[end_pos] {
+            b'0'..=b'9' | b'_' => {
+                // 仍在有效的数字之中
+                end_pos += 1;
+            }
+            b'.' => {
+                if bytes.get(end_pos + 1) == Some(&b'.') {
+                    // 遇到了范围表达式符号 `..`
+                    break;
+                } else {
+                    // 遇到了浮点数
+                    return continue_lex_float_number(source, end_pos + 1);
                 }
             }
-            None => {
-                // 到了末尾
+            b'\'' => {
+                if is_chars(['\'', '\''], &source[end_pos + 1..]) {
+                    // 遇到了文档注释
+                    break;
+                } else {
+                    // 遇到了比特数
+                    return continue_lex_bit_number(source, end_pos + 1);
+                }
+            }
+            b'i' if !is_integer_suffix_width(&source[end_pos + 1..]) => {
+                // `i` 后面不是数字，所以是虚数而不是类型后缀 `i8`、`i32` 等
+                return continue_lex_imaginary_number(source, end_pos);
+            }
+            b'e' => {
+                return continue_lex_float_number_exponent(source, end_pos + 1);
+            }
+            _ => {
+                // 遇到了一个非数字
                 break;
             }
         }
     }
 
-    let value_chars = &source_chars[..end_pos];
-    let value_string = value_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
+    continue_lex_integer_suffix(source, end_pos)
+}
 
-    // 将字符串转换为数字
-    let value: i64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid integer number".to_string()))?;
+fn is_integer_suffix_width(source: &str) -> bool {
+    source.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn continue_lex_integer_suffix(source: &str, pos: usize) -> Result<(Token, &str), Diagnostic> {
+    // 解析整数的类型后缀
+    // 123u8
+    // ^  ^--- pos
+    // |------ source
+    //
+    // 后缀有 `i8`、`i16`、`i32`、`i64`、`i128`、`u8`、`u16`、`u32`、`u64`、`u128`
+    // 以及表示大整数的 `n`。
+    // 如果后缀后面紧跟着标识符的字符（比如 `1name`），则视为没有后缀。
+
+    let suffix_length = match source.as_bytes().get(pos) {
+        Some(b'i' | b'u') => {
+            1 + source[pos + 1..]
+                .bytes()
+                .take_while(|b| b.is_ascii_digit())
+                .count()
+        }
+        Some(b'n') => 1,
+        _ => 0,
+    };
+
+    let is_followed_by_letter = source[pos + suffix_length..]
+        .starts_with(|c: char| is_valid_letter_of_identifier_or_keyword(c));
+
+    let (integer_type, end_pos) = if suffix_length == 0 || is_followed_by_letter {
+        (None, pos)
+    } else {
+        let suffix = &source[pos..pos + suffix_length];
+        match IntegerType::from_suffix(suffix) {
+            Some(integer_type) => (Some(integer_type), pos + suffix_length),
+            None => {
+                return Err(new_error_at(
+                    DiagnosticCode::InvalidNumber,
+                    &format!("invalid integer type suffix \"{}\"", suffix),
+                    0,
+                    pos + suffix_length,
+                )
+                .with_help(
+                    "valid suffixes are i8, i16, i32, i64, i128, u8, u16, u32, u64, u128 and n",
+                ))
+            }
+        }
+    };
+
+    let literal = IntegerLiteral {
+        digits: source[..pos].to_string(),
+        integer_type,
+    };
+
+    check_integer_range(&literal, end_pos)?;
+
+    // 剩余的字符应该从数字（以及后缀）位置之后开始
+    let rest = move_forword(source, end_pos);
+    Ok((Token::Integer(literal), rest))
+}
+
+// 检查整数字面量是否超出其数据类型的范围，没有后缀的字面量按照 Int64 检查
+//
+// 因为负号不属于字面量，所以有符号整数允许数值等于最小值的绝对值，
+// 比如 `128i8`（即 `-128i8` 的数值部分），
+// 语法分析阶段再检查没有负号的情况。
+//
+// 参数 length 为字面量（包括后缀）的字节数，错误的位置为整个字面量。
+pub(crate) fn check_integer_range(
+    literal: &IntegerLiteral,
+    length: usize,
+) -> Result<(), Diagnostic> {
+    let integer_type = literal.integer_type.unwrap_or(IntegerType::Int64);
+
+    let max_magnitude = match integer_type.max_value() {
+        Some(max_value) => max_value.max(integer_type.min_magnitude().unwrap_or(0)),
+        None => return Ok(()), // 大整数没有范围限制
+    };
+
+    match literal.to_u128() {
+        Some(value) if value <= max_magnitude => Ok(()),
+        _ => {
+            let mut diagnostic = new_integer_overflow_error(integer_type);
+            diagnostic.location = Location {
+                file_id: 0,
+                start: 0,
+                end: length,
+            };
+            Err(diagnostic)
+        }
+    }
+}
 
-    // 当前 end_pos 处于标识符的最后一个数字位置
-    // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字符即可。
-    let rest = move_forword(source_chars, end_pos);
+pub fn new_integer_overflow_error(integer_type: IntegerType) -> Diagnostic {
+    let (min_value, max_value) = match (integer_type.min_magnitude(), integer_type.max_value()) {
+        (Some(0), Some(max)) => ("0".to_string(), max.to_string()),
+        (Some(min), Some(max)) => (format!("-{}", min), max.to_string()),
+        _ => unreachable!(),
+    };
 
-    Ok((new_token_detail(Token::Integer(value)), rest))
+    new_error(
+        DiagnosticCode::IntegerOverflow,
+        &format!(
+            "integer literal is out of range for type {}",
+            integer_type.name()
+        ),
+    )
+    .with_note(&format!(
+        "the range of type {} is {}..={}",
+        integer_type.name(),
+        min_value,
+        max_value
+    ))
+    .with_help("use a wider type suffix, or the suffix \"n\" for big integers")
 }
 
-fn extend_vec_with_with_separator_and_char_slice(
-    mut left: Vec<char>,
-    separator: char,
-    right: &[char],
-) -> Vec<char> {
-    left.push(separator);
-    left.extend_from_slice(right);
-    left
+// 移除数字文本当中的下划线，然后转换为数字
+fn parse_number_text<T: FromStr>(text: &str) -> Option<T> {
+    let value_string = if text.contains('_') {
+        Cow::Owned(text.replace('_', ""))
+    } else {
+        Cow::Borrowed(text)
+    };
+
+    value_string.parse().ok()
 }
 
-fn continue_lex_float_number(
-    previous_chars: Vec<char>,
-    remain_chars: &[char],
-) -> Result<(TokenDetail, &[char]), Error> {
+fn continue_lex_float_number(source: &str, pos: usize) -> Result<(Token, &str), Diagnostic> {
     // 继续解析小数点后面部分
     // 123.456
-    // ___ ___ remain_chars
-    //   |____ previous_chars
+    // ^   ^--- pos
+    // |------- source
 
-    let mut chars = remain_chars;
-    let mut end_pos: usize = 0;
+    let bytes = source.as_bytes();
+    let mut end_pos = pos;
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) => {
-                match *first {
-                    '0'..='9' | '_' => {
-                        // 仍在有效的数字之中
-                        end_pos += 1;
-                        rest
-                    }
-                    '.' => return Err(Error::LexerError("invalid float number".to_string())),
-                    '\'' => return Err(Error::LexerError("invalid bit number".to_string())),
-                    'i' => {
-                        let extend_chars = extend_vec_with_with_separator_and_char_slice(
-                            previous_chars,
-                            '.',
-                            &remain_chars[..end_pos],
-                        );
-                        return continue_lex_imaginary_number(extend_chars, rest);
-                    }
-                    'e' => {
-                        let extend_chars = extend_vec_with_with_separator_and_char_slice(
-                            previous_chars,
-                            '.',
-                            &remain_chars[..end_pos],
-                        );
-                        return continue_lex_float_number_exponent(extend_chars, rest);
-                    }
-                    _ => {
-                        // 遇到了一个非数字
-                        break;
-                    }
-                }
+    while end_pos < bytes.len() {
+        match bytes[end_pos] {
+            b'0'..=b'9' | b'_' => {
+                // 仍在有效的数字之中
+                end_pos += 1;
             }
-            None => {
-                // 到了末尾
+            b'.' => {
+                return Err(new_error(
+                    DiagnosticCode::InvalidNumber,
+                    "invalid float number",
+                ))
+            }
+            b'\'' => {
+                return Err(new_error(
+                    DiagnosticCode::InvalidNumber,
+                    "invalid bit number",
+                ))
+            }
+            b'i' => {
+                return continue_lex_imaginary_number(source, end_pos);
+            }
+            b'e' => {
+                return continue_lex_float_number_exponent(source, end_pos + 1);
+            }
+            _ => {
+                // 遇到了一个非数字
                 break;
             }
         }
     }
 
-    let value_chars = extend_vec_with_with_separator_and_char_slice(
-        previous_chars,
-        '.',
-        &remain_chars[..end_pos],
-    );
-
-    let value_string = value_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
-
     // 将字符串转换为数字
-    let value: f64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid float number".to_string()))?;
+    let value: f64 = parse_number_text(&source[..end_pos])
+        .ok_or_else(|| new_error(DiagnosticCode::InvalidNumber, "invalid float number"))?;
+    check_float_range(value, end_pos)?;
 
-    // 当前 end_pos 处于数字的最后一个字符位置
-    // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字符即可。
-    let rest = move_forword(remain_chars, end_pos);
+    // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字节即可。
+    let rest = move_forword(source, end_pos);
 
-    Ok((new_token_detail(Token::Float(value)), rest))
+    Ok((Token::Float(value), rest))
 }
 
-fn continue_lex_imaginary_number(
-    previous_chars: Vec<char>,
-    remain_chars: &[char],
-) -> Result<(TokenDetail, &[char]), Error> {
+fn continue_lex_imaginary_number(source: &str, pos: usize) -> Result<(Token, &str), Diagnostic> {
     // 解析虚数部分
     // 123i...
-    // ___ ___ remain_chars
-    //   |____ previous_chars
-
-    let value_string = previous_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
+    // ^  ^--- pos
+    // |------ source
 
     // 将字符串转换为数字
-    let value: f64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid float number".to_string()))?;
+    let value: f64 = parse_number_text(&source[..pos])
+        .ok_or_else(|| new_error(DiagnosticCode::InvalidNumber, "invalid float number"))?;
+    check_float_range(value, pos + 1)?;
+
+    // 跳过字符 `i`
+    Ok((Token::Imaginary(value), move_forword(source, pos + 1)))
+}
 
-    Ok((new_token_detail(Token::Imaginary(value)), remain_chars))
+// 检查浮点数（以及虚数）字面量是否在 f64 的范围之内
+//
+// 超出范围的字面量（比如 `1e3001`）会被转换为无穷大，
+// 而无穷大无法再以字面量的形式写回源代码，所以在词法分析阶段就报告错误。
+// `length` 是整个字面量（包括虚数的后缀 `i`）的长度，用于指出错误的范围。
+fn check_float_range(value: f64, length: usize) -> Result<(), Diagnostic> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(new_error_at(
+            DiagnosticCode::InvalidNumber,
+            "float number is out of range",
+            0,
+            length,
+        )
+        .with_note(&format!("the maximum float number is {:e}", f64::MAX)))
+    }
 }
 
-fn continue_lex_bit_number(
-    previous_chars: Vec<char>,
-    remain_chars: &[char],
-) -> Result<(TokenDetail, &[char]), Error> {
-    todo!()
+// 比特数的最大位宽
+const MAX_BIT_WIDTH: usize = 65536;
+
+fn continue_lex_bit_number(source: &str, pos: usize) -> Result<(Token, &str), Diagnostic> {
+    // 解析比特数的进制以及数值部分
+    // 8'xff
+    // ^ ^--- pos
+    // |----- source
+
+    let width: usize = parse_number_text(&source[..pos - 1])
+        .ok_or_else(|| new_error(DiagnosticCode::InvalidNumber, "invalid bit number"))?;
+
+    if width == 0 || width > MAX_BIT_WIDTH {
+        return Err(new_error_at(
+            DiagnosticCode::InvalidNumber,
+            "invalid bit width",
+            0,
+            pos - 1,
+        )
+        .with_note(&format!(
+            "the width of bit number should be between 1 and {}",
+            MAX_BIT_WIDTH
+        )));
+    }
+
+    let radix = match source.as_bytes().get(pos) {
+        Some(b'b') => 2,
+        Some(b'o') => 8,
+        Some(b'd') => 10,
+        Some(b'x') => 16,
+        _ => {
+            return Err(new_error_at(
+                DiagnosticCode::InvalidNumber,
+                "invalid bit number",
+                pos - 1,
+                pos,
+            )
+            .with_help("the base of bit number should be 'b, 'o, 'd or 'x, e.g. 8'xff"))
+        }
+    };
+
+    // 数值部分包括数字、字母以及下划线，其中的字母用于报告无效的数字
+    let digits_pos = pos + 1;
+    let digits_length = source[digits_pos..]
+        .bytes()
+        .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        .count();
+    let end_pos = digits_pos + digits_length;
+    let digits_text = &source[digits_pos..end_pos];
+
+    if let Some(invalid_pos) = digits_text
+        .bytes()
+        .position(|b| b != b'_' && !(b as char).is_digit(radix))
+    {
+        return Err(new_error_at(
+            DiagnosticCode::InvalidNumber,
+            &format!("invalid digit for a base {} bit number", radix),
+            digits_pos + invalid_pos,
+            digits_pos + invalid_pos + 1,
+        ));
+    }
+
+    let digits = digits_text.replace('_', "");
+    if digits.is_empty() {
+        return Err(new_error_at(
+            DiagnosticCode::InvalidNumber,
+            "expected the value of bit number",
+            0,
+            end_pos,
+        ));
+    }
+
+    let literal = BitLiteral::from_digits(width, radix, &digits).ok_or_else(|| {
+        new_error_at(
+            DiagnosticCode::IntegerOverflow,
+            &format!("bit number is wider than its width of {} bits", width),
+            0,
+            end_pos,
+        )
+        .with_help("increase the width, or remove the leading digits")
+    })?;
+
+    // 剩余的字符应该从数值之后开始
+    let rest = move_forword(source, end_pos);
+    Ok((Token::Bit(literal), rest))
 }
 
 fn continue_lex_float_number_exponent(
-    previous_chars: Vec<char>,
-    remain_chars: &[char],
-) -> Result<(TokenDetail, &[char]), Error> {
+    source: &str,
+    pos: usize,
+) -> Result<(Token, &str), Diagnostic> {
     // 继续解析 e 后面部分
     // 123e-30
-    // ___ ___ remain_chars
-    //   |____ previous_chars
+    // ^   ^--- pos
+    // |------- source
 
-    let mut chars = remain_chars;
-    let mut end_pos: usize = 0;
+    let bytes = source.as_bytes();
+    let mut end_pos = pos;
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) => {
-                match *first {
-                    '-' => {
-                        if end_pos == 0 {
-                            end_pos += 1;
-                            rest
-                        } else {
-                            return Err(Error::LexerError("invalid exponent number".to_string()));
-                        }
-                    }
-                    '0'..='9' | '_' => {
-                        // 仍在有效的数字之中
-                        end_pos += 1;
-                        rest
-                    }
-                    '.' => {
-                        if is_char('.', rest) {
-                            // 遇到了范围表达式符号 `..`
-                            break;
-                        } else {
-                            // 不支持 "指数值为小数" 的浮点数
-                            return Err(Error::LexerError("unsupport float exponent".to_string()));
-                        }
-                    }
-                    '\'' => return Err(Error::LexerError("invalid bit number".to_string())),
-                    'i' => {
-                        let extend_chars = extend_vec_with_with_separator_and_char_slice(
-                            previous_chars,
-                            'e',
-                            &remain_chars[..end_pos],
-                        );
-                        return continue_lex_imaginary_number(extend_chars, rest);
-                    }
-                    'e' => return Err(Error::LexerError("invalid exponent number".to_string())),
-                    _ => {
-                        // 遇到了一个非数字
-                        break;
-                    }
+    while end_pos < bytes.len() {
+        match bytes[end_pos] {
+            b'-' => {
+                if end_pos == pos {
+                    end_pos += 1;
+                } else {
+                    return Err(new_error(
+                        DiagnosticCode::InvalidNumber,
+                        "invalid exponent number",
+                    ));
                 }
             }
-            None => {
-                // 到了末尾
+            b'0'..=b'9' | b'_' => {
+                // 仍在有效的数字之中
+                end_pos += 1;
+            }
+            b'.' => {
+                if bytes.get(end_pos + 1) == Some(&b'.') {
+                    // 遇到了范围表达式符号 `..`
+                    break;
+                } else {
+                    // 不支持 "指数值为小数" 的浮点数
+                    return Err(new_error(
+                        DiagnosticCode::InvalidNumber,
+                        "unsupport float exponent",
+                    ));
+                }
+            }
+            b'\'' => {
+                return Err(new_error(
+                    DiagnosticCode::InvalidNumber,
+                    "invalid bit number",
+                ))
+            }
+            b'i' => {
+                return continue_lex_imaginary_number(source, end_pos);
+            }
+            b'e' => {
+                return Err(new_error(
+                    DiagnosticCode::InvalidNumber,
+                    "invalid exponent number",
+                ))
+            }
+            _ => {
+                // 遇到了一个非数字
                 break;
             }
         }
     }
 
-    let value_chars = extend_vec_with_with_separator_and_char_slice(
-        previous_chars,
-        'e',
-        &remain_chars[..end_pos],
-    );
-
-    let value_string = value_chars
-        .iter()
-        .filter(|c| **c != '_') // 移除字符串当中的下划线
-        .collect::<String>();
-
     // 将字符串转换为数字
-    let value: f64 = value_string
-        .parse()
-        .map_err(|_| Error::LexerError("invalid float number".to_string()))?;
+    let value: f64 = parse_number_text(&source[..end_pos])
+        .ok_or_else(|| new_error(DiagnosticCode::InvalidNumber, "invalid float number"))?;
+    check_float_range(value, end_pos)?;
 
-    // 当前 end_pos 处于数字的最后一个字符位置
-    // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字符即可。
-    let rest = move_forword(remain_chars, end_pos);
+    // 剩余的字符应该从数字位置之后开始，即跳过 end_pos 个字节即可。
+    let rest = move_forword(source, end_pos);
 
-    Ok((new_token_detail(Token::Float(value)), rest))
+    Ok((Token::Float(value), rest))
 }
 
-fn lex_identifier_or_keyword(source_chars: &[char]) -> Result<(TokenDetail, &[char]), Error> {
+fn lex_identifier_or_keyword<'a>(
+    source: &'a str,
+    symbols: &mut HashMap<&'a str, Rc<str>>,
+    keywords: &KeywordTable,
+) -> (Token, &'a str) {
     // 标识符或者关键字
     // 查找连续的字符
     //
@@ -1259,174 +1984,156 @@ fn lex_identifier_or_keyword(source_chars: &[char]) -> Result<(TokenDetail, &[ch
     // foo_bar
     // ^-------- 当前所在的位置
 
-    let mut chars = source_chars;
-    let mut end_pos: usize = 0;
-
     // 注：第一个字符已经验证过是合法的标识符首个字符，无需再检查
 
-    loop {
-        chars = match chars.split_first() {
-            Some((first, rest)) => {
-                if is_valid_letter_of_identifier_or_keyword(*first) {
-                    // 仍在有效标识符字符之中
-                    end_pos += 1;
-                    rest
-                } else {
-                    // 遇到无效的标识符字符，提前退出循环
-                    break;
-                }
-            }
-            None => {
-                // 到了末尾
-                break;
-            }
+    let end_pos = find_end_of_identifier(source);
+    let value = &source[..end_pos];
+
+    // 剩余的字符应该从标识符位置之后开始，即跳过 end_pos 个字节即可。
+    let rest = move_forword(source, end_pos);
+
+    // 标识符按 NFC 规范化，以便组合形式与预组合形式的同一个名称相等
+    let normalized = normalize_identifier(value);
+
+    match keywords.lookup(&normalized) {
+        Some(token) => (token, rest),
+        None => {
+            // 同名的标识符共享同一个 Rc<str>
+            let name = symbols
+                .entry(value)
+                .or_insert_with(|| Rc::from(normalized.as_ref()))
+                .clone();
+            (Token::Identifier(name), rest)
         }
     }
+}
 
-    let value_chars = &source_chars[..end_pos];
-    let value = value_chars.iter().collect::<String>();
-
-    // 当前 end_pos 处于标识符的最后一个字符位置
-    // 剩余的字符应该从标识符位置之后开始，即跳过 end_pos 个字符即可。
-    let rest = move_forword(source_chars, end_pos);
+// 查找连续的标识符字符的结束位置
+fn find_end_of_identifier(source: &str) -> usize {
+    // 大部分标识符只包含 ASCII 字符，所以先逐个字节检查，
+    // 遇到非 ASCII 字符之后再逐个字符检查
+    for (index, b) in source.bytes().enumerate() {
+        if !b.is_ascii() {
+            let rest = &source[index..];
+            return index
+                + rest
+                    .find(|c| !is_valid_letter_of_identifier_or_keyword(c))
+                    .unwrap_or(rest.len());
+        }
 
-    match lookup_keyword(&value) {
-        Some(token) => Ok((new_token_detail(token), rest)),
-        None => Ok((new_token_detail(Token::Identifier(value)), rest)),
+        if !is_valid_letter_of_identifier_or_keyword(b as char) {
+            return index;
+        }
     }
+
+    source.len()
 }
 
 fn is_none_zero_number(c: char) -> bool {
-    match c {
-        '1'..='9' => true,
-        _ => false,
-    }
+    matches!(c, '1'..='9')
 }
 
-// 可以作为标识符或者关键字的首位的文字
+// 可以作为标识符或者关键字的首位的文字，非 ASCII 字符遵循 UAX#31 的 XID_Start
 fn is_valid_first_letter_of_identifier_or_keyword(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '_' => true,
-        _ => false,
-    }
+    is_identifier_start(c)
 }
 
-// 可以作为标识符或者关键字的文字（数字、字母、中文文字等）
+// 可以作为标识符或者关键字的文字（数字、字母、中文文字等），非 ASCII 字符遵循 UAX#31 的 XID_Continue
 fn is_valid_letter_of_identifier_or_keyword(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '_' | '0'..='9' => true,
-        _ => false,
-    }
+    is_identifier_continue(c)
 }
 
-fn is_char(expected: char, source_chars: &[char]) -> bool {
-    match source_chars.first() {
-        Some(first_char) if *first_char == expected => true,
-        _ => false,
-    }
+fn split_first_char(source: &str) -> Option<(char, &str)> {
+    let mut chars = source.chars();
+    chars.next().map(|first| (first, chars.as_str()))
 }
 
-fn is_chars(expected: [char; 2], source_chars: &[char]) -> bool {
-    match source_chars.split_first() {
-        Some((first, rest)) if *first == expected[0] => is_char(expected[1], rest),
-        _ => false,
-    }
+fn is_char(expected: char, source: &str) -> bool {
+    source.starts_with(expected)
 }
 
-fn move_forword(source_chars: &[char], count: usize) -> &[char] {
-    &source_chars[count..]
+fn is_chars(expected: [char; 2], source: &str) -> bool {
+    let mut chars = source.chars();
+    chars.next() == Some(expected[0]) && chars.next() == Some(expected[1])
 }
 
-fn add_token_detail(
-    token_details: &mut Vec<TokenDetail>,
-    token_detail: TokenDetail,
-) -> &mut Vec<TokenDetail> {
-    token_details.push(token_detail);
-    token_details
+// 参数 count 为字节数
+fn move_forword(source: &str, count: usize) -> &str {
+    &source[count..]
 }
 
-fn new_token_detail(token: Token) -> TokenDetail {
-    TokenDetail {
-        location: new_location(),
-        token,
-    }
+// 错误的位置由 Lexer 统一设置，即当前 token 的第一个字符
+pub(crate) fn new_error(code: DiagnosticCode, message: &str) -> Diagnostic {
+    Diagnostic::error(
+        code,
+        message,
+        Location {
+            file_id: 0,
+            start: 0,
+            end: 0,
+        },
+    )
 }
 
-fn new_location() -> Location {
-    // todo::
-    // Location 各成员值应该由参数传入
-    Location {
-        file_id: 0,
-        start: 0,
-        end: 0,
-    }
+// 指定错误位置的错误，参数 start 和 end 为相对于当前 token 开始位置的偏移量（字节数）
+pub(crate) fn new_error_at(
+    code: DiagnosticCode,
+    message: &str,
+    start: usize,
+    end: usize,
+) -> Diagnostic {
+    Diagnostic::error(
+        code,
+        message,
+        Location {
+            file_id: 0,
+            start,
+            end,
+        },
+    )
 }
 
-// 用于检测字符是关键字还是标识符
-fn lookup_keyword(name: &str) -> Option<Token> {
-    match name {
-        // 字面量
-        "true" => Some(Token::Boolean(true)),
-        "false" => Some(Token::Boolean(false)),
-
-        // 关键字
-        "do" => Some(Token::Do),
-        "join" => Some(Token::Join),
-
-        "let" => Some(Token::Let),
-        "fn" => Some(Token::Fn),
-        "sign" => Some(Token::Sign),
-
-        "if" => Some(Token::If),
-        "then" => Some(Token::Then),
-        "else" => Some(Token::Else),
-        "for" => Some(Token::For),
-        "next" => Some(Token::Next),
-        "each" => Some(Token::Each),
-        "in" => Some(Token::In),
-
-        "branch" => Some(Token::Branch),
-        "match" => Some(Token::Match),
-        "case" => Some(Token::Case),
-        "default" => Some(Token::Default),
-        "where" => Some(Token::Where),
-        "only" => Some(Token::Only),
-        // "as" => Some(Token::As),
-        "into" => Some(Token::Into),
-        "regular" => Some(Token::Regular),
-        "template" => Some(Token::Template),
-
-        "function" => Some(Token::Function),
-        "type" => Some(Token::Type),
-        "which" => Some(Token::Which),
-        "empty" => Some(Token::Empty),
-        "pattern" => Some(Token::Pattern),
-        "limit" => Some(Token::Limit),
-
-        // "namespace" => Some(Token::Namespace),
-        "use" => Some(Token::Use),
-        "const" => Some(Token::Const),
-        "enum" => Some(Token::Enum),
-        "struct" => Some(Token::Struct),
-        "union" => Some(Token::Union),
-        "trait" => Some(Token::Trait),
-        "impl" => Some(Token::Impl),
-        "alias" => Some(Token::Alias),
-        _ => None,
+// 获取错误相对于当前 token 开始位置的范围
+//
+// 参数 source 为从当前 token 开始的源文本，由 new_error 产生的错误
+// 没有指定位置，此时为当前 token 的第一个字符。
+pub(crate) fn get_error_range(diagnostic: &Diagnostic, source: &str) -> (usize, usize) {
+    let location = &diagnostic.location;
+    if location.end > 0 {
+        (location.start, location.end)
+    } else {
+        (0, source.chars().next().map_or(0, char::len_utf8))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        lexer::new_location,
-        token::{Token, TokenDetail},
+        error::{DiagnosticCode, Severity},
+        token::{
+            BitLiteral, IntegerLiteral, IntegerType, Location, Token, TokenDetail, TriviaKind,
+        },
     };
 
-    use super::tokenize;
+    use std::rc::Rc;
+
+    use crate::keyword::{KeywordTable, KeywordTables};
+
+    use super::{
+        split_template_string, tokenize, tokenize_lossless, tokenize_recovering,
+        tokenize_with_file_id, tokenize_with_locale, Lexer, TemplatePlaceholder,
+    };
 
     // 辅助函数
 
+    fn new_location(start: usize, end: usize) -> Location {
+        Location {
+            file_id: 0,
+            start,
+            end,
+        }
+    }
+
     fn token_details_to_string(token_details: &[TokenDetail]) -> Vec<String> {
         let strings: Vec<String> = token_details.iter().map(|t| t.token.to_string()).collect();
         strings
@@ -1438,6 +2145,106 @@ mod tests {
         assert_eq!(token_details.len(), 0);
     }
 
+    #[test]
+    fn test_location() {
+        let token_details = tokenize_with_file_id("let a = \"文字\"\n  foo", 2).unwrap();
+        let locations: Vec<(usize, usize, usize)> = token_details
+            .iter()
+            .map(|t| (t.location.file_id, t.location.start, t.location.end))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                (2, 0, 3),
+                (2, 4, 5),
+                (2, 6, 7),
+                (2, 8, 16),
+                (2, 16, 17),
+                (2, 19, 22)
+            ]
+        );
+
+        // 注释和空白不产生 token
+        let token_details = tokenize("1 /* foo */ 23 // bar").unwrap();
+        let locations: Vec<(usize, usize)> = token_details
+            .iter()
+            .map(|t| (t.location.start, t.location.end))
+            .collect();
+        assert_eq!(locations, vec![(0, 1), (12, 14)]);
+    }
+
+    #[test]
+    fn test_lossless() {
+        let texts = [
+            "",
+            "  ",
+            "// foo",
+            "let a = 1 // one\n\tlet b = /* two */ 2\r\n",
+            "/* foo\n bar */ a\n  '''doc\n'''\nfunction f() = 1_000 ;  \n// end",
+            "1\n  /* tail */",
+            "\"文字\" #[test] ~/a\\/b/ :op: 3.14e2i",
+        ];
+
+        for text in texts {
+            let lossless_tokens = tokenize_lossless(text, 0).unwrap();
+            assert_eq!(lossless_tokens.to_source_text(), text);
+
+            // 无损模式的 token 跟普通模式的一致
+            let tokens1: Vec<Token> = lossless_tokens
+                .token_details
+                .iter()
+                .map(|t| t.token.clone())
+                .collect();
+            let tokens2: Vec<Token> = tokenize(text)
+                .unwrap()
+                .iter()
+                .map(|t| t.token.clone())
+                .collect();
+            assert_eq!(tokens1, tokens2);
+        }
+
+        // 检查 trivia 的附着位置
+        let lossless_tokens = tokenize_lossless("a /* b */ // c\n  d  ", 0).unwrap();
+        let token_details = &lossless_tokens.token_details;
+        assert_eq!(token_details.len(), 3);
+
+        let t0 = token_details[0].trivia.as_ref().unwrap();
+        assert!(t0.leading.is_empty());
+        assert_eq!(t0.lexeme, "a");
+        assert_eq!(
+            t0.trailing
+                .iter()
+                .map(|t| t.kind)
+                .collect::<Vec<TriviaKind>>(),
+            vec![
+                TriviaKind::Whitespace,
+                TriviaKind::BlockComment,
+                TriviaKind::Whitespace,
+                TriviaKind::LineComment
+            ]
+        );
+        assert_eq!(t0.trailing[3].text, "// c");
+        assert_eq!(t0.trailing[3].location.start, 10);
+
+        let t1 = token_details[1].trivia.as_ref().unwrap();
+        assert!(t1.leading.is_empty());
+        assert!(t1.trailing.is_empty());
+
+        let t2 = token_details[2].trivia.as_ref().unwrap();
+        assert_eq!(t2.leading[0].text, "  ");
+        assert_eq!(t2.trailing[0].text, "  ");
+
+        // 源文件末尾的 trivia
+        let lossless_tokens = tokenize_lossless("a\n// end", 0).unwrap();
+        assert_eq!(
+            lossless_tokens.end_of_file_trivia[0].kind,
+            TriviaKind::LineComment
+        );
+
+        // 普通模式不附带 trivia
+        assert!(tokenize("a // b").unwrap()[0].trivia.is_none());
+    }
+
     #[test]
     fn test_comment() {
         let tokens1 = tokenize("/").unwrap();
@@ -1453,9 +2260,64 @@ mod tests {
         let tokens4 = tokenize("1/*com//me**nt*/2").unwrap();
         assert_eq!(token_details_to_string(&tokens4), vec!["1", "2"]);
 
+        // 测试嵌套的区域注释
+        let tokens4b = tokenize("1/* outer /* inner */ still outer */2 /*/ a */3").unwrap();
+        assert_eq!(token_details_to_string(&tokens4b), vec!["1", "2", "3"]);
+
         // 测试文档注释
         let tokens5 = tokenize("1'''docu//ment''com'ment/*foo*/bar'''2").unwrap();
-        assert_eq!(token_details_to_string(&tokens5), vec!["1", "2"]);
+        assert_eq!(
+            token_details_to_string(&tokens5),
+            vec!["1", "'''docu//ment''com'ment/*foo*/bar'''", "2"]
+        );
+        assert_eq!(
+            tokens5[1].token,
+            Token::DocComment("docu//ment''com'ment/*foo*/bar".to_string())
+        );
+
+        let tokens6 = tokenize("'''\nfoo\n'''\nfunction").unwrap();
+        assert_eq!(
+            tokens6
+                .iter()
+                .map(|t| t.token.clone())
+                .collect::<Vec<Token>>(),
+            vec![
+                Token::DocComment("\nfoo\n".to_string()),
+                Token::NewLine,
+                Token::Function
+            ]
+        );
+
+        // 测试范围注释
+        let tokens7 = tokenize("a\n// title\n  // desc\n// ====\nb\n// ---\nc").unwrap();
+        assert_eq!(
+            tokens7
+                .iter()
+                .map(|t| t.token.clone())
+                .collect::<Vec<Token>>(),
+            vec![
+                Token::Identifier(Rc::from("a")),
+                Token::NewLine,
+                Token::RegionStart("title\ndesc".to_string()),
+                Token::NewLine,
+                Token::Identifier(Rc::from("b")),
+                Token::NewLine,
+                Token::RegionEnd,
+                Token::NewLine,
+                Token::Identifier(Rc::from("c")),
+            ]
+        );
+        assert_eq!(tokens7[2].location, new_location(2, 28));
+
+        // 不包含粗横线的连续行注释是普通的行注释
+        let tokens8 = tokenize("// foo\n// bar\n// --\na").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens8),
+            vec!["\n", "\n", "\n", "a"]
+        );
+
+        let lossless = tokenize_lossless("// foo\n// ===\na\n// ---\n", 0).unwrap();
+        assert_eq!(lossless.to_source_text(), "// foo\n// ===\na\n// ---\n");
     }
 
     #[test]
@@ -1474,6 +2336,23 @@ mod tests {
             token_details_to_string(&tokens1),
             vec!["a", "ab", "a_b", "a123", "_", "_a", "a_"]
         );
+
+        // Unicode 标识符（UAX#31）
+        let tokens2 = tokenize("中文 名称Id αβ café").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens2),
+            vec!["中文", "名称Id", "αβ", "café"]
+        );
+
+        // 组合形式与预组合形式按 NFC 规范化为同一个标识符
+        let tokens3 = tokenize("cafe\u{301} caf\u{e9}").unwrap();
+        assert_eq!(tokens3[0].token, tokens3[1].token);
+        assert_eq!(tokens3[0].token.to_string(), "caf\u{e9}");
+        assert_eq!(tokens3[0].location.end, 6);
+
+        // 不属于 XID_Start 的字符不能作为标识符的开头
+        assert!(tokenize("\u{301}a").is_err());
+        assert!(tokenize("🐱").is_err());
     }
 
     #[test]
@@ -1482,8 +2361,13 @@ mod tests {
         assert_eq!(
             tokens1,
             vec![TokenDetail {
-                token: Token::Integer(123),
-                location: new_location()
+                token: Token::Integer(IntegerLiteral::new("123", None)),
+                location: Location {
+                    file_id: 0,
+                    start: 0,
+                    end: 3
+                },
+                trivia: None
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["123"]);
@@ -1491,20 +2375,115 @@ mod tests {
         let tokens2 = tokenize("1 100 1_234 1_2_3").unwrap();
         assert_eq!(
             token_details_to_string(&tokens2),
-            vec!["1", "100", "1234", "123"]
+            vec!["1", "100", "1_234", "1_2_3"]
+        );
+
+        // 16 进制和 2 进制表示法的整数
+        let tokens3 = tokenize("0xff 0x_a__b 0b1010 0b0000_1111").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens3),
+            vec!["0xff", "0x_a__b", "0b1010", "0b0000_1111"]
+        );
+
+        // 数字 0
+        let tokens4 = tokenize("0 0.5 0..2 0i").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens4),
+            vec!["0", "0.5", "0", "..", "2", "0i"]
         );
 
-        // todo:: 测试 16 进制和 2 进制表示法的整数
+        // 类型后缀
+        let tokens5 =
+            tokenize("255u8 1i32 0xffff_ffff_ffff_ffffu64 10_000_000_000_000_000_000n").unwrap();
+        assert_eq!(
+            tokens5
+                .iter()
+                .map(|t| t.token.clone())
+                .collect::<Vec<Token>>(),
+            vec![
+                Token::Integer(IntegerLiteral::new("255", Some(IntegerType::Natural8))),
+                Token::Integer(IntegerLiteral::new("1", Some(IntegerType::Int32))),
+                Token::Integer(IntegerLiteral::new(
+                    "0xffff_ffff_ffff_ffff",
+                    Some(IntegerType::Natural64)
+                )),
+                Token::Integer(IntegerLiteral::new(
+                    "10_000_000_000_000_000_000",
+                    Some(IntegerType::BigInt)
+                )),
+            ]
+        );
+        assert_eq!(
+            token_details_to_string(&tokens5),
+            vec![
+                "255u8",
+                "1i32",
+                "0xffff_ffff_ffff_ffffu64",
+                "10_000_000_000_000_000_000n"
+            ]
+        );
+
+        // 128 位整数
+        let tokens6 = tokenize("340282366920938463463374607431768211455u128").unwrap();
+        match &tokens6[0].token {
+            Token::Integer(literal) => assert_eq!(literal.to_u128(), Some(u128::MAX)),
+            _ => unreachable!(),
+        }
+
+        // 后缀后面紧跟标识符的字符，视为没有后缀
+        let tokens7 = tokenize("1name").unwrap();
+        assert_eq!(token_details_to_string(&tokens7), vec!["1", "name"]);
+
+        // 超出范围
+        let e1 = tokenize("256u8").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::IntegerOverflow);
+        assert_eq!(
+            &*e1.message,
+            "integer literal is out of range for type Natural8"
+        );
+        assert_eq!(e1.notes, vec!["the range of type Natural8 is 0..=255"]);
+        assert_eq!((e1.location.start, e1.location.end), (0, 5));
+
+        let e2 = tokenize("let a = 1_000u8").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::IntegerOverflow);
+        assert_eq!((e2.location.start, e2.location.end), (8, 15));
+
+        assert_eq!(
+            tokenize("9223372036854775809").unwrap_err().code,
+            DiagnosticCode::IntegerOverflow
+        );
+        assert_eq!(
+            tokenize("129i8").unwrap_err().code,
+            DiagnosticCode::IntegerOverflow
+        );
+
+        // `128i8` 可能是 `-128i8` 的一部分，由语法分析检查
+        assert!(tokenize("128i8").is_ok());
+
+        // 无效的后缀以及数字
+        let e3 = tokenize("a + 1u7").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::InvalidNumber);
+        assert_eq!((e3.location.start, e3.location.end), (4, 7));
+        assert_eq!(
+            tokenize("0x").unwrap_err().code,
+            DiagnosticCode::InvalidNumber
+        );
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_float_literal() {
         let tokens1 = tokenize("3.14").unwrap();
         assert_eq!(
             tokens1,
             vec![TokenDetail {
                 token: Token::Float(3.14),
-                location: new_location()
+                location: Location {
+                    file_id: 0,
+                    start: 0,
+                    end: 4
+                },
+                trivia: None
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["3.14"]);
@@ -1513,10 +2492,10 @@ mod tests {
         assert_eq!(token_details_to_string(&tokens2), vec!["27.182818"]);
 
         let tokens3 = tokenize("5e2").unwrap();
-        assert_eq!(token_details_to_string(&tokens3), vec!["500"]);
+        assert_eq!(token_details_to_string(&tokens3), vec!["500.0"]);
 
         let tokens4 = tokenize("1.6e2").unwrap();
-        assert_eq!(token_details_to_string(&tokens4), vec!["160"]);
+        assert_eq!(token_details_to_string(&tokens4), vec!["160.0"]);
 
         let tokens5 = tokenize("1.6e-2").unwrap();
         assert_eq!(token_details_to_string(&tokens5), vec!["0.016"]);
@@ -1533,6 +2512,19 @@ mod tests {
             token_details_to_string(&tokens8),
             vec!["[", "0", "..", "100", "]"]
         );
+
+        // 超出 f64 范围的浮点数
+        let e1 = tokenize("1e3001").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidNumber);
+        assert_eq!(&*e1.message, "float number is out of range");
+        assert_eq!((e1.location.start, e1.location.end), (0, 6));
+
+        let e2 = tokenize("a = 1.5e400 + 1").unwrap_err();
+        assert_eq!((e2.location.start, e2.location.end), (4, 11));
+
+        // 很小的数会被舍入为 0，仍然是有效的浮点数
+        let tokens9 = tokenize("1e-400").unwrap();
+        assert_eq!(tokens9[0].token, Token::Float(0.0));
     }
 
     #[test]
@@ -1542,7 +2534,12 @@ mod tests {
             tokens1,
             vec![TokenDetail {
                 token: Token::Imaginary(5.0),
-                location: new_location()
+                location: Location {
+                    file_id: 0,
+                    start: 0,
+                    end: 2
+                },
+                trivia: None
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["5i"]);
@@ -1558,11 +2555,79 @@ mod tests {
 
         let tokens5 = tokenize("1.6e-2i").unwrap();
         assert_eq!(token_details_to_string(&tokens5), vec!["0.016i"]);
+
+        // 超出 f64 范围的虚数
+        let e1 = tokenize("1e3002i").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidNumber);
+        assert_eq!(&*e1.message, "float number is out of range");
+        assert_eq!((e1.location.start, e1.location.end), (0, 7));
     }
 
     #[test]
     fn test_bit_literal() {
-        // todo::
+        let tokens1 = tokenize("4'b1010").unwrap();
+        assert_eq!(
+            tokens1,
+            vec![TokenDetail {
+                token: Token::Bit(BitLiteral::new(4, 2, vec![0b1010])),
+                location: Location {
+                    file_id: 0,
+                    start: 0,
+                    end: 7
+                },
+                trivia: None
+            }]
+        );
+
+        // 各种进制，以及按原始进制输出
+        let tokens2 = tokenize("8'xFF 6'o17 8'd10 16'b0000_1111_0000_1111 12'x1").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens2),
+            vec!["8'xff", "6'o17", "8'd10", "16'b0000111100001111", "12'x001"]
+        );
+
+        // 超过 64 位的位宽
+        let tokens3 = tokenize("80'xffff_ffff_ffff_ffff_ffff").unwrap();
+        assert_eq!(
+            tokens3[0].token,
+            Token::Bit(BitLiteral::new(80, 16, vec![0xff; 10]))
+        );
+
+        // 后面紧随其他符号
+        let tokens4 = tokenize("(2'b01,3'o7)").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens4),
+            vec!["(", "2'b01", ",", "3'o7", ")"]
+        );
+
+        // 数值超出位宽
+        let e1 = tokenize("a=4'xff").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::IntegerOverflow);
+        assert_eq!(&*e1.message, "bit number is wider than its width of 4 bits");
+        assert_eq!((e1.location.start, e1.location.end), (2, 7));
+
+        assert_eq!(
+            tokenize("3'o10").unwrap_err().code,
+            DiagnosticCode::IntegerOverflow
+        );
+        assert!(tokenize("3'o7 8'd255 9'd256").is_ok());
+
+        // 无效的数字
+        let e2 = tokenize("4'b1021").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::InvalidNumber);
+        assert_eq!((e2.location.start, e2.location.end), (5, 6));
+
+        // 无效的进制以及位宽
+        let e3 = tokenize("8'z12").unwrap_err();
+        assert_eq!((e3.location.start, e3.location.end), (1, 2));
+        assert_eq!(
+            tokenize("0'b1").unwrap_err().code,
+            DiagnosticCode::InvalidNumber
+        );
+        assert_eq!(
+            &*tokenize("8'x").unwrap_err().message,
+            "expected the value of bit number"
+        );
     }
 
     #[test]
@@ -1572,7 +2637,12 @@ mod tests {
             tokens1,
             vec![TokenDetail {
                 token: Token::Boolean(true),
-                location: new_location()
+                location: Location {
+                    file_id: 0,
+                    start: 0,
+                    end: 4
+                },
+                trivia: None
             }]
         );
         assert_eq!(token_details_to_string(&tokens1), vec!["true"]);
@@ -1586,7 +2656,56 @@ mod tests {
         let tokens1 = tokenize("'a' 'b'").unwrap();
         assert_eq!(token_details_to_string(&tokens1), vec!["'a'", "'b'"]);
 
-        // todo:: 测试转义字符
+        // 转义字符
+        let tokens2 = tokenize(r"'\n' '\'' '\\' '\x41' '\u{6587}' '\0' '\u{1b}'").unwrap();
+        assert_eq!(
+            tokens2
+                .iter()
+                .map(|t| t.token.clone())
+                .collect::<Vec<Token>>(),
+            vec![
+                Token::Char('\n'),
+                Token::Char('\''),
+                Token::Char('\\'),
+                Token::Char('A'),
+                Token::Char('文'),
+                Token::Char('\0'),
+                Token::Char('\u{1b}'),
+            ]
+        );
+        assert_eq!(
+            token_details_to_string(&tokens2),
+            vec![
+                "'\\n'",
+                "'\\''",
+                "'\\\\'",
+                "'A'",
+                "'文'",
+                "'\\0'",
+                "'\\u{1b}'"
+            ]
+        );
+
+        // 多于一个字符
+        let e1 = tokenize("x 'ab'").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidChar);
+        assert_eq!((e1.location.start, e1.location.end), (2, 6));
+
+        // 无效的转义字符
+        let e2 = tokenize(r"'\q'").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::InvalidEscape);
+        assert_eq!((e2.location.start, e2.location.end), (1, 3));
+
+        let e3 = tokenize(r"'\x80'").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::InvalidEscape);
+
+        let e4 = tokenize(r"'\u{d800}'").unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::InvalidEscape);
+        assert_eq!((e4.location.start, e4.location.end), (1, 9));
+
+        assert!(tokenize(r"'\u{1234567}'").is_err());
+        assert!(tokenize(r"'\u{+1}'").is_err());
+        assert!(tokenize(r"'\x+1'").is_err());
     }
 
     #[test]
@@ -1601,7 +2720,27 @@ mod tests {
         let tokens2 = tokenize("\"foo\n    bar\"").unwrap();
         assert_eq!(token_details_to_string(&tokens2), vec!["\"foo\n    bar\""]);
 
-        // todo:: 测试转义字符
+        // 转义字符，值为转义之后的文本
+        let tokens4 = tokenize(r#""a\tb\\c\u{6587}" "\\" "\x41\r\n""#).unwrap();
+        assert_eq!(
+            tokens4
+                .iter()
+                .map(|t| t.token.clone())
+                .collect::<Vec<Token>>(),
+            vec![
+                Token::GeneralString("a\tb\\c文".to_string()),
+                Token::GeneralString("\\".to_string()),
+                Token::GeneralString("A\r\n".to_string()),
+            ]
+        );
+        assert_eq!(
+            token_details_to_string(&tokens4),
+            vec!["\"a\tb\\\\c文\"", "\"\\\\\"", "\"A\\r\n\""]
+        );
+
+        let e1 = tokenize(r#"x = "a\qb""#).unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidEscape);
+        assert_eq!((e1.location.start, e1.location.end), (6, 8));
 
         // 测试原始字符串
         let tokens3 = tokenize(r#"11"""foo bar"""22"#).unwrap();
@@ -1621,11 +2760,64 @@ mod tests {
             vec!["`foo`", "`b'a\"r`", "`a\\`b`", "`user: {{name}}`"]
         );
 
-        // todo:: 测试转义字符
+        // 占位符里的 '`'、字符串以及花括号
+        let tokens2 = tokenize(r#"`a{`b{c}`}d` `{"`"}` `{'}'}` `{ {x: 1} }`"#).unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens2),
+            vec!["`a{`b{c}`}d`", "`{\"`\"}`", "`{'}'}`", "`{ {x: 1} }`"]
+        );
+
+        // 占位符里的注释
+        let tokens3 = tokenize("`{a /* } */}` `{b // }\n}`").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens3),
+            vec!["`{a /* } */}`", "`{b // }\n}`"]
+        );
+
+        // 未闭合的占位符
+        let e1 = tokenize("`a{b`").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnterminatedTemplateString);
 
         // todo:: 测试截断模板字符串每行的共同前缀空白
     }
 
+    #[test]
+    fn test_split_template_string() {
+        let p1 = split_template_string(r"a\tb{c}\{d\}{ e + 1 }", 0, 10).unwrap();
+        assert_eq!(
+            p1.fragments,
+            vec!["a\tb".to_string(), "{d}".to_string(), "".to_string()]
+        );
+        assert_eq!(
+            p1.placeholders,
+            vec![
+                TemplatePlaceholder {
+                    text: "c".to_string(),
+                    location: new_location(15, 16)
+                },
+                TemplatePlaceholder {
+                    text: " e + 1 ".to_string(),
+                    location: new_location(23, 30)
+                }
+            ]
+        );
+
+        // 多字节字符
+        let p2 = split_template_string("文{字}", 0, 0).unwrap();
+        assert_eq!(p2.placeholders[0].location, new_location(4, 7));
+
+        let e1 = split_template_string("a}", 0, 0).unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidTemplateString);
+        assert_eq!(e1.location, new_location(1, 2));
+
+        let e2 = split_template_string(r"a\x", 0, 0).unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::InvalidEscape);
+        assert_eq!(e2.location, new_location(1, 3));
+
+        let e3 = split_template_string("a{b", 0, 0).unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::InvalidTemplateString);
+    }
+
     #[test]
     fn test_hash_string_literal() {
         let tokens1 = tokenize("\"foo\" #foo #_bar").unwrap();
@@ -1633,6 +2825,12 @@ mod tests {
             token_details_to_string(&tokens1),
             vec!["\"foo\"", "#foo", "#_bar"]
         );
+
+        // 结构注释
+        let tokens2 = tokenize("#disable #禁用 #disabled").unwrap();
+        assert_eq!(tokens2[0].token, Token::Disable);
+        assert_eq!(tokens2[1].token, Token::Disable);
+        assert_eq!(tokens2[2].token, Token::HashString("disabled".to_string()));
     }
 
     #[test]
@@ -1642,6 +2840,36 @@ mod tests {
             token_details_to_string(&tokens1),
             vec!["~/foo/", "~/b\\/a\\/r/", "a", "/", "b", "/", "c"]
         );
+
+        // 标志
+        let tokens2 = tokenize("~/^foo$/im ~/[a-z]+/s)").unwrap();
+        assert_eq!(
+            tokens2[0].token,
+            Token::Regexp("^foo$".to_string(), "im".to_string(), 0)
+        );
+        assert_eq!(
+            tokenize("~/(a)(?:b)((c))/").unwrap()[0].token,
+            Token::Regexp("(a)(?:b)((c))".to_string(), "".to_string(), 3)
+        );
+        assert_eq!(
+            token_details_to_string(&tokens2),
+            vec!["~/^foo$/im", "~/[a-z]+/s", ")"]
+        );
+
+        // 语法错误的位置位于正则表达式内部
+        let e1 = tokenize("let a = ~/(a|b/").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidRegexp);
+        assert_eq!(&*e1.message, "unclosed group");
+        assert_eq!((e1.location.start, e1.location.end), (10, 11));
+
+        let e2 = tokenize("~/a\\q/").unwrap_err();
+        assert_eq!(&*e2.message, "invalid escape '\\q'");
+        assert_eq!((e2.location.start, e2.location.end), (3, 5));
+
+        let e3 = tokenize("x ~/a/ig").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::InvalidRegexp);
+        assert_eq!(&*e3.message, "invalid regular expression flag 'g'");
+        assert_eq!((e3.location.start, e3.location.end), (7, 8));
     }
 
     #[test]
@@ -1675,6 +2903,13 @@ mod tests {
             token_details_to_string(&tokens2),
             vec!["??", "&", "^", "?", ".", "[", "]", "!", "(", ")", ":", "::", "..", "...", ",",]
         );
+
+        // 属性赋值符号 `:=`
+        let tokens3 = tokenize("a.b:=1 :=").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens3),
+            vec!["a", ".", "b", ":=", "1", ":="]
+        );
     }
 
     #[test]
@@ -1711,4 +2946,245 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_localized_keywords() {
+        let tables = KeywordTables::new();
+
+        // 首行的语言标记
+        let tokens1 = tokenize_with_locale("#lang=zh_CN\n令 甲 等于 真", 0, &tables, None).unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens1),
+            vec!["\n", "let", "甲", "=", "true"]
+        );
+        assert_eq!(tokens1[1].token, Token::Let);
+        assert_eq!(tokens1[1].location, new_location(12, 15));
+
+        // 包的语言
+        let tokens2 = tokenize_with_locale("令 let", 0, &tables, Some("zh_CN")).unwrap();
+        assert_eq!(tokens2[0].token, Token::Let);
+        assert_eq!(tokens2[1].token, Token::Identifier(Rc::from("let")));
+
+        // 默认语言
+        let tokens3 = tokenize_with_locale("令 let", 0, &tables, None).unwrap();
+        assert_eq!(tokens3[0].token, Token::Identifier(Rc::from("令")));
+        assert_eq!(tokens3[1].token, Token::Let);
+
+        // 注册的语言
+        let mut tables = KeywordTables::new();
+        tables.register(KeywordTable::new("fr").with_keyword("soit", Token::Let));
+        let tokens4 = tokenize_with_locale("#lang=fr\nsoit a", 0, &tables, None).unwrap();
+        assert_eq!(token_details_to_string(&tokens4), vec!["\n", "let", "a"]);
+
+        // 未知的语言
+        let e1 = tokenize_with_locale("#lang=xx\nlet", 0, &tables, None).unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnknownLocale);
+
+        // 未指定关键字表时根据语言标记选择内置的关键字表
+        let tokens5 = tokenize("#lang=zh_CN\n令 甲 等于 真").unwrap();
+        assert_eq!(
+            token_details_to_string(&tokens5),
+            vec!["\n", "let", "甲", "=", "true"]
+        );
+
+        let e2 = tokenize("#lang=fr\nlet a").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::UnknownLocale);
+        assert_eq!(e2.location, new_location(6, 8));
+
+        let (tokens6, diagnostics) = tokenize_recovering("#lang=fr\nlet a", 0);
+        assert_eq!(token_details_to_string(&tokens6), vec!["\n", "let", "a"]);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::UnknownLocale);
+
+        // 语言标记作为 trivia
+        let lossless = tokenize_lossless("#lang=en\nlet a", 0).unwrap();
+        let trivia = lossless.token_details[0].trivia.as_ref().unwrap();
+        assert_eq!(trivia.leading[0].kind, TriviaKind::LanguageHeader);
+        assert_eq!(lossless.to_source_text(), "#lang=en\nlet a");
+    }
+
+    #[test]
+    fn test_lexer_iterator() {
+        // 逐个读取 token
+        let mut lexer = Lexer::new("foo = bar + foo", 0);
+        let t1 = lexer.next().unwrap().unwrap();
+        assert_eq!(t1.token.to_string(), "foo");
+        assert_eq!(t1.location, new_location(0, 3));
+        assert_eq!(lexer.next().unwrap().unwrap().token, Token::Assign);
+
+        let rest: Vec<TokenDetail> = lexer.map(|r| r.unwrap()).collect();
+        assert_eq!(token_details_to_string(&rest), vec!["bar", "+", "foo"]);
+
+        // 同名的标识符共享同一个字符串
+        match (&t1.token, &rest[2].token) {
+            (Token::Identifier(a), Token::Identifier(b)) => assert!(Rc::ptr_eq(a, b)),
+            _ => unreachable!(),
+        }
+
+        // 遇到错误之后不再返回 token
+        let mut lexer = Lexer::new("a $ b", 0);
+        assert!(lexer.next().unwrap().is_ok());
+        assert!(lexer.next().unwrap().is_err());
+        assert!(lexer.next().is_none());
+
+        // 嵌入的源文本
+        let t2 = Lexer::with_base_offset("x", 3, 10).next().unwrap().unwrap();
+        assert_eq!(
+            t2.location,
+            Location {
+                file_id: 3,
+                start: 10,
+                end: 11
+            }
+        );
+    }
+
+    #[test]
+    fn test_recovering() {
+        let (t1, d1) = tokenize_recovering("let a = $ + \"abc\nlet b = 1.2.3\nc # d", 0);
+        assert_eq!(
+            token_details_to_string(&t1),
+            vec![
+                "let", "a", "=", "$", "+", "\"abc", "\n", "let", "b", "=", "1.2.3", "\n", "c", "#",
+                "d"
+            ]
+        );
+        assert_eq!(t1[3].token, Token::Unknown("$".to_string()));
+        assert_eq!(t1[5].location, new_location(12, 16));
+
+        let codes: Vec<DiagnosticCode> = d1.iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                DiagnosticCode::InvalidChar,
+                DiagnosticCode::UnterminatedString,
+                DiagnosticCode::InvalidNumber,
+                DiagnosticCode::InvalidChar
+            ]
+        );
+        assert_eq!(d1[0].location, new_location(8, 9));
+        assert_eq!(d1[2].location.start, 25);
+
+        // 未闭合的块注释直到源文件末尾
+        let (t2, d2) = tokenize_recovering("a /* b\nc", 0);
+        assert_eq!(token_details_to_string(&t2), vec!["a", "/* b\nc"]);
+        assert_eq!(d2.len(), 1);
+
+        // 没有错误
+        let (t3, d3) = tokenize_recovering("a + 1", 0);
+        assert_eq!(t3.len(), 3);
+        assert!(d3.is_empty());
+
+        // 标识符检查的警告
+        let (t4, d4) = tokenize_recovering("let a = 1\nlet \u{430} = $", 0);
+        assert_eq!(t4.len(), 9);
+        let codes: Vec<(DiagnosticCode, Severity)> =
+            d4.iter().map(|d| (d.code, d.severity)).collect();
+        assert_eq!(
+            codes,
+            vec![
+                (DiagnosticCode::InvalidChar, Severity::Error),
+                (DiagnosticCode::ConfusableIdentifier, Severity::Warning)
+            ]
+        );
+        assert_eq!(d4[1].location, new_location(14, 16));
+
+        // 一个错误只产生一个 Token::Unknown，然后从错误范围之后继续分析
+        let (t5, d5) = tokenize_recovering("'ab' 0x 4", 0);
+        assert_eq!(
+            t5.iter().map(|t| t.token.clone()).collect::<Vec<Token>>(),
+            vec![
+                Token::Unknown("'ab'".to_string()),
+                Token::Unknown("0x".to_string()),
+                Token::Integer(IntegerLiteral::new("4", None))
+            ]
+        );
+        let codes: Vec<DiagnosticCode> = d5.iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            vec![DiagnosticCode::InvalidChar, DiagnosticCode::InvalidNumber]
+        );
+
+        // 字面量内部的错误跳过整个字面量
+        let (t6, d6) = tokenize_recovering("\"a\\qb\" + '\\q' + ~/(/i c", 0);
+        assert_eq!(
+            token_details_to_string(&t6),
+            vec!["\"a\\qb\"", "+", "'\\q'", "+", "~/(/i", "c"]
+        );
+        let codes: Vec<DiagnosticCode> = d6.iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                DiagnosticCode::InvalidEscape,
+                DiagnosticCode::InvalidEscape,
+                DiagnosticCode::InvalidRegexp
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let e1 = tokenize("foo \"bar").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnterminatedString);
+        assert_eq!(
+            e1.location,
+            Location {
+                file_id: 0,
+                start: 4,
+                end: 5
+            }
+        );
+
+        let e2 = tokenize("1 /* foo").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::UnterminatedBlockComment);
+        assert_eq!(e2.location.start, 2);
+
+        // 未闭合的嵌套区域注释，错误的位置为最内层的未闭合的开始符号
+        let e2b = tokenize("1 /* a /* b */ c").unwrap_err();
+        assert_eq!(e2b.location, new_location(2, 4));
+        assert!(e2b.notes.is_empty());
+
+        let e2c = tokenize("1 /* a /* b /* c */").unwrap_err();
+        assert_eq!(e2c.code, DiagnosticCode::UnterminatedBlockComment);
+        assert_eq!(e2c.location, new_location(7, 9));
+        assert_eq!(
+            e2c.notes,
+            vec!["block comments can be nested, 2 comments are not closed".to_string()]
+        );
+
+        let (t2d, d2d) = tokenize_recovering("a /* b /* c", 0);
+        assert_eq!(token_details_to_string(&t2d), vec!["a", "/* b /* c"]);
+        assert_eq!(d2d[0].location, new_location(7, 9));
+
+        let e3 = tokenize("a = ¢ $").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::InvalidChar);
+        assert_eq!(
+            e3.location,
+            Location {
+                file_id: 0,
+                start: 4,
+                end: 6
+            }
+        );
+
+        assert_eq!(
+            tokenize("'a").unwrap_err().code,
+            DiagnosticCode::UnterminatedChar
+        );
+        assert_eq!(
+            tokenize("`a").unwrap_err().code,
+            DiagnosticCode::UnterminatedTemplateString
+        );
+        assert_eq!(
+            tokenize("~/a").unwrap_err().code,
+            DiagnosticCode::UnterminatedRegexp
+        );
+        assert_eq!(
+            tokenize("#[a").unwrap_err().code,
+            DiagnosticCode::UnterminatedAttribute
+        );
+        assert_eq!(
+            tokenize("0a").unwrap_err().code,
+            DiagnosticCode::InvalidIdentifier
+        );
+    }
 }
@@ -6,8 +6,21 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
 
+// 源文件头部的版权声明使用 `/** ... */` 注释，它不是第一个模块的文档注释
+#[allow(clippy::empty_line_after_doc_comments)]
+pub mod ast;
+pub mod cn_lexer;
 pub mod error;
-pub mod token;
+pub mod highlight;
+pub mod identifier;
+pub mod incremental;
+pub mod keyword;
 pub mod lexer;
-pub mod ast;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod regexp;
+pub mod source;
+pub mod token;
+
+// 单元测试的辅助工具
+#[cfg(test)]
+mod testing;
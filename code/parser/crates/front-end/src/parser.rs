@@ -5,27 +5,254 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
+use std::{borrow::Cow, collections::HashMap};
+
 use crate::{
     ast::{
         AnonymousFunction, AnonymousParameter, Argument, BinaryExpression, Bit, BlockExpression,
-        Boolean, BranchCase, BranchExpression, Char, Complex, ConstructorExpression, DataType,
-        EachExpression, Ellipsis, Expression, Float, ForExpression, FunctionCallExpression,
+        Boolean, BranchCase, BranchExpression, Char, Complex, ConstDeclaration,
+        ConstGroupDeclaration, ConstMember, ConstructorExpression, DataType, DisabledCode,
+        EachExpression, Ellipsis, EmptyFunctionDeclaration, EmptyFunctionParameter,
+        EmptyStructDeclaration, Expression, Float, ForExpression, FunctionCallExpression,
         FunctionDeclaration, FunctionParameter, GeneralString, HashString, Identifier,
         IfExpression, Integer, Interval, JoinExpression, LetExpression, List, Literal, Map,
         MapEntry, MatchCase, MatchExpression, MemberExpression, MemberIndex, MemberProperty,
-        NamedOperator, NextExpression, Node, PatternExpression, PrefixIdentifier, Program, Range,
-        Sign, SignParameter, Statement, TemplateString, Tuple, UnaryExpression, WhichEntry,
+        MemberStructDeclaration, NamePath, NamePathItem, NamedOperator, NextExpression, Node,
+        PatternExpression, PatternFunctionDeclaration, PatternFunctionGroup,
+        PatternFunctionParameter, PrefixIdentifier, Program, PropertyGetExpression,
+        PropertySetExpression, Range, Region, Sign, SignParameter, Statement, StructMember,
+        TemplateString, Tuple, TupleStructDeclaration, UnaryExpression, UseStatement, WhichEntry,
         WhichEntryLimit, WhichEntryType,
     },
-    error::Error,
-    token::{Token, TokenDetail},
+    error::{Diagnostic, DiagnosticCode},
+    lexer::{
+        new_integer_overflow_error, split_template_string, tokenize_embedded, TemplatePlaceholder,
+    },
+    token::{IntegerType, Location, Token, TokenDetail},
 };
 
-pub fn parse(source_token_details: &[TokenDetail]) -> Result<Node, Error> {
-    let program = parse_program(source_token_details)?;
+pub fn parse(source_token_details: &[TokenDetail]) -> Result<Node, Diagnostic> {
+    let locate_error = |mut diagnostic: Diagnostic| {
+        // 源文件意外结束的错误，其位置为最后一个 token 的末尾
+        if diagnostic.code == DiagnosticCode::UnexpectedEndOfFile {
+            if let Some(last) = source_token_details.last() {
+                diagnostic.location = Location {
+                    file_id: last.location.file_id,
+                    start: last.location.end,
+                    end: last.location.end,
+                };
+            }
+        }
+        diagnostic
+    };
+
+    let (token_details, regions) = strip_regions(source_token_details);
+    let token_details = strip_inline_doc_comments(&token_details);
+    let (token_details, disabled) = strip_disabled_code(&token_details).map_err(locate_error)?;
+    let mut program = parse_program(&token_details).map_err(locate_error)?;
+    program.disabled = disabled;
+    program.regions = regions;
     Ok(Node::Program(program))
 }
 
+// 移除范围注释的 token，并根据其位置生成 Region
+//
+// 范围注释可以嵌套，缺少结束横线的范围一直到源文件末尾，多余的结束横线则被忽略。
+fn strip_regions(source_token_details: &[TokenDetail]) -> (Cow<'_, [TokenDetail]>, Vec<Region>) {
+    if !source_token_details
+        .iter()
+        .any(|token_detail| matches!(token_detail.token, Token::RegionStart(_) | Token::RegionEnd))
+    {
+        return (Cow::Borrowed(source_token_details), vec![]);
+    }
+
+    let mut token_details: Vec<TokenDetail> = vec![];
+    let mut regions: Vec<Region> = vec![];
+
+    // 尚未结束的范围，即 Region 的索引以及范围内第一个 token 的索引
+    let mut open_regions: Vec<(usize, usize)> = vec![];
+
+    for token_detail in source_token_details {
+        match &token_detail.token {
+            Token::RegionStart(value) => {
+                let (title, description) = match value.trim().split_once('\n') {
+                    Some((title, description)) => (title, description.trim()),
+                    None => (value.trim(), ""),
+                };
+
+                let location = &token_detail.location;
+                regions.push(Region {
+                    title: title.to_string(),
+                    description: description.to_string(),
+                    range: Range {
+                        file_id: location.file_id,
+                        start: location.end,
+                        end: location.end,
+                    },
+                });
+                open_regions.push((regions.len() - 1, token_details.len()));
+            }
+            Token::RegionEnd => {
+                if let Some((region_index, token_index)) = open_regions.pop() {
+                    update_region_range(&mut regions[region_index], &token_details[token_index..]);
+                }
+            }
+            _ => token_details.push(token_detail.clone()),
+        }
+    }
+
+    while let Some((region_index, token_index)) = open_regions.pop() {
+        update_region_range(&mut regions[region_index], &token_details[token_index..]);
+    }
+
+    (Cow::Owned(token_details), regions)
+}
+
+// 移除不位于行首的文档注释，比如 `let a = 1 '''foo''' + 2`
+//
+// 只有位于行首（以及符号 `{` 和 `,` 之后，即结构体成员之前）的文档注释才有可能
+// 属于声明语句，其他位置的文档注释跟普通注释一样没有意义，而且会打断表达式。
+fn strip_inline_doc_comments(source_token_details: &[TokenDetail]) -> Cow<'_, [TokenDetail]> {
+    let is_inline = |index: usize| {
+        let previous = source_token_details[..index]
+            .iter()
+            .rev()
+            .find(|token_detail| !matches!(token_detail.token, Token::DocComment(_)));
+        !matches!(
+            previous,
+            None | Some(TokenDetail {
+                token: Token::NewLine | Token::LeftBrace | Token::Comma,
+                ..
+            })
+        )
+    };
+
+    let inline_indices: Vec<usize> = source_token_details
+        .iter()
+        .enumerate()
+        .filter(|(index, token_detail)| {
+            matches!(token_detail.token, Token::DocComment(_)) && is_inline(*index)
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    if inline_indices.is_empty() {
+        return Cow::Borrowed(source_token_details);
+    }
+
+    Cow::Owned(
+        source_token_details
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| inline_indices.binary_search(index).is_err())
+            .map(|(_, token_detail)| token_detail.clone())
+            .collect(),
+    )
+}
+
+fn update_region_range(region: &mut Region, token_details: &[TokenDetail]) {
+    let mut tokens = token_details
+        .iter()
+        .filter(|token_detail| token_detail.token != Token::NewLine);
+
+    if let Some(first) = tokens.next() {
+        let last = tokens.next_back().unwrap_or(first);
+        region.range.start = first.location.start;
+        region.range.end = last.location.end;
+    }
+}
+
+// 移除被结构注释 `#disable` 注释掉的语句或者表达式
+//
+// 从后往前处理，因此处理每一个 `#disable` 时，其后面的（包括嵌套的）结构注释
+// 都已经被移除，被外层结构注释包含的内层结构注释会合并到外层。
+fn strip_disabled_code(
+    source_token_details: &[TokenDetail],
+) -> Result<(Cow<'_, [TokenDetail]>, Vec<DisabledCode>), Diagnostic> {
+    if !source_token_details
+        .iter()
+        .any(|token_detail| token_detail.token == Token::Disable)
+    {
+        return Ok((Cow::Borrowed(source_token_details), vec![]));
+    }
+
+    let mut token_details = source_token_details.to_vec();
+    let mut disabled: Vec<DisabledCode> = vec![];
+
+    while let Some(index) = token_details
+        .iter()
+        .rposition(|token_detail| token_detail.token == Token::Disable)
+    {
+        let length = get_disabled_code_length(&token_details[index..])?;
+        let mut removed: Vec<TokenDetail> = token_details.drain(index..index + length).collect();
+
+        let location = Location {
+            file_id: removed[0].location.file_id,
+            start: removed[0].location.start,
+            end: removed[removed.len() - 1].location.end,
+        };
+
+        // 合并被包含的内层结构注释
+        let inner_count = disabled
+            .iter()
+            .take_while(|code| code.location.start < location.end)
+            .count();
+        for code in disabled.drain(..inner_count) {
+            removed.extend(code.token_details);
+        }
+        removed.sort_by_key(|token_detail| token_detail.location.start);
+
+        disabled.insert(
+            0,
+            DisabledCode {
+                location,
+                token_details: removed,
+            },
+        );
+    }
+
+    Ok((Cow::Owned(token_details), disabled))
+}
+
+// 获取 `#disable` 以及被其注释掉的语句或者表达式的 token 数量
+fn get_disabled_code_length(source_token_details: &[TokenDetail]) -> Result<usize, Diagnostic> {
+    // 消除 `#disable` 以及后面的空行
+    let post_disable = skip_new_lines(&source_token_details[1..]);
+
+    let (is_statement, post_construct) = match post_disable.first() {
+        Some(TokenDetail {
+            token:
+                Token::Function
+                | Token::Empty
+                | Token::Pattern
+                | Token::Use
+                | Token::Const
+                | Token::Struct
+                | Token::Union
+                | Token::Trait
+                | Token::Impl
+                | Token::Alias
+                | Token::At,
+            ..
+        }) => (true, parse_statement(post_disable, vec![])?.1),
+        _ => (false, parse_expression(post_disable)?.1),
+    };
+
+    let mut length = source_token_details.len() - post_construct.len();
+
+    // 语句会消耗行尾的换行符，而换行符需要保留，用于分隔前后两个语句
+    if is_statement && source_token_details[length - 1].token == Token::NewLine {
+        length -= 1;
+    }
+
+    // 被注释掉的是列表或者参数等的其中一项时，一并移除其后面的逗号
+    if matches!(source_token_details.get(length), Some(first) if first.token == Token::Comma) {
+        length += 1;
+    }
+
+    Ok(length)
+}
+
 // Program
 //  : StatementList
 //  ;
@@ -34,20 +261,50 @@ pub fn parse(source_token_details: &[TokenDetail]) -> Result<Node, Error> {
 //  : Statement
 //  | StatementList NEW_LINE Statement
 //  ;
-fn parse_program(source_token_details: &[TokenDetail]) -> Result<Program, Error> {
+fn parse_program(source_token_details: &[TokenDetail]) -> Result<Program, Diagnostic> {
     let mut token_details = source_token_details;
     let mut statements = Vec::<Statement>::new();
 
+    // 模式函数的名称，以及它在 statements 里的索引和第一个定义语句的位置
+    let mut pattern_function_groups: HashMap<String, (usize, Location)> = HashMap::new();
+
     loop {
-        // 消除前导的空行
-        let post_new_lines = skip_new_lines(token_details);
+        // 消除前导的空行，并收集语句之前的文档注释
+        let (docs, post_docs) = continue_parse_docs(token_details);
 
-        if post_new_lines.first() == None {
+        if post_docs.is_empty() {
             break;
         }
 
-        let (statement, post_statement) = parse_statement(post_new_lines)?;
-        statements.push(statement);
+        let (statement, post_statement) = parse_statement(post_docs, docs)?;
+
+        match statement {
+            Statement::PatternFunctionGroup(group) => {
+                // 同名的模式函数组合为一个逻辑上的函数，位置在第一个定义语句处
+                if let Some((index, first_location)) = pattern_function_groups.get(&group.name) {
+                    if let Statement::PatternFunctionGroup(first_group) = &mut statements[*index] {
+                        for declaration in group.declarations {
+                            check_pattern_function_signature(
+                                &first_group.declarations[0],
+                                &declaration,
+                                first_location,
+                                post_docs,
+                            )?;
+                            first_group.declarations.push(declaration);
+                        }
+                    }
+                } else {
+                    pattern_function_groups.insert(
+                        group.name.clone(),
+                        (statements.len(), post_docs[0].location.clone()),
+                    );
+                    statements.push(Statement::PatternFunctionGroup(group));
+                }
+            }
+            _ => {
+                statements.push(statement);
+            }
+        }
 
         // 解析剩余的 token
         // 直到解析完所有 token 为止
@@ -56,10 +313,83 @@ fn parse_program(source_token_details: &[TokenDetail]) -> Result<Program, Error>
 
     Ok(Program {
         body: statements,
+        disabled: vec![],
+        regions: vec![],
         range: new_range(),
     })
 }
 
+// 检查模式函数的签名是否跟第一个同名模式函数的一致
+//
+// 同名的模式函数必须有相同的泛型、参数个数、参数数据类型以及返回值数据类型，
+// 省略了数据类型的参数（即 `regular` 和 `template` 模式）不检查数据类型。
+fn check_pattern_function_signature(
+    first: &PatternFunctionDeclaration,
+    declaration: &PatternFunctionDeclaration,
+    first_location: &Location,
+    token_details: &[TokenDetail],
+) -> Result<(), Diagnostic> {
+    let mismatched = |message: &str| {
+        Err(new_error(
+            DiagnosticCode::MismatchedPatternFunction,
+            message,
+            token_details,
+        )
+        .with_label(
+            first_location.clone(),
+            &format!(
+                "the first pattern function `{}` is defined here",
+                first.name
+            ),
+        )
+        .with_note("pattern functions with the same name must have the same signature"))
+    };
+
+    if declaration.generics != first.generics {
+        return mismatched(&format!(
+            "the generics of the pattern function `{}` do not match",
+            first.name
+        ));
+    }
+
+    if declaration.parameters.len() != first.parameters.len() {
+        return mismatched(&format!(
+            "the pattern function `{}` expects {} parameters, found {}",
+            first.name,
+            first.parameters.len(),
+            declaration.parameters.len()
+        ));
+    }
+
+    for (index, (expected, found)) in first
+        .parameters
+        .iter()
+        .zip(&declaration.parameters)
+        .enumerate()
+    {
+        if let (Some(e), Some(f)) = (&expected.data_type, &found.data_type) {
+            if e != f {
+                return mismatched(&format!(
+                    "the data type of parameter {} of the pattern function `{}` does not match, expected `{}`, found `{}`",
+                    index + 1,
+                    first.name,
+                    e,
+                    f
+                ));
+            }
+        }
+    }
+
+    if declaration.return_data_type != first.return_data_type {
+        return mismatched(&format!(
+            "the return data type of the pattern function `{}` does not match",
+            first.name
+        ));
+    }
+
+    Ok(())
+}
+
 // Statement
 //  : FunctionDeclaration
 //  | EmptyFunctionDeclaration
@@ -68,6 +398,7 @@ fn parse_program(source_token_details: &[TokenDetail]) -> Result<Program, Error>
 //  | NamespaceStatement
 //  | UseStatement
 //  | ConstDeclaration
+//  | ConstGroupDeclaration
 //
 //  | MemberStructDeclaration
 //  | TupleStructDeclaration
@@ -79,20 +410,24 @@ fn parse_program(source_token_details: &[TokenDetail]) -> Result<Program, Error>
 //  | AliasStatement
 //  | Expression
 //  ;
+//
+// 参数 docs 为语句之前的文档注释，只有函数、结构体、联合体以及特性的定义语句会保留文档注释
 fn parse_statement(
     source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
+    docs: Vec<String>,
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
     let first = &source_token_details[0];
     match first.token {
-        Token::Function => parse_function_declaration(source_token_details),
-        Token::Empty => parse_empty_function_declaration(source_token_details),
-        Token::Pattern => parse_pattern_function_declaration(source_token_details),
+        Token::Function => parse_function_declaration(source_token_details, docs),
+        Token::Empty => parse_empty_function_declaration(source_token_details, docs),
+        Token::Pattern => parse_pattern_function_declaration(source_token_details, docs),
         // Token::Namespace => parse_namespace_statement(source_token_details),
         Token::Use => parse_use_statement(source_token_details),
         Token::Const => parse_const_statement(source_token_details),
-        Token::Struct => parse_struct(source_token_details),
-        Token::Union => parse_union(source_token_details),
-        Token::Trait => parse_trait_declaration(source_token_details),
+        Token::Struct => parse_struct(source_token_details, docs),
+        Token::At => parse_struct(source_token_details, docs), // 带有数据验证约束的结构体
+        Token::Union => parse_union(source_token_details, docs),
+        Token::Trait => parse_trait_declaration(source_token_details, docs),
         Token::Impl => parse_impl_statement(source_token_details),
         Token::Alias => parse_alias_statement(source_token_details),
         _ => {
@@ -104,7 +439,8 @@ fn parse_statement(
 
 fn parse_function_declaration(
     source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
+    docs: Vec<String>,
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
     // 普通函数的定义
     //
     // function name (Int a, Int b) type Int = ...
@@ -115,6 +451,7 @@ fn parse_function_declaration(
     // function name<T, E> (T a) type E which {
     //    T: limit Display
     // } {...}                                      // 支持泛型，支持 which 从属表达式
+    // function Int name (Int a, Int b) {...}       // 返回值类型也可以写在函数名称之前
 
     let mut token_details = source_token_details;
 
@@ -130,10 +467,25 @@ fn parse_function_declaration(
     token_details = skip_new_lines(token_details);
 
     // 解析函数名称（包括泛型）
-    let (function_name, post_function_name) = continue_parse_identifier(token_details)?;
+    let (mut function_name, post_function_name) = continue_parse_identifier(token_details)?;
     // 消除函数名称后面的空行
     token_details = skip_new_lines(post_function_name);
 
+    // 如果函数名称后面还有一个标识符，则前一个是返回值的数据类型
+    if matches!(
+        token_details.first(),
+        Some(TokenDetail {
+            token: Token::Identifier(_),
+            ..
+        })
+    ) {
+        return_data_type = Some(DataType::Identifier(function_name));
+
+        let (name, post_name) = continue_parse_identifier(token_details)?;
+        function_name = name;
+        token_details = skip_new_lines(post_name);
+    }
+
     // 解析参数列表
 
     // 消除符号 `(`
@@ -151,14 +503,17 @@ fn parse_function_declaration(
                 } else {
                     if is_expected_end {
                         // 当前的状态是一心寻找结束符号
-                        return Err(Error::ParserError(
-                            "expected the right paren symbol \")\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right paren symbol \")\"",
+                            token_details,
                         ));
                     } else {
                         // 获取参数的数据类型
                         let (data_type_expression, post_data_type_expression) =
                             parse_expression(token_details)?;
-                        let data_type = convert_expression_to_data_type(data_type_expression)?;
+                        let data_type =
+                            convert_expression_to_data_type(data_type_expression, token_details)?;
 
                         let (parameter_name, post_parameter_name) = if let Some((
                             TokenDetail {
@@ -171,8 +526,10 @@ fn parse_function_declaration(
                         {
                             (name, rest)
                         } else {
-                            return Err(Error::ParserError(
-                                "incomplete function parameter".to_string(),
+                            return Err(new_error(
+                                DiagnosticCode::InvalidParameter,
+                                "incomplete function parameter",
+                                post_data_type_expression,
                             ));
                         };
 
@@ -204,8 +561,8 @@ fn parse_function_declaration(
                         let post_consume_new_lines = skip_new_lines(post_consume_comma);
 
                         let parameter = FunctionParameter {
-                            data_type: data_type,
-                            name: parameter_name.clone(),
+                            data_type,
+                            name: parameter_name.to_string(),
                             value: default_value,
                             range: new_range(),
                         };
@@ -217,8 +574,10 @@ fn parse_function_declaration(
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right paren symbol \")\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right paren symbol \")\"",
+                    token_details,
                 ));
             }
         }
@@ -233,6 +592,14 @@ fn parse_function_declaration(
         // 尝试解析 type, which 等从属表达式
         token_details = match token_details.first() {
             Some(t) if t.token == Token::Type => {
+                if return_data_type.is_some() {
+                    return Err(new_error(
+                        DiagnosticCode::InvalidDataType,
+                        "the return data type of the function is specified more than once",
+                        token_details,
+                    ));
+                }
+
                 let (data_type, post_data_type_expression) =
                     continue_parse_type_expression(token_details)?;
 
@@ -272,10 +639,11 @@ fn parse_function_declaration(
     let f = FunctionDeclaration {
         name: function_name.name,
         generics: function_name.generics,
-        parameters: parameters,
-        return_data_type: return_data_type,
+        parameters,
+        return_data_type,
         whiches,
-        body: body,
+        body,
+        docs,
         range: new_range(),
     };
 
@@ -284,997 +652,2422 @@ fn parse_function_declaration(
 
 fn parse_empty_function_declaration(
     source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    docs: Vec<String>,
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    // 空函数的定义
+    // 空函数没有函数主体，参数必须有名称，且不支持默认值
+    //
+    // empty function name (Int a, Int b) type Int
+    // empty function name<T, E> (T a) type E which {
+    //    T: limit Display
+    // }
+    // empty function Int name (Int a, Int b)       // 返回值类型也可以写在函数名称之前
 
-fn parse_pattern_function_declaration(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    let mut token_details = source_token_details;
 
-// fn parse_namespace_statement(
-//     source_token_details: &[TokenDetail],
-// ) -> Result<(Statement, &[TokenDetail]), Error> {
-//     // todo!()
-// }
+    let mut parameters: Vec<EmptyFunctionParameter> = vec![];
+    let mut return_data_type: Option<DataType> = None;
+    let mut whiches: Vec<WhichEntry> = vec![];
 
-fn parse_use_statement(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    let mut is_expected_end = false; // 标记当前是否处于寻找参数列表结束符号 `)` 的状态
 
-fn parse_const_statement(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    // 消除关键字 `empty` 和 `function`
+    token_details = consume_token(&Token::Empty, token_details)?;
+    token_details = skip_new_lines_and_consume_token(&Token::Function, token_details)?;
+    // 消除关键字 `function` 后面的空行
+    token_details = skip_new_lines(token_details);
 
-fn parse_struct(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    // 解析函数名称（包括泛型）
+    let (mut function_name, post_function_name) = continue_parse_identifier(token_details)?;
+    // 消除函数名称后面的空行
+    token_details = skip_new_lines(post_function_name);
 
-fn parse_union(source_token_details: &[TokenDetail]) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    // 如果函数名称后面还有一个标识符，则前一个是返回值的数据类型
+    if matches!(
+        token_details.first(),
+        Some(TokenDetail {
+            token: Token::Identifier(_),
+            ..
+        })
+    ) {
+        return_data_type = Some(DataType::Identifier(function_name));
 
-fn parse_trait_declaration(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+        let (name, post_name) = continue_parse_identifier(token_details)?;
+        function_name = name;
+        token_details = skip_new_lines(post_name);
+    }
 
-fn parse_impl_statement(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    // 解析参数列表
 
-fn parse_alias_statement(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    todo!()
-}
+    // 消除符号 `(`
+    token_details = consume_token(&Token::LeftParen, token_details)?;
+    // 消除符号 `(` 后面的空行
+    token_details = skip_new_lines(token_details);
 
-// ExpressionStatement
-//  : Expression NEW_LINE
-//  | Expression EOF
-//  ;
-fn parse_expression_statement(
-    source_token_details: &[TokenDetail],
-) -> Result<(Statement, &[TokenDetail]), Error> {
-    let (expression, rest) = parse_expression(source_token_details)?;
+    // 解析参数列表
+    loop {
+        token_details = match token_details.first() {
+            Some(first) => {
+                if first.token == Token::RightParen {
+                    // 找到了结束符号 `)`，退出循环
+                    break;
+                } else {
+                    if is_expected_end {
+                        // 当前的状态是一心寻找结束符号
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right paren symbol \")\"",
+                            token_details,
+                        ));
+                    } else {
+                        // 获取参数的数据类型
+                        let (data_type_expression, post_data_type_expression) =
+                            parse_expression(token_details)?;
+                        let data_type =
+                            convert_expression_to_data_type(data_type_expression, token_details)?;
 
-    // statement 以 Token::NewLine 或者 EOF 结束，消耗这个换行符（如果存在的话）
-    consume_new_line_or_end_of_file(rest)
-        .map(|post_rest| (Statement::Expression(expression), post_rest))
+                        let (parameter_name, post_parameter_name) = if let Some((
+                            TokenDetail {
+                                token: Token::Identifier(name),
+                                ..
+                            },
+                            rest,
+                        )) =
+                            post_data_type_expression.split_first()
+                        {
+                            (name, rest)
+                        } else {
+                            return Err(new_error(
+                                DiagnosticCode::InvalidParameter,
+                                "incomplete function parameter",
+                                post_data_type_expression,
+                            )
+                            .with_note("parameters of an empty function must have names"));
+                        };
+
+                        // 空函数的参数不支持默认值
+                        if is_token(&Token::Assign, post_parameter_name) {
+                            return Err(new_error(
+                                DiagnosticCode::InvalidParameter,
+                                "parameters of an empty function can not have default values",
+                                post_parameter_name,
+                            )
+                            .with_help(
+                                "specify the default value in the implementation of the function",
+                            ));
+                        }
+
+                        // 消除逗号
+                        let post_consume_comma = if is_token(&Token::Comma, post_parameter_name) {
+                            consume_token(&Token::Comma, post_parameter_name)?
+                        } else {
+                            // 设置标记，表示如果项目后面没有逗号，则表示当前已经是最后一项
+                            // 后面只能允许列表结束
+                            is_expected_end = true;
+                            post_parameter_name
+                        };
+
+                        parameters.push(EmptyFunctionParameter {
+                            data_type,
+                            name: parameter_name.to_string(),
+                            range: new_range(),
+                        });
+
+                        // 消除空行
+                        skip_new_lines(post_consume_comma)
+                    }
+                }
+            }
+            None => {
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right paren symbol \")\"",
+                    token_details,
+                ));
+            }
+        }
+    }
+
+    // 消除右括号
+    token_details = consume_token(&Token::RightParen, token_details)?;
+
+    loop {
+        // 尝试解析 type, which 等从属表达式
+        // 注：空函数没有函数主体，所以只有后面是从属表达式时才消除空行
+        token_details = if is_token_ignore_new_lines(&Token::Type, token_details) {
+            let post_new_lines = skip_new_lines(token_details);
+            if return_data_type.is_some() {
+                return Err(new_error(
+                    DiagnosticCode::InvalidDataType,
+                    "the return data type of the function is specified more than once",
+                    post_new_lines,
+                ));
+            }
+
+            let (data_type, post_data_type_expression) =
+                continue_parse_type_expression(post_new_lines)?;
+            return_data_type = Some(data_type);
+            post_data_type_expression
+        } else if is_token_ignore_new_lines(&Token::Which, token_details) {
+            let (which_entries, post_which_expression) =
+                continue_parse_which_expression(skip_new_lines(token_details))?;
+            whiches = which_entries;
+            post_which_expression
+        } else {
+            break;
+        }
+    }
+
+    // 空函数没有函数主体
+    if any_token(&[Token::Assign, Token::LeftBrace], token_details) {
+        return Err(new_error(
+            DiagnosticCode::UnexpectedToken,
+            "an empty function can not have a body",
+            token_details,
+        )
+        .with_help("remove the keyword \"empty\" to declare a normal function"));
+    }
+
+    let post_statement = consume_new_line_or_end_of_file(token_details)?;
+
+    let f = EmptyFunctionDeclaration {
+        name: function_name.name,
+        generics: function_name.generics,
+        parameters,
+        return_data_type,
+        whiches,
+        docs,
+        range: new_range(),
+    };
+
+    Ok((Statement::EmptyFunctionDeclaration(f), post_statement))
 }
 
-// Expression
-//  : BlockExpression
-//  | JoinExpression
-//  | LetExpression
-//
-//  | IfExpression
-//  | ForExpression
-//  | NextExpression
-//  | EachExpression
-//  | BranchExpression
-//  | MatchExpression
-//
-//  | BinaryExpression
-//  | UnaryExpression
-//  | FunctionCallExpression
-//  | MemberExpression
-//  | SliceExpression
-//  | ConstructorExpression
-//
-//  | AnonymousFunction
-//  | SignExpression
-//  | Identifier
-//  | PrefixIdentifier
-//  | Tuple
-//  | List
-//  | Map
-//  | Literal
-//  ;
-fn parse_expression(
-    source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    if let Some(first) = source_token_details.first() {
-        match first.token {
-            Token::Do => parse_do_expression(source_token_details),
-            Token::Join => parse_join_expression(source_token_details),
-            Token::Let => parse_let_expression(source_token_details),
-            Token::If => parse_if_expression(source_token_details),
-            Token::For => parse_for_expression(source_token_details),
-            Token::Next => parse_next_expression(source_token_details),
-            Token::Each => parse_each_expression(source_token_details),
-            Token::Branch => parse_branch_expression(source_token_details),
-            Token::Match => parse_match_expression(source_token_details),
-            _ => {
-                // 二元运算表达式的开始
-                parse_pipe_expression(source_token_details)
-            }
-        }
-    } else {
-        Err(Error::ParserError("expected expression".to_string()))
-    }
-}
-
-// BlockExpression
-//  : 'do' BlockExpression
-//  ;
-fn parse_do_expression(
+fn parse_pattern_function_declaration(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // 解析 do 表达式 `do {...}`，do 表达式是一个显式表达式块
+    docs: Vec<String>,
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    // 模式函数的定义
+    // 模式函数的参数可以是模式表达式，参数必须指明数据类型（除非模式表达式是 `regular` 或者 `template`），
+    // 且不支持默认值
+    //
+    // pattern function name (List<Int> [a, b]) {...}
+    // pattern function name (Int i @ in [1..10], Point (x, y) only x > y where let c = x - y) {...}
+    // pattern function name (s @ regular ~/.../ (m,), template "...") {...}
+    // pattern function name (Int x, Int y) type Int only x > y {...}   // 支持函数范围的 only 从属表达式
+    //
+    // 同名的模式函数会在 parse_program 里组合为一个 PatternFunctionGroup
 
-    // 消除 do
-    let post_consume_token_do = consume_token(&Token::Do, source_token_details)?;
+    let mut token_details = source_token_details;
 
-    // 消除换行符
-    // do 关键字后面允许换行
-    let post_consume_new_lines = skip_new_lines(post_consume_token_do);
+    let mut parameters: Vec<PatternFunctionParameter> = vec![];
+    let mut return_data_type: Option<DataType> = None;
+    let mut only: Option<Expression> = None;
+    let mut whiches: Vec<WhichEntry> = vec![];
 
-    let (expressions, post_expression_block) =
-        continue_parse_expression_block(post_consume_new_lines)?;
+    let mut is_expected_end = false; // 标记当前是否处于寻找参数列表结束符号 `)` 的状态
 
-    Ok((
-        Expression::BlockExpression(BlockExpression {
-            is_explicit: true,
-            body: expressions,
-            range: new_range(),
-        }),
-        post_expression_block,
-    ))
-}
+    // 消除关键字 `pattern` 和 `function`
+    token_details = consume_token(&Token::Pattern, token_details)?;
+    token_details = skip_new_lines_and_consume_token(&Token::Function, token_details)?;
+    // 消除关键字 `function` 后面的空行
+    token_details = skip_new_lines(token_details);
 
-// BlockExpression
-//  : '{' ExpressionList '}'
-//  ;
-//
-// ExpressionList
-//  : Expression
-//  | ExpressionList NEW_LINE Expression
-//  ;
-fn continue_parse_expression_block(
-    source_token_details: &[TokenDetail],
-) -> Result<(Vec<Expression>, &[TokenDetail]), Error> {
-    // 解析表达式块 `{...}`（也叫 `隠式 Do 表达式`）
-    // 注意表达式块仅存在某些关键字后面，比如 `join`、`do` 等，而不能单独存在，
-    // 当一对花括号单独存在时，会被解析为 Map。
-    let mut token_details = source_token_details;
+    // 解析函数名称（包括泛型）
+    let (function_name, post_function_name) = continue_parse_identifier(token_details)?;
+    // 消除函数名称后面的空行
+    token_details = skip_new_lines(post_function_name);
 
-    // 消除符号 `{`
-    token_details = consume_token(&Token::LeftBrace, token_details)?;
-    // 消除符号 `{` 后面的空行
+    // 消除符号 `(`
+    token_details = consume_token(&Token::LeftParen, token_details)?;
+    // 消除符号 `(` 后面的空行
     token_details = skip_new_lines(token_details);
 
-    let mut expressions: Vec<Expression> = vec![];
-
+    // 解析参数列表
     loop {
-        // 遇到了结束符号 `}`，退出循环
-        if is_token(&Token::RightBrace, token_details) {
-            break;
-        }
+        token_details = match token_details.first() {
+            Some(first) => {
+                if first.token == Token::RightParen {
+                    // 找到了结束符号 `)`，退出循环
+                    break;
+                } else {
+                    if is_expected_end {
+                        // 当前的状态是一心寻找结束符号
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right paren symbol \")\"",
+                            token_details,
+                        ));
+                    } else {
+                        let (parameter, post_parameter) =
+                            continue_parse_pattern_function_parameter(token_details)?;
+                        parameters.push(parameter);
 
-        // 解析表达式
-        let (expression, post_expression) = parse_expression(token_details)?;
-        expressions.push(expression);
+                        // 模式函数的参数不支持默认值
+                        if is_token(&Token::Assign, post_parameter) {
+                            return Err(new_error(
+                                DiagnosticCode::InvalidParameter,
+                                "parameters of a pattern function can not have default values",
+                                post_parameter,
+                            ));
+                        }
 
-        // 消除表达式末尾的符号 `,`（假如存在的话）
-        token_details = if is_token(&Token::Comma, post_expression) {
-            consume_token(&Token::Comma, post_expression)?
-        } else {
-            post_expression
-        };
+                        // 消除逗号
+                        let post_consume_comma = if is_token(&Token::Comma, post_parameter) {
+                            consume_token(&Token::Comma, post_parameter)?
+                        } else {
+                            // 设置标记，表示如果项目后面没有逗号，则表示当前已经是最后一项
+                            // 后面只能允许列表结束
+                            is_expected_end = true;
+                            post_parameter
+                        };
 
-        // 消除符号 `,` 后面的空行
-        token_details = skip_new_lines(token_details);
+                        // 消除空行
+                        skip_new_lines(post_consume_comma)
+                    }
+                }
+            }
+            None => {
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right paren symbol \")\"",
+                    token_details,
+                ));
+            }
+        }
     }
 
-    // 消除符号 `}`
-    token_details = consume_token(&Token::RightBrace, token_details)?;
+    // 消除右括号
+    token_details = consume_token(&Token::RightParen, token_details)?;
+    // 消除参数列表后面的空行
+    token_details = skip_new_lines(token_details);
 
-    Ok((expressions, token_details))
-}
+    loop {
+        // 尝试解析 type, only, which 等从属表达式
+        token_details = match token_details.first() {
+            Some(t) if t.token == Token::Type => {
+                let (data_type, post_data_type_expression) =
+                    continue_parse_type_expression(token_details)?;
 
-fn continue_parse_expression_block_or_single_expression(
-    source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // 解析 `{...}` 或者 `...`
-    // 在诸如 `if`、`then`、`else` 等关键字后面，即可以是单独一个表达式，
-    // 也可以是一个表达式块。
-    //
-    // 解析优先级：
-    // 1. 如果存在 `{...}`，则解析为隠式表达式块
-    // 2. 否则解析为普通的表达式
+                return_data_type = Some(data_type);
 
-    match source_token_details.first() {
-        Some(first) => match first.token {
-            Token::LeftBrace => {
-                let (expressions, post_expression_block) =
-                    continue_parse_expression_block(source_token_details)?;
+                // 消除从属表达式后面的空行
+                skip_new_lines(post_data_type_expression)
+            }
+            Some(t) if t.token == Token::Only => {
+                let (exp, post_only_expression) =
+                    continue_parse_function_only_expression(token_details)?;
 
-                Ok((
-                    Expression::BlockExpression(BlockExpression {
-                        is_explicit: false,
-                        body: expressions,
-                        range: new_range(),
-                    }),
-                    post_expression_block,
-                ))
+                only = Some(exp);
+
+                // 消除从属表达式后面的空行
+                skip_new_lines(post_only_expression)
             }
-            _ => parse_expression(source_token_details),
-        },
-        None => Err(Error::ParserError(
-            "expected an expression or an expression block".to_string(),
-        )),
-    }
-}
+            Some(t) if t.token == Token::Which => {
+                let (which_entries, post_which_expression) =
+                    continue_parse_which_expression(token_details)?;
 
-fn parse_join_expression(
-    source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // 解析 join 表达式
-    // join {...}
+                whiches = which_entries;
 
-    let mut token_details = source_token_details;
+                // 消除从属表达式后面的空行
+                skip_new_lines(post_which_expression)
+            }
+            _ => {
+                break;
+            }
+        }
+    }
 
-    // 消除关键字 `join`
-    token_details = consume_token(&Token::Join, token_details)?;
-    // 消除关键字 `join` 后面的空行
-    token_details = skip_new_lines(token_details);
+    // 消除赋值符号（如果存在的话）
+    let post_assignment = if is_token(&Token::Assign, token_details) {
+        let post_assignment_token = consume_token(&Token::Assign, token_details)?;
+        // 消除空行
+        skip_new_lines(post_assignment_token)
+    } else {
+        token_details
+    };
 
-    let (expressions, post_expression_block) = continue_parse_expression_block(token_details)?;
+    // 解析函数主体
+    let (body, post_body) = continue_parse_expression_block_or_single_expression(post_assignment)?;
+
+    let f = PatternFunctionDeclaration {
+        name: function_name.name.clone(),
+        generics: function_name.generics,
+        parameters,
+        return_data_type,
+        only,
+        whiches,
+        body,
+        docs,
+        range: new_range(),
+    };
 
     Ok((
-        Expression::JoinExpression(JoinExpression {
-            body: expressions,
+        Statement::PatternFunctionGroup(PatternFunctionGroup {
+            name: function_name.name,
+            declarations: vec![f],
             range: new_range(),
         }),
-        post_expression_block,
+        post_body,
     ))
 }
 
-fn parse_let_expression(
+fn continue_parse_pattern_function_parameter(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // `let 表达式` 的 `左手边值` 也叫 `模式表达式`，
-    // `模式表达式` 属于 `单一表达式`，但只允许如下几种：
+) -> Result<(PatternFunctionParameter, &[TokenDetail]), Diagnostic> {
+    // 模式函数的参数由 4 部分组成：
+    // 1. 数据类型
+    // 2. 变量
+    // 3. 模式表达式
+    // 4. where/only 从属表达式
     //
-    // - 各种类型结构体、联合体的实例化表达式
-    // - Identifier
-    // - Tuple
-    // - List
-    // - Map
-    // - 数字、字符串等字面量
-    //
-    // let data_type left = right
-    // let left = right // 数据类型可以省略
-    // ~~~
-    //   ^--- 当前所处的位置
+    // Int i
+    // Int i @ in [1..10]
+    // Point (x, y) only x > y where let c = x - y
+    // s @ regular ~/.../ (m,)   // 模式表达式是 `regular` 或者 `template` 时可以省略数据类型
+    // template "..."
+    // ^
+    // |--- 当前所处的位置
 
     let mut token_details = source_token_details;
 
-    // 消除关键字 `let`
-    token_details = consume_token(&Token::Let, token_details)?;
-    // 消除关键字 `let` 后面的空行
-    token_details = skip_new_lines(token_details);
-
-    // 解析 `左手边的数据类型` 或者 `左手边值`
-    let (maybe_lhs, post_maybe_lhs) = parse_mono_expression(token_details)?;
+    // 检查是否省略了数据类型，即参数以 `regular`、`template` 或者 `变量 @ regular/template` 开始
+    let is_data_type_omitted = match token_details.split_first() {
+        Some((first, _)) if matches!(first.token, Token::Regular | Token::Template) => true,
+        Some((
+            TokenDetail {
+                token: Token::Identifier(_),
+                ..
+            },
+            post_identifier,
+        )) if is_token(&Token::At, post_identifier) => any_token(
+            &[Token::Regular, Token::Template],
+            skip_new_lines(&post_identifier[1..]),
+        ),
+        _ => false,
+    };
 
-    let (data_type, lhs) = if is_token(&Token::Assign, post_maybe_lhs) {
-        // 当前表达式没有数据类型，只有 `左手边值`（即 `模式表达式`）
-        token_details = post_maybe_lhs;
-        (None, maybe_lhs)
+    let data_type = if is_data_type_omitted {
+        None
     } else {
-        // 当前表达式有数据类型
-
-        // 转换成数据类型
-        let data_type = convert_expression_to_data_type(maybe_lhs)?;
+        // 获取参数的数据类型
+        let (data_type_expression, post_data_type_expression) =
+            parse_primary_expression(token_details)?;
+        let data_type = convert_expression_to_data_type(data_type_expression, token_details)?;
 
-        // 解析左手边值
-        let (lhs, post_lhs) = parse_primary_expression(post_maybe_lhs)?;
-        token_details = post_lhs;
-        (Some(data_type), lhs)
+        token_details = skip_new_lines(post_data_type_expression);
+        Some(data_type)
     };
 
-    if !is_valid_left_hand_side(&lhs) {
-        return Err(Error::ParserError(
-            "invalid left-hand-side value".to_string(),
-        ));
-    }
-
-    // 消除 `左手边值` 后面的空行
-    token_details = skip_new_lines(token_details);
-
-    // 消除赋值符号 `=`
-    token_details = consume_token(&Token::Assign, token_details)?;
+    // 解析 `变量` 和 `模式表达式` 部分
+    let ((variable, pattern), post_pattern) = continue_parse_pattern(token_details)?;
 
-    // 消除赋值符号 `=` 后面的空行
-    token_details = skip_new_lines(token_details);
+    if variable.is_none() && pattern.is_none() {
+        return Err(new_error(
+            DiagnosticCode::InvalidParameter,
+            "expected a pattern or a parameter name after the data type",
+            token_details,
+        )
+        .with_note("the data type of a pattern function parameter can only be omitted when the pattern is `regular` or `template`"));
+    }
 
-    // 解析右手边值
-    let (rhs, post_rhs) = parse_expression(token_details)?;
+    // 解析 only, where 从属表达式
+    let ((only, where_exp), post_subsidiary_expressions) =
+        continue_parse_only_and_where_expressions(post_pattern)?;
 
-    let exp = LetExpression {
-        data_type: data_type,
-        object: Box::new(lhs),
-        value: Box::new(rhs),
+    let parameter = PatternFunctionParameter {
+        data_type,
+        variable,
+        pattern: pattern.map(Box::new),
+        where_exp: where_exp.map(Box::new),
+        only: only.map(Box::new),
         range: new_range(),
     };
 
-    Ok((Expression::LetExpression(exp), post_rhs))
+    Ok((parameter, post_subsidiary_expressions))
 }
 
-fn is_valid_left_hand_side(exp: &Expression) -> bool {
-    // todo:: 检查左手边的值是否符合语法
-    true
-}
+// fn parse_namespace_statement(
+//     source_token_details: &[TokenDetail],
+// ) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+//     // todo!()
+// }
 
-fn parse_if_expression(
+fn parse_use_statement(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // if ... then ...
-    // if ... then ... else ...
-    // ~~
-    //  ^--- 当前所处的位置
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    // 导入标识符
+    //
+    // use std::List
+    // use foo::{bar, baz}
+    // use foo::{bar, sub::{one, two}}
+    // use foo{bar, baz}                // 花括号之前的 `::` 可以省略
+    // use foo::bar as baz              // 别名
+    // use foo::{bar as one, baz}
+    //
+    // `use` 不支持 `*` 通配符
 
     let mut token_details = source_token_details;
 
-    // 消除关键字 `if`
-    token_details = consume_token(&Token::If, token_details)?;
-    // 消除关键字 `if` 后面的空行
-    token_details = skip_new_lines(token_details);
+    // 消除关键字 `use`
+    token_details = consume_token(&Token::Use, token_details)?;
 
-    let (testing, post_testing) = parse_expression(token_details)?;
+    let (name_path, post_name_path) = continue_parse_name_path(token_details)?;
 
-    // 消除 `if` 子表达式后面的空行
-    token_details = skip_new_lines(post_testing);
+    // use 语句以 Token::NewLine 或者 EOF 结束，消耗这个换行符（如果存在的话）
+    let post_statement = consume_new_line_or_end_of_file(post_name_path)?;
 
-    // 检查是否存在 `where` 子表达式
-    let where_exp = if is_token(&Token::Where, token_details) {
-        let (where_exp, post_where_expression) = continue_parse_where_expression(token_details)?;
+    Ok((
+        Statement::UseStatement(UseStatement {
+            name_path,
+            range: new_range(),
+        }),
+        post_statement,
+    ))
+}
 
-        token_details = post_where_expression;
-        Some(where_exp)
-    } else {
-        None
-    };
+fn continue_parse_name_path(
+    source_token_details: &[TokenDetail],
+) -> Result<(NamePath, &[TokenDetail]), Diagnostic> {
+    // 名称路径
+    //
+    // one::two::three
+    // one::two::{three, four::five}
+    // one::two{three, four}
+    // one::two as three
+    // ^
+    // |--- 当前所处的位置
 
-    // 消除关键字 `then` (包括前缀空行)
-    token_details = skip_new_lines_and_consume_token(&Token::Then, token_details)?;
-    // 消除关键字 `then` 后面的空行
-    token_details = skip_new_lines(token_details);
+    let mut token_details = source_token_details;
+    let mut directories: Vec<NamePathItem> = vec![];
 
-    let (consequent, post_consequent) =
-        continue_parse_expression_block_or_single_expression(token_details)?;
+    loop {
+        let name = match token_details.split_first() {
+            Some((
+                TokenDetail {
+                    token: Token::Identifier(name),
+                    ..
+                },
+                post_name,
+            )) => {
+                token_details = post_name;
+                name.to_string()
+            }
+            Some((first, _)) if first.token == Token::Asterisk => {
+                return Err(new_error(
+                    DiagnosticCode::UnexpectedToken,
+                    "the use statement does not support glob imports",
+                    token_details,
+                )
+                .with_help("list the names explicitly, e.g. `use foo::{bar, baz}`"));
+            }
+            _ => {
+                return Err(new_error(
+                    DiagnosticCode::ExpectedIdentifier,
+                    "expected identifier",
+                    token_details,
+                ));
+            }
+        };
 
-    // 检查是否存在 `else` 子表达式
-    let alternate = if is_token_ignore_new_lines(&Token::Else, post_consequent) {
-        // 消除关键字 `else` (包括前缀空行)
-        token_details = skip_new_lines_and_consume_token(&Token::Else, post_consequent)?;
-        // 消除关键字 `else` 后面的空行
-        token_details = skip_new_lines(token_details);
+        // 花括号之前的 `::` 可以省略
+        let post_separator = if is_token(&Token::Separator, token_details) {
+            &token_details[1..]
+        } else {
+            token_details
+        };
 
-        let (alternate, post_alternate) =
-            continue_parse_expression_block_or_single_expression(token_details)?;
+        if is_token(&Token::LeftBrace, post_separator) {
+            // 子路径列表，子路径列表只能是名称路径的最后一项
+            let (children, post_children) = continue_parse_name_path_children(post_separator)?;
+            directories.push(NamePathItem::Children(name, children));
+            token_details = post_children;
+            break;
+        }
 
-        token_details = post_alternate;
-        Some(alternate)
-    } else {
-        token_details = post_consequent;
-        None
-    };
+        directories.push(NamePathItem::Name(name));
 
-    let exp = Expression::IfExpression(IfExpression {
-        testing: Box::new(testing),
-        where_exp: where_exp.map(|e| Box::new(e)),
-        consequent: Box::new(consequent),
-        alternate: alternate.map(|e| Box::new(e)),
-        range: new_range(),
-    });
+        if is_token(&Token::Separator, token_details) {
+            // 消除符号 `::`
+            token_details = consume_token(&Token::Separator, token_details)?;
+        } else {
+            break;
+        }
+    }
 
-    Ok((exp, token_details))
+    // 检查是否存在别名 `as name`
+    // 注：`as` 不是关键字，而是一个普通的标识符
+    let alias = match token_details.split_first() {
+        Some((
+            TokenDetail {
+                token: Token::Identifier(name),
+                ..
+            },
+            post_as,
+        )) if name.as_ref() == "as" => {
+            if let Some(NamePathItem::Children(..)) = directories.last() {
+                return Err(new_error(
+                    DiagnosticCode::UnexpectedToken,
+                    "a list of names can not have an alias",
+                    token_details,
+                )
+                .with_help(
+                    "give an alias to each name in the braces, e.g. `use foo::{bar as baz}`",
+                ));
+            }
+
+            match post_as.split_first() {
+                Some((
+                    TokenDetail {
+                        token: Token::Identifier(alias_name),
+                        ..
+                    },
+                    post_alias_name,
+                )) => {
+                    token_details = post_alias_name;
+                    Some(alias_name.to_string())
+                }
+                _ => {
+                    return Err(new_error(
+                        DiagnosticCode::ExpectedIdentifier,
+                        "expected the alias name",
+                        post_as,
+                    ));
+                }
+            }
+        }
+        _ => None,
+    };
+
+    Ok((NamePath { directories, alias }, token_details))
 }
 
-fn continue_parse_where_expression(
+fn continue_parse_name_path_children(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // where ...
-    // ~~~~~
-    //     |--- 当前所处的位置
+) -> Result<(Vec<NamePath>, &[TokenDetail]), Diagnostic> {
+    // {one, two::three, four::{five, six}}
+    // ^
+    // |--- 当前所处的位置
 
     let mut token_details = source_token_details;
+    let mut children: Vec<NamePath> = vec![];
 
-    // 消除 `where` 关键字
-    token_details = consume_token(&Token::Where, token_details)?;
-    // 消除空行
+    let mut is_expected_end = false; // 标记当前是否处于寻找结束符号 `}` 的状态
+
+    // 消除符号 `{`
+    token_details = consume_token(&Token::LeftBrace, token_details)?;
+    // 消除符号 `{` 后面的空行
     token_details = skip_new_lines(token_details);
 
-    continue_parse_expression_block_or_single_expression(token_details)
+    loop {
+        token_details = match token_details.first() {
+            Some(first) => {
+                if first.token == Token::RightBrace {
+                    // 找到了结束符号 `}`，退出循环
+                    break;
+                } else if is_expected_end {
+                    // 当前的状态是一心寻找结束符号
+                    return Err(new_error(
+                        DiagnosticCode::UnclosedDelimiter,
+                        "expected the right brace symbol \"}\"",
+                        token_details,
+                    ));
+                } else {
+                    let (name_path, post_name_path) = continue_parse_name_path(token_details)?;
+                    children.push(name_path);
+
+                    // 消除逗号
+                    let post_consume_comma = if is_token(&Token::Comma, post_name_path) {
+                        consume_token(&Token::Comma, post_name_path)?
+                    } else {
+                        // 设置标记，表示如果项目后面没有逗号，则表示当前已经是最后一项
+                        // 后面只能允许列表结束
+                        is_expected_end = true;
+                        post_name_path
+                    };
+
+                    // 消除空行
+                    skip_new_lines(post_consume_comma)
+                }
+            }
+            None => {
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right brace symbol \"}\"",
+                    token_details,
+                ));
+            }
+        }
+    }
+
+    if children.is_empty() {
+        return Err(new_error(
+            DiagnosticCode::ExpectedIdentifier,
+            "expected at least one name in the braces",
+            token_details,
+        ));
+    }
+
+    // 消除符号 `}`
+    token_details = consume_token(&Token::RightBrace, token_details)?;
+
+    Ok((children, token_details))
 }
 
-fn parse_for_expression(
+fn parse_const_statement(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // for let ... = ... ...
-    // for let ... = ... {...}
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    // 常量以及常量组
+    //
+    // const Int Code = 123
+    // const Code = 123                 // 数据类型可以省略
+    // const Int Code {                 // 常量组，成员之间以换行或者逗号分隔
+    //     Ok = 200
+    //     Moved = 301
+    // }
 
     let mut token_details = source_token_details;
 
-    // 消除关键字 `for`
-    token_details = consume_token(&Token::For, token_details)?;
-    // 消除关键字 `for` 后面的空行
+    // 消除关键字 `const`
+    token_details = consume_token(&Token::Const, token_details)?;
+    // 消除关键字 `const` 后面的空行
     token_details = skip_new_lines(token_details);
 
-    // 解析 `初始化子表达式`
+    // 如果第一个表达式后面紧接着 `=` 或者 `{`，则它是常量（组）的名称，
+    // 否则它是数据类型
+    let (first, post_first) = parse_primary_expression(token_details)?;
 
-    // 消除关键字 `let`
-    token_details = consume_token(&Token::Let, token_details)?;
-    // 消除关键字 `let` 后面的空行
-    token_details = skip_new_lines(token_details);
+    let (data_type, name, post_name) =
+        if is_token(&Token::Assign, post_first) || is_token(&Token::LeftBrace, post_first) {
+            (None, first, post_first)
+        } else {
+            let data_type = convert_expression_to_data_type(first, token_details)?;
+            let (name, post_name) = parse_primary_expression(post_first)?;
+            (Some(data_type), name, post_name)
+        };
 
-    // 解析 `左手边的数据类型` 或者 `左手边值`
-    let (maybe_lhs, post_maybe_lhs) = parse_mono_expression(token_details)?;
+    let name = match name {
+        Expression::Identifier(Identifier {
+            dirs,
+            name,
+            generics,
+            ..
+        }) if dirs.is_empty() && generics.is_empty() => name,
+        _ => {
+            return Err(new_error(
+                DiagnosticCode::ExpectedIdentifier,
+                "expected the name of the constant",
+                if data_type.is_some() {
+                    post_first
+                } else {
+                    token_details
+                },
+            ));
+        }
+    };
 
-    let (data_type, lhs) = if is_token(&Token::Assign, post_maybe_lhs) {
-        // 当前表达式没有数据类型，只有 `左手边值`（即 `模式表达式`）
-        token_details = post_maybe_lhs;
-        (None, maybe_lhs)
+    token_details = post_name;
+
+    if is_token(&Token::LeftBrace, token_details) {
+        // 常量组
+        let (members, post_members) = continue_parse_const_members(token_details)?;
+
+        // 常量组以 Token::NewLine 或者 EOF 结束，消耗这个换行符（如果存在的话）
+        let post_statement = consume_new_line_or_end_of_file(post_members)?;
+
+        let group = ConstGroupDeclaration {
+            data_type,
+            name,
+            members,
+            range: new_range(),
+        };
+
+        Ok((Statement::ConstGroupDeclaration(group), post_statement))
     } else {
-        // 当前表达式有数据类型
+        // 消除符号 `=`
+        token_details = consume_token(&Token::Assign, token_details)?;
+        // 消除符号 `=` 后面的空行
+        token_details = skip_new_lines(token_details);
 
-        // 转换成数据类型
-        let data_type = convert_expression_to_data_type(maybe_lhs)?;
+        let (value, post_value) = parse_expression(token_details)?;
 
-        // 解析左手边值
-        let (lhs, post_lhs) = parse_primary_expression(post_maybe_lhs)?;
-        token_details = post_lhs;
-        (Some(data_type), lhs)
-    };
+        // 常量以 Token::NewLine 或者 EOF 结束，消耗这个换行符（如果存在的话）
+        let post_statement = consume_new_line_or_end_of_file(post_value)?;
 
-    if !is_valid_left_hand_side(&lhs) {
-        return Err(Error::ParserError(
-            "invalid left-hand-side value".to_string(),
-        ));
+        let c = ConstDeclaration {
+            data_type,
+            name,
+            value,
+            range: new_range(),
+        };
+
+        Ok((Statement::ConstDeclaration(c), post_statement))
     }
+}
 
-    // 消除 `左手边值` 后面的空行
-    token_details = skip_new_lines(token_details);
+fn continue_parse_const_members(
+    source_token_details: &[TokenDetail],
+) -> Result<(Vec<ConstMember>, &[TokenDetail]), Diagnostic> {
+    // {
+    //     Ok = 200
+    //     Moved = 301, Found = 302
+    // }
+    // ^
+    // |--- 当前所处的位置
 
-    // 消除赋值符号 `=`
-    token_details = consume_token(&Token::Assign, token_details)?;
+    let mut token_details = source_token_details;
+    let mut members: Vec<ConstMember> = vec![];
 
-    // 消除赋值符号 `=` 后面的空行
+    // 消除符号 `{`
+    token_details = consume_token(&Token::LeftBrace, token_details)?;
+    // 消除符号 `{` 后面的空行
     token_details = skip_new_lines(token_details);
 
-    // 解析 `右手边值`
-
-    // 先检查 `右手边值` 是否类似 `identifier {...` 这样的结构，
-    // 如果是的话，则花括号应该解析为 `隠式 do 表达式`
-    // 这时不能直接使用 `parse_expression` 函数解析 `右手边值`，因为
-    // 这个函数会把花括号解析为结构体实例化。
-
-    let (rhs, post_rhs) = match continue_parse_identifier(token_details) {
-        Ok((maybe_identifier, post_maybe_identifier))
-            if is_token(&Token::LeftBrace, post_maybe_identifier) =>
-        {
-            (
-                Expression::Identifier(maybe_identifier),
-                post_maybe_identifier,
-            )
-        }
-        _ => parse_expression(token_details)?,
-    };
-
-    let let_expression = LetExpression {
-        data_type: data_type,
-        object: Box::new(lhs),
-        value: Box::new(rhs),
-        range: new_range(),
-    };
-
-    // 消除 `右手边值` 后面的空行
-    token_details = skip_new_lines(post_rhs);
+    loop {
+        let (name, post_name) = match token_details.split_first() {
+            Some((first, _)) if first.token == Token::RightBrace => {
+                // 找到了结束符号 `}`，退出循环
+                break;
+            }
+            Some((
+                TokenDetail {
+                    token: Token::Identifier(name),
+                    ..
+                },
+                post_name,
+            )) => (name.to_string(), post_name),
+            Some(_) => {
+                return Err(new_error(
+                    DiagnosticCode::ExpectedIdentifier,
+                    "expected the name of the constant member",
+                    token_details,
+                ));
+            }
+            None => {
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right brace symbol \"}\"",
+                    token_details,
+                ));
+            }
+        };
 
-    // 解析 `循环体表达式`
-    let (body_exp, post_body_exp) =
-        continue_parse_expression_block_or_single_expression(token_details)?;
+        if members.iter().any(|m| m.name == name) {
+            return Err(new_error(
+                DiagnosticCode::DuplicateMember,
+                &format!("the constant member `{}` is defined multiple times", name),
+                token_details,
+            ));
+        }
 
-    let exp = Expression::ForExpression(ForExpression {
-        initializer: Box::new(let_expression),
-        body: Box::new(body_exp),
-        range: new_range(),
-    });
+        // 消除符号 `=`
+        let post_assign = consume_token(&Token::Assign, post_name)?;
+        // 消除符号 `=` 后面的空行
+        let post_new_lines = skip_new_lines(post_assign);
 
-    Ok((exp, post_body_exp))
-}
+        let (value, post_value) = parse_expression(post_new_lines)?;
 
-fn parse_next_expression(
-    source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // next ...
-    let mut token_details = source_token_details;
+        members.push(ConstMember {
+            name,
+            value,
+            range: new_range(),
+        });
 
-    // 消除关键字 `next`
-    token_details = consume_token(&Token::Next, token_details)?;
-    // 消除关键字 `next` 后面的空行
-    token_details = skip_new_lines(token_details);
+        // 成员之间以换行或者逗号分隔
+        token_details = match post_value.first() {
+            Some(t) if t.token == Token::Comma => skip_new_lines(&post_value[1..]),
+            Some(t) if t.token == Token::NewLine => skip_new_lines(post_value),
+            Some(t) if t.token == Token::RightBrace => post_value,
+            _ => {
+                return Err(new_error(
+                    DiagnosticCode::ExpectedNewLine,
+                    "expected the comma or new-line symbol",
+                    post_value,
+                ));
+            }
+        };
+    }
 
-    // 解析表达式
-    let (expression, post_expression) = parse_expression(token_details)?;
+    // 消除符号 `}`
+    token_details = consume_token(&Token::RightBrace, token_details)?;
 
-    Ok((
-        Expression::NextExpression(NextExpression {
-            value: Box::new(expression),
-            range: new_range(),
-        }),
-        post_expression,
-    ))
+    Ok((members, token_details))
 }
 
-fn parse_each_expression(
+fn parse_struct(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // each ... in ... ...
-    // each ... in ... {...}
+    docs: Vec<String>,
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    // 结构体的定义
+    //
+    // struct User {                       // 一般结构体，成员之间以换行或者逗号分隔
+    //     Int id
+    //     String name = "foo"             // 成员可以指定默认值
+    // }
+    // struct User (Int id, String name)   // 一般结构体也可以写成一行
+    // struct Point (Int, Int)             // 匿名成员结构体（元组风格的结构体）
+    // struct Writer {}                    // 无成员结构体
+    // struct Pair<T> (T, T)               // 支持泛型
+    //
+    // @validate(id > 0)                   // 数据验证约束，可以标注在结构体以及成员之上
+    // struct User {
+    //     @validate(name != "")
+    //     String name
+    // }
 
-    let mut token_details = source_token_details;
+    // 解析结构体的数据验证约束
+    let (validates, post_validates) = continue_parse_validates(source_token_details)?;
 
-    // 消除关键字 `each`
-    token_details = consume_token(&Token::Each, token_details)?;
-    // 消除关键字 `each` 后面的空行
-    token_details = skip_new_lines(token_details);
+    let mut token_details = post_validates;
 
-    // 解析 `变量表达式`
-    let (variable, post_variable) = parse_mono_expression(token_details)?;
+    // 消除关键字 `struct`
+    token_details = consume_token(&Token::Struct, token_details)?;
+    // 消除关键字 `struct` 后面的空行
+    token_details = skip_new_lines(token_details);
 
-    if !is_valid_left_hand_side(&variable) {
-        return Err(Error::ParserError(
-            "invalid left-hand-side value".to_string(),
+    // 解析结构体名称（包括泛型）
+    let (struct_name, post_struct_name) = continue_parse_identifier(token_details)?;
+    if !struct_name.dirs.is_empty() {
+        return Err(new_error(
+            DiagnosticCode::ExpectedIdentifier,
+            "expected the name of the struct",
+            token_details,
         ));
     }
 
-    // 消除 `变量表达式` 后面的空行
-    token_details = skip_new_lines(post_variable);
-
-    // 消除关键字 `in`
-    token_details = consume_token(&Token::In, token_details)?;
-    // 消除关键字 `in` 后面的空行
-    token_details = skip_new_lines(token_details);
-
-    // 解析 `目标对象表达式`
-
-    // 先检查 `目标对象表达式` 是否类似 `identifier {...` 这样的结构，
-    // 如果是的话，则花括号应该解析为 `隠式 do 表达式`
-    // 这时不能直接使用 `parse_expression` 函数解析 `目标对象表达式`，因为
-    // 这个函数会把花括号解析为结构体实例化。
+    // 消除结构体名称后面的空行
+    token_details = skip_new_lines(post_struct_name);
 
-    let (object, post_object) = match continue_parse_identifier(token_details) {
-        Ok((maybe_identifier, post_maybe_identifier))
-            if is_token(&Token::LeftBrace, post_maybe_identifier) =>
-        {
-            (
-                Expression::Identifier(maybe_identifier),
-                post_maybe_identifier,
+    let (members, post_members) = match token_details.first() {
+        Some(t) if t.token == Token::LeftBrace => continue_parse_struct_members(token_details)?,
+        Some(t) if t.token == Token::LeftParen => {
+            continue_parse_struct_one_line_members(token_details)?
+        }
+        _ => {
+            return Err(new_error(
+                DiagnosticCode::UnexpectedToken,
+                "expected the struct members",
+                token_details,
             )
+            .with_help(
+                "a struct without members still needs the braces, e.g. `struct Writer {}`",
+            ));
         }
-        _ => parse_expression(token_details)?,
     };
 
-    // 消除 `目标对象表达式` 后面的空行
-    token_details = skip_new_lines(post_object);
+    // 结构体以 Token::NewLine 或者 EOF 结束，消耗这个换行符（如果存在的话）
+    let post_statement = consume_new_line_or_end_of_file(post_members)?;
+
+    let statement = match members {
+        StructMembers::Named(members) if !members.is_empty() => {
+            Statement::MemberStructDeclaration(MemberStructDeclaration {
+                name: struct_name.name,
+                members,
+                generics: struct_name.generics,
+                validates,
+                docs,
+                range: new_range(),
+            })
+        }
+        StructMembers::Anonymous(members) if !members.is_empty() => {
+            check_no_validates(&validates, source_token_details)?;
+
+            Statement::TupleStructDeclaration(TupleStructDeclaration {
+                name: struct_name.name,
+                members,
+                generics: struct_name.generics,
+                docs,
+                range: new_range(),
+            })
+        }
+        _ => {
+            check_no_validates(&validates, source_token_details)?;
 
-    // 解析 `循环体表达式`
-    let (body_exp, post_body_exp) =
-        continue_parse_expression_block_or_single_expression(token_details)?;
+            Statement::EmptyStructDeclaration(EmptyStructDeclaration {
+                name: struct_name.name,
+                generics: struct_name.generics,
+                docs,
+                range: new_range(),
+            })
+        }
+    };
 
-    let exp = Expression::EachExpression(EachExpression {
-        variable: Box::new(variable),
-        object: Box::new(object),
-        body: Box::new(body_exp),
-        range: new_range(),
-    });
+    Ok((statement, post_statement))
+}
 
-    Ok((exp, post_body_exp))
+// 结构体的成员列表
+enum StructMembers {
+    Named(Vec<StructMember>),
+    Anonymous(Vec<DataType>),
 }
 
-fn parse_branch_expression(
+// 数据验证约束需要引用成员的名称，所以只有一般结构体支持
+fn check_no_validates(
+    validates: &[Expression],
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // branch {...}
-    // branch where ... {         // where 从属表达式
-    //   ...
-    // }
+) -> Result<(), Diagnostic> {
+    if validates.is_empty() {
+        Ok(())
+    } else {
+        Err(new_error(
+            DiagnosticCode::UnexpectedToken,
+            "only structs with named members can have validation constraints",
+            source_token_details,
+        ))
+    }
+}
+
+fn continue_parse_validates(
+    source_token_details: &[TokenDetail],
+) -> Result<(Vec<Expression>, &[TokenDetail]), Diagnostic> {
+    // 数据验证约束（标注）
     //
-    // branch {
-    //    case testing_exp: exp,
-    //    case testing_exp: exp           // 逗号可省略
-    //    case testing_exp
-    //         where ...: exp // 分支可以携带一个 where 从属表达式
-    //    default: exp
-    //}
+    // @validate(id > 0)
+    // @validate(name != "")
+    // ^
+    // |--- 当前所处的位置
 
     let mut token_details = source_token_details;
-    let mut cases: Vec<BranchCase> = vec![];
-    let mut default_exp: Option<Expression> = None;
+    let mut validates: Vec<Expression> = vec![];
 
-    // 标记当前是否处于查找分支结束的状态，即已经遇到 default 分支，用于
-    // 防止 default 后面仍存在其他分支的情况。
-    let mut is_expected_end = false;
+    while is_token(&Token::At, token_details) {
+        // 消除符号 `@`
+        token_details = consume_token(&Token::At, token_details)?;
 
-    // 消除关键字 `branch`
-    token_details = consume_token(&Token::Branch, token_details)?;
-    // 消除关键字 `branch` 后面的空行
-    token_details = skip_new_lines(token_details);
+        match token_details.first() {
+            Some(TokenDetail {
+                token: Token::Identifier(name),
+                ..
+            }) if name.as_ref() == "validate" => {
+                token_details = &token_details[1..];
+            }
+            _ => {
+                return Err(new_error(
+                    DiagnosticCode::UnexpectedToken,
+                    "unknown annotation",
+                    token_details,
+                )
+                .with_note("only the `@validate(...)` annotation is supported"));
+            }
+        }
 
-    // 检查是否存在 `where` 子表达式
-    let where_exp = if is_token(&Token::Where, token_details) {
-        let (where_exp, post_where_expression) = continue_parse_where_expression(token_details)?;
+        // 消除符号 `(`
+        token_details = consume_token(&Token::LeftParen, token_details)?;
+        // 消除符号 `(` 后面的空行
+        token_details = skip_new_lines(token_details);
 
-        // 消除 `where` 子表达式后面的空行
-        token_details = skip_new_lines(post_where_expression);
+        let (exp, post_exp) = parse_expression(token_details)?;
+        validates.push(exp);
 
-        Some(where_exp)
-    } else {
-        None
-    };
+        // 消除符号 `)`
+        token_details = skip_new_lines_and_consume_token(&Token::RightParen, post_exp)?;
+        // 消除标注后面的空行
+        token_details = skip_new_lines(token_details);
+    }
+
+    Ok((validates, token_details))
+}
+
+fn continue_parse_struct_members(
+    source_token_details: &[TokenDetail],
+) -> Result<(StructMembers, &[TokenDetail]), Diagnostic> {
+    // {
+    //     '''document'''
+    //     @validate(id > 0)
+    //     Int id
+    //     String name = "foo", Boolean checked
+    // }
+    // ^
+    // |--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+    let mut members: Vec<StructMember> = vec![];
 
     // 消除符号 `{`
     token_details = consume_token(&Token::LeftBrace, token_details)?;
-    // 消除符号 `{` 后面的空行
-    token_details = skip_new_lines(token_details);
 
-    // 开始解析 case 和 default
     loop {
-        token_details = match token_details.first() {
-            Some(first) => {
-                if first.token == Token::RightBrace {
-                    // 找到了结束符号 `}`，退出循环
-                    break;
-                } else {
-                    if is_expected_end {
-                        // 当前的状态是一心寻找结束符号 `}`
-                        return Err(Error::ParserError(
-                            "expected the right brace symbol \"}\"".to_string(),
-                        ));
-                    } else {
-                        if is_token(&Token::Case, token_details) {
-                            let (case_exp, post_case_exp) =
-                                continue_parse_branch_case(token_details)?;
-                            cases.push(case_exp);
+        // 消除前导的空行，并收集成员之前的文档注释
+        let (docs, post_docs) = continue_parse_docs(token_details);
+        token_details = post_docs;
 
-                            // 消除当前分支后面的符号 `,`（如果存在的话）
-                            let post_comma = if is_token(&Token::Comma, post_case_exp) {
-                                consume_token(&Token::Comma, post_case_exp)?
-                            } else {
-                                post_case_exp
-                            };
+        if is_token(&Token::RightBrace, token_details) {
+            // 找到了结束符号 `}`，退出循环
+            break;
+        }
 
-                            // 消除符号 `,` 后面的空行
-                            let post_new_lines = skip_new_lines(post_comma);
-                            post_new_lines
-                        } else if is_token(&Token::Default, token_details) {
-                            let (expression, post_default_exp) =
-                                continue_parse_default_case(token_details)?;
-                            default_exp = Some(expression);
+        if token_details.is_empty() {
+            return Err(new_error(
+                DiagnosticCode::UnclosedDelimiter,
+                "expected the right brace symbol \"}\"",
+                token_details,
+            ));
+        }
 
-                            // 标记所有分支均已结束，因为已经遇到了默认分支
-                            is_expected_end = true;
+        // 解析成员的数据验证约束
+        let (validates, post_validates) = continue_parse_validates(token_details)?;
 
-                            // 消除当前分支后面的符号 `,`（如果存在的话）
-                            let post_comma = if is_token(&Token::Comma, post_default_exp) {
-                                consume_token(&Token::Comma, post_default_exp)?
-                            } else {
-                                post_default_exp
-                            };
+        let (data_type, name, post_name) = continue_parse_struct_member(post_validates)?;
 
-                            // 消除符号 `,` 后面的空行
-                            let post_new_lines = skip_new_lines(post_comma);
-                            post_new_lines
-                        } else {
-                            return Err(Error::ParserError(
-                                "invalid branch expression".to_string(),
-                            ));
-                        }
-                    }
-                }
-            }
+        let name = match name {
+            Some(n) => n,
             None => {
-                return Err(Error::ParserError(
-                    "expected the right brace symbol \"}\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::ExpectedIdentifier,
+                    "expected the name of the struct member",
+                    post_name,
                 ));
             }
+        };
+
+        if members.iter().any(|m| m.name == name) {
+            return Err(new_error(
+                DiagnosticCode::DuplicateMember,
+                &format!("the struct member `{}` is defined multiple times", name),
+                post_validates,
+            ));
         }
-    }
 
-    // 消除符号 `}`
-    token_details = consume_token(&Token::RightBrace, token_details)?;
+        let (value, post_value) = continue_parse_struct_member_value(post_name)?;
 
-    let exp = Expression::BranchExpression(BranchExpression {
-        where_exp: where_exp.map(|e| Box::new(e)),
-        cases: cases,
-        default_exp: default_exp.map(|e| Box::new(e)),
-        range: new_range(),
-    });
-
-    Ok((exp, token_details))
-}
-
-fn continue_parse_branch_case(
-    source_token_details: &[TokenDetail],
-) -> Result<(BranchCase, &[TokenDetail]), Error> {
-    // case ...: ...
-    // case ... where ...: ...
-    // ~~~~
-    //    |--- 当前所处的位置
-
-    let mut token_details = source_token_details;
-
-    // 消除 `case` 关键字
-    token_details = consume_token(&Token::Case, token_details)?;
-    // 消除 `case` 关键字后面的空行
-    token_details = skip_new_lines(token_details);
-
-    // 解析 `条件表达式`
-    let (testing_exp, post_testing) = parse_expression(token_details)?;
-    // 消除 `条件表达式 后面的空行
-    token_details = skip_new_lines(post_testing);
-
-    // 解析 `where 从属表达式`
-    let where_exp = if is_token(&Token::Where, token_details) {
-        let (expression, post_where_expression) = continue_parse_where_expression(token_details)?;
-        token_details = post_where_expression;
-        Some(expression)
-    } else {
-        None
-    };
-    // 消除 `where 从属表达式 后面的空行
-    token_details = skip_new_lines(token_details);
-
-    // 消除符号 `:`
-    token_details = consume_token(&Token::Colon, token_details)?;
-
-    // 解析 `结果表达式`（可以是 `隠式 do 表达式`）
-    let (consequent_exp, post_consequent) =
-        continue_parse_expression_block_or_single_expression(token_details)?;
-
-    let case = BranchCase {
-        testing: Box::new(testing_exp),
-        where_exp: where_exp.map(|e| Box::new(e)),
-        consequent: Box::new(consequent_exp),
-        range: new_range(),
-    };
-
-    Ok((case, post_consequent))
-}
-
-fn continue_parse_default_case(
-    source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // default: ...
-    // ~~~~~~~
-    //       |--- 当前所处的位置
+        members.push(StructMember {
+            data_type,
+            name,
+            value,
+            validates,
+            docs,
+            range: new_range(),
+        });
 
-    let mut token_details = source_token_details;
+        // 成员之间以换行或者逗号分隔
+        token_details = match post_value.first() {
+            Some(t) if t.token == Token::Comma => &post_value[1..],
+            Some(t) if t.token == Token::NewLine || t.token == Token::RightBrace => post_value,
+            _ => {
+                return Err(new_error(
+                    DiagnosticCode::ExpectedNewLine,
+                    "expected the comma or new-line symbol",
+                    post_value,
+                ));
+            }
+        };
+    }
 
-    // 消除 `default` 关键字
-    token_details = consume_token(&Token::Default, token_details)?;
-    // 消除符号 `:`
-    token_details = consume_token(&Token::Colon, token_details)?;
-    // 消除空行
-    token_details = skip_new_lines(token_details);
+    // 消除符号 `}`
+    token_details = consume_token(&Token::RightBrace, token_details)?;
 
-    // 解析 `结果表达式`（可以是 `隠式 do 表达式`）
-    continue_parse_expression_block_or_single_expression(token_details)
+    Ok((StructMembers::Named(members), token_details))
 }
 
-fn parse_match_expression(
+fn continue_parse_struct_one_line_members(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
-    // match obj {...}
-    // match obj where ... {
-    //   ...
-    // }
-    // match obj {
-    //    case pattern_exp: exp,
-    //    case pattern_exp: exp             // 逗号可省略
-    //
-    //    case variable @ pattern_exp: exp  // `模式表达式` 之前可以添加 `变量名` + `@`
-    //
-    //    case in ...: ...                  // 模式表达式还可以是 `into`, `regular`, `template` 其中的一种
-    //    case into Email e: ...
-    //    case regular REGEXP_LITERAL (tuple,...): ...
-    //    case template "STRING": ...
-    //
-    //    case pattern_exp
-    //         where ...
-    //         only ...: ...              // 模式表达式之后可以添加 where, only 从属表达式
+) -> Result<(StructMembers, &[TokenDetail]), Diagnostic> {
+    // 一般结构体的单行形式，以及匿名成员结构体
+    // 以第一个成员是否有名称来决定结构体的类型
     //
-    //    default: exp                      // match 可以有一个 default 分支
-    //}
+    // (Int id, String name = "foo")
+    // (Int, String)
+    // ^
+    // |--- 当前所处的位置
 
     let mut token_details = source_token_details;
-    let mut cases: Vec<MatchCase> = vec![];
-    let mut default_exp: Option<Expression> = None;
-
-    // 标记当前是否处于查找分支结束的状态，即已经遇到 default 分支，用于
-    // 防止 default 后面仍存在其他分支的情况。
-    let mut is_expected_end = false;
-
-    // 消除关键字 `match`
-    token_details = consume_token(&Token::Match, token_details)?;
-    // 消除关键字 `match` 后面的空行
-    token_details = skip_new_lines(token_details);
-
-    // 解析 `目标对象表达式`
-
-    // 先检查 `目标对象表达式` 是否类似 `identifier {...` 这样的结构，
-    // 如果是的话，则花括号应该解析为 `隠式 do 表达式`
-    // 这时不能直接使用 `parse_expression` 函数解析 `目标对象表达式`，因为
-    // 这个函数会把花括号解析为结构体实例化。
-
-    let (object, post_object) = match continue_parse_identifier(token_details) {
-        Ok((maybe_identifier, post_maybe_identifier))
-            if is_token(&Token::LeftBrace, post_maybe_identifier) =>
-        {
-            (
-                Expression::Identifier(maybe_identifier),
-                post_maybe_identifier,
-            )
-        }
-        _ => parse_expression(token_details)?,
-    };
 
-    // 消除 `目标对象表达式` 后面的空行
-    token_details = skip_new_lines(post_object);
-
-    // 检查是否存在 `where` 子表达式
-    let where_exp = if is_token(&Token::Where, token_details) {
-        let (where_exp, post_where_expression) = continue_parse_where_expression(token_details)?;
-
-        // 消除 `where` 子表达式后面的空行
-        token_details = skip_new_lines(post_where_expression);
+    let mut named_members: Vec<StructMember> = vec![];
+    let mut anonymous_members: Vec<DataType> = vec![];
 
-        Some(where_exp)
-    } else {
-        None
-    };
+    let mut is_expected_end = false; // 标记当前是否处于寻找成员列表结束符号 `)` 的状态
 
-    // 消除符号 `{`
-    token_details = consume_token(&Token::LeftBrace, token_details)?;
-    // 消除符号 `{` 后面的空行
+    // 消除符号 `(`
+    token_details = consume_token(&Token::LeftParen, token_details)?;
+    // 消除符号 `(` 后面的空行
     token_details = skip_new_lines(token_details);
 
-    // 开始解析 case 和 default
     loop {
         token_details = match token_details.first() {
             Some(first) => {
-                if first.token == Token::RightBrace {
-                    // 找到了结束符号 `}`，退出循环
+                if first.token == Token::RightParen {
+                    // 找到了结束符号 `)`，退出循环
                     break;
+                } else if is_expected_end {
+                    // 当前的状态是一心寻找结束符号
+                    return Err(new_error(
+                        DiagnosticCode::UnclosedDelimiter,
+                        "expected the right paren symbol \")\"",
+                        token_details,
+                    ));
                 } else {
-                    if is_expected_end {
-                        // 当前的状态是一心寻找结束符号 `}`
-                        return Err(Error::ParserError(
-                            "expected the right brace symbol \"}\"".to_string(),
-                        ));
-                    } else {
-                        if is_token(&Token::Case, token_details) {
-                            let (case_exp, post_case_exp) =
-                                continue_parse_match_case(token_details)?;
-                            cases.push(case_exp);
+                    let (data_type, name, post_name) = continue_parse_struct_member(token_details)?;
 
-                            // 消除当前分支后面的符号 `,`（如果存在的话）
-                            let post_comma = if is_token(&Token::Comma, post_case_exp) {
-                                consume_token(&Token::Comma, post_case_exp)?
-                            } else {
-                                post_case_exp
-                            };
+                    let is_named = if named_members.is_empty() && anonymous_members.is_empty() {
+                        name.is_some()
+                    } else {
+                        !named_members.is_empty()
+                    };
+
+                    let post_member = if is_named {
+                        let name = match name {
+                            Some(n) => n,
+                            None => {
+                                return Err(new_error(
+                                    DiagnosticCode::ExpectedIdentifier,
+                                    "expected the name of the struct member",
+                                    post_name,
+                                ));
+                            }
+                        };
 
-                            // 消除符号 `,` 后面的空行
-                            let post_new_lines = skip_new_lines(post_comma);
-                            post_new_lines
-                        } else if is_token(&Token::Default, token_details) {
-                            let (expression, post_default_exp) =
-                                continue_parse_default_case(token_details)?;
-                            default_exp = Some(expression);
+                        if named_members.iter().any(|m| m.name == name) {
+                            return Err(new_error(
+                                DiagnosticCode::DuplicateMember,
+                                &format!("the struct member `{}` is defined multiple times", name),
+                                token_details,
+                            ));
+                        }
 
-                            // 标记所有分支均已结束，因为已经遇到了默认分支
-                            is_expected_end = true;
+                        let (value, post_value) = continue_parse_struct_member_value(post_name)?;
 
-                            // 消除当前分支后面的符号 `,`（如果存在的话）
-                            let post_comma = if is_token(&Token::Comma, post_default_exp) {
-                                consume_token(&Token::Comma, post_default_exp)?
-                            } else {
-                                post_default_exp
-                            };
+                        named_members.push(StructMember {
+                            data_type,
+                            name,
+                            value,
+                            validates: vec![],
+                            docs: vec![],
+                            range: new_range(),
+                        });
 
-                            // 消除符号 `,` 后面的空行
-                            let post_new_lines = skip_new_lines(post_comma);
-                            post_new_lines
-                        } else {
-                            return Err(Error::ParserError("invalid match expression".to_string()));
+                        post_value
+                    } else {
+                        if name.is_some() {
+                            return Err(new_error(
+                                DiagnosticCode::UnexpectedToken,
+                                "the members of a tuple struct can not have names",
+                                token_details,
+                            ));
                         }
-                    }
+
+                        anonymous_members.push(data_type);
+                        post_name
+                    };
+
+                    // 消除逗号
+                    let post_consume_comma = if is_token(&Token::Comma, post_member) {
+                        consume_token(&Token::Comma, post_member)?
+                    } else {
+                        // 设置标记，表示如果项目后面没有逗号，则表示当前已经是最后一项
+                        // 后面只能允许列表结束
+                        is_expected_end = true;
+                        post_member
+                    };
+
+                    // 消除空行
+                    skip_new_lines(post_consume_comma)
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right brace symbol \"}\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right paren symbol \")\"",
+                    token_details,
                 ));
             }
         }
     }
 
-    // 消除符号 `}`
-    token_details = consume_token(&Token::RightBrace, token_details)?;
+    // 消除符号 `)`
+    token_details = consume_token(&Token::RightParen, token_details)?;
 
-    let exp = Expression::MatchExpression(MatchExpression {
-        object: Box::new(object),
-        where_exp: where_exp.map(|e| Box::new(e)),
-        cases: cases,
-        default_exp: default_exp.map(|e| Box::new(e)),
-        range: new_range(),
-    });
+    let members = if named_members.is_empty() {
+        StructMembers::Anonymous(anonymous_members)
+    } else {
+        StructMembers::Named(named_members)
+    };
 
-    Ok((exp, token_details))
+    Ok((members, token_details))
 }
 
-fn continue_parse_match_case(
+fn continue_parse_struct_member(
     source_token_details: &[TokenDetail],
-) -> Result<(MatchCase, &[TokenDetail]), Error> {
-    // `match case` 由 3 部分组成：
-    // 1. 变量
-    // 2. 模式表达式
-    // 3. where/only 从属表达式
-    //
-    // case pattern_exp: exp,
-    // case pattern_exp: exp             // 逗号可省略
-    //
-    // case variable @ pattern_exp: exp  // `模式表达式` 之前可以添加 `变量名` + `@`
-    //
-    // case in ...: ...                  // 模式表达式还可以是 `into`, `regular`, `template` 其中的一种
-    // case into Email e: ...
-    // case regular REGEXP_LITERAL (tuple,...): ...
-    // case template "STRING": ...
+) -> Result<(DataType, Option<String>, &[TokenDetail]), Diagnostic> {
+    // 解析成员的数据类型以及名称（如果存在的话）
     //
-    // case pattern_exp
-    //      only ...                     // 模式表达式之后可以添加 where, only 从属表达式
-    //      where ...: ...
-    // ~~~~
-    //    |--- 当前所处的位置
-
-    let mut token_details = source_token_details;
+    // Int id
+    // Int
+    // ^
+    // |--- 当前所处的位置
 
-    // 消除 `case` 关键字
-    token_details = consume_token(&Token::Case, token_details)?;
-    // 消除 `case` 关键字后面的空行
-    token_details = skip_new_lines(token_details);
+    let (data_type_expression, post_data_type_expression) =
+        parse_primary_expression(source_token_details)?;
+    let data_type = convert_expression_to_data_type(data_type_expression, source_token_details)?;
 
-    // 先检查有无语法错误，match case 不允许由 `从属表达式` 开始。
-    if any_token(&vec![Token::Only, Token::Where], token_details) {
-        return Err(Error::ParserError(
-            "invalid match case expression".to_string(),
-        ));
+    match post_data_type_expression.split_first() {
+        Some((
+            TokenDetail {
+                token: Token::Identifier(name),
+                ..
+            },
+            post_name,
+        )) => Ok((data_type, Some(name.to_string()), post_name)),
+        _ => Ok((data_type, None, post_data_type_expression)),
     }
+}
 
-    let mut variable: Option<String> = None;
+fn continue_parse_struct_member_value(
+    source_token_details: &[TokenDetail],
+) -> Result<(Option<Expression>, &[TokenDetail]), Diagnostic> {
+    // 解析成员的默认值（如果存在的话）
+    //
+    // = value
+    // ^
+    // |--- 当前所处的位置
 
-    // 解析 `变量` 部分，`变量` 部分的结构是 `identifier @`
-    if !any_token(
-        &vec![Token::In, Token::Into, Token::Regular, Token::Template],
-        token_details,
-    ) {
-        match parse_primary_expression(token_details) {
+    if is_token(&Token::Assign, source_token_details) {
+        // 消除符号 `=`
+        let post_assign = consume_token(&Token::Assign, source_token_details)?;
+        // 消除符号 `=` 后面的空行
+        let post_new_lines = skip_new_lines(post_assign);
+
+        let (value, post_value) = parse_expression(post_new_lines)?;
+        Ok((Some(value), post_value))
+    } else {
+        Ok((None, source_token_details))
+    }
+}
+
+fn parse_union(
+    _source_token_details: &[TokenDetail],
+    _docs: Vec<String>,
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    todo!()
+}
+
+fn parse_trait_declaration(
+    _source_token_details: &[TokenDetail],
+    _docs: Vec<String>,
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    todo!()
+}
+
+fn parse_impl_statement(
+    _source_token_details: &[TokenDetail],
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    todo!()
+}
+
+fn parse_alias_statement(
+    _source_token_details: &[TokenDetail],
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    todo!()
+}
+
+// ExpressionStatement
+//  : Expression NEW_LINE
+//  | Expression EOF
+//  ;
+fn parse_expression_statement(
+    source_token_details: &[TokenDetail],
+) -> Result<(Statement, &[TokenDetail]), Diagnostic> {
+    let (expression, rest) = parse_expression(source_token_details)?;
+
+    // statement 以 Token::NewLine 或者 EOF 结束，消耗这个换行符（如果存在的话）
+    consume_new_line_or_end_of_file(rest)
+        .map(|post_rest| (Statement::Expression(expression), post_rest))
+}
+
+// Expression
+//  : BlockExpression
+//  | JoinExpression
+//  | LetExpression
+//
+//  | IfExpression
+//  | ForExpression
+//  | NextExpression
+//  | EachExpression
+//  | BranchExpression
+//  | MatchExpression
+//
+//  | BinaryExpression
+//  | UnaryExpression
+//  | FunctionCallExpression
+//  | MemberExpression
+//  | SliceExpression
+//  | ConstructorExpression
+//
+//  | AnonymousFunction
+//  | SignExpression
+//  | Identifier
+//  | PrefixIdentifier
+//  | Tuple
+//  | List
+//  | Map
+//  | Literal
+//  ;
+fn parse_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    if let Some(first) = source_token_details.first() {
+        match first.token {
+            Token::Do => parse_do_expression(source_token_details),
+            Token::Join => parse_join_expression(source_token_details),
+            Token::Let => parse_let_expression(source_token_details),
+            Token::If => parse_if_expression(source_token_details),
+            Token::For => parse_for_expression(source_token_details),
+            Token::Next => parse_next_expression(source_token_details),
+            Token::Each => parse_each_expression(source_token_details),
+            Token::Branch => parse_branch_expression(source_token_details),
+            Token::Match => parse_match_expression(source_token_details),
+            _ => {
+                // 二元运算表达式的开始
+                let (exp, post_exp) = parse_pipe_expression(source_token_details)?;
+
+                if is_token(&Token::ColonAssign, post_exp) {
+                    // 属性设置表达式 `object.property := value`
+                    continue_parse_property_set_expression(exp, post_exp)
+                } else {
+                    Ok((exp, post_exp))
+                }
+            }
+        }
+    } else {
+        Err(new_error(
+            DiagnosticCode::ExpectedExpression,
+            "expected expression",
+            source_token_details,
+        ))
+    }
+}
+
+// BlockExpression
+//  : 'do' BlockExpression
+//  ;
+fn parse_do_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // 解析 do 表达式 `do {...}`，do 表达式是一个显式表达式块
+
+    // 消除 do
+    let post_consume_token_do = consume_token(&Token::Do, source_token_details)?;
+
+    // 消除换行符
+    // do 关键字后面允许换行
+    let post_consume_new_lines = skip_new_lines(post_consume_token_do);
+
+    let (expressions, post_expression_block) =
+        continue_parse_expression_block(post_consume_new_lines)?;
+
+    Ok((
+        Expression::BlockExpression(BlockExpression {
+            is_explicit: true,
+            body: expressions,
+            range: new_range(),
+        }),
+        post_expression_block,
+    ))
+}
+
+// BlockExpression
+//  : '{' ExpressionList '}'
+//  ;
+//
+// ExpressionList
+//  : Expression
+//  | ExpressionList NEW_LINE Expression
+//  ;
+fn continue_parse_expression_block(
+    source_token_details: &[TokenDetail],
+) -> Result<(Vec<Expression>, &[TokenDetail]), Diagnostic> {
+    // 解析表达式块 `{...}`（也叫 `隠式 Do 表达式`）
+    // 注意表达式块仅存在某些关键字后面，比如 `join`、`do` 等，而不能单独存在，
+    // 当一对花括号单独存在时，会被解析为 Map。
+    let mut token_details = source_token_details;
+
+    // 消除符号 `{`
+    token_details = consume_token(&Token::LeftBrace, token_details)?;
+    // 消除符号 `{` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    let mut expressions: Vec<Expression> = vec![];
+
+    loop {
+        // 遇到了结束符号 `}`，退出循环
+        if is_token(&Token::RightBrace, token_details) {
+            break;
+        }
+
+        // 解析表达式
+        let (expression, post_expression) = parse_expression(token_details)?;
+        expressions.push(expression);
+
+        // 消除表达式末尾的符号 `,`（假如存在的话）
+        token_details = if is_token(&Token::Comma, post_expression) {
+            consume_token(&Token::Comma, post_expression)?
+        } else {
+            post_expression
+        };
+
+        // 消除符号 `,` 后面的空行
+        token_details = skip_new_lines(token_details);
+    }
+
+    // 消除符号 `}`
+    token_details = consume_token(&Token::RightBrace, token_details)?;
+
+    Ok((expressions, token_details))
+}
+
+fn continue_parse_expression_block_or_single_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // 解析 `{...}` 或者 `...`
+    // 在诸如 `if`、`then`、`else` 等关键字后面，即可以是单独一个表达式，
+    // 也可以是一个表达式块。
+    //
+    // 解析优先级：
+    // 1. 如果存在 `{...}`，则解析为隠式表达式块
+    // 2. 否则解析为普通的表达式
+
+    match source_token_details.first() {
+        Some(first) => match first.token {
+            Token::LeftBrace => {
+                let (expressions, post_expression_block) =
+                    continue_parse_expression_block(source_token_details)?;
+
+                Ok((
+                    Expression::BlockExpression(BlockExpression {
+                        is_explicit: false,
+                        body: expressions,
+                        range: new_range(),
+                    }),
+                    post_expression_block,
+                ))
+            }
+            _ => parse_expression(source_token_details),
+        },
+        None => Err(new_error(
+            DiagnosticCode::ExpectedExpression,
+            "expected an expression or an expression block",
+            source_token_details,
+        )),
+    }
+}
+
+fn parse_join_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // 解析 join 表达式
+    // join {...}
+
+    let mut token_details = source_token_details;
+
+    // 消除关键字 `join`
+    token_details = consume_token(&Token::Join, token_details)?;
+    // 消除关键字 `join` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    let (expressions, post_expression_block) = continue_parse_expression_block(token_details)?;
+
+    Ok((
+        Expression::JoinExpression(JoinExpression {
+            body: expressions,
+            range: new_range(),
+        }),
+        post_expression_block,
+    ))
+}
+
+fn parse_let_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // `let 表达式` 的 `左手边值` 也叫 `模式表达式`，
+    // `模式表达式` 属于 `单一表达式`，但只允许如下几种：
+    //
+    // - 各种类型结构体、联合体的实例化表达式
+    // - Identifier
+    // - Tuple
+    // - List
+    // - Map
+    // - 数字、字符串等字面量
+    //
+    // let data_type left = right
+    // let left = right // 数据类型可以省略
+    // ~~~
+    //   ^--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
+    // 消除关键字 `let`
+    token_details = consume_token(&Token::Let, token_details)?;
+    // 消除关键字 `let` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `左手边的数据类型` 或者 `左手边值`
+    let (maybe_lhs, post_maybe_lhs) = parse_mono_expression(token_details)?;
+
+    let (data_type, lhs) = if is_token(&Token::Assign, post_maybe_lhs)
+        || is_token(&Token::ColonAssign, post_maybe_lhs)
+    {
+        // 当前表达式没有数据类型，只有 `左手边值`（即 `模式表达式`）
+        token_details = post_maybe_lhs;
+        (None, maybe_lhs)
+    } else {
+        // 当前表达式有数据类型
+
+        // 转换成数据类型
+        let data_type = convert_expression_to_data_type(maybe_lhs, token_details)?;
+
+        // 解析左手边值
+        let (lhs, post_lhs) = parse_primary_expression(post_maybe_lhs)?;
+        token_details = post_lhs;
+        (Some(data_type), lhs)
+    };
+
+    if !is_valid_left_hand_side(&lhs) {
+        return Err(new_error(
+            DiagnosticCode::InvalidLeftHandSide,
+            "invalid left-hand-side value",
+            token_details,
+        ));
+    }
+
+    // 消除 `左手边值` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    if is_token(&Token::ColonAssign, token_details) {
+        // 属性读取表达式 `let data_type left := object.property`
+        return continue_parse_property_get_expression(data_type, lhs, token_details);
+    }
+
+    // 消除赋值符号 `=`
+    token_details = consume_token(&Token::Assign, token_details)?;
+
+    // 消除赋值符号 `=` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析右手边值
+    let (rhs, post_rhs) = parse_expression(token_details)?;
+
+    let exp = LetExpression {
+        data_type,
+        object: Box::new(lhs),
+        value: Box::new(rhs),
+        range: new_range(),
+    };
+
+    Ok((Expression::LetExpression(exp), post_rhs))
+}
+
+fn continue_parse_property_get_expression(
+    data_type: Option<DataType>,
+    lhs: Expression,
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // let data_type left := object.property
+    //                    ~~
+    //                     ^--- 当前所处的位置
+
+    // 消除符号 `:=` 及其后面的空行
+    let mut token_details = consume_token(&Token::ColonAssign, source_token_details)?;
+    token_details = skip_new_lines(token_details);
+
+    // 解析右手边的属性
+    let (rhs, post_rhs) = parse_mono_expression(token_details)?;
+    let member = convert_expression_to_member_property(rhs, token_details)?;
+
+    let exp = PropertyGetExpression {
+        data_type,
+        object: Box::new(lhs),
+        member,
+        range: new_range(),
+    };
+
+    Ok((Expression::PropertyGetExpression(exp), post_rhs))
+}
+
+fn continue_parse_property_set_expression(
+    lhs: Expression,
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // object.property := value
+    //                 ~~
+    //                  ^--- 当前所处的位置
+
+    // `:=` 的左手边必须是属性
+    let member = match lhs {
+        Expression::MemberExpression(MemberExpression::Property(member))
+            if matches!(*member.property, Expression::Identifier(_)) =>
+        {
+            member
+        }
+        _ => {
+            return Err(new_error(
+                DiagnosticCode::InvalidLeftHandSide,
+                "the left-hand side of `:=` should be a property",
+                source_token_details,
+            ))
+        }
+    };
+
+    // 消除符号 `:=` 及其后面的空行
+    let mut token_details = consume_token(&Token::ColonAssign, source_token_details)?;
+    token_details = skip_new_lines(token_details);
+
+    // 解析右手边值
+    let (rhs, post_rhs) = parse_expression(token_details)?;
+
+    let exp = PropertySetExpression {
+        member,
+        value: Box::new(rhs),
+        range: new_range(),
+    };
+
+    Ok((Expression::PropertySetExpression(exp), post_rhs))
+}
+
+// 将表达式转换为属性，属性名称只允许标识符
+fn convert_expression_to_member_property(
+    exp: Expression,
+    source_token_details: &[TokenDetail],
+) -> Result<MemberProperty, Diagnostic> {
+    match exp {
+        Expression::MemberExpression(MemberExpression::Property(member))
+            if matches!(*member.property, Expression::Identifier(_)) =>
+        {
+            Ok(member)
+        }
+        _ => Err(new_error(
+            DiagnosticCode::InvalidPropertyName,
+            "expected a property, e.g. `object.property`",
+            source_token_details,
+        )),
+    }
+}
+
+fn is_valid_left_hand_side(_exp: &Expression) -> bool {
+    // todo:: 检查左手边的值是否符合语法
+    true
+}
+
+fn parse_if_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // if ... then ...
+    // if ... then ... else ...
+    // ~~
+    //  ^--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
+    // 消除关键字 `if`
+    token_details = consume_token(&Token::If, token_details)?;
+    // 消除关键字 `if` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    let (testing, post_testing) = parse_expression(token_details)?;
+
+    // 消除 `if` 子表达式后面的空行
+    token_details = skip_new_lines(post_testing);
+
+    // 检查是否存在 `where` 子表达式
+    let where_exp = if is_token(&Token::Where, token_details) {
+        let (where_exp, post_where_expression) = continue_parse_where_expression(token_details)?;
+
+        token_details = post_where_expression;
+        Some(where_exp)
+    } else {
+        None
+    };
+
+    // 消除关键字 `then` (包括前缀空行)
+    token_details = skip_new_lines_and_consume_token(&Token::Then, token_details)?;
+    // 消除关键字 `then` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    let (consequent, post_consequent) =
+        continue_parse_expression_block_or_single_expression(token_details)?;
+
+    // 检查是否存在 `else` 子表达式
+    let alternate = if is_token_ignore_new_lines(&Token::Else, post_consequent) {
+        // 消除关键字 `else` (包括前缀空行)
+        token_details = skip_new_lines_and_consume_token(&Token::Else, post_consequent)?;
+        // 消除关键字 `else` 后面的空行
+        token_details = skip_new_lines(token_details);
+
+        let (alternate, post_alternate) =
+            continue_parse_expression_block_or_single_expression(token_details)?;
+
+        token_details = post_alternate;
+        Some(alternate)
+    } else {
+        token_details = post_consequent;
+        None
+    };
+
+    let exp = Expression::IfExpression(IfExpression {
+        testing: Box::new(testing),
+        where_exp: where_exp.map(Box::new),
+        consequent: Box::new(consequent),
+        alternate: alternate.map(Box::new),
+        range: new_range(),
+    });
+
+    Ok((exp, token_details))
+}
+
+fn continue_parse_where_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // where ...
+    // ~~~~~
+    //     |--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
+    // 消除 `where` 关键字
+    token_details = consume_token(&Token::Where, token_details)?;
+    // 消除空行
+    token_details = skip_new_lines(token_details);
+
+    continue_parse_expression_block_or_single_expression(token_details)
+}
+
+fn parse_for_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // for let ... = ... ...
+    // for let ... = ... {...}
+
+    let mut token_details = source_token_details;
+
+    // 消除关键字 `for`
+    token_details = consume_token(&Token::For, token_details)?;
+    // 消除关键字 `for` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `初始化子表达式`
+
+    // 消除关键字 `let`
+    token_details = consume_token(&Token::Let, token_details)?;
+    // 消除关键字 `let` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `左手边的数据类型` 或者 `左手边值`
+    let (maybe_lhs, post_maybe_lhs) = parse_mono_expression(token_details)?;
+
+    let (data_type, lhs) = if is_token(&Token::Assign, post_maybe_lhs) {
+        // 当前表达式没有数据类型，只有 `左手边值`（即 `模式表达式`）
+        token_details = post_maybe_lhs;
+        (None, maybe_lhs)
+    } else {
+        // 当前表达式有数据类型
+
+        // 转换成数据类型
+        let data_type = convert_expression_to_data_type(maybe_lhs, token_details)?;
+
+        // 解析左手边值
+        let (lhs, post_lhs) = parse_primary_expression(post_maybe_lhs)?;
+        token_details = post_lhs;
+        (Some(data_type), lhs)
+    };
+
+    if !is_valid_left_hand_side(&lhs) {
+        return Err(new_error(
+            DiagnosticCode::InvalidLeftHandSide,
+            "invalid left-hand-side value",
+            token_details,
+        ));
+    }
+
+    // 消除 `左手边值` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 消除赋值符号 `=`
+    token_details = consume_token(&Token::Assign, token_details)?;
+
+    // 消除赋值符号 `=` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `右手边值`
+
+    // 先检查 `右手边值` 是否类似 `identifier {...` 这样的结构，
+    // 如果是的话，则花括号应该解析为 `隠式 do 表达式`
+    // 这时不能直接使用 `parse_expression` 函数解析 `右手边值`，因为
+    // 这个函数会把花括号解析为结构体实例化。
+
+    let (rhs, post_rhs) = match continue_parse_identifier(token_details) {
+        Ok((maybe_identifier, post_maybe_identifier))
+            if is_token(&Token::LeftBrace, post_maybe_identifier) =>
+        {
+            (
+                Expression::Identifier(maybe_identifier),
+                post_maybe_identifier,
+            )
+        }
+        _ => parse_expression(token_details)?,
+    };
+
+    let let_expression = LetExpression {
+        data_type,
+        object: Box::new(lhs),
+        value: Box::new(rhs),
+        range: new_range(),
+    };
+
+    // 消除 `右手边值` 后面的空行
+    token_details = skip_new_lines(post_rhs);
+
+    // 解析 `循环体表达式`
+    let (body_exp, post_body_exp) =
+        continue_parse_expression_block_or_single_expression(token_details)?;
+
+    let exp = Expression::ForExpression(ForExpression {
+        initializer: Box::new(let_expression),
+        body: Box::new(body_exp),
+        range: new_range(),
+    });
+
+    Ok((exp, post_body_exp))
+}
+
+fn parse_next_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // next ...
+    let mut token_details = source_token_details;
+
+    // 消除关键字 `next`
+    token_details = consume_token(&Token::Next, token_details)?;
+    // 消除关键字 `next` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析表达式
+    let (expression, post_expression) = parse_expression(token_details)?;
+
+    Ok((
+        Expression::NextExpression(NextExpression {
+            value: Box::new(expression),
+            range: new_range(),
+        }),
+        post_expression,
+    ))
+}
+
+fn parse_each_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // each ... in ... ...
+    // each ... in ... {...}
+
+    let mut token_details = source_token_details;
+
+    // 消除关键字 `each`
+    token_details = consume_token(&Token::Each, token_details)?;
+    // 消除关键字 `each` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `变量表达式`
+    let (variable, post_variable) = parse_mono_expression(token_details)?;
+
+    if !is_valid_left_hand_side(&variable) {
+        return Err(new_error(
+            DiagnosticCode::InvalidLeftHandSide,
+            "invalid left-hand-side value",
+            token_details,
+        ));
+    }
+
+    // 消除 `变量表达式` 后面的空行
+    token_details = skip_new_lines(post_variable);
+
+    // 消除关键字 `in`
+    token_details = consume_token(&Token::In, token_details)?;
+    // 消除关键字 `in` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `目标对象表达式`
+
+    // 先检查 `目标对象表达式` 是否类似 `identifier {...` 这样的结构，
+    // 如果是的话，则花括号应该解析为 `隠式 do 表达式`
+    // 这时不能直接使用 `parse_expression` 函数解析 `目标对象表达式`，因为
+    // 这个函数会把花括号解析为结构体实例化。
+
+    let (object, post_object) = match continue_parse_identifier(token_details) {
+        Ok((maybe_identifier, post_maybe_identifier))
+            if is_token(&Token::LeftBrace, post_maybe_identifier) =>
+        {
+            (
+                Expression::Identifier(maybe_identifier),
+                post_maybe_identifier,
+            )
+        }
+        _ => parse_expression(token_details)?,
+    };
+
+    // 消除 `目标对象表达式` 后面的空行
+    token_details = skip_new_lines(post_object);
+
+    // 解析 `循环体表达式`
+    let (body_exp, post_body_exp) =
+        continue_parse_expression_block_or_single_expression(token_details)?;
+
+    let exp = Expression::EachExpression(EachExpression {
+        variable: Box::new(variable),
+        object: Box::new(object),
+        body: Box::new(body_exp),
+        range: new_range(),
+    });
+
+    Ok((exp, post_body_exp))
+}
+
+fn parse_branch_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // branch {...}
+    // branch where ... {         // where 从属表达式
+    //   ...
+    // }
+    //
+    // branch {
+    //    case testing_exp: exp,
+    //    case testing_exp: exp           // 逗号可省略
+    //    case testing_exp
+    //         where ...: exp // 分支可以携带一个 where 从属表达式
+    //    default: exp
+    //}
+
+    let mut token_details = source_token_details;
+    let mut cases: Vec<BranchCase> = vec![];
+    let mut default_exp: Option<Expression> = None;
+
+    // 标记当前是否处于查找分支结束的状态，即已经遇到 default 分支，用于
+    // 防止 default 后面仍存在其他分支的情况。
+    let mut is_expected_end = false;
+
+    // 消除关键字 `branch`
+    token_details = consume_token(&Token::Branch, token_details)?;
+    // 消除关键字 `branch` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 检查是否存在 `where` 子表达式
+    let where_exp = if is_token(&Token::Where, token_details) {
+        let (where_exp, post_where_expression) = continue_parse_where_expression(token_details)?;
+
+        // 消除 `where` 子表达式后面的空行
+        token_details = skip_new_lines(post_where_expression);
+
+        Some(where_exp)
+    } else {
+        None
+    };
+
+    // 消除符号 `{`
+    token_details = consume_token(&Token::LeftBrace, token_details)?;
+    // 消除符号 `{` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 开始解析 case 和 default
+    loop {
+        token_details = match token_details.first() {
+            Some(first) => {
+                if first.token == Token::RightBrace {
+                    // 找到了结束符号 `}`，退出循环
+                    break;
+                } else {
+                    if is_expected_end {
+                        // 当前的状态是一心寻找结束符号 `}`
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right brace symbol \"}\"",
+                            token_details,
+                        ));
+                    } else {
+                        if is_token(&Token::Case, token_details) {
+                            let (case_exp, post_case_exp) =
+                                continue_parse_branch_case(token_details)?;
+                            cases.push(case_exp);
+
+                            // 消除当前分支后面的符号 `,`（如果存在的话）
+                            let post_comma = if is_token(&Token::Comma, post_case_exp) {
+                                consume_token(&Token::Comma, post_case_exp)?
+                            } else {
+                                post_case_exp
+                            };
+
+                            // 消除符号 `,` 后面的空行
+                            let post_new_lines = skip_new_lines(post_comma);
+                            post_new_lines
+                        } else if is_token(&Token::Default, token_details) {
+                            let (expression, post_default_exp) =
+                                continue_parse_default_case(token_details)?;
+                            default_exp = Some(expression);
+
+                            // 标记所有分支均已结束，因为已经遇到了默认分支
+                            is_expected_end = true;
+
+                            // 消除当前分支后面的符号 `,`（如果存在的话）
+                            let post_comma = if is_token(&Token::Comma, post_default_exp) {
+                                consume_token(&Token::Comma, post_default_exp)?
+                            } else {
+                                post_default_exp
+                            };
+
+                            // 消除符号 `,` 后面的空行
+                            let post_new_lines = skip_new_lines(post_comma);
+                            post_new_lines
+                        } else {
+                            return Err(new_error(
+                                DiagnosticCode::InvalidCase,
+                                "invalid branch expression",
+                                token_details,
+                            ));
+                        }
+                    }
+                }
+            }
+            None => {
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right brace symbol \"}\"",
+                    token_details,
+                ));
+            }
+        }
+    }
+
+    // 消除符号 `}`
+    token_details = consume_token(&Token::RightBrace, token_details)?;
+
+    let exp = Expression::BranchExpression(BranchExpression {
+        where_exp: where_exp.map(Box::new),
+        cases,
+        default_exp: default_exp.map(Box::new),
+        range: new_range(),
+    });
+
+    Ok((exp, token_details))
+}
+
+fn continue_parse_branch_case(
+    source_token_details: &[TokenDetail],
+) -> Result<(BranchCase, &[TokenDetail]), Diagnostic> {
+    // case ...: ...
+    // case ... where ...: ...
+    // ~~~~
+    //    |--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
+    // 消除 `case` 关键字
+    token_details = consume_token(&Token::Case, token_details)?;
+    // 消除 `case` 关键字后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `条件表达式`
+    let (testing_exp, post_testing) = parse_expression(token_details)?;
+    // 消除 `条件表达式 后面的空行
+    token_details = skip_new_lines(post_testing);
+
+    // 解析 `where 从属表达式`
+    let where_exp = if is_token(&Token::Where, token_details) {
+        let (expression, post_where_expression) = continue_parse_where_expression(token_details)?;
+        token_details = post_where_expression;
+        Some(expression)
+    } else {
+        None
+    };
+    // 消除 `where 从属表达式 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 消除符号 `:`
+    token_details = consume_token(&Token::Colon, token_details)?;
+
+    // 解析 `结果表达式`（可以是 `隠式 do 表达式`）
+    let (consequent_exp, post_consequent) =
+        continue_parse_expression_block_or_single_expression(token_details)?;
+
+    let case = BranchCase {
+        testing: Box::new(testing_exp),
+        where_exp: where_exp.map(Box::new),
+        consequent: Box::new(consequent_exp),
+        range: new_range(),
+    };
+
+    Ok((case, post_consequent))
+}
+
+fn continue_parse_default_case(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // default: ...
+    // ~~~~~~~
+    //       |--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
+    // 消除 `default` 关键字
+    token_details = consume_token(&Token::Default, token_details)?;
+    // 消除符号 `:`
+    token_details = consume_token(&Token::Colon, token_details)?;
+    // 消除空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `结果表达式`（可以是 `隠式 do 表达式`）
+    continue_parse_expression_block_or_single_expression(token_details)
+}
+
+fn parse_match_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // match obj {...}
+    // match obj where ... {
+    //   ...
+    // }
+    // match obj {
+    //    case pattern_exp: exp,
+    //    case pattern_exp: exp             // 逗号可省略
+    //
+    //    case variable @ pattern_exp: exp  // `模式表达式` 之前可以添加 `变量名` + `@`
+    //
+    //    case in ...: ...                  // 模式表达式还可以是 `into`, `regular`, `template` 其中的一种
+    //    case into Email e: ...
+    //    case regular REGEXP_LITERAL (tuple,...): ...
+    //    case template "STRING": ...
+    //
+    //    case pattern_exp
+    //         where ...
+    //         only ...: ...              // 模式表达式之后可以添加 where, only 从属表达式
+    //
+    //    default: exp                      // match 可以有一个 default 分支
+    //}
+
+    let mut token_details = source_token_details;
+    let mut cases: Vec<MatchCase> = vec![];
+    let mut default_exp: Option<Expression> = None;
+
+    // 标记当前是否处于查找分支结束的状态，即已经遇到 default 分支，用于
+    // 防止 default 后面仍存在其他分支的情况。
+    let mut is_expected_end = false;
+
+    // 消除关键字 `match`
+    token_details = consume_token(&Token::Match, token_details)?;
+    // 消除关键字 `match` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 解析 `目标对象表达式`
+
+    // 先检查 `目标对象表达式` 是否类似 `identifier {...` 这样的结构，
+    // 如果是的话，则花括号应该解析为 `隠式 do 表达式`
+    // 这时不能直接使用 `parse_expression` 函数解析 `目标对象表达式`，因为
+    // 这个函数会把花括号解析为结构体实例化。
+
+    let (object, post_object) = match continue_parse_identifier(token_details) {
+        Ok((maybe_identifier, post_maybe_identifier))
+            if is_token(&Token::LeftBrace, post_maybe_identifier) =>
+        {
+            (
+                Expression::Identifier(maybe_identifier),
+                post_maybe_identifier,
+            )
+        }
+        _ => parse_expression(token_details)?,
+    };
+
+    // 消除 `目标对象表达式` 后面的空行
+    token_details = skip_new_lines(post_object);
+
+    // 检查是否存在 `where` 子表达式
+    let where_exp = if is_token(&Token::Where, token_details) {
+        let (where_exp, post_where_expression) = continue_parse_where_expression(token_details)?;
+
+        // 消除 `where` 子表达式后面的空行
+        token_details = skip_new_lines(post_where_expression);
+
+        Some(where_exp)
+    } else {
+        None
+    };
+
+    // 消除符号 `{`
+    token_details = consume_token(&Token::LeftBrace, token_details)?;
+    // 消除符号 `{` 后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 开始解析 case 和 default
+    loop {
+        token_details = match token_details.first() {
+            Some(first) => {
+                if first.token == Token::RightBrace {
+                    // 找到了结束符号 `}`，退出循环
+                    break;
+                } else {
+                    if is_expected_end {
+                        // 当前的状态是一心寻找结束符号 `}`
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right brace symbol \"}\"",
+                            token_details,
+                        ));
+                    } else {
+                        if is_token(&Token::Case, token_details) {
+                            let (case_exp, post_case_exp) =
+                                continue_parse_match_case(token_details)?;
+                            cases.push(case_exp);
+
+                            // 消除当前分支后面的符号 `,`（如果存在的话）
+                            let post_comma = if is_token(&Token::Comma, post_case_exp) {
+                                consume_token(&Token::Comma, post_case_exp)?
+                            } else {
+                                post_case_exp
+                            };
+
+                            // 消除符号 `,` 后面的空行
+                            let post_new_lines = skip_new_lines(post_comma);
+                            post_new_lines
+                        } else if is_token(&Token::Default, token_details) {
+                            let (expression, post_default_exp) =
+                                continue_parse_default_case(token_details)?;
+                            default_exp = Some(expression);
+
+                            // 标记所有分支均已结束，因为已经遇到了默认分支
+                            is_expected_end = true;
+
+                            // 消除当前分支后面的符号 `,`（如果存在的话）
+                            let post_comma = if is_token(&Token::Comma, post_default_exp) {
+                                consume_token(&Token::Comma, post_default_exp)?
+                            } else {
+                                post_default_exp
+                            };
+
+                            // 消除符号 `,` 后面的空行
+                            let post_new_lines = skip_new_lines(post_comma);
+                            post_new_lines
+                        } else {
+                            return Err(new_error(
+                                DiagnosticCode::InvalidCase,
+                                "invalid match expression",
+                                token_details,
+                            ));
+                        }
+                    }
+                }
+            }
+            None => {
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right brace symbol \"}\"",
+                    token_details,
+                ));
+            }
+        }
+    }
+
+    // 消除符号 `}`
+    token_details = consume_token(&Token::RightBrace, token_details)?;
+
+    let exp = Expression::MatchExpression(MatchExpression {
+        object: Box::new(object),
+        where_exp: where_exp.map(Box::new),
+        cases,
+        default_exp: default_exp.map(Box::new),
+        range: new_range(),
+    });
+
+    Ok((exp, token_details))
+}
+
+fn continue_parse_match_case(
+    source_token_details: &[TokenDetail],
+) -> Result<(MatchCase, &[TokenDetail]), Diagnostic> {
+    // `match case` 由 3 部分组成：
+    // 1. 变量
+    // 2. 模式表达式
+    // 3. where/only 从属表达式
+    //
+    // case pattern_exp: exp,
+    // case pattern_exp: exp             // 逗号可省略
+    //
+    // case variable @ pattern_exp: exp  // `模式表达式` 之前可以添加 `变量名` + `@`
+    //
+    // case in ...: ...                  // 模式表达式还可以是 `into`, `regular`, `template` 其中的一种
+    // case into Email e: ...
+    // case regular REGEXP_LITERAL (tuple,...): ...
+    // case template "STRING": ...
+    //
+    // case pattern_exp
+    //      only ...                     // 模式表达式之后可以添加 where, only 从属表达式
+    //      where ...: ...
+    // ~~~~
+    //    |--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
+    // 消除 `case` 关键字
+    token_details = consume_token(&Token::Case, token_details)?;
+    // 消除 `case` 关键字后面的空行
+    token_details = skip_new_lines(token_details);
+
+    // 先检查有无语法错误，match case 不允许由 `从属表达式` 开始。
+    if any_token(&[Token::Only, Token::Where], token_details) {
+        return Err(new_error(
+            DiagnosticCode::InvalidCase,
+            "invalid match case expression",
+            token_details,
+        ));
+    }
+
+    // 解析 `变量` 和 `模式表达式` 部分
+    let ((variable, pattern), post_pattern) = continue_parse_pattern(token_details)?;
+
+    // 解析 only, where 从属表达式
+    let ((only, where_exp), post_subsidiary_expressions) =
+        continue_parse_only_and_where_expressions(post_pattern)?;
+    token_details = post_subsidiary_expressions;
+
+    // 消除符号 `:`
+    token_details = consume_token(&Token::Colon, token_details)?;
+
+    // 解析 `结果表达式`（可以是 `隠式 do 表达式`）
+    let (consequent_exp, post_consequent) =
+        continue_parse_expression_block_or_single_expression(token_details)?;
+
+    let case = MatchCase {
+        variable,
+        pattern: pattern.map(Box::new),
+        only: only.map(Box::new),
+        where_exp: where_exp.map(Box::new),
+        consequent: Box::new(consequent_exp),
+        range: new_range(),
+    };
+
+    Ok((case, post_consequent))
+}
+
+// `变量` 和 `模式表达式`
+type VariableAndPattern = (Option<String>, Option<PatternExpression>);
+
+fn continue_parse_pattern(
+    source_token_details: &[TokenDetail],
+) -> Result<(VariableAndPattern, &[TokenDetail]), Diagnostic> {
+    // 解析 `变量` 和 `模式表达式`，用于 match case 以及模式函数的参数
+    //
+    // variable @ pattern_exp
+    // pattern_exp
+    // in ...
+    // into Email e
+    // regular REGEXP_LITERAL (tuple,...)
+    // template "STRING"
+    // ^
+    // |--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
+    let mut variable: Option<String> = None;
+
+    // 解析 `变量` 部分，`变量` 部分的结构是 `identifier @`
+    if !any_token(
+        &[Token::In, Token::Into, Token::Regular, Token::Template],
+        token_details,
+    ) {
+        match parse_primary_expression(token_details) {
             Ok((Expression::Identifier(Identifier { name, .. }), post_identifier))
                 if is_token(&Token::At, post_identifier) =>
             {
@@ -1306,6 +3099,9 @@ fn continue_parse_match_case(
             Token::Colon => {
                 // 跳过
             }
+            Token::Comma | Token::RightParen => {
+                // 模式函数参数的结束，跳过
+            }
             Token::In => {
                 // 解析 `in 模式表达式`
                 //
@@ -1333,7 +3129,8 @@ fn continue_parse_match_case(
 
                 let (data_type_expression, post_data_type_expression) =
                     parse_primary_expression(token_details)?;
-                let data_type = convert_expression_to_data_type(data_type_expression)?;
+                let data_type =
+                    convert_expression_to_data_type(data_type_expression, token_details)?;
 
                 let (identifier_expression, post_identifier_expression) =
                     parse_primary_expression(post_data_type_expression)?;
@@ -1342,8 +3139,10 @@ fn continue_parse_match_case(
                     pattern = Some(PatternExpression::Into(data_type, name));
                     token_details = post_identifier_expression;
                 } else {
-                    return Err(Error::ParserError(
-                        "invalid into pattern expression".to_string(),
+                    return Err(new_error(
+                        DiagnosticCode::InvalidPattern,
+                        "invalid into pattern expression",
+                        post_data_type_expression,
                     ));
                 }
             }
@@ -1382,16 +3181,18 @@ fn continue_parse_match_case(
                 // };
 
                 let regexp_token = if let Some(TokenDetail {
-                    token: rt @ Token::Regexp(_),
+                    token: rt @ Token::Regexp(..),
                     ..
                 }) = token_details.first()
                 {
                     token_details = &token_details[1..];
                     rt
                 } else {
-                    return Err(Error::ParserError(
-                        "expected regular expression literal".to_string(),
-                    ))
+                    return Err(new_error(
+                        DiagnosticCode::InvalidPattern,
+                        "expected regular expression literal",
+                        token_details,
+                    ));
                 };
 
                 // 消除正则表达式字面量后的空行
@@ -1402,11 +3203,35 @@ fn continue_parse_match_case(
                     parse_primary_expression(token_details)?;
 
                 if let Expression::Tuple(tuple) = tuple_expression {
+                    // 元组的第一个元素绑定整个匹配，其余元素依次绑定各个捕获分组
+                    let group_count = match regexp_token {
+                        Token::Regexp(_, _, group_count) => *group_count,
+                        _ => unreachable!(),
+                    };
+
+                    if tuple.elements.len() != group_count + 1 {
+                        return Err(new_error(
+                            DiagnosticCode::InvalidPattern,
+                            &format!(
+                                "the regular pattern expression expects {} bindings, found {}",
+                                group_count + 1,
+                                tuple.elements.len()
+                            ),
+                            token_details,
+                        )
+                        .with_note(&format!(
+                            "the regular expression has {} capture groups, and the first binding is the whole match",
+                            group_count
+                        )));
+                    }
+
                     pattern = Some(PatternExpression::Regular(regexp_token.clone(), tuple));
                     token_details = post_tuple_expression;
                 } else {
-                    return Err(Error::ParserError(
-                        "invalid regular pattern expression".to_string(),
+                    return Err(new_error(
+                        DiagnosticCode::InvalidPattern,
+                        "invalid regular pattern expression",
+                        token_details,
                     ));
                 }
             }
@@ -1420,28 +3245,22 @@ fn continue_parse_match_case(
                 // 消除关键字 `template` 后面的空行
                 token_details = skip_new_lines(token_details);
 
-                let (s, post_template_string) = parse_primary_expression(token_details)?;
-                let template_string = match s {
-                    Expression::Literal(Literal::GeneralString(GeneralString {
-                        value, ..
-                    })) => value,
-                    Expression::Literal(Literal::TemplateString(TemplateString {
-                        fragments,
-                        expressions,
+                // 模板模式的占位符是 `{变量名称:正则表达式}`，而不是表达式，
+                // 所以这里直接读取字符串的原始文本，而不解析占位符。
+                let template_string = match token_details.first() {
+                    Some(TokenDetail {
+                        token: Token::GeneralString(v) | Token::TemplateString(v),
                         ..
-                    })) => {
-                        // 如果模板字符串里无占位符，也是允许的
-                        if expressions.len() > 0 {
-                            return Err(Error::ParserError("invalid template string".to_string()));
-                        }
-                        fragments.join("")
-                    }
+                    }) => v.clone(),
                     _ => {
-                        return Err(Error::ParserError(
-                            "invalid template pattern expression".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::InvalidPattern,
+                            "invalid template pattern expression",
+                            token_details,
                         ));
                     }
                 };
+                let post_template_string = &token_details[1..];
 
                 pattern = Some(PatternExpression::Template(template_string));
                 token_details = post_template_string;
@@ -1451,7 +3270,11 @@ fn continue_parse_match_case(
                 let (lhs, post_lhs) = parse_mono_expression(token_details)?;
 
                 if !is_valid_left_hand_side(&lhs) {
-                    return Err(Error::ParserError("invalid pattern expression".to_string()));
+                    return Err(new_error(
+                        DiagnosticCode::InvalidPattern,
+                        "invalid pattern expression",
+                        token_details,
+                    ));
                 }
 
                 pattern = Some(PatternExpression::Primary(lhs));
@@ -1460,6 +3283,23 @@ fn continue_parse_match_case(
         }
     };
 
+    Ok(((variable, pattern), token_details))
+}
+
+// only 和 where 从属表达式
+type OnlyAndWhere = (Option<Expression>, Option<Expression>);
+
+fn continue_parse_only_and_where_expressions(
+    source_token_details: &[TokenDetail],
+) -> Result<(OnlyAndWhere, &[TokenDetail]), Diagnostic> {
+    // 解析模式表达式后面的 only, where 从属表达式，两者的顺序不限
+    //
+    // pattern_exp only ... where ...
+    //             ^
+    //             |--- 当前所处的位置
+
+    let mut token_details = source_token_details;
+
     // 消除从属表达式前面的空行
     token_details = skip_new_lines(token_details);
 
@@ -1490,28 +3330,12 @@ fn continue_parse_match_case(
         }
     }
 
-    // 消除符号 `:`
-    token_details = consume_token(&Token::Colon, token_details)?;
-
-    // 解析 `结果表达式`（可以是 `隠式 do 表达式`）
-    let (consequent_exp, post_consequent) =
-        continue_parse_expression_block_or_single_expression(token_details)?;
-
-    let case = MatchCase {
-        variable: variable,
-        pattern: pattern.map(|e| Box::new(e)),
-        only: only.map(|e| Box::new(e)),
-        where_exp: where_exp.map(|e| Box::new(e)),
-        consequent: Box::new(consequent_exp),
-        range: new_range(),
-    };
-
-    Ok((case, post_consequent))
+    Ok(((only, where_exp), token_details))
 }
 
 fn continue_parse_only_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // only ...
     // ~~~~
     //    |--- 当前所处的位置
@@ -1526,9 +3350,55 @@ fn continue_parse_only_expression(
     continue_parse_expression_block_or_single_expression(token_details)
 }
 
+fn continue_parse_function_only_expression(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
+    // 函数范围的 only 从属表达式
+    // only x > y {...}
+    // ~~~~
+    //    |--- 当前所处的位置
+    //
+    // 跟 if、match 的目标对象表达式一样，only 从属表达式后面紧接着的花括号
+    // 应该解析为函数主体，而不是结构体的实例化表达式（比如 `y {...}`），
+    // 所以只解析第一个（不在括号之内的）花括号之前的 token，
+    // 在 only 从属表达式里实例化结构体时需要加上括号，比如 `only (User {...} == u) {...}`
+
+    let mut token_details = source_token_details;
+
+    // 消除 `only` 关键字
+    token_details = consume_token(&Token::Only, token_details)?;
+    // 消除空行
+    token_details = skip_new_lines(token_details);
+
+    if is_token(&Token::LeftBrace, token_details) {
+        // only {...}
+        return continue_parse_expression_block_or_single_expression(token_details);
+    }
+
+    let mut depth = 0;
+    let head_length = token_details
+        .iter()
+        .position(|t| {
+            match t.token {
+                Token::LeftParen | Token::LeftBracket => depth += 1,
+                Token::LeftBrace if depth == 0 => return true,
+                Token::LeftBrace => depth += 1,
+                Token::RightParen | Token::RightBracket | Token::RightBrace => depth -= 1,
+                _ => {}
+            }
+            false
+        })
+        .unwrap_or(token_details.len());
+
+    let (exp, post_expression) = parse_expression(&token_details[..head_length])?;
+    let consumed_length = head_length - post_expression.len();
+
+    Ok((exp, &token_details[consumed_length..]))
+}
+
 fn continue_parse_generic_names(
     source_token_details: &[TokenDetail],
-) -> Result<(Vec<DataType>, &[TokenDetail]), Error> {
+) -> Result<(Vec<DataType>, &[TokenDetail]), Diagnostic> {
     // <A>
     // <A, B, C>
     // <A, B<C>>
@@ -1554,14 +3424,17 @@ fn continue_parse_generic_names(
                 } else {
                     if is_expected_end {
                         // 当前的状态是一心寻找结束符号 `>`
-                        return Err(Error::ParserError(
-                            "expected the right angle bracket symbol \">\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right angle bracket symbol \">\"",
+                            token_details,
                         ));
                     } else {
                         // 寻找泛型的 `数据类型`
                         let (data_type_expression, post_primary_expression) =
                             parse_primary_expression(token_details)?;
-                        let data_type = convert_expression_to_data_type(data_type_expression)?;
+                        let data_type =
+                            convert_expression_to_data_type(data_type_expression, token_details)?;
 
                         generics.push(data_type);
 
@@ -1581,8 +3454,10 @@ fn continue_parse_generic_names(
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right angle bracket symbol \">\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right angle bracket symbol \">\"",
+                    token_details,
                 ))
             }
         }
@@ -1596,7 +3471,7 @@ fn continue_parse_generic_names(
 
 fn continue_parse_type_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(DataType, &[TokenDetail]), Error> {
+) -> Result<(DataType, &[TokenDetail]), Diagnostic> {
     // type ...
     // ~~~~
     //    |-- 当前位置
@@ -1608,13 +3483,13 @@ fn continue_parse_type_expression(
 
     let (data_type_expression, post_data_type_expression) =
         parse_primary_expression(post_new_lines)?;
-    let data_type = convert_expression_to_data_type(data_type_expression)?;
+    let data_type = convert_expression_to_data_type(data_type_expression, post_new_lines)?;
     Ok((data_type, post_data_type_expression))
 }
 
 fn continue_parse_which_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Vec<WhichEntry>, &[TokenDetail]), Error> {
+) -> Result<(Vec<WhichEntry>, &[TokenDetail]), Diagnostic> {
     // which ...
     // which {...}
     // ~~~~~
@@ -1656,8 +3531,10 @@ fn continue_parse_which_expression(
                             } else {
                                 if is_expected_end {
                                     // 当前的状态是一心寻找结束符号 `}`
-                                    return Err(Error::ParserError(
-                                        "expected the right brace symbol \"}\"".to_string(),
+                                    return Err(new_error(
+                                        DiagnosticCode::UnclosedDelimiter,
+                                        "expected the right brace symbol \"}\"",
+                                        token_details,
                                     ));
                                 } else {
                                     let (entry, post_entry) =
@@ -1695,8 +3572,10 @@ fn continue_parse_which_expression(
                             }
                         }
                         None => {
-                            return Err(Error::ParserError(
-                                "expected the right brace symbol \"}\"".to_string(),
+                            return Err(new_error(
+                                DiagnosticCode::UnclosedDelimiter,
+                                "expected the right brace symbol \"}\"",
+                                token_details,
                             ));
                         }
                     }
@@ -1713,8 +3592,10 @@ fn continue_parse_which_expression(
             }
         }
         None => {
-            return Err(Error::ParserError(
-                "expected \"which\" expression".to_string(),
+            return Err(new_error(
+                DiagnosticCode::InvalidWhichEntry,
+                "expected \"which\" expression",
+                token_details,
             ));
         }
     };
@@ -1724,7 +3605,7 @@ fn continue_parse_which_expression(
 
 fn continue_parse_which_entry(
     source_token_details: &[TokenDetail],
-) -> Result<(WhichEntry, &[TokenDetail]), Error> {
+) -> Result<(WhichEntry, &[TokenDetail]), Diagnostic> {
     // 解析单一行 WhichEntry
     //
     // T: std::Int                  // 单独一个数据类型
@@ -1757,8 +3638,8 @@ fn continue_parse_which_entry(
                         continue_parse_which_entry_data_type_list(post_new_lines_after_limit)?;
 
                     let entry = WhichEntry::Limit(WhichEntryLimit {
-                        name: name.clone(),
-                        data_types: data_types,
+                        name: name.to_string(),
+                        data_types,
                         range: new_range(),
                     });
 
@@ -1767,33 +3648,38 @@ fn continue_parse_which_entry(
                     // 当前是单一数据类型说明
                     let (data_type_expression, post_data_type_expression) =
                         parse_primary_expression(post_new_lines_after_colon)?;
-                    let data_type = convert_expression_to_data_type(data_type_expression)?;
+                    let data_type = convert_expression_to_data_type(
+                        data_type_expression,
+                        post_new_lines_after_colon,
+                    )?;
 
                     let entry = WhichEntry::Type(WhichEntryType {
-                        name: name.clone(),
-                        data_type: data_type,
+                        name: name.to_string(),
+                        data_type,
                         range: new_range(),
                     });
 
                     Ok((entry, post_data_type_expression))
                 }
             }
-            None => {
-                return Err(Error::ParserError(
-                    "expected which expression entry value".to_string(),
-                ));
-            }
+            None => Err(new_error(
+                DiagnosticCode::InvalidWhichEntry,
+                "expected which expression entry value",
+                post_new_lines_after_colon,
+            )),
         }
     } else {
-        return Err(Error::ParserError(
-            "invalid name of which expression entry".to_string(),
-        ));
+        Err(new_error(
+            DiagnosticCode::InvalidWhichEntry,
+            "invalid name of which expression entry",
+            source_token_details,
+        ))
     }
 }
 
 fn continue_parse_which_entry_data_type_list(
     source_token_details: &[TokenDetail],
-) -> Result<(Vec<DataType>, &[TokenDetail]), Error> {
+) -> Result<(Vec<DataType>, &[TokenDetail]), Diagnostic> {
     // Display + Debug + Eq
     // ^
     // |--- 当前处在这个位置
@@ -1805,7 +3691,7 @@ fn continue_parse_which_entry_data_type_list(
     loop {
         let (data_type_expression, post_data_type_expression) =
             parse_primary_expression(token_details)?;
-        let data_type = convert_expression_to_data_type(data_type_expression)?;
+        let data_type = convert_expression_to_data_type(data_type_expression, token_details)?;
         data_types.push(data_type);
 
         // let post_plus =
@@ -1824,6 +3710,9 @@ fn continue_parse_which_entry_data_type_list(
     Ok((data_types, token_details))
 }
 
+type ParseExpressionFunction =
+    fn(&[TokenDetail]) -> Result<(Expression, &[TokenDetail]), Diagnostic>;
+
 // 解析 `从左向右` 结合的二元运算的通用函数
 //
 // BinaryExpression
@@ -1832,22 +3721,18 @@ fn continue_parse_which_entry_data_type_list(
 //  ;
 fn parse_binary_expression<'a>(
     operator_tokens: &[Token],
-    next_parse_function: fn(&[TokenDetail]) -> Result<(Expression, &[TokenDetail]), Error>,
+    next_parse_function: ParseExpressionFunction,
     source_token_details: &'a [TokenDetail],
-) -> Result<(Expression, &'a [TokenDetail]), Error> {
+) -> Result<(Expression, &'a [TokenDetail]), Diagnostic> {
     let mut token_details = source_token_details;
 
     let (mut left, post_left_expression) = next_parse_function(token_details)?;
     token_details = post_left_expression;
 
-    loop {
-        let next_token = match token_details.first() {
-            Some(first) => &first.token,
-            None => {
-                break;
-            }
-        };
-
+    while let Some(TokenDetail {
+        token: next_token, ..
+    }) = token_details.first()
+    {
         let index = match operator_tokens.iter().position(|t| t == next_token) {
             Some(i) => i,
             None => {
@@ -1887,9 +3772,9 @@ fn parse_binary_expression<'a>(
 //  ;
 fn parse_right_2_left_binary_expression<'a>(
     operator_token: &Token,
-    next_parse_function: fn(&[TokenDetail]) -> Result<(Expression, &[TokenDetail]), Error>,
+    next_parse_function: ParseExpressionFunction,
     source_token_details: &'a [TokenDetail],
-) -> Result<(Expression, &'a [TokenDetail]), Error> {
+) -> Result<(Expression, &'a [TokenDetail]), Diagnostic> {
     let mut token_details = source_token_details;
 
     let (mut left, post_left_expression) = next_parse_function(token_details)?;
@@ -1920,10 +3805,10 @@ fn parse_right_2_left_binary_expression<'a>(
 
 fn parse_pipe_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left | right
     parse_binary_expression(
-        &vec![Token::Pipe],
+        &[Token::Pipe],
         parse_logic_or_expression,
         source_token_details,
     )
@@ -1931,10 +3816,10 @@ fn parse_pipe_expression(
 
 fn parse_logic_or_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left || right
     parse_binary_expression(
-        &vec![Token::LogicOr],
+        &[Token::LogicOr],
         parse_logic_and_expression,
         source_token_details,
     )
@@ -1942,10 +3827,10 @@ fn parse_logic_or_expression(
 
 fn parse_logic_and_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left && right
     parse_binary_expression(
-        &vec![Token::LogicAnd],
+        &[Token::LogicAnd],
         parse_equality_expression,
         source_token_details,
     )
@@ -1953,10 +3838,10 @@ fn parse_logic_and_expression(
 
 fn parse_equality_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left == right, left != right
     parse_binary_expression(
-        &vec![Token::Equal, Token::NotEqual],
+        &[Token::Equal, Token::NotEqual],
         parse_relational_expression,
         source_token_details,
     )
@@ -1964,10 +3849,10 @@ fn parse_equality_expression(
 
 fn parse_relational_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left > right, left >= right, left < right, left <= right
     parse_binary_expression(
-        &vec![
+        &[
             Token::GreaterThan,
             Token::GreaterThanOrEqual,
             Token::LessThan,
@@ -1980,7 +3865,7 @@ fn parse_relational_expression(
 
 fn parse_named_operator_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left :bitOr: right
     //
     // 注：
@@ -2019,10 +3904,10 @@ fn parse_named_operator_expression(
 
 fn parse_concat_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left ++ right
     parse_binary_expression(
-        &vec![Token::Concat],
+        &[Token::Concat],
         parse_additive_expression,
         source_token_details,
     )
@@ -2030,10 +3915,10 @@ fn parse_concat_expression(
 
 fn parse_additive_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left + right, left - right
     parse_binary_expression(
-        &vec![Token::Plus, Token::Minus],
+        &[Token::Plus, Token::Minus],
         parse_multiplicative_expression,
         source_token_details,
     )
@@ -2041,10 +3926,10 @@ fn parse_additive_expression(
 
 fn parse_multiplicative_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left * right, left / right
     parse_binary_expression(
-        &vec![Token::Asterisk, Token::Slash],
+        &[Token::Asterisk, Token::Slash],
         parse_optional_or_expression,
         source_token_details,
     )
@@ -2052,10 +3937,10 @@ fn parse_multiplicative_expression(
 
 fn parse_optional_or_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left ?? right
     parse_binary_expression(
-        &vec![Token::OptionalOr],
+        &[Token::OptionalOr],
         parse_optional_and_expression,
         source_token_details,
     )
@@ -2063,10 +3948,10 @@ fn parse_optional_or_expression(
 
 fn parse_optional_and_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left >> right
     parse_binary_expression(
-        &vec![Token::OptionalAnd],
+        &[Token::OptionalAnd],
         parse_combine_expression,
         source_token_details,
     )
@@ -2074,7 +3959,7 @@ fn parse_optional_and_expression(
 
 fn parse_combine_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // left & right
     // 结合方向：从右向左
     parse_right_2_left_binary_expression(
@@ -2086,7 +3971,7 @@ fn parse_combine_expression(
 
 fn parse_cast_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 一元运算表达式 object^
     let (left, post_expression) = parse_negative_expression(source_token_details)?;
 
@@ -2108,10 +3993,10 @@ fn parse_cast_expression(
 
 fn parse_negative_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 一元运算表达式 -object
     if is_token(&Token::Minus, source_token_details) {
-        let post_consume_token_operator = consume_token(&Token::Cast, source_token_details)?;
+        let post_consume_token_operator = consume_token(&Token::Minus, source_token_details)?;
         let (left, post_expression) = parse_unwrap_expression(post_consume_token_operator)?;
 
         Ok((
@@ -2123,13 +4008,28 @@ fn parse_negative_expression(
             post_expression,
         ))
     } else {
-        parse_unwrap_expression(source_token_details)
+        let (left, post_expression) = parse_unwrap_expression(source_token_details)?;
+
+        // 词法分析阶段允许有符号整数的数值等于该类型最小值的绝对值（比如 `128i8`），
+        // 以便表示 `-128i8`，所以对于没有负号的整数字面量这里需要再检查一次。
+        if let Expression::Literal(Literal::Integer(Integer { value, .. })) = &left {
+            let integer_type = value.integer_type.unwrap_or(IntegerType::Int64);
+            if let (Some(number), Some(max_value)) = (value.to_u128(), integer_type.max_value()) {
+                if number > max_value {
+                    let mut diagnostic = new_integer_overflow_error(integer_type);
+                    diagnostic.location = source_token_details[0].location.clone();
+                    return Err(diagnostic);
+                }
+            }
+        }
+
+        Ok((left, post_expression))
     }
 }
 
 fn parse_unwrap_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 一元运算表达式 object?
     let (left, post_expression) = parse_mono_expression(source_token_details)?;
 
@@ -2151,7 +4051,7 @@ fn parse_unwrap_expression(
 
 fn parse_mono_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 解析 `单一表达式`
     //
     // `单一表达式` 是指用于组成一元运算、二元运算的表达式。
@@ -2162,7 +4062,7 @@ fn parse_mono_expression(
 
 fn parse_function_call_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 函数调用表达式
     // - 被调用者必须是一个标识符、一个对象的成员值（属性或索引）、或者一个匿名函数；
     // - 被调用者也可以是一个用括号包围起来的表达式或者表达式块，只要是返回函数即可；
@@ -2198,7 +4098,7 @@ fn parse_function_call_expression(
                 let (arguments, post_arguments) = continue_parse_arguments(token_details)?;
                 object = Expression::FunctionCallExpression(FunctionCallExpression {
                     callee: Box::new(object),
-                    arguments: arguments,
+                    arguments,
                     range: new_range(),
                 });
 
@@ -2214,7 +4114,7 @@ fn parse_function_call_expression(
 
 fn continue_parse_arguments(
     source_token_details: &[TokenDetail],
-) -> Result<(Vec<Argument>, &[TokenDetail]), Error> {
+) -> Result<(Vec<Argument>, &[TokenDetail]), Diagnostic> {
     // (value)
     // (value1, value2)
     // (value1, value2,) // 参数列表末尾也允许有逗号
@@ -2247,8 +4147,10 @@ fn continue_parse_arguments(
                 } else {
                     if is_expected_end {
                         // 当前的状态是一心寻找结束符号 `)`
-                        return Err(Error::ParserError(
-                            "expected the right paren symbol \")\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right paren symbol \")\"",
+                            token_details,
                         ));
                     } else {
                         // 当前是 `key = value` 表达式
@@ -2283,8 +4185,10 @@ fn continue_parse_arguments(
                                 post_value_expression
                             } else {
                                 // 参数名称不正确
-                                return Err(Error::ParserError(
-                                    "invalid argument name".to_string(),
+                                return Err(new_error(
+                                    DiagnosticCode::InvalidParameter,
+                                    "invalid argument name",
+                                    token_details,
                                 ));
                             }
                         } else {
@@ -2318,8 +4222,10 @@ fn continue_parse_arguments(
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right paren symbol \")\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right paren symbol \")\"",
+                    token_details,
                 ));
             }
         }
@@ -2333,7 +4239,7 @@ fn continue_parse_arguments(
 
 fn parse_member_or_slice_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 对象的成员（包括属性和索引）以及对象的切片，有相似的结构，
     // 且优先级相同：
     //
@@ -2390,7 +4296,11 @@ fn parse_member_or_slice_expression(
                     token_details = post_property;
                 }
                 _ => {
-                    return Err(Error::ParserError("invalid property name".to_string()));
+                    return Err(new_error(
+                        DiagnosticCode::InvalidPropertyName,
+                        "invalid property name",
+                        post_dot,
+                    ));
                 }
             }
         } else {
@@ -2403,7 +4313,7 @@ fn parse_member_or_slice_expression(
 
 fn continue_parse_index_or_slice(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // object["foo"]
     // object[0]
     // object[0..10]
@@ -2430,10 +4340,7 @@ fn continue_parse_index_or_slice(
         index_or_slice_expression = Expression::Interval(Interval {
             is_inclusive,
             from: Box::new(index_or_slice_expression),
-            to: match optional_to_expression {
-                Some(end_expression) => Some(Box::new(end_expression)),
-                None => None,
-            },
+            to: optional_to_expression.map(Box::new),
             range: new_range(),
         });
 
@@ -2450,7 +4357,7 @@ fn continue_parse_index_or_slice(
 
 fn parse_constructor_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 解析 `通过花括号` 实例化结构体的表达式
     // object {name: vale, ...}
 
@@ -2482,7 +4389,7 @@ fn parse_constructor_expression(
 //  ;
 fn parse_primary_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 注：
     // 由于范围表达式 `A..B`、`A..=B` 和省略号表达式 `...A` 仅存在于
     // 元组、列表、映射表、切片等场合，所以不单独解析，而是在解析以上节点
@@ -2501,15 +4408,17 @@ fn parse_primary_expression(
                 Ok((Expression::Literal(literal), post_literal))
             }
         },
-        None => Err(Error::ParserError(
-            "expected primary expression".to_string(),
+        None => Err(new_error(
+            DiagnosticCode::ExpectedExpression,
+            "expected primary expression",
+            source_token_details,
         )),
     }
 }
 
 fn parse_anonymous_function(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 匿名函数
     // 匿名函数没有函数名称、不支持泛型、不支持默认值、参数和返回值可省略数据类型
     //
@@ -2550,8 +4459,10 @@ fn parse_anonymous_function(
                         } else {
                             if is_expected_end {
                                 // 当前的状态是一心寻找结束符号
-                                return Err(Error::ParserError(
-                                    "expected the right paren symbol \")\"".to_string(),
+                                return Err(new_error(
+                                    DiagnosticCode::UnclosedDelimiter,
+                                    "expected the right paren symbol \")\"",
+                                    token_details,
                                 ));
                             } else {
                                 // 先尝试寻找参数的数据类型
@@ -2569,14 +4480,15 @@ fn parse_anonymous_function(
                                         {
                                             parameters.push(AnonymousParameter {
                                                 data_type: None,
-                                                name: name,
+                                                name,
                                                 range: new_range(),
                                             });
                                             post_part_one
                                         } else {
-                                            return Err(Error::ParserError(
-                                                "invalid anonymous function parameter name"
-                                                    .to_string(),
+                                            return Err(new_error(
+                                                DiagnosticCode::InvalidParameter,
+                                                "invalid anonymous function parameter name",
+                                                token_details,
                                             ));
                                         }
                                     }
@@ -2588,17 +4500,22 @@ fn parse_anonymous_function(
                                         post_part_two,
                                     )) => {
                                         // 当前参数有数据类型
-                                        let data_type = convert_expression_to_data_type(part_one)?;
+                                        let data_type = convert_expression_to_data_type(
+                                            part_one,
+                                            token_details,
+                                        )?;
                                         parameters.push(AnonymousParameter {
                                             data_type: Some(data_type),
-                                            name: name.clone(),
+                                            name: name.to_string(),
                                             range: new_range(),
                                         });
                                         post_part_two
                                     }
                                     _ => {
-                                        return Err(Error::ParserError(
-                                            "incomplete anonymous function parameter".to_string(),
+                                        return Err(new_error(
+                                            DiagnosticCode::InvalidParameter,
+                                            "incomplete anonymous function parameter",
+                                            post_part_one,
                                         ));
                                     }
                                 };
@@ -2621,8 +4538,10 @@ fn parse_anonymous_function(
                         }
                     }
                     None => {
-                        return Err(Error::ParserError(
-                            "expected the right paren symbol \")\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right paren symbol \")\"",
+                            token_details,
                         ));
                     }
                 }
@@ -2641,14 +4560,16 @@ fn parse_anonymous_function(
             // 参数列表只有一个参数，且无括号包围
             parameters.push(AnonymousParameter {
                 data_type: None,
-                name: name.clone(),
+                name: name.to_string(),
                 range: new_range(),
             });
             post_left_paren
         }
         _ => {
-            return Err(Error::ParserError(
-                "expected anonymous function parameter".to_string(),
+            return Err(new_error(
+                DiagnosticCode::InvalidParameter,
+                "expected anonymous function parameter",
+                token_details,
             ));
         }
     };
@@ -2697,8 +4618,8 @@ fn parse_anonymous_function(
 
     // 构造匿名函数对象
     let anonymous_function = AnonymousFunction {
-        parameters: parameters,
-        return_data_type: return_data_type,
+        parameters,
+        return_data_type,
         whiches,
         // where_exp: where_exp,
         body: Box::new(body),
@@ -2708,18 +4629,26 @@ fn parse_anonymous_function(
     Ok((Expression::AnonymousFunction(anonymous_function), post_body))
 }
 
-fn convert_expression_to_data_type(exp: Expression) -> Result<DataType, Error> {
+// 参数 source_token_details 为表达式开始位置的 token，用于错误定位
+fn convert_expression_to_data_type(
+    exp: Expression,
+    source_token_details: &[TokenDetail],
+) -> Result<DataType, Diagnostic> {
     match exp {
         Expression::Identifier(identifier) => Ok(DataType::Identifier(identifier)),
         Expression::Sign(sign) => Ok(DataType::Sign(sign)),
         Expression::Tuple(tuple) => Ok(DataType::Tuple(tuple)),
-        _ => Err(Error::ParserError(
-            "invalid anonymous function parameter data type".to_string(),
+        _ => Err(new_error(
+            DiagnosticCode::InvalidDataType,
+            "invalid data type",
+            source_token_details,
         )),
     }
 }
 
-fn parse_list(source_token_details: &[TokenDetail]) -> Result<(Expression, &[TokenDetail]), Error> {
+fn parse_list(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // list
     //
     // e.g.
@@ -2756,8 +4685,10 @@ fn parse_list(source_token_details: &[TokenDetail]) -> Result<(Expression, &[Tok
                 } else {
                     if is_expected_end {
                         // 当前的状态是一心寻找结束符号 `]`
-                        return Err(Error::ParserError(
-                            "expected the right bracket symbol \"]\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right bracket symbol \"]\"",
+                            token_details,
                         ));
                     } else {
                         // 先检查是否 `省略符表达式`
@@ -2794,10 +4725,7 @@ fn parse_list(source_token_details: &[TokenDetail]) -> Result<(Expression, &[Tok
                                 let interval_expression = Expression::Interval(Interval {
                                     is_inclusive,
                                     from: Box::new(expression),
-                                    to: match optional_to_expression {
-                                        Some(end_expression) => Some(Box::new(end_expression)),
-                                        None => None,
-                                    },
+                                    to: optional_to_expression.map(Box::new),
                                     range: new_range(),
                                 });
 
@@ -2830,8 +4758,10 @@ fn parse_list(source_token_details: &[TokenDetail]) -> Result<(Expression, &[Tok
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right bracket symbol \")\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right bracket symbol \")\"",
+                    token_details,
                 ))
             }
         }
@@ -2851,7 +4781,7 @@ fn parse_list(source_token_details: &[TokenDetail]) -> Result<(Expression, &[Tok
 
 fn parse_tuple_or_parenthesized(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // tuple or parenthesized
     //
     // e.g.
@@ -2895,8 +4825,10 @@ fn parse_tuple_or_parenthesized(
                 } else {
                     if is_expected_end {
                         // 当前的状态是一心寻找结束符号 `)`
-                        return Err(Error::ParserError(
-                            "expected the right paren symbol \")\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right paren symbol \")\"",
+                            token_details,
                         ));
                     } else {
                         // 先检查是否 `省略符表达式`
@@ -2941,8 +4873,10 @@ fn parse_tuple_or_parenthesized(
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right paren symbol \")\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right paren symbol \")\"",
+                    token_details,
                 ))
             }
         }
@@ -2951,7 +4885,7 @@ fn parse_tuple_or_parenthesized(
     // 消除右括号 `)`
     token_details = consume_token(&Token::RightParen, token_details)?;
 
-    if expressions.len() == 0 {
+    if expressions.is_empty() {
         // 空元组
         Ok((
             Expression::Tuple(Tuple {
@@ -2979,7 +4913,7 @@ fn parse_tuple_or_parenthesized(
 
 fn continue_parse_ellipsis(
     source_token_details: &[TokenDetail],
-) -> Result<(Ellipsis, &[TokenDetail]), Error> {
+) -> Result<(Ellipsis, &[TokenDetail]), Diagnostic> {
     // ...
     // ..._
     // ...abc
@@ -3000,7 +4934,7 @@ fn continue_parse_ellipsis(
         // 省略号 `...` 后面有标识符
         Ok((
             Ellipsis {
-                name: Some(name.clone()),
+                name: Some(name.to_string()),
                 range: new_range(),
             },
             post_consume_token_identifier,
@@ -3021,7 +4955,7 @@ fn continue_parse_ellipsis(
 // 返回 (`to` 是否闭区间, `to` 表达式, 剩余的 token)
 fn continue_parse_interval(
     source_token_details: &[TokenDetail],
-) -> Result<(bool, Option<Expression>, &[TokenDetail]), Error> {
+) -> Result<(bool, Option<Expression>, &[TokenDetail]), Diagnostic> {
     // exp1..=
     // exp1..=exp2
     // exp1..
@@ -3050,8 +4984,10 @@ fn continue_parse_interval(
             // 遇到了逗号或者右中括号（方括号）
             if is_inclusive {
                 // 对于闭区间的范围表达式，`to` 部分是不能省略的。
-                Err(Error::ParserError(
-                    "expected inclusive range end".to_string(),
+                Err(new_error(
+                    DiagnosticCode::ExpectedExpression,
+                    "expected inclusive range end",
+                    post_new_lines,
                 ))
             } else {
                 // 当前范围表达式缺省了 `to` 部分。
@@ -3066,14 +5002,16 @@ fn continue_parse_interval(
     }
 }
 
-fn parse_map(source_token_details: &[TokenDetail]) -> Result<(Expression, &[TokenDetail]), Error> {
+fn parse_map(
+    source_token_details: &[TokenDetail],
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     let (map, post_continue_parse_map) = continue_parse_map(source_token_details)?;
     Ok((Expression::Map(map), post_continue_parse_map))
 }
 
 fn continue_parse_map(
     source_token_details: &[TokenDetail],
-) -> Result<(Map, &[TokenDetail]), Error> {
+) -> Result<(Map, &[TokenDetail]), Diagnostic> {
     // map
     //
     // e.g.
@@ -3104,8 +5042,10 @@ fn continue_parse_map(
                 } else {
                     if is_expected_end {
                         // 当前的状态是一心寻找结束符号 `}`
-                        return Err(Error::ParserError(
-                            "expected the right brace symbol \"}\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right brace symbol \"}\"",
+                            token_details,
                         ));
                     } else {
                         // 先检查是否 `省略符表达式`
@@ -3206,8 +5146,10 @@ fn continue_parse_map(
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right brace symbol \"}\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right brace symbol \"}\"",
+                    token_details,
                 ));
             }
         }
@@ -3227,7 +5169,7 @@ fn continue_parse_map(
 
 fn parse_prefix_identifier(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // prefix identifier
     let post_consume_token_exclamation = consume_token(&Token::Exclamation, source_token_details)?;
 
@@ -3236,7 +5178,7 @@ fn parse_prefix_identifier(
 
     Ok((
         Expression::PrefixIdentifier(PrefixIdentifier {
-            identifier: identifier,
+            identifier,
             range: new_range(),
         }),
         post_continue_parse_identifier,
@@ -3245,7 +5187,7 @@ fn parse_prefix_identifier(
 
 fn parse_identifier(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // identifier
     //
     // One::Two::Three::Name
@@ -3262,7 +5204,7 @@ fn parse_identifier(
 
 fn continue_parse_identifier(
     source_token_details: &[TokenDetail],
-) -> Result<(Identifier, &[TokenDetail]), Error> {
+) -> Result<(Identifier, &[TokenDetail]), Diagnostic> {
     // identifier
     //
     // e.g.
@@ -3279,7 +5221,7 @@ fn continue_parse_identifier(
     )) = token_details.split_first()
     {
         // 获取第一个 identifier
-        names.push(name.clone());
+        names.push(name.to_string());
         token_details = rest;
 
         // 获取其余的 identifier
@@ -3296,11 +5238,15 @@ fn continue_parse_identifier(
                     )) = post_token_separator.split_first()
                     {
                         // 检测到一个 identifier
-                        names.push(name.clone());
+                        names.push(name.to_string());
                         post_token_identifier
                     } else {
                         // 在 namespace path 分隔符 `::` 后面必须是一个 identifier
-                        return Err(Error::ParserError("expected identifier".to_string()));
+                        return Err(new_error(
+                            DiagnosticCode::ExpectedIdentifier,
+                            "expected identifier",
+                            token_details,
+                        ));
                     }
                 }
                 _ => {
@@ -3310,8 +5256,12 @@ fn continue_parse_identifier(
         }
     }
 
-    if names.len() == 0 {
-        return Err(Error::ParserError("expected identifier".to_string()));
+    if names.is_empty() {
+        return Err(new_error(
+            DiagnosticCode::ExpectedIdentifier,
+            "expected identifier",
+            token_details,
+        ));
     }
 
     let mut generics: Vec<DataType> = vec![];
@@ -3330,9 +5280,9 @@ fn continue_parse_identifier(
     let len = names.len();
     Ok((
         Identifier {
-            dirs: names[..len - 1].iter().map(|n| n.clone()).collect(),
+            dirs: names[..len - 1].to_vec(),
             name: names[len - 1].clone(),
-            generics: generics,
+            generics,
             range: new_range(),
         },
         token_details,
@@ -3341,7 +5291,7 @@ fn continue_parse_identifier(
 
 fn parse_sign_expression(
     source_token_details: &[TokenDetail],
-) -> Result<(Expression, &[TokenDetail]), Error> {
+) -> Result<(Expression, &[TokenDetail]), Diagnostic> {
     // 函数签名
     // 签名没有名称、参数名称可省略，不支持默认值
     //
@@ -3389,14 +5339,17 @@ fn parse_sign_expression(
                 } else {
                     if is_expected_end {
                         // 当前的状态是一心寻找结束符号
-                        return Err(Error::ParserError(
-                            "expected the right paren symbol \")\"".to_string(),
+                        return Err(new_error(
+                            DiagnosticCode::UnclosedDelimiter,
+                            "expected the right paren symbol \")\"",
+                            token_details,
                         ));
                     } else {
                         // 获取参数的数据类型
                         let (data_type_expression, post_data_type_expression) =
                             parse_expression(token_details)?;
-                        let data_type = convert_expression_to_data_type(data_type_expression)?;
+                        let data_type =
+                            convert_expression_to_data_type(data_type_expression, token_details)?;
 
                         let post_one_parameter = match post_data_type_expression.split_first() {
                             Some((maybe_comma_or_right_paren, _))
@@ -3405,7 +5358,7 @@ fn parse_sign_expression(
                             {
                                 // 当前参数无名称
                                 parameters.push(SignParameter {
-                                    data_type: data_type,
+                                    data_type,
                                     name: None,
                                     range: new_range(),
                                 });
@@ -3420,15 +5373,17 @@ fn parse_sign_expression(
                             )) => {
                                 // 当前参数有名称
                                 parameters.push(SignParameter {
-                                    data_type: data_type,
-                                    name: Some(name.clone()),
+                                    data_type,
+                                    name: Some(name.to_string()),
                                     range: new_range(),
                                 });
                                 post_name
                             }
                             _ => {
-                                return Err(Error::ParserError(
-                                    "incomplete function parameter".to_string(),
+                                return Err(new_error(
+                                    DiagnosticCode::InvalidParameter,
+                                    "incomplete function parameter",
+                                    post_data_type_expression,
                                 ));
                             }
                         };
@@ -3450,8 +5405,10 @@ fn parse_sign_expression(
                 }
             }
             None => {
-                return Err(Error::ParserError(
-                    "expected the right paren symbol \")\"".to_string(),
+                return Err(new_error(
+                    DiagnosticCode::UnclosedDelimiter,
+                    "expected the right paren symbol \")\"",
+                    token_details,
                 ));
             }
         }
@@ -3491,9 +5448,9 @@ fn parse_sign_expression(
 
     // 构造函数签名对象
     let sign = Sign {
-        parameters: parameters,
-        return_data_type: return_data_type.map(|d| Box::new(d)),
-        generics: generics,
+        parameters,
+        return_data_type: return_data_type.map(Box::new),
+        generics,
         whiches,
         range: new_range(),
     };
@@ -3514,14 +5471,16 @@ fn parse_sign_expression(
 //  | NamedOperator
 //  ;
 
-fn parse_literal(source_token_details: &[TokenDetail]) -> Result<(Literal, &[TokenDetail]), Error> {
+fn parse_literal(
+    source_token_details: &[TokenDetail],
+) -> Result<(Literal, &[TokenDetail]), Diagnostic> {
     match source_token_details.split_first() {
         Some((first, rest)) => match &first.token {
             Token::Integer(v) => match continue_parse_imaginary(rest) {
                 // 整数或复数
                 Ok((f, post_rest)) => Ok((
                     Literal::Complex(Complex {
-                        real: *v as f64,
+                        real: v.to_f64(),
                         imaginary: f,
                         range: new_range(),
                     }),
@@ -3529,7 +5488,7 @@ fn parse_literal(source_token_details: &[TokenDetail]) -> Result<(Literal, &[Tok
                 )),
                 _ => Ok((
                     Literal::Integer(Integer {
-                        value: *v,
+                        value: v.clone(),
                         range: new_range(),
                     }),
                     rest,
@@ -3564,10 +5523,9 @@ fn parse_literal(source_token_details: &[TokenDetail]) -> Result<(Literal, &[Tok
                     rest,
                 ))
             }
-            Token::Bit(width, bytes) => Ok((
+            Token::Bit(v) => Ok((
                 Literal::Bit(Bit {
-                    width: *width,
-                    bytes: bytes.clone(),
+                    value: v.clone(),
                     range: new_range(),
                 }),
                 rest,
@@ -3594,10 +5552,26 @@ fn parse_literal(source_token_details: &[TokenDetail]) -> Result<(Literal, &[Tok
                 rest,
             )),
             Token::TemplateString(v) => {
-                // todo::
-                // 这里需要重新 tokenize 模板字符串里面的占位符表达式，
-                // 然后重新解析这些表达式
-                todo!()
+                // 拆分模板字符串，然后重新 tokenize 以及解析占位符里面的表达式
+                let parts = split_template_string(
+                    v,
+                    first.location.file_id,
+                    first.location.start + 1, // 跳过符号 '`'
+                )?;
+
+                let mut expressions: Vec<Expression> = vec![];
+                for placeholder in &parts.placeholders {
+                    expressions.push(parse_template_placeholder(placeholder)?);
+                }
+
+                Ok((
+                    Literal::TemplateString(TemplateString {
+                        fragments: parts.fragments,
+                        expressions,
+                        range: new_range(),
+                    }),
+                    rest,
+                ))
             }
             Token::HashString(v) => Ok((
                 Literal::HashString(HashString {
@@ -3613,10 +5587,69 @@ fn parse_literal(source_token_details: &[TokenDetail]) -> Result<(Literal, &[Tok
                 }),
                 rest,
             )),
-            _ => Err(Error::ParserError("invalid literal".to_string())),
+            _ => Err(new_error(
+                DiagnosticCode::InvalidLiteral,
+                "invalid literal",
+                source_token_details,
+            )),
         },
-        None => Err(Error::ParserError("expected literal".to_string())),
+        None => Err(new_error(
+            DiagnosticCode::ExpectedExpression,
+            "expected literal",
+            source_token_details,
+        )),
+    }
+}
+
+// 解析模板字符串占位符里面的表达式
+//
+// 占位符里面必须有且只有一个表达式，表达式的前后允许有空行。
+fn parse_template_placeholder(placeholder: &TemplatePlaceholder) -> Result<Expression, Diagnostic> {
+    let location = &placeholder.location;
+    let token_details = tokenize_embedded(&placeholder.text, location.file_id, location.start)?;
+
+    let post_new_lines = skip_new_lines(&token_details);
+    if post_new_lines.is_empty() {
+        return Err(Diagnostic::error(
+            DiagnosticCode::ExpectedExpression,
+            "expected an expression in the template string placeholder",
+            location.clone(),
+        ));
+    }
+
+    let (expression, post_expression) =
+        parse_expression(post_new_lines).map_err(|mut diagnostic| {
+            // 占位符内容意外结束的错误，其位置为占位符的末尾（即符号 `}` 所在的位置），
+            // 因为这并不是源文件的结束，所以改为 UnexpectedToken。
+            if diagnostic.code == DiagnosticCode::UnexpectedEndOfFile {
+                diagnostic.code = DiagnosticCode::UnexpectedToken;
+                diagnostic.message = diagnostic
+                    .message
+                    .replacen(
+                        "unexpected end of file",
+                        "unexpected end of the template string placeholder",
+                        1,
+                    )
+                    .into();
+                diagnostic.location = Location {
+                    file_id: location.file_id,
+                    start: location.end,
+                    end: location.end,
+                };
+            }
+            diagnostic
+        })?;
+
+    let post_new_lines = skip_new_lines(post_expression);
+    if !post_new_lines.is_empty() {
+        return Err(new_error(
+            DiagnosticCode::UnexpectedToken,
+            "expected the right brace symbol \"}\" of the template string placeholder",
+            post_new_lines,
+        ));
     }
+
+    Ok(expression)
 }
 
 // 尝试解析复数，如果成功则返回虚数及剩余的 token，
@@ -3647,12 +5680,17 @@ fn continue_parse_imaginary(
 
 // 跳过空白的行，在 lexer 里产生的 Token 序列当中，有可能存在多行连续的空行，
 // 在解析一个statement 之前，或者 expression 之间，需要消除这些空白的前导空行
+// 跳过空行
+//
+// 不位于声明语句之前的文档注释跟普通注释一样没有意义，所以也一并跳过
 fn skip_new_lines(source_token_details: &[TokenDetail]) -> &[TokenDetail] {
     let mut token_details = source_token_details;
 
     loop {
         token_details = match token_details.split_first() {
-            Some((first, rest)) if first.token == Token::NewLine => rest,
+            Some((first, rest)) if matches!(first.token, Token::NewLine | Token::DocComment(_)) => {
+                rest
+            }
             _ => {
                 break;
             }
@@ -3662,26 +5700,45 @@ fn skip_new_lines(source_token_details: &[TokenDetail]) -> &[TokenDetail] {
     token_details
 }
 
+// 跳过空行，并收集其中的文档注释
+fn continue_parse_docs(source_token_details: &[TokenDetail]) -> (Vec<String>, &[TokenDetail]) {
+    let mut token_details = source_token_details;
+    let mut docs: Vec<String> = vec![];
+
+    loop {
+        token_details = match token_details.split_first() {
+            Some((first, rest)) => match &first.token {
+                Token::NewLine => rest,
+                Token::DocComment(value) => {
+                    docs.push(value.clone());
+                    rest
+                }
+                _ => break,
+            },
+            None => break,
+        }
+    }
+
+    (docs, token_details)
+}
+
 fn skip_new_lines_and_consume_token<'a>(
     expected: &Token,
     source_token_details: &'a [TokenDetail],
-) -> Result<&'a [TokenDetail], Error> {
+) -> Result<&'a [TokenDetail], Diagnostic> {
     let post_new_lines = skip_new_lines(source_token_details);
     consume_token(expected, post_new_lines)
 }
 
 fn is_token(expected: &Token, source_token_details: &[TokenDetail]) -> bool {
-    match source_token_details.first() {
-        Some(first) if &first.token == expected => true,
-        _ => false,
-    }
+    matches!(source_token_details.first(), Some(first) if &first.token == expected)
 }
 
 fn any_token(expecteds: &[Token], source_token_details: &[TokenDetail]) -> bool {
-    match source_token_details.first() {
-        Some(TokenDetail { token, .. }) if expecteds.iter().any(|t| t == token) => true,
-        _ => false,
-    }
+    matches!(
+        source_token_details.first(),
+        Some(TokenDetail { token, .. }) if expecteds.iter().any(|t| t == token)
+    )
 }
 
 fn is_token_ignore_new_lines(expected: &Token, source_token_details: &[TokenDetail]) -> bool {
@@ -3692,13 +5749,14 @@ fn is_token_ignore_new_lines(expected: &Token, source_token_details: &[TokenDeta
 fn consume_token<'a>(
     expected: &Token,
     source_token_details: &'a [TokenDetail],
-) -> Result<&'a [TokenDetail], Error> {
+) -> Result<&'a [TokenDetail], Diagnostic> {
     match source_token_details.split_first() {
         Some((first, rest)) if &first.token == expected => Ok(rest),
-        _ => Err(Error::ParserError(format!(
-            "expected the specified symbol \"{}\"",
-            expected
-        ))),
+        _ => Err(new_error(
+            DiagnosticCode::UnexpectedToken,
+            &format!("expected the specified symbol \"{}\"", expected),
+            source_token_details,
+        )),
     }
 }
 
@@ -3714,18 +5772,56 @@ fn consume_token<'a>(
 
 fn consume_new_line_or_end_of_file(
     source_token_details: &[TokenDetail],
-) -> Result<&[TokenDetail], Error> {
-    match source_token_details.split_first() {
+) -> Result<&[TokenDetail], Diagnostic> {
+    // 跳过行尾的文档注释
+    let mut token_details = source_token_details;
+    while let Some((
+        TokenDetail {
+            token: Token::DocComment(_),
+            ..
+        },
+        rest,
+    )) = token_details.split_first()
+    {
+        token_details = rest;
+    }
+
+    match token_details.split_first() {
         Some((first, rest)) => {
             if first.token == Token::NewLine {
                 Ok(rest)
             } else {
-                Err(Error::ParserError(
-                    "expected the new-line symbol".to_string(),
+                Err(new_error(
+                    DiagnosticCode::ExpectedNewLine,
+                    "expected the new-line symbol",
+                    token_details,
                 ))
             }
         }
-        None => Ok(source_token_details),
+        None => Ok(token_details),
+    }
+}
+
+// 以当前 token 的位置作为错误的位置
+//
+// 如果已经到达源文件的末尾，则诊断代码统一为 UnexpectedEndOfFile，
+// 其位置由 parse 函数设置。
+fn new_error(
+    code: DiagnosticCode,
+    message: &str,
+    source_token_details: &[TokenDetail],
+) -> Diagnostic {
+    match source_token_details.first() {
+        Some(first) => Diagnostic::error(code, message, first.location.clone()),
+        None => Diagnostic::error(
+            DiagnosticCode::UnexpectedEndOfFile,
+            &format!("unexpected end of file, {}", message),
+            Location {
+                file_id: 0,
+                start: 0,
+                end: 0,
+            },
+        ),
     }
 }
 
@@ -3740,17 +5836,19 @@ fn new_range() -> Range {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use crate::{
         ast::{
-            BinaryExpression, BlockExpression, Complex, Ellipsis, Expression, Float, Identifier,
-            Integer, Interval, LetExpression, List, Literal, Node, PrefixIdentifier, Program,
-            Statement, Tuple,
+            BinaryExpression, BlockExpression, Expression, FunctionDeclaration, Identifier,
+            Integer, LetExpression, Literal, MemberStructDeclaration, Node, PatternFunctionGroup,
+            Program, Range, Region, Statement,
         },
-        error::Error,
+        error::{Diagnostic, DiagnosticCode},
         lexer,
         parser::new_range,
-        token::Token,
+        testing::Random,
+        token::{format_float, IntegerLiteral, Location, Token},
     };
 
     use super::parse;
@@ -3766,23 +5864,224 @@ mod tests {
         }
     }
 
-    fn new_literal_integer(value: i64) -> Literal {
-        Literal::Integer(Integer {
-            value: value,
-            range: new_range(),
-        })
-    }
+    fn new_literal_integer(value: i64) -> Literal {
+        Literal::Integer(Integer {
+            value: IntegerLiteral::new(&value.to_string(), None),
+            range: new_range(),
+        })
+    }
+
+    fn parse_from_string(text: &str) -> Result<Node, Diagnostic> {
+        let token_details = lexer::tokenize(text)?;
+        parse(&token_details)
+    }
+
+    fn trim_left_margin(s: &str) -> String {
+        s.split("\n")
+            .map(|s| s.trim_start().to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        // 语法树输出的源文本重新解析之后应该得到相同的语法树
+        // 注：被注释掉的代码以及范围注释记录的是源文本的位置，不是语法树的一部分，所以只比较语句
+        let sources = [
+            "0xffu8 + 1_000n",
+            "-128i8",
+            "3.14e2 + 1e300 + 0.5e-10",
+            "1.414+2.718i + 3+4i + 5i + 3.14e2i",
+            "16'x08cd + 8'b10000001 + 12'o7 + 8'd255",
+            "true && false",
+            r"'a' ++ '文' ++ '\n' ++ '\'' ++ '\\' ++ '\x41' ++ '\u{1b}' ++ '\0'",
+            "\"abc\" ++ \"中文🐱\" ++ \"foo\nbar\n  baz\"",
+            r#""a\"b\\c\td\r\u{6587}" ++ """raw "quoted" \n""""#,
+            "`Hello {name}` ++ `{a}+{b} = {a + b}.` ++ `\\{x\\} \\` {\"}\"}`",
+            "#abc ++ #中文 ++ :abc: ++ :中文:",
+            "!foo::bar",
+            "[123,1.732,] ++ [] ++ [123,...abc] ++ [1,3..=9] ++ [1..]",
+            "{x:10,y:20,} ++ {x, y:20, ...rest}",
+            "std::Result<T,E>",
+            "fn a = a+1",
+            "User{id:123,name:\"foo\"} ++ User{id,name,...user001}",
+            "users[0].name ++ user[\"name\"] ++ users[0..=9] ++ user.0",
+            "1>2:bit_or:3 ++ 1*2??3 ++ 1->2&3 ++ (1+2)*3 ++ 1-(2-3)",
+            "let Int i=1+2*3",
+            "let a=let b=1",
+            "window.title := \"hello\"\nlet String title := window.title",
+            "if a>b where let i=a+b then a+1 else b+2",
+            "for let i=1 i+1",
+            "do {\na\n#disable b+1\nc\n}",
+            "let i=85\nbranch {\ncase i>90:100\ncase i>60: i+10\ndefault: writeLine(\"failed\")\n}",
+            "match foo{\ncase (a,b,1,2):10\ncase [x,y,...]:20\ncase User{id:123, name}:40\ndefault:60\n}",
+            "function foo(Int a=10, Int b=20) type Int {a+b}",
+            "function foo()=1+2",
+            "empty function Result<T, E> foo<T>(T a, (Int, Int) b) which {\nT: limit Display\n}",
+            "use foo::{bar, sub{one as two, three}}\nuse std::List as L",
+            "const Int A = 1\nconst B {\nX = 1, Y = (2, 3)\n}\nB::X + A",
+            "@validate(x > 0)\nstruct P<T> {\n'''x'''\n@validate(x < 9)\nInt x = 1, T y\n}\nstruct Q(Int, P<Int>)\nstruct R{}",
+            "pattern function f(Int i @ in [1..10] only i > 2 where let j = i, s @ template \"{a}\") type Int {i}\nlet x = 1\npattern function f(Int 0, String s) type Int only flag = {0}",
+        ];
+
+        for source in sources {
+            let n1 = parse_from_string(source).unwrap();
+            let text = n1.to_string();
+            let n2 = parse_from_string(&text)
+                .unwrap_or_else(|e| panic!("source: {:?}, text: {:?}, {:?}", source, text, e));
+            match (n1, n2) {
+                (Node::Program(p1), Node::Program(p2)) => {
+                    assert_eq!(p1.body, p2.body, "source: {:?}, text: {:?}", source, text)
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    // 随机生成表达式的源文本，用于语法树输出的往返测试
+    fn random_expression_text(random: &mut Random, depth: usize) -> String {
+        // 注：运算符 `<` 跟在标识符后面时会被解析为泛型参数，所以这里不生成 `<` 和 `<=`
+        const OPERATORS: [&str; 14] = [
+            "|", "||", "&&", "==", "!=", ">", ">=", "++", "+", "-", "*", "/", "??", "->",
+        ];
+
+        let mut text = random_primary_expression_text(random, depth);
+        for _ in 0..random.below(4) {
+            text.push_str(&format!(
+                " {} {}",
+                random.pick(&OPERATORS),
+                random_primary_expression_text(random, depth)
+            ));
+        }
+        text
+    }
+
+    fn random_primary_expression_text(random: &mut Random, depth: usize) -> String {
+        const NAMES: [&str; 4] = ["a", "b", "foo", "中文"];
+        const LITERALS: [&str; 7] = ["true", "false", "'c'", "\"abc\"", "#tag", "8'xff", "!foo"];
+
+        let kind = if depth == 0 {
+            random.below(4)
+        } else {
+            random.below(12)
+        };
+
+        match kind {
+            0 => random.pick(&NAMES).to_string(),
+            1 => random.below(100_000).to_string(),
+            2 => format_float(random.finite_float()),
+            3 => random.pick(&LITERALS).to_string(),
+            4 => format!("{}i", format_float(random.finite_float())),
+            5 => format!("({})", random_expression_text(random, depth - 1)),
+            6 => format!("-{}", random_unary_operand_text(random, depth - 1)),
+            7 => format!(
+                "[{}, {}]",
+                random_expression_text(random, depth - 1),
+                random_expression_text(random, depth - 1)
+            ),
+            8 => format!(
+                "{}({}, {})",
+                random.pick(&NAMES),
+                random_expression_text(random, depth - 1),
+                random_expression_text(random, depth - 1)
+            ),
+            9 => format!(
+                "{}.{}[{}]",
+                random.pick(&NAMES),
+                random.pick(&NAMES),
+                random_expression_text(random, depth - 1)
+            ),
+            10 => format!(
+                "(if {} then {} else {})",
+                random_expression_text(random, depth - 1),
+                random_expression_text(random, depth - 1),
+                random_expression_text(random, depth - 1)
+            ),
+            _ => format!(
+                "({}, {})",
+                random_expression_text(random, depth - 1),
+                random_expression_text(random, depth - 1)
+            ),
+        }
+    }
+
+    fn random_unary_operand_text(random: &mut Random, depth: usize) -> String {
+        // 注：负号不能连续出现，比如 `--a`
+        let text = random_primary_expression_text(random, depth);
+        if text.starts_with('-') {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn random_statement_text(random: &mut Random) -> String {
+        let expression = random_expression_text(random, 2);
+        match random.below(3) {
+            0 => format!("let {} = {}", random.pick(&["a", "b", "foo"]), expression),
+            1 => format!("function f(Int x, Int y) = {}", expression),
+            _ => expression,
+        }
+    }
+
+    // 随机生成程序的源文本，其中部分语句被结构注释 `#disable` 注释掉，
+    // 部分语句位于（可能嵌套以及缺少结束横线的）范围注释之内，
+    // 返回源文本以及被注释掉的语句和范围注释的数量
+    fn random_program_text(random: &mut Random) -> (String, usize, usize) {
+        let mut lines: Vec<String> = vec![];
+        let mut disabled_count = 0;
+        let mut region_count = 0;
+        let mut open_region_count = 0;
+
+        for _ in 0..random.below(3) + 1 {
+            if random.below(4) == 0 {
+                lines.push(format!("// region {}", region_count));
+                lines.push("// ===".to_string());
+                region_count += 1;
+                open_region_count += 1;
+            }
+
+            let statement = random_statement_text(random);
+            if random.below(4) == 0 {
+                lines.push(format!("#disable {}", statement));
+                disabled_count += 1;
+            } else {
+                lines.push(statement);
+            }
+
+            if open_region_count > 0 && random.below(2) == 0 {
+                lines.push("// ---".to_string());
+                open_region_count -= 1;
+            }
+        }
 
-    fn parse_from_string(text: &str) -> Result<Node, Error> {
-        let token_details = lexer::tokenize(text)?;
-        parse(&token_details)
+        (lines.join("\n"), disabled_count, region_count)
     }
 
-    fn trim_left_margin(s: &str) -> String {
-        s.split("\n")
-            .map(|s| s.trim_start().to_string())
-            .collect::<Vec<String>>()
-            .join("\n")
+    #[test]
+    fn test_display_round_trip_generated() {
+        // 随机生成的源文本解析之后输出，再重新解析，应该得到相同的语法树
+        // 注：输出的文本不包括被注释掉的代码以及范围注释，见 `impl Display for Program`
+        let mut random = Random::new(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..1000 {
+            let (source, disabled_count, region_count) = random_program_text(&mut random);
+
+            let n1 = parse_from_string(&source)
+                .unwrap_or_else(|e| panic!("source: {:?}, {:?}", source, e));
+            let text = n1.to_string();
+            let n2 = parse_from_string(&text)
+                .unwrap_or_else(|e| panic!("source: {:?}, text: {:?}, {:?}", source, text, e));
+            match (n1, n2) {
+                (Node::Program(p1), Node::Program(p2)) => {
+                    assert_eq!(p1.disabled.len(), disabled_count, "source: {:?}", source);
+                    assert_eq!(p1.regions.len(), region_count, "source: {:?}", source);
+                    assert_eq!(p1.body, p2.body, "source: {:?}, text: {:?}", source, text);
+                    assert!(p2.disabled.is_empty() && p2.regions.is_empty());
+                }
+                _ => unreachable!(),
+            }
+        }
     }
 
     // literal
@@ -3795,15 +6094,35 @@ mod tests {
             Node::Program(Program {
                 body: vec![Statement::Expression(Expression::Literal(
                     Literal::Integer(Integer {
-                        value: 123,
+                        value: IntegerLiteral::new("123", None),
                         range: new_range()
                     })
                 ))],
+                disabled: vec![],
+                regions: vec![],
                 range: new_range()
             })
         );
 
         assert_eq!(n1.to_string(), "123\n"); // Statement 以符号 '\n' 结尾
+
+        // 类型后缀以及原始的数字文本
+        let n2 = parse_from_string("0xffu8 + 1_000n").unwrap();
+        assert_eq!(n2.to_string(), "(0xffu8 + 1_000n)\n");
+
+        // 负数
+        let n3 = parse_from_string("-128i8").unwrap();
+        assert_eq!(n3.to_string(), "-128i8\n");
+
+        // 没有负号时超出范围
+        let e1 = parse_from_string("let a = 128i8").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::IntegerOverflow);
+        assert_eq!(e1.location.start, 8);
+
+        let e2 = parse_from_string("9223372036854775808").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::IntegerOverflow);
+
+        assert!(parse_from_string("-9223372036854775808").is_ok());
     }
 
     #[test]
@@ -3812,7 +6131,7 @@ mod tests {
         assert_eq!(n1.to_string(), "3.14\n");
 
         let n2 = parse_from_string("3.14e2").unwrap();
-        assert_eq!(n2.to_string(), "314\n");
+        assert_eq!(n2.to_string(), "314.0\n");
 
         let n3 = parse_from_string("3.14e-1").unwrap();
         assert_eq!(n3.to_string(), "0.314\n");
@@ -3844,12 +6163,14 @@ mod tests {
 
     #[test]
     fn test_bit_literal() {
-        // todo::
-        // let n1 = parse_from_string("16'x08cd").unwrap();
-        // assert_eq!(n1.to_string(), "16'x08cd\n");
-        //
-        // let n2 = parse_from_string("8'b10000001").unwrap();
-        // assert_eq!(n2.to_string(), "8'x81\n");
+        let n1 = parse_from_string("16'x08cd").unwrap();
+        assert_eq!(n1.to_string(), "16'x08cd\n");
+
+        let n2 = parse_from_string("8'b10000001").unwrap();
+        assert_eq!(n2.to_string(), "8'b10000001\n");
+
+        let n3 = parse_from_string("12'o7").unwrap();
+        assert_eq!(n3.to_string(), "12'o0007\n");
     }
 
     #[test]
@@ -3890,7 +6211,51 @@ mod tests {
 
     #[test]
     fn test_template_string_literal() {
-        // todo::
+        let n1 = parse_from_string("`Hello {name}`").unwrap();
+        assert_eq!(n1.to_string(), "`Hello {name}`\n");
+
+        let n2 = parse_from_string("`{a}+{b} = {a + b}.`").unwrap();
+        assert_eq!(n2.to_string(), "`{a}+{b} = {(a + b)}.`\n");
+
+        // 无占位符
+        let n3 = parse_from_string("`foo`").unwrap();
+        assert_eq!(n3.to_string(), "`foo`\n");
+
+        // 转义字符
+        let n4 = parse_from_string(r"`a\{b\}\`c\td`").unwrap();
+        assert_eq!(n4.to_string(), "`a\\{b\\}\\`c\td`\n");
+
+        // 占位符里的字符串、函数调用以及嵌套的模板字符串
+        let n5 = parse_from_string(r#"`{foo("}")} {`x{y}`}`"#).unwrap();
+        assert_eq!(n5.to_string(), "`{(foo)(\"}\")} {`x{y}`}`\n");
+
+        // 占位符里的注释
+        let n6 = parse_from_string("`{a /* } */ + 1}`").unwrap();
+        assert_eq!(n6.to_string(), "`{(a + 1)}`\n");
+
+        // 占位符里的表达式的位置
+        let e1 = parse_from_string("`ab{1 +}`").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(e1.location.start, 7);
+
+        let e2 = parse_from_string("`ab{x y}`").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(e2.location.start, 6);
+
+        // 空的占位符
+        let e3 = parse_from_string("`ab{ }`").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::ExpectedExpression);
+        assert_eq!(e3.location.start, 4);
+
+        // 不成对的 `}`
+        let e4 = parse_from_string("`a}b`").unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::InvalidTemplateString);
+        assert_eq!(e4.location.start, 2);
+
+        // 未知的转义字符
+        let e5 = parse_from_string(r"`a\qb`").unwrap_err();
+        assert_eq!(e5.code, DiagnosticCode::InvalidEscape);
+        assert_eq!(e5.location.start, 2);
     }
 
     #[test]
@@ -3901,9 +6266,15 @@ mod tests {
         let n2 = parse_from_string("#foo_bar").unwrap();
         assert_eq!(n2.to_string(), "#foo_bar\n");
 
-        // todo:: 添加中文的支持
-        // let n3 = parse_from_string("#中文🐱").unwrap();
-        // assert_eq!(n3.to_string(), "#中文🐱\n");
+        let n3 = parse_from_string("#中文").unwrap();
+        assert_eq!(n3.to_string(), "#中文\n");
+
+        // 组合形式的字符按 NFC 规范化
+        let n4 = parse_from_string("#cafe\u{301}").unwrap();
+        assert_eq!(n4.to_string(), "#caf\u{e9}\n");
+
+        // emoji 不属于 XID_Continue
+        assert!(parse_from_string("#中文🐱").is_err());
     }
 
     #[test]
@@ -3914,9 +6285,11 @@ mod tests {
         let n2 = parse_from_string(":foo_bar:").unwrap();
         assert_eq!(n2.to_string(), ":foo_bar:\n");
 
-        // todo:: 添加中文的支持
-        // let n3 = parse_from_string(":中文🐱:").unwrap();
-        // assert_eq!(n3.to_string(), ":中文🐱:\n");
+        let n3 = parse_from_string(":中文:").unwrap();
+        assert_eq!(n3.to_string(), ":中文:\n");
+
+        // emoji 不属于 XID_Continue
+        assert!(parse_from_string(":中文🐱:").is_err());
     }
 
     // primary expressions
@@ -4197,6 +6570,8 @@ mod tests {
                     generics: vec![],
                     range: new_range()
                 }))],
+                disabled: vec![],
+                regions: vec![],
                 range: new_range()
             })
         );
@@ -4615,823 +6990,1722 @@ mod tests {
         // assert_eq!(n12.to_string(), "((foo & bar))(1)\n");
     }
 
-    #[test]
-    fn test_unary_expression() {
-        //
-    }
+    #[test]
+    fn test_unary_expression() {
+        //
+    }
+
+    #[test]
+    fn test_binary_expression_additive() {
+        let n1 = parse_from_string("1+2").unwrap();
+        assert_eq!(
+            n1,
+            Node::Program(Program {
+                body: vec![Statement::Expression(Expression::BinaryExpression(
+                    BinaryExpression {
+                        operator: Token::Plus,
+                        left: Box::new(Expression::Literal(Literal::Integer(Integer {
+                            value: IntegerLiteral::new("1", None),
+                            range: new_range()
+                        }))),
+                        right: Box::new(Expression::Literal(Literal::Integer(Integer {
+                            value: IntegerLiteral::new("2", None),
+                            range: new_range()
+                        }))),
+                        range: new_range()
+                    }
+                ))],
+                disabled: vec![],
+                regions: vec![],
+                range: new_range()
+            })
+        );
+        assert_eq!(n1.to_string(), "(1 + 2)\n"); // Statement 以符号 '\n' 结尾
+
+        let n2 = parse_from_string("1+2+3").unwrap();
+        assert_eq!(n2.to_string(), "((1 + 2) + 3)\n");
+
+        let n3 = parse_from_string("1.414+1.732").unwrap();
+        assert_eq!(n3.to_string(), "(1.414 + 1.732)\n");
+
+        // 测试复数和加法并存的情况
+        let n4 = parse_from_string("3+4i+9i").unwrap();
+        assert_eq!(n4.to_string(), "(3+4i + 0+9i)\n");
+    }
+
+    #[test]
+    fn test_binary_expression_precedence() {
+        let n1 = parse_from_string("1|2||3").unwrap();
+        assert_eq!(n1.to_string(), "(1 | (2 || 3))\n");
+
+        let n2 = parse_from_string("1||2&&3").unwrap();
+        assert_eq!(n2.to_string(), "(1 || (2 && 3))\n");
+
+        let n3 = parse_from_string("1&&2==3").unwrap();
+        assert_eq!(n3.to_string(), "(1 && (2 == 3))\n");
+
+        let n4 = parse_from_string("1==2>3").unwrap();
+        assert_eq!(n4.to_string(), "(1 == (2 > 3))\n");
+
+        let n5 = parse_from_string("1>2:bit_or:3").unwrap();
+        assert_eq!(n5.to_string(), "(1 > (2 :bit_or: 3))\n");
+
+        let n6 = parse_from_string("1:bit_and:2++3").unwrap();
+        assert_eq!(n6.to_string(), "(1 :bit_and: (2 ++ 3))\n");
+
+        let n7 = parse_from_string("1++2+3").unwrap();
+        assert_eq!(n7.to_string(), "(1 ++ (2 + 3))\n");
+
+        let n8 = parse_from_string("1+2*3").unwrap();
+        assert_eq!(n8.to_string(), "(1 + (2 * 3))\n");
+
+        let n9 = parse_from_string("1*2??3").unwrap();
+        assert_eq!(n9.to_string(), "(1 * (2 ?? 3))\n");
+
+        let n10 = parse_from_string("1??2->3").unwrap();
+        assert_eq!(n10.to_string(), "(1 ?? (2 -> 3))\n");
+
+        let n11 = parse_from_string("1->2&3").unwrap();
+        assert_eq!(n11.to_string(), "(1 -> (2 & 3))\n");
+    }
+
+    #[test]
+    fn test_binary_expression_parenthesized() {
+        let n1 = parse_from_string("(123)").unwrap();
+        assert_eq!(
+            n1,
+            Node::Program(Program {
+                body: vec![Statement::Expression(Expression::Literal(
+                    Literal::Integer(Integer {
+                        value: IntegerLiteral::new("123", None),
+                        range: new_range()
+                    })
+                ))],
+                disabled: vec![],
+                regions: vec![],
+                range: new_range()
+            })
+        );
+        assert_eq!(n1.to_string(), "123\n");
+
+        let n2 = parse_from_string("(1+2)").unwrap();
+        assert_eq!(n2.to_string(), "(1 + 2)\n");
+
+        let n3 = parse_from_string("(1+2)*3").unwrap();
+        assert_eq!(n3.to_string(), "((1 + 2) * 3)\n");
+    }
+
+    #[test]
+    fn test_binary_expression_associativitye() {
+        // 测试结合方向
+
+        // 操作符 `+` 从左向右结合
+        let n1 = parse_from_string("1+2+3").unwrap();
+        assert_eq!(n1.to_string(), "((1 + 2) + 3)\n");
+
+        // 操作符 `&` 从右向左结合
+        let n2 = parse_from_string("1&2&3").unwrap();
+        assert_eq!(n2.to_string(), "(1 & (2 & 3))\n");
+    }
+
+    // genernal expression
+
+    #[test]
+    fn test_do_expression() {
+        let n1 = parse_from_string(
+            "do {
+                123
+                abc
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            n1,
+            Node::Program(Program {
+                body: vec![Statement::Expression(Expression::BlockExpression(
+                    BlockExpression {
+                        is_explicit: true,
+                        body: vec![
+                            Expression::Literal(Literal::Integer(Integer {
+                                value: IntegerLiteral::new("123", None),
+                                range: new_range()
+                            })),
+                            Expression::Identifier(Identifier {
+                                dirs: vec![],
+                                name: "abc".to_string(),
+                                generics: vec![],
+                                range: new_range()
+                            }),
+                        ],
+                        range: new_range()
+                    }
+                ))],
+                disabled: vec![],
+                regions: vec![],
+                range: new_range()
+            })
+        );
+
+        assert_eq!(
+            n1.to_string(),
+            trim_left_margin(
+                "do {
+                    123
+                    abc
+                }\n"
+            )
+        );
+
+        // 测试表达式末尾添加逗号
+        let n2 = parse_from_string(&trim_left_margin(
+            "do {
+                123,
+                abc,
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n2.to_string(),
+            trim_left_margin(
+                "do {
+                    123
+                    abc
+                }\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_join_expression() {
+        let n1 = parse_from_string(
+            "join {
+                    123
+                    abc
+                }",
+        )
+        .unwrap();
+        assert_eq!(
+            n1.to_string(),
+            trim_left_margin(
+                "join {
+                    123
+                    abc
+                }\n"
+            )
+        );
+
+        // 测试表达式末尾添加逗号
+        let n2 = parse_from_string(&trim_left_margin(
+            "join {
+                123,
+                abc,
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n2.to_string(),
+            trim_left_margin(
+                "join {
+                    123
+                    abc
+                }\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_let_expression() {
+        let n1 = parse_from_string("let Int i=1").unwrap();
+        assert_eq!(n1.to_string(), "let Int i = 1\n");
+
+        // 省略数据类型
+        let n2 = parse_from_string("let i=100").unwrap();
+        assert_eq!(n2.to_string(), "let i = 100\n");
+
+        // 右手边值是一个表达式
+        let n3 = parse_from_string("let i=1+2*3").unwrap();
+        assert_eq!(n3.to_string(), "let i = (1 + (2 * 3))\n");
+
+        // 左手边值是一个元组
+        let n4 = parse_from_string("let (Int,Int) (x,y)=foo.point").unwrap();
+        assert_eq!(n4.to_string(), "let (Int, Int,) (x, y,) = (foo.point)\n");
+
+        // 左手边值是一个元组，省略数据类型
+        let n5 = parse_from_string("let (x,y)=foo.point").unwrap();
+        assert_eq!(n5.to_string(), "let (x, y,) = (foo.point)\n");
 
-    #[test]
-    fn test_binary_expression_additive() {
-        let n1 = parse_from_string("1+2").unwrap();
+        // 连续赋值
+        let n6 = parse_from_string("let a=let b=1").unwrap();
         assert_eq!(
-            n1,
+            n6,
             Node::Program(Program {
-                body: vec![Statement::Expression(Expression::BinaryExpression(
-                    BinaryExpression {
-                        operator: Token::Plus,
-                        left: Box::new(Expression::Literal(Literal::Integer(Integer {
-                            value: 1,
-                            range: new_range()
-                        }))),
-                        right: Box::new(Expression::Literal(Literal::Integer(Integer {
-                            value: 2,
+                body: vec![Statement::Expression(Expression::LetExpression(
+                    LetExpression {
+                        data_type: None,
+                        object: Box::new(Expression::Identifier(new_identifier("a"))),
+                        value: Box::new(Expression::LetExpression(LetExpression {
+                            data_type: None,
+                            object: Box::new(Expression::Identifier(new_identifier("b"))),
+                            value: Box::new(Expression::Literal(new_literal_integer(1))),
                             range: new_range()
-                        }))),
+                        })),
                         range: new_range()
                     }
                 ))],
+                disabled: vec![],
+                regions: vec![],
                 range: new_range()
             })
         );
-        assert_eq!(n1.to_string(), "(1 + 2)\n"); // Statement 以符号 '\n' 结尾
+        assert_eq!(n6.to_string(), "let a = let b = 1\n");
+    }
 
-        let n2 = parse_from_string("1+2+3").unwrap();
-        assert_eq!(n2.to_string(), "((1 + 2) + 3)\n");
+    #[test]
+    fn test_property_expression() {
+        // 属性设置
+        let n1 = parse_from_string("window.title := \"hello\"").unwrap();
+        assert_eq!(n1.to_string(), "(window.title) := \"hello\"\n");
+        assert_eq!(
+            desugar_first_expression(&n1),
+            "(!set)(window, #title, \"hello\")"
+        );
 
-        let n3 = parse_from_string("1.414+1.732").unwrap();
-        assert_eq!(n3.to_string(), "(1.414 + 1.732)\n");
+        // 属性链
+        let n2 = parse_from_string("frame.rect.left :=\n 10 + 1").unwrap();
+        assert_eq!(n2.to_string(), "((frame.rect).left) := (10 + 1)\n");
+        assert_eq!(
+            desugar_first_expression(&n2),
+            "(!set)((!get)(frame, #rect), #left, (10 + 1))"
+        );
 
-        // 测试复数和加法并存的情况
-        let n4 = parse_from_string("3+4i+9i").unwrap();
-        assert_eq!(n4.to_string(), "(3+4i + 0+9i)\n");
+        // 属性读取
+        let n3 = parse_from_string("let String title := window.title").unwrap();
+        assert_eq!(n3.to_string(), "let String title := (window.title)\n");
+        assert_eq!(
+            desugar_first_expression(&n3),
+            "let String title = (!get)(window, #title)"
+        );
+
+        // 省略数据类型
+        let n4 = parse_from_string("let left :=\n frame.rect.left").unwrap();
+        assert_eq!(n4.to_string(), "let left := ((frame.rect).left)\n");
+        assert_eq!(
+            desugar_first_expression(&n4),
+            "let left = (!get)((!get)(frame, #rect), #left)"
+        );
+
+        // 索引不是属性
+        let e1 = parse_from_string("items[0] := 1");
+        assert_eq!(e1.unwrap_err().code, DiagnosticCode::InvalidLeftHandSide);
+
+        let e2 = parse_from_string("let a := items[0]");
+        assert_eq!(e2.unwrap_err().code, DiagnosticCode::InvalidPropertyName);
+
+        let e3 = parse_from_string("let a := b");
+        assert_eq!(e3.unwrap_err().code, DiagnosticCode::InvalidPropertyName);
+    }
+
+    fn desugar_first_expression(node: &Node) -> String {
+        if let Node::Program(Program { body, .. }) = node {
+            match &body[0] {
+                Statement::Expression(Expression::PropertySetExpression(e)) => {
+                    e.desugar().to_string()
+                }
+                Statement::Expression(Expression::PropertyGetExpression(e)) => {
+                    e.desugar().to_string()
+                }
+                _ => panic!("expected property expression"),
+            }
+        } else {
+            panic!("expected program")
+        }
     }
 
     #[test]
-    fn test_binary_expression_precedence() {
-        let n1 = parse_from_string("1|2||3").unwrap();
-        assert_eq!(n1.to_string(), "(1 | (2 || 3))\n");
+    fn test_if_expression() {
+        let n1 = parse_from_string("if 1 then 2").unwrap();
+        assert_eq!(n1.to_string(), "if 1 then 2\n");
 
-        let n2 = parse_from_string("1||2&&3").unwrap();
-        assert_eq!(n2.to_string(), "(1 || (2 && 3))\n");
+        // 测试 else 子表达式
+        let n2 = parse_from_string("if 1 then 2 else 3").unwrap();
+        assert_eq!(n2.to_string(), "if 1 then 2 else 3\n");
 
-        let n3 = parse_from_string("1&&2==3").unwrap();
-        assert_eq!(n3.to_string(), "(1 && (2 == 3))\n");
+        // 测试子表达式为表达式
+        let n3 = parse_from_string("if a>b then a+1 else b+2").unwrap();
+        assert_eq!(n3.to_string(), "if (a > b) then (a + 1) else (b + 2)\n");
 
-        let n4 = parse_from_string("1==2>3").unwrap();
-        assert_eq!(n4.to_string(), "(1 == (2 > 3))\n");
+        // 测试 where 从属表达式
+        let n4 = parse_from_string("if a>b where let i=a+b then a+1 else b+2").unwrap();
+        assert_eq!(
+            n4.to_string(),
+            "if (a > b) where let i = (a + b) then (a + 1) else (b + 2)\n"
+        );
 
-        let n5 = parse_from_string("1>2:bit_or:3").unwrap();
-        assert_eq!(n5.to_string(), "(1 > (2 :bit_or: 3))\n");
+        // 测试换行
+        let n5 = parse_from_string(&trim_left_margin(
+            "if
+              a>b
+            then
+              a+1
+            else
+              b+2
+            ",
+        ))
+        .unwrap();
+        assert_eq!(n5.to_string(), "if (a > b) then (a + 1) else (b + 2)\n");
 
-        let n6 = parse_from_string("1:bit_and:2++3").unwrap();
-        assert_eq!(n6.to_string(), "(1 :bit_and: (2 ++ 3))\n");
+        // 测试换行以及 where 从属表达式
+        let n6 = parse_from_string(&trim_left_margin(
+            "if
+              a>b
+            where
+              let i=a+b
+            then
+              a+1
+            else
+              b+2
+            ",
+        ))
+        .unwrap();
+        assert_eq!(
+            n6.to_string(),
+            "if (a > b) where let i = (a + b) then (a + 1) else (b + 2)\n"
+        );
 
-        let n7 = parse_from_string("1++2+3").unwrap();
-        assert_eq!(n7.to_string(), "(1 ++ (2 + 3))\n");
+        // 测试表达式块
+        let n7 = parse_from_string(&trim_left_margin(
+            "if {
+              a>b
+            } where {
+              let i=a+b
+            } then {
+              a+1
+            } else {
+              b+2
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n7.to_string(),
+            trim_left_margin(
+                "if {
+                    (a > b)
+                } where {
+                    let i = (a + b)
+                } then {
+                    (a + 1)
+                } else {
+                    (b + 2)
+                }
+            "
+            )
+        );
 
-        let n8 = parse_from_string("1+2*3").unwrap();
-        assert_eq!(n8.to_string(), "(1 + (2 * 3))\n");
+        // 测试连续 if 表达式
+        let n8 = parse_from_string(&trim_left_margin(
+            "if a>90 then
+                a
+            else if a>80 then
+                b
+            else if a>60 then
+                c
+            else
+                d
+            ",
+        ))
+        .unwrap();
 
-        let n9 = parse_from_string("1*2??3").unwrap();
-        assert_eq!(n9.to_string(), "(1 * (2 ?? 3))\n");
+        assert_eq!(
+            n8.to_string(),
+            "if (a > 90) then a else (if (a > 80) then b else (if (a > 60) then c else d))\n"
+        );
 
-        let n10 = parse_from_string("1??2->3").unwrap();
-        assert_eq!(n10.to_string(), "(1 ?? (2 -> 3))\n");
+        // 测试嵌套表达式
+        let n9 = parse_from_string(&trim_left_margin(
+            "if
+              if a>b then true else false
+            then
+              if a>10 then 100 else 101
+            else
+              if b>20 then 200 else 202
+            ",
+        ))
+        .unwrap();
 
-        let n11 = parse_from_string("1->2&3").unwrap();
-        assert_eq!(n11.to_string(), "(1 -> (2 & 3))\n");
+        assert_eq!(
+            n9.to_string(),
+            vec![
+                "if (if (a > b) then true else false)",
+                "then (if (a > 10) then 100 else 101)",
+                "else (if (b > 20) then 200 else 202)\n"
+            ]
+            .join(" ")
+        );
+
+        // 测试 if let 表达式
+        let n10 = parse_from_string("if let i=1 then i+2 else 3").unwrap();
+        assert_eq!(n10.to_string(), "if let i = 1 then (i + 2) else 3\n");
+    }
+
+    #[test]
+    fn test_for_expression() {
+        let n1 = parse_from_string("for let i=1 i+1").unwrap();
+        assert_eq!(n1.to_string(), "for let i = 1 (i + 1)\n");
+
+        // 测试循环体为 `隠式 do 表达式`
+        let n2 = parse_from_string(&trim_left_margin(
+            "for let i=user {
+                i+1
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n2.to_string(),
+            trim_left_margin(
+                "for let i = user {
+                    (i + 1)
+                }
+                "
+            )
+        );
+
+        // 测试循环体为 `if 表达式`
+        let n3 = parse_from_string(&trim_left_margin(
+            "for let i=0 if i<10 then {
+                print(i)
+                next i+1
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n3.to_string(),
+            trim_left_margin(
+                "for let i = 0 if (i < 10) then {
+                    (print)(i)
+                    next (i + 1)
+                }
+                "
+            )
+        );
+
+        // 测试初始化表达式为元组赋值
+        let n4 = parse_from_string(&trim_left_margin(
+            "for let (sum, i)=(0, 1) if i<=100 then {
+                next (sum+i, i+1)
+            }else {
+                sum
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n4.to_string(),
+            trim_left_margin(
+                "for let (sum, i,) = (0, 1,) if (i <= 100) then {
+                    next ((sum + i), (i + 1),)
+                } else {
+                    sum
+                }
+                ",
+            )
+        );
     }
 
     #[test]
-    fn test_binary_expression_parenthesized() {
-        let n1 = parse_from_string("(123)").unwrap();
+    fn test_each_expression() {
+        let n1 = parse_from_string("each i in [1,2,3] writeLine(i)").unwrap();
+        assert_eq!(n1.to_string(), "each i in [1, 2, 3,] (writeLine)(i)\n");
+
+        // 测试循环体为 `隠式 do 表达式`
+        let n2 = parse_from_string(&trim_left_margin(
+            "each num in users {
+                writeLine (i+1)
+            }",
+        ))
+        .unwrap();
         assert_eq!(
-            n1,
-            Node::Program(Program {
-                body: vec![Statement::Expression(Expression::Literal(
-                    Literal::Integer(Integer {
-                        value: 123,
-                        range: new_range()
-                    })
-                ))],
-                range: new_range()
-            })
+            n2.to_string(),
+            trim_left_margin(
+                "each num in users {
+                    (writeLine)((i + 1))
+                }
+                "
+            )
         );
-        assert_eq!(n1.to_string(), "123\n");
-
-        let n2 = parse_from_string("(1+2)").unwrap();
-        assert_eq!(n2.to_string(), "(1 + 2)\n");
 
-        let n3 = parse_from_string("(1+2)*3").unwrap();
-        assert_eq!(n3.to_string(), "((1 + 2) * 3)\n");
+        // 测试循环体为 `if 表达式`
+        let n3 = parse_from_string(&trim_left_margin(
+            "each num in [1..100] if i:mod:2==0 then{
+                writeLineFormat(\"even number: {}\",num)
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n3.to_string(),
+            trim_left_margin(
+                "each num in [1..100,] if ((i :mod: 2) == 0) then {
+                    (writeLineFormat)(\"even number: {}\", num)
+                }
+                "
+            )
+        );
     }
 
     #[test]
-    fn test_binary_expression_associativitye() {
-        // 测试结合方向
-
-        // 操作符 `+` 从左向右结合
-        let n1 = parse_from_string("1+2+3").unwrap();
-        assert_eq!(n1.to_string(), "((1 + 2) + 3)\n");
+    fn test_branch_expression() {
+        let n1 = parse_from_string(&trim_left_margin(
+            "branch {
+                case 1:10
+                case 2:20
+                default: 30
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n1.to_string(),
+            trim_left_margin(
+                "branch {
+                    case 1: 10
+                    case 2: 20
+                    default: 30
+                }
+                "
+            )
+        );
 
-        // 操作符 `&` 从右向左结合
-        let n2 = parse_from_string("1&2&3").unwrap();
-        assert_eq!(n2.to_string(), "(1 & (2 & 3))\n");
-    }
+        // 测试复杂表达式
+        let n2 = parse_from_string(&trim_left_margin(
+            "let i=85
+            branch {
+                case i>90:100
+                case i>60: i+10
+                default: writeLine(\"failed\")
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n2.to_string(),
+            trim_left_margin(
+                "let i = 85
+                branch {
+                    case (i > 90): 100
+                    case (i > 60): (i + 10)
+                    default: (writeLine)(\"failed\")
+                }
+                "
+            )
+        );
 
-    // genernal expression
+        // 测试分支表达式末尾添加了逗号的情况
+        let n3 = parse_from_string(&trim_left_margin(
+            "branch {
+                case i>90:100,
+                case i>60: i+10,
+                default: writeLine(\"failed\"),
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n3.to_string(),
+            trim_left_margin(
+                "branch {
+                    case (i > 90): 100
+                    case (i > 60): (i + 10)
+                    default: (writeLine)(\"failed\")
+                }
+                "
+            )
+        );
 
-    #[test]
-    fn test_do_expression() {
-        let n1 = parse_from_string(
-            "do {
-                123
-                abc
+        // 测试表达式块
+        let n4 = parse_from_string(&trim_left_margin(
+            "branch {
+                case i>=60:{
+                    writeLine(\"pass\")
+                }
+                default: { writeLine(\"failed\") }
             }",
-        )
+        ))
         .unwrap();
         assert_eq!(
-            n1,
-            Node::Program(Program {
-                body: vec![Statement::Expression(Expression::BlockExpression(
-                    BlockExpression {
-                        is_explicit: true,
-                        body: vec![
-                            Expression::Literal(Literal::Integer(Integer {
-                                value: 123,
-                                range: new_range()
-                            })),
-                            Expression::Identifier(Identifier {
-                                dirs: vec![],
-                                name: "abc".to_string(),
-                                generics: vec![],
-                                range: new_range()
-                            }),
-                        ],
-                        range: new_range()
+            n4.to_string(),
+            trim_left_margin(
+                "branch {
+                    case (i >= 60): {
+                        (writeLine)(\"pass\")
                     }
-                ))],
-                range: new_range()
-            })
+                    default: {
+                        (writeLine)(\"failed\")
+                    }
+                }
+                "
+            )
+        );
+
+        // 测试 `where 从属表达式`
+        let n5 = parse_from_string(&trim_left_margin(
+            "branch where {
+                let i=10
+                let j=20
+            }{
+                case i>m where let m=foo(i,j):3
+                case i>n where {let n=bar(i,j)}:4
+                default:5
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n5.to_string(),
+            trim_left_margin(
+                "branch where {
+                    let i = 10
+                    let j = 20
+                } {
+                    case (i > m) where let m = (foo)(i, j): 3
+                    case (i > n) where {
+                        let n = (bar)(i, j)
+                    }: 4
+                    default: 5
+                }
+                "
+            )
         );
+    }
 
+    #[test]
+    fn test_match_expression() {
+        let n1 = parse_from_string(&trim_left_margin(
+            "match foo{
+                case 1:10
+                case 2:20
+                default:30
+            }",
+        ))
+        .unwrap();
         assert_eq!(
             n1.to_string(),
             trim_left_margin(
-                "do {
-                    123
-                    abc
-                }\n"
+                "match foo {
+                    case 1: 10
+                    case 2: 20
+                    default: 30
+                }
+                "
             )
         );
 
-        // 测试表达式末尾添加逗号
+        // 测试复杂的模式表达式
         let n2 = parse_from_string(&trim_left_margin(
-            "do {
-                123,
-                abc,
+            "match foo{
+                case (a,b,1,2):10
+                case [x,y,...]:20
+                case (m,n):30
+                case User{id:123, name}:40
+                case Point(456,top):50
+                default:60
+            }",
+        ))
+        .unwrap();
+        assert_eq!(
+            n2.to_string(),
+            trim_left_margin(
+                "match foo {
+                    case (a, b, 1, 2,): 10
+                    case [x, y, ...,]: 20
+                    case (m, n,): 30
+                    case User {
+                        id: 123
+                        name
+                    }: 40
+                    case (Point)(456, top): 50
+                    default: 60
+                }
+                "
+            )
+        );
+
+        // 测试 only 和 where 从属表达式
+        let n3 = parse_from_string(&trim_left_margin(
+            "match foo where let x=1{
+                case [1] only x>y where let y=2:10
+                case [2] only x>3:20
+                default:30
             }",
         ))
         .unwrap();
         assert_eq!(
-            n2.to_string(),
+            n3.to_string(),
             trim_left_margin(
-                "do {
-                    123
-                    abc
-                }\n"
+                "match foo where let x = 1 {
+                    case [1,] only (x > y) where let y = 2: 10
+                    case [2,] only (x > 3): 20
+                    default: 30
+                }
+                "
             )
         );
-    }
 
-    #[test]
-    fn test_join_expression() {
-        let n1 = parse_from_string(
-            "join {
-                    123
-                    abc
-                }",
-        )
+        // 测试变量
+        let n4 = parse_from_string(&trim_left_margin(
+            "match foo(123){
+                case t @ (1,2):10
+                case i @ only i>3:20
+                case m @:30
+                default:40
+            }",
+        ))
         .unwrap();
         assert_eq!(
-            n1.to_string(),
+            n4.to_string(),
             trim_left_margin(
-                "join {
-                    123
-                    abc
-                }\n"
+                "match (foo)(123) {
+                    case t @ (1, 2,): 10
+                    case i @ only (i > 3): 20
+                    case m @: 30
+                    default: 40
+                }
+                "
             )
         );
 
-        // 测试表达式末尾添加逗号
-        let n2 = parse_from_string(&trim_left_margin(
-            "join {
-                123,
-                abc,
+        // 测试其他类型的模式表达式
+        let n5 = parse_from_string(&trim_left_margin(
+            "match foo{
+                case in [1,2,3]:10
+                case into User user:20
+                case regular ~/abc/ (m,):30
+                case template \"id={id}\": 40
+                case n @ in [4,5,6,]: 50
+                default:60
             }",
         ))
         .unwrap();
         assert_eq!(
-            n2.to_string(),
+            n5.to_string(),
             trim_left_margin(
-                "join {
-                    123
-                    abc
-                }\n"
+                "match foo {
+                    case in [1, 2, 3,]: 10
+                    case into User user: 20
+                    case regular ~/abc/ (m,): 30
+                    case template \"id={id}\": 40
+                    case n @ in [4, 5, 6,]: 50
+                    default: 60
+                }
+                "
             )
         );
-    }
-
-    #[test]
-    fn test_let_expression() {
-        let n1 = parse_from_string("let Int i=1").unwrap();
-        assert_eq!(n1.to_string(), "let Int i = 1\n");
-
-        // 省略数据类型
-        let n2 = parse_from_string("let i=100").unwrap();
-        assert_eq!(n2.to_string(), "let i = 100\n");
-
-        // 右手边值是一个表达式
-        let n3 = parse_from_string("let i=1+2*3").unwrap();
-        assert_eq!(n3.to_string(), "let i = (1 + (2 * 3))\n");
-
-        // 左手边值是一个元组
-        let n4 = parse_from_string("let (Int,Int) (x,y)=foo.point").unwrap();
-        assert_eq!(n4.to_string(), "let (Int, Int,) (x, y,) = (foo.point)\n");
-
-        // 左手边值是一个元组，省略数据类型
-        let n5 = parse_from_string("let (x,y)=foo.point").unwrap();
-        assert_eq!(n5.to_string(), "let (x, y,) = (foo.point)\n");
 
-        // 连续赋值
-        let n6 = parse_from_string("let a=let b=1").unwrap();
+        // 测试 regular 模式表达式的绑定数量
+        let n6 = parse_from_string("match s {case regular ~/^(.+)@(.+)$/i (_, name, domain): 1}")
+            .unwrap();
         assert_eq!(
-            n6,
-            Node::Program(Program {
-                body: vec![Statement::Expression(Expression::LetExpression(
-                    LetExpression {
-                        data_type: None,
-                        object: Box::new(Expression::Identifier(new_identifier("a"))),
-                        value: Box::new(Expression::LetExpression(LetExpression {
-                            data_type: None,
-                            object: Box::new(Expression::Identifier(new_identifier("b"))),
-                            value: Box::new(Expression::Literal(new_literal_integer(1))),
-                            range: new_range()
-                        })),
-                        range: new_range()
-                    }
-                ))],
-                range: new_range()
-            })
+            n6.to_string(),
+            trim_left_margin(
+                "match s {
+                    case regular ~/^(.+)@(.+)$/i (_, name, domain,): 1
+                }
+                "
+            )
         );
-        assert_eq!(n6.to_string(), "let a = let b = 1\n");
-    }
-
-    #[test]
-    fn test_if_expression() {
-        let n1 = parse_from_string("if 1 then 2").unwrap();
-        assert_eq!(n1.to_string(), "if 1 then 2\n");
-
-        // 测试 else 子表达式
-        let n2 = parse_from_string("if 1 then 2 else 3").unwrap();
-        assert_eq!(n2.to_string(), "if 1 then 2 else 3\n");
 
-        // 测试子表达式为表达式
-        let n3 = parse_from_string("if a>b then a+1 else b+2").unwrap();
-        assert_eq!(n3.to_string(), "if (a > b) then (a + 1) else (b + 2)\n");
-
-        // 测试 where 从属表达式
-        let n4 = parse_from_string("if a>b where let i=a+b then a+1 else b+2").unwrap();
+        let e1 = parse_from_string("match s {case regular ~/^(.+)@(.+)$/ (name, domain): 1}");
+        let d1 = e1.unwrap_err();
+        assert_eq!(d1.code, DiagnosticCode::InvalidPattern);
         assert_eq!(
-            n4.to_string(),
-            "if (a > b) where let i = (a + b) then (a + 1) else (b + 2)\n"
+            &*d1.message,
+            "the regular pattern expression expects 3 bindings, found 2"
         );
+    }
 
-        // 测试换行
-        let n5 = parse_from_string(&trim_left_margin(
-            "if
-              a>b
-            then
-              a+1
-            else
-              b+2
-            ",
-        ))
-        .unwrap();
-        assert_eq!(n5.to_string(), "if (a > b) then (a + 1) else (b + 2)\n");
+    // statements
 
-        // 测试换行以及 where 从属表达式
-        let n6 = parse_from_string(&trim_left_margin(
-            "if
-              a>b
-            where
-              let i=a+b
-            then
-              a+1
-            else
-              b+2
-            ",
-        ))
-        .unwrap();
+    #[test]
+    fn test_function_declaration_statement() {
+        let n1 = parse_from_string("function foo(Int a, Int b) type Int = a+b").unwrap();
         assert_eq!(
-            n6.to_string(),
-            "if (a > b) where let i = (a + b) then (a + 1) else (b + 2)\n"
+            n1.to_string(),
+            "function foo (Int a, Int b) type Int = (a + b)\n"
         );
 
-        // 测试表达式块
-        let n7 = parse_from_string(&trim_left_margin(
-            "if {
-              a>b
-            } where {
-              let i=a+b
-            } then {
-              a+1
-            } else {
-              b+2
-            }",
-        ))
-        .unwrap();
+        // 函数体为 `隠式 do 表达式`
+        let n2 = parse_from_string("function foo(Int a, Int b) type Int {a+b}").unwrap();
         assert_eq!(
-            n7.to_string(),
+            n2.to_string(),
             trim_left_margin(
-                "if {
-                    (a > b)
-                } where {
-                    let i = (a + b)
-                } then {
-                    (a + 1)
-                } else {
-                    (b + 2)
+                "function foo (Int a, Int b) type Int {
+                    (a + b)
                 }
-            "
+                "
             )
         );
 
-        // 测试连续 if 表达式
-        let n8 = parse_from_string(&trim_left_margin(
-            "if a>90 then
-                a
-            else if a>80 then
-                b
-            else if a>60 then
-                c
-            else
-                d
-            ",
-        ))
-        .unwrap();
-
-        assert_eq!(
-            n8.to_string(),
-            "if (a > 90) then a else {if (a > 80) then b else {if (a > 60) then c else d}}\n"
-        );
-
-        // 测试嵌套表达式
-        let n9 = parse_from_string(&trim_left_margin(
-            "if
-              if a>b then true else false
-            then
-              if a>10 then 100 else 101
-            else
-              if b>20 then 200 else 202
-            ",
-        ))
-        .unwrap();
-
+        // 测试参数默认值
+        let n3 = parse_from_string("function foo(Int a=10, Int b=20) type Int {a+b}").unwrap();
         assert_eq!(
-            n9.to_string(),
-            vec![
-                "if {if (a > b) then true else false}",
-                "then {if (a > 10) then 100 else 101}",
-                "else {if (b > 20) then 200 else 202}\n"
-            ]
-            .join(" ")
-        );
-
-        // 测试 if let 表达式
-        let n10 = parse_from_string("if let i=1 then i+2 else 3").unwrap();
-        assert_eq!(n10.to_string(), "if let i = 1 then (i + 2) else 3\n");
-    }
+            n3.to_string(),
+            trim_left_margin(
+                "function foo (Int a = 10, Int b = 20) type Int {
+                    (a + b)
+                }
+                "
+            )
+        );
 
-    #[test]
-    fn test_for_expression() {
-        let n1 = parse_from_string("for let i=1 i+1").unwrap();
-        assert_eq!(n1.to_string(), "for let i = 1 (i + 1)\n");
+        // 省略返回值类型
+        let n4 = parse_from_string("function foo()=1+2").unwrap();
+        assert_eq!(
+            n4.to_string(),
+            trim_left_margin(
+                "function foo () = (1 + 2)
+                "
+            )
+        );
 
-        // 测试循环体为 `隠式 do 表达式`
-        let n2 = parse_from_string(&trim_left_margin(
-            "for let i=user {
-                i+1
-            }",
+        // 测试类型说明
+        let n5 = parse_from_string(&trim_left_margin(
+            "function bar (T a) type String which T:sign(Int)type String=conv(a)",
         ))
         .unwrap();
         assert_eq!(
-            n2.to_string(),
+            n5.to_string(),
             trim_left_margin(
-                "for let i = user {
-                    (i + 1)
-                }
+                "function bar (T a) type String which {
+                    T: sign (Int) type String
+                } = (conv)(a)
                 "
             )
         );
 
-        // 测试循环体为 `if 表达式`
-        let n3 = parse_from_string(&trim_left_margin(
-            "for let i=0 if i<10 then {
-                print(i)
-                next i+1
+        // 测试泛型
+        let n6 = parse_from_string(&trim_left_margin(
+            "function bar<T, E>(T a)type E which {
+                T: limit Display+Eq
+                E: String}{
+                let b=conv(a)
+                b++'.'
             }",
         ))
         .unwrap();
         assert_eq!(
-            n3.to_string(),
+            n6.to_string(),
             trim_left_margin(
-                "for let i = 0 if (i < 10) then {
-                    (print)(i)
-                    next (i + 1)
+                "function bar <T, E> (T a) type E which {
+                    T: limit Display + Eq
+                    E: String
+                } {
+                    let b = (conv)(a)
+                    (b ++ '.')
                 }
                 "
             )
         );
 
-        // 测试初始化表达式为元组赋值
-        let n4 = parse_from_string(&trim_left_margin(
-            "for let (sum, i)=(0, 1) if i<=100 then {
-                next (sum+i, i+1)
-            }else {
-                sum
-            }",
+        // 返回值类型写在函数名称之前
+        let n7 = parse_from_string("function Int max(Int a, Int b) = a").unwrap();
+        assert_eq!(n7.to_string(), "function max (Int a, Int b) type Int = a\n");
+
+        // 返回值类型不能重复指定
+        let d1 = parse_from_string("function Int max(Int a) type Int = a").unwrap_err();
+        assert_eq!(d1.code, DiagnosticCode::InvalidDataType);
+    }
+
+    #[test]
+    fn test_empty_function_declaration_statement() {
+        let n1 = parse_from_string("empty function foo(Int a, Int b) type Int").unwrap();
+        assert_eq!(
+            n1.to_string(),
+            "empty function foo (Int a, Int b) type Int\n"
+        );
+
+        // 无参数以及无返回值
+        let n2 = parse_from_string("empty function foo()\nfoo()").unwrap();
+        assert_eq!(n2.to_string(), "empty function foo ()\n(foo)()\n");
+
+        // 返回值类型写在函数名称之前
+        let n3 = parse_from_string("empty function Ticket get(Self self, Int id)").unwrap();
+        assert_eq!(
+            n3.to_string(),
+            "empty function get (Self self, Int id) type Ticket\n"
+        );
+
+        let n4 =
+            parse_from_string("empty function Result<Unit, Error> add(Self self, Int id)").unwrap();
+        assert_eq!(
+            n4.to_string(),
+            "empty function add (Self self, Int id) type Result<Unit, Error>\n"
+        );
+
+        // 泛型、which 从属表达式、文档注释以及换行
+        let n5 = parse_from_string(&trim_left_margin(
+            "'''write a line'''
+            empty function writeLine<D, W>(
+                D data,
+                W output,
+            )
+                type Int
+                which {
+                    D: limit Display
+                    W: limit Writer
+                }
+            let a = 1",
         ))
         .unwrap();
         assert_eq!(
-            n4.to_string(),
+            n5.to_string(),
             trim_left_margin(
-                "for let (sum, i,) = (0, 1,) if (i <= 100) then {
-                    next ((sum + i), (i + 1),)
-                } else {
-                    sum
+                "'''write a line'''
+                empty function writeLine <D, W> (D data, W output) type Int which {
+                D: limit Display
+                W: limit Writer
                 }
-                ",
+                let a = 1
+                "
             )
         );
+
+        // 参数必须有名称
+        let e1 = parse_from_string("empty function foo(Int, Int b)").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidParameter);
+        assert_eq!(e1.location.start, 22);
+
+        // 参数不支持默认值
+        let e2 = parse_from_string("empty function foo(Int a = 1)").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::InvalidParameter);
+        assert_eq!(
+            &*e2.message,
+            "parameters of an empty function can not have default values"
+        );
+        assert_eq!(e2.location.start, 25);
+
+        // 空函数没有函数主体
+        let e3 = parse_from_string("empty function foo() type Int = 1").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(&*e3.message, "an empty function can not have a body");
+        assert_eq!(e3.location.start, 30);
+
+        let e4 = parse_from_string("empty function foo() {1}").unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::UnexpectedToken);
+
+        // 重复指定返回值类型
+        let e5 = parse_from_string("empty function Int foo() type Int").unwrap_err();
+        assert_eq!(e5.code, DiagnosticCode::InvalidDataType);
+
+        let e6 = parse_from_string("empty function foo() bar").unwrap_err();
+        assert_eq!(e6.code, DiagnosticCode::ExpectedNewLine);
     }
 
     #[test]
-    fn test_each_expression() {
-        let n1 = parse_from_string("each i in [1,2,3] writeLine(i)").unwrap();
-        assert_eq!(n1.to_string(), "each i in [1, 2, 3,] (writeLine)(i)\n");
+    fn test_pattern_function_declaration_statement() {
+        let n1 = parse_from_string("pattern function check(List<Int> []) = writeLine(\"empty\")")
+            .unwrap();
+        assert_eq!(
+            n1.to_string(),
+            "pattern function check (List<Int> []) = (writeLine)(\"empty\")\n"
+        );
 
-        // 测试循环体为 `隠式 do 表达式`
+        // 变量、in 模式表达式以及 only/where 从属表达式
         let n2 = parse_from_string(&trim_left_margin(
-            "each num in users {
-                writeLine (i+1)
-            }",
+            "pattern function test(
+                Int i @ in [1..10],
+                Point (x, y) only x + y > c
+                    where let c = x - y,
+                String s @ into Email e
+            ) {i}",
         ))
         .unwrap();
         assert_eq!(
             n2.to_string(),
             trim_left_margin(
-                "each num in users {
-                    (writeLine)((i + 1))
+                "pattern function test (Int i @ in [1..10,], Point (x, y,) only ((x + y) > c) where let c = (x - y), String s @ into Email e) {
+                i
                 }
                 "
             )
         );
 
-        // 测试循环体为 `if 表达式`
-        let n3 = parse_from_string(&trim_left_margin(
-            "each num in [1..100] if i:mod:2==0 then{
-                writeLineFormat(\"even number: {}\",num)
-            }",
-        ))
-        .unwrap();
+        // 函数范围的 only 从属表达式
+        let n3 = parse_from_string("pattern function test(Int x, Int y) type Int only x > y = {x}")
+            .unwrap();
         assert_eq!(
             n3.to_string(),
             trim_left_margin(
-                "each num in [1..100,] if ((i :mod: 2) == 0) then {
-                    (writeLineFormat)(\"even number: {}\", num)
+                "pattern function test (Int x, Int y) type Int only (x > y) {
+                x
                 }
                 "
             )
         );
-    }
 
-    #[test]
-    fn test_branch_expression() {
-        let n1 = parse_from_string(&trim_left_margin(
-            "branch {
-                case 1:10
-                case 2:20
-                default: 30
-            }",
-        ))
-        .unwrap();
+        // only 从属表达式后面紧接着的花括号是函数主体
+        let n31 =
+            parse_from_string("pattern function test(Int x, Int y) only x > y { 1 }").unwrap();
         assert_eq!(
-            n1.to_string(),
+            n31.to_string(),
             trim_left_margin(
-                "branch {
-                    case 1: 10
-                    case 2: 20
-                    default: 30
+                "pattern function test (Int x, Int y) only (x > y) {
+                1
                 }
                 "
             )
         );
 
-        // 测试复杂表达式
-        let n2 = parse_from_string(&trim_left_margin(
-            "let i=85
-            branch {
-                case i>90:100
-                case i>60: i+10
-                default: writeLine(\"failed\")
-            }",
-        ))
+        let n32 = parse_from_string(
+            "pattern function test(Point p) only p == (Point {x: 1}) {p}\npattern function test(Point p) only [p.x, (p.y)] != [] {0}",
+        )
         .unwrap();
         assert_eq!(
-            n2.to_string(),
+            n32.to_string(),
             trim_left_margin(
-                "let i = 85
-                branch {
-                    case (i > 90): 100
-                    case (i > 60): (i + 10)
-                    default: (writeLine)(\"failed\")
+                "pattern function test (Point p) only (p == Point {
+                x: 1
+                }) {
+                p
+                }
+                pattern function test (Point p) only ([(p.x), (p.y),] != []) {
+                0
                 }
                 "
             )
         );
 
-        // 测试分支表达式末尾添加了逗号的情况
-        let n3 = parse_from_string(&trim_left_margin(
-            "branch {
-                case i>90:100,
-                case i>60: i+10,
-                default: writeLine(\"failed\"),
-            }",
+        // regular 和 template 模式表达式可以省略数据类型
+        let n4 = parse_from_string(
+            "pattern function parse(s @ regular ~/(\\d+)-(\\d+)/ (m, a, b), template \"id={id}\") = a",
+        )
+        .unwrap();
+        assert_eq!(
+            n4.to_string(),
+            "pattern function parse (s @ regular ~/(\\d+)-(\\d+)/ (m, a, b,), template \"id={id}\") = a\n"
+        );
+
+        // 同名的模式函数组合为一个逻辑上的函数
+        let n5 = parse_from_string(&trim_left_margin(
+            "pattern function check(List<Int> []) = 0
+            let a = 1
+            '''two elements'''
+            pattern function check(List<Int> [x, y]) = 2
+            pattern function other(Int i) = i
+            pattern function check(List<Int> list) = 3",
         ))
         .unwrap();
         assert_eq!(
-            n3.to_string(),
+            n5.to_string(),
             trim_left_margin(
-                "branch {
-                    case (i > 90): 100
-                    case (i > 60): (i + 10)
-                    default: (writeLine)(\"failed\")
-                }
+                "pattern function check (List<Int> []) = 0
+                '''two elements'''
+                pattern function check (List<Int> [x, y,]) = 2
+                pattern function check (List<Int> list) = 3
+                let a = 1
+                pattern function other (Int i) = i
                 "
             )
         );
 
-        // 测试表达式块
-        let n4 = parse_from_string(&trim_left_margin(
-            "branch {
-                case i>=60:{
-                    writeLine(\"pass\")
-                }
-                default: { writeLine(\"failed\") }
-            }",
+        if let Node::Program(Program { body, .. }) = &n5 {
+            assert_eq!(body.len(), 3);
+            if let Statement::PatternFunctionGroup(PatternFunctionGroup {
+                name,
+                declarations,
+                ..
+            }) = &body[0]
+            {
+                assert_eq!(name, "check");
+                assert_eq!(declarations.len(), 3);
+                assert_eq!(declarations[1].docs, vec!["two elements".to_string()]);
+            } else {
+                panic!("expected pattern function group");
+            }
+        }
+
+        // 参数不支持默认值
+        let e1 = parse_from_string("pattern function foo(Int a = 1) = a").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::InvalidParameter);
+        assert_eq!(
+            &*e1.message,
+            "parameters of a pattern function can not have default values"
+        );
+        assert_eq!(e1.location.start, 27);
+
+        // 缺少模式表达式
+        let e2 = parse_from_string("pattern function foo(Int) = 1").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::InvalidParameter);
+        assert_eq!(e2.location.start, 24);
+
+        let e3 = parse_from_string("pattern function foo(Int a Int b) = 1").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::UnclosedDelimiter);
+
+        // 同名模式函数的签名不一致
+        let e4 = parse_from_string(
+            "pattern function foo(Int a) = 1\npattern function foo(Int a, Int b) = 2",
+        )
+        .unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::MismatchedPatternFunction);
+        assert_eq!(
+            &*e4.message,
+            "the pattern function `foo` expects 1 parameters, found 2"
+        );
+        assert_eq!(e4.location.start, 32);
+        assert_eq!(e4.labels[0].location.start, 0);
+
+        let e5 = parse_from_string(
+            "pattern function foo(Int a) = 1\npattern function foo(String a) = 2",
+        )
+        .unwrap_err();
+        assert_eq!(
+            &*e5.message,
+            "the data type of parameter 1 of the pattern function `foo` does not match, expected `Int`, found `String`"
+        );
+
+        let e6 = parse_from_string(
+            "pattern function foo(Int a) type Int = 1\npattern function foo(Int 0) = 2",
+        )
+        .unwrap_err();
+        assert_eq!(e6.code, DiagnosticCode::MismatchedPatternFunction);
+
+        // 省略了数据类型的参数不检查数据类型
+        assert!(parse_from_string(
+            "pattern function foo(s @ template \"{x}\") = 1\npattern function foo(String s) = 2"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_use_statement() {
+        let n1 = parse_from_string("use std::List\nuse foo").unwrap();
+        assert_eq!(n1.to_string(), "use std::List\nuse foo\n");
+
+        // 子路径列表以及换行
+        let n2 = parse_from_string(&trim_left_margin(
+            "use foo::{
+                bar,
+                sub::{one, two},
+                three::baz,
+            }
+            let a = 1",
         ))
         .unwrap();
+        assert_eq!(
+            n2.to_string(),
+            "use foo::{bar, sub::{one, two}, three::baz}\nlet a = 1\n"
+        );
+
+        // 花括号之前的 `::` 可以省略
+        let n3 =
+            parse_from_string("use name::name{one, two, three::baz, four::{foo, bar}}").unwrap();
+        assert_eq!(
+            n3.to_string(),
+            "use name::name::{one, two, three::baz, four::{foo, bar}}\n"
+        );
+
+        if let Node::Program(Program { body, .. }) = &n3 {
+            if let Statement::UseStatement(u) = &body[0] {
+                assert_eq!(
+                    u.flatten()
+                        .into_iter()
+                        .map(|item| item.path)
+                        .collect::<Vec<Vec<String>>>(),
+                    vec![
+                        vec!["name", "name", "one"],
+                        vec!["name", "name", "two"],
+                        vec!["name", "name", "three", "baz"],
+                        vec!["name", "name", "four", "foo"],
+                        vec!["name", "name", "four", "bar"],
+                    ]
+                );
+            } else {
+                panic!("expected use statement");
+            }
+        }
+
+        // 别名
+        let n4 = parse_from_string(
+            "use RegBuilder::new as rb\nuse foo::{bar as one, sub::{two as three, four}}",
+        )
+        .unwrap();
         assert_eq!(
             n4.to_string(),
-            trim_left_margin(
-                "branch {
-                    case (i >= 60): {
-                        (writeLine)(\"pass\")
-                    }
-                    default: {
-                        (writeLine)(\"failed\")
-                    }
-                }
-                "
-            )
+            "use RegBuilder::new as rb\nuse foo::{bar as one, sub::{two as three, four}}\n"
         );
 
-        // 测试 `where 从属表达式`
-        let n5 = parse_from_string(&trim_left_margin(
-            "branch where {
-                let i=10
-                let j=20
-            }{
-                case i>m where let m=foo(i,j):3
-                case i>n where {let n=bar(i,j)}:4
-                default:5
-            }",
+        if let Node::Program(Program { body, .. }) = &n4 {
+            let items = body
+                .iter()
+                .flat_map(|statement| match statement {
+                    Statement::UseStatement(u) => u.flatten(),
+                    _ => panic!("expected use statement"),
+                })
+                .map(|item| (item.name().to_string(), item.path.join("::")))
+                .collect::<Vec<(String, String)>>();
+            assert_eq!(
+                items,
+                vec![
+                    ("rb".to_string(), "RegBuilder::new".to_string()),
+                    ("one".to_string(), "foo::bar".to_string()),
+                    ("three".to_string(), "foo::sub::two".to_string()),
+                    ("four".to_string(), "foo::sub::four".to_string()),
+                ]
+            );
+        }
+
+        // 不支持通配符
+        let e1 = parse_from_string("use foo::*").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(
+            &*e1.message,
+            "the use statement does not support glob imports"
+        );
+        assert_eq!(e1.location.start, 9);
+
+        let e2 = parse_from_string("use foo::{bar, baz} as one").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(&*e2.message, "a list of names can not have an alias");
+        assert_eq!(e2.location.start, 20);
+
+        let e3 = parse_from_string("use foo::{}").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::ExpectedIdentifier);
+
+        let e4 = parse_from_string("use foo::{bar baz}").unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::UnclosedDelimiter);
+
+        let e5 = parse_from_string("use foo::").unwrap_err();
+        assert_eq!(e5.code, DiagnosticCode::UnexpectedEndOfFile);
+
+        let e6 = parse_from_string("use foo::1").unwrap_err();
+        assert_eq!(e6.code, DiagnosticCode::ExpectedIdentifier);
+
+        // 子路径列表只能是名称路径的最后一项
+        let e7 = parse_from_string("use foo::{bar}::baz").unwrap_err();
+        assert_eq!(e7.code, DiagnosticCode::ExpectedNewLine);
+
+        let e8 = parse_from_string("use foo::bar as").unwrap_err();
+        assert_eq!(e8.code, DiagnosticCode::UnexpectedEndOfFile);
+
+        let e9 = parse_from_string("use foo::bar as baz::one").unwrap_err();
+        assert_eq!(e9.code, DiagnosticCode::ExpectedNewLine);
+    }
+
+    #[test]
+    fn test_const_statement() {
+        let n1 = parse_from_string("const Int Code = 123\nconst Name = \"foo\"").unwrap();
+        assert_eq!(
+            n1.to_string(),
+            "const Int Code = 123\nconst Name = \"foo\"\n"
+        );
+
+        let n2 = parse_from_string("const List<Int> Primes =\n [2, 3, 5]").unwrap();
+        assert_eq!(n2.to_string(), "const List<Int> Primes = [2, 3, 5,]\n");
+
+        // 常量组，成员之间以换行或者逗号分隔
+        let n3 = parse_from_string(&trim_left_margin(
+            "const Int ResponseCode {
+                Ok = 200
+                Moved = 301, Found = 302,
+                NotFound =
+                    404
+            }
+            writeLine(ResponseCode::Ok)",
         ))
         .unwrap();
         assert_eq!(
-            n5.to_string(),
+            n3.to_string(),
             trim_left_margin(
-                "branch where {
-                    let i = 10
-                    let j = 20
-                } {
-                    case (i > m) where let m = (foo)(i, j): 3
-                    case (i > n) where {
-                        let n = (bar)(i, j)
-                    }: 4
-                    default: 5
+                "const Int ResponseCode {
+                Ok = 200
+                Moved = 301
+                Found = 302
+                NotFound = 404
                 }
+                (writeLine)(ResponseCode::Ok)
                 "
             )
         );
+
+        // `组名::成员名` 指向常量组的成员
+        if let Node::Program(program) = &n3 {
+            let identifier = Identifier {
+                dirs: vec!["ResponseCode".to_string()],
+                generics: vec![],
+                name: "Found".to_string(),
+                range: new_range(),
+            };
+            assert_eq!(
+                program.resolve_const(&identifier).map(|e| e.to_string()),
+                Some("302".to_string())
+            );
+            assert_eq!(program.resolve_const(&new_identifier("ResponseCode")), None);
+        }
+
+        let n4 = parse_from_string("const Color {Red = 1, Green = 2}").unwrap();
+        assert_eq!(n4.to_string(), "const Color {\nRed = 1\nGreen = 2\n}\n");
+
+        let n5 = parse_from_string("const Int Empty {\n}").unwrap();
+        assert_eq!(n5.to_string(), "const Int Empty {\n}\n");
+
+        // 错误
+        let e1 = parse_from_string("const Int Code").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnexpectedEndOfFile);
+
+        let e2 = parse_from_string("const Int foo::Code = 1").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::ExpectedIdentifier);
+        assert_eq!(e2.location.start, 10);
+
+        let e3 = parse_from_string("const Code {Ok = 1 Moved = 2}").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::ExpectedNewLine);
+        assert_eq!(e3.location.start, 19);
+
+        let e4 = parse_from_string("const Code {Ok = 1\nOk = 2}").unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::DuplicateMember);
+        assert_eq!(
+            &*e4.message,
+            "the constant member `Ok` is defined multiple times"
+        );
+        assert_eq!(e4.location.start, 19);
+
+        let e5 = parse_from_string("const Code {1 = 2}").unwrap_err();
+        assert_eq!(e5.code, DiagnosticCode::ExpectedIdentifier);
+
+        let e6 = parse_from_string("const Code {Ok = 1").unwrap_err();
+        assert_eq!(e6.code, DiagnosticCode::UnexpectedEndOfFile);
+
+        let e7 = parse_from_string("const Code {Ok = 1\n").unwrap_err();
+        assert_eq!(
+            &*e7.message,
+            "unexpected end of file, expected the right brace symbol \"}\""
+        );
+
+        let e8 = parse_from_string("const Code = 1 2").unwrap_err();
+        assert_eq!(e8.code, DiagnosticCode::ExpectedNewLine);
     }
 
     #[test]
-    fn test_match_expression() {
+    fn test_struct_statement() {
+        // 成员之间以换行或者逗号分隔，成员可以指定默认值
         let n1 = parse_from_string(&trim_left_margin(
-            "match foo{
-                case 1:10
-                case 2:20
-                default:30
+            "struct User {
+                Int id
+                String name = \"foo\", Boolean checked
             }",
         ))
         .unwrap();
         assert_eq!(
             n1.to_string(),
-            trim_left_margin(
-                "match foo {
-                    case 1: 10
-                    case 2: 20
-                    default: 30
-                }
-                "
-            )
+            "struct User {\nInt id\nString name = \"foo\"\nBoolean checked\n}\n"
         );
 
-        // 测试复杂的模式表达式
+        // 数据验证约束、文档注释以及泛型
         let n2 = parse_from_string(&trim_left_margin(
-            "match foo{
-                case (a,b,1,2):10
-                case [x,y,...]:20
-                case (m,n):30
-                case User{id:123, name}:40
-                case Point(456,top):50
-                default:60
-            }",
+            "'''user'''
+            @validate(id > 0 && name != \"\")
+            @validate(id < 100)
+            struct User<T> {
+                '''the id'''
+                @validate(id > 0)
+                T id
+                String name
+            }
+            let a = 1",
         ))
         .unwrap();
         assert_eq!(
             n2.to_string(),
             trim_left_margin(
-                "match foo {
-                    case (a, b, 1, 2,): 10
-                    case [x, y, ...,]: 20
-                    case (m, n,): 30
-                    case User {
-                        id: 123
-                        name
-                    }: 40
-                    case (Point)(456, top): 50
-                    default: 60
+                "'''user'''
+                @validate(((id > 0) && (name != \"\")))
+                @validate((id < 100))
+                struct User <T> {
+                '''the id'''
+                @validate((id > 0))
+                T id
+                String name
                 }
+                let a = 1
                 "
             )
         );
 
-        // 测试 only 和 where 从属表达式
-        let n3 = parse_from_string(&trim_left_margin(
-            "match foo where let x=1{
-                case [1] only x>y where let y=2:10
-                case [2] only x>3:20
-                default:30
-            }",
-        ))
-        .unwrap();
+        if let Node::Program(Program { body, .. }) = &n2 {
+            if let Statement::MemberStructDeclaration(MemberStructDeclaration {
+                validates,
+                members,
+                ..
+            }) = &body[0]
+            {
+                assert_eq!(validates.len(), 2);
+                assert_eq!(members[0].validates.len(), 1);
+                assert_eq!(members[0].docs, vec!["the id".to_string()]);
+            } else {
+                panic!("expected member struct declaration");
+            }
+        }
+
+        // 一般结构体的单行形式
+        let n3 = parse_from_string("struct User(Int id, String name = \"x\")").unwrap();
         assert_eq!(
             n3.to_string(),
-            trim_left_margin(
-                "match foo where let x = 1 {
-                    case [1,] only (x > y) where let y = 2: 10
-                    case [2,] only (x > 3): 20
-                    default: 30
-                }
-                "
-            )
+            "struct User {\nInt id\nString name = \"x\"\n}\n"
         );
 
-        // 测试变量
-        let n4 = parse_from_string(&trim_left_margin(
-            "match foo(123){
-                case t @ (1,2):10
-                case i @ only i>3:20
-                case m @:30
-                default:40
-            }",
-        ))
-        .unwrap();
+        // 匿名成员结构体
+        let n4 = parse_from_string("struct Point(Int, Int)\nstruct Pair<T>(T, (T, Int))").unwrap();
         assert_eq!(
             n4.to_string(),
-            trim_left_margin(
-                "match (foo)(123) {
-                    case t @ (1, 2,): 10
-                    case i @ only (i > 3): 20
-                    case m @: 30
-                    default: 40
-                }
-                "
-            )
+            "struct Point (Int, Int)\nstruct Pair <T> (T, (T, Int,))\n"
         );
 
-        // 测试其他类型的模式表达式
-        let n5 = parse_from_string(&trim_left_margin(
-            "match foo{
-                case in [1,2,3]:10
-                case into User user:20
-                case regular ~/abc/ (m,):30
-                case template \"id={id}\": 40
-                case n @ in [4,5,6,]: 50
-                default:60
-            }",
-        ))
-        .unwrap();
+        // 无成员结构体
+        let n5 = parse_from_string("struct Writer {}\nstruct Marker<T>()").unwrap();
+        assert_eq!(n5.to_string(), "struct Writer {}\nstruct Marker <T> {}\n");
+
+        // 错误
+        let e1 = parse_from_string("struct Writer\n").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnexpectedEndOfFile);
+
+        let e2 = parse_from_string("struct User {Int id Int b}").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::ExpectedNewLine);
+        assert_eq!(e2.location.start, 20);
+
+        let e3 = parse_from_string("struct User {Int id, String id}").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::DuplicateMember);
         assert_eq!(
-            n5.to_string(),
-            trim_left_margin(
-                "match foo {
-                    case in [1, 2, 3,]: 10
-                    case into User user: 20
-                    case regular ~/abc/ (m,): 30
-                    case template \"id={id}\": 40
-                    case n @ in [4, 5, 6,]: 50
-                    default: 60
-                }
-                "
-            )
+            &*e3.message,
+            "the struct member `id` is defined multiple times"
         );
-    }
+        assert_eq!(e3.location.start, 21);
 
-    // statements
+        let e4 = parse_from_string("struct User(Int id, Int)").unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::ExpectedIdentifier);
+        assert_eq!(e4.location.start, 23);
 
-    #[test]
-    fn test_function_declaration_statement() {
-        let n1 = parse_from_string("function foo(Int a, Int b) type Int = a+b").unwrap();
+        let e5 = parse_from_string("struct User(Int, Int b)").unwrap_err();
+        assert_eq!(e5.code, DiagnosticCode::UnexpectedToken);
         assert_eq!(
-            n1.to_string(),
-            "function foo (Int a, Int b) type Int = (a + b)\n"
+            &*e5.message,
+            "the members of a tuple struct can not have names"
         );
 
-        // 函数体为 `隠式 do 表达式`
-        let n2 = parse_from_string("function foo(Int a, Int b) type Int {a+b}").unwrap();
+        let e6 = parse_from_string("@validate(true)\nstruct P(Int, Int)").unwrap_err();
         assert_eq!(
-            n2.to_string(),
-            trim_left_margin(
-                "function foo (Int a, Int b) type Int {
-                    (a + b)
-                }
-                "
-            )
+            &*e6.message,
+            "only structs with named members can have validation constraints"
         );
 
-        // 测试参数默认值
-        let n3 = parse_from_string("function foo(Int a=10, Int b=20) type Int {a+b}").unwrap();
+        let e7 = parse_from_string("@foo(1)\nstruct P {Int x}").unwrap_err();
+        assert_eq!(e7.code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(e7.location.start, 1);
+
+        let e8 = parse_from_string("struct User {\nInt id\n").unwrap_err();
+        assert_eq!(e8.code, DiagnosticCode::UnexpectedEndOfFile);
+
+        let e9 = parse_from_string("struct foo::User {}").unwrap_err();
+        assert_eq!(e9.code, DiagnosticCode::ExpectedIdentifier);
+
+        let e10 = parse_from_string("struct User {} 1").unwrap_err();
+        assert_eq!(e10.code, DiagnosticCode::ExpectedNewLine);
+    }
+
+    #[test]
+    fn test_document_comment() {
+        let n1 = parse_from_string(&trim_left_margin(
+            "'''
+            foo
+            '''
+            '''bar'''
+
+            function f() = 1",
+        ))
+        .unwrap();
+
+        if let Node::Program(Program { body, .. }) = &n1 {
+            if let Statement::FunctionDeclaration(FunctionDeclaration { docs, .. }) = &body[0] {
+                assert_eq!(docs, &vec!["\nfoo\n".to_string(), "bar".to_string()]);
+            } else {
+                panic!("expected function declaration");
+            }
+        }
+
         assert_eq!(
-            n3.to_string(),
+            n1.to_string(),
             trim_left_margin(
-                "function foo (Int a = 10, Int b = 20) type Int {
-                    (a + b)
-                }
+                "'''
+                foo
+                '''
+                '''bar'''
+                function f () = 1
                 "
             )
         );
 
-        // 省略返回值类型
-        let n4 = parse_from_string("function foo()=1+2").unwrap();
+        // 不位于声明语句之前的文档注释将被忽略
+        let n2 = parse_from_string("'''foo'''\nlet a = 1 '''bar'''\nlet b = (1, '''baz'''\n 2)")
+            .unwrap();
+        assert_eq!(n2.to_string(), "let a = 1\nlet b = (1, 2,)\n");
+
+        // 表达式当中的文档注释
+        let n3 = parse_from_string(
+            "let a = 1 '''x''' + 2
+let b = -'''y''' a",
+        )
+        .unwrap();
+        assert_eq!(n3.to_string(), "let a = (1 + 2)\nlet b = -a\n");
+
+        let n4 = parse_from_string("foo('''x''' 1, '''y'''\n2) '''z''' ++ [3]").unwrap();
+        assert_eq!(n4.to_string(), "((foo)(1, 2) ++ [3,])\n");
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        // 不是期望的 token
+        let e1 = parse_from_string("let a = (1, 2").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnexpectedEndOfFile);
         assert_eq!(
-            n4.to_string(),
-            trim_left_margin(
-                "function foo () = (1 + 2)
-                "
-            )
+            e1.location,
+            Location {
+                file_id: 0,
+                start: 13,
+                end: 13
+            }
         );
 
-        // 测试类型说明
-        let n5 = parse_from_string(&trim_left_margin(
-            "function bar (T a) type String which T:sign(Int)type String=conv(a)",
-        ))
-        .unwrap();
+        let e2 = parse_from_string("let a = (1 2)").unwrap_err();
+        assert_eq!(e2.code, DiagnosticCode::UnclosedDelimiter);
         assert_eq!(
-            n5.to_string(),
-            trim_left_margin(
-                "function bar (T a) type String which {
-                    T: sign (Int) type String
-                } = (conv)(a)
-                "
-            )
+            e2.location,
+            Location {
+                file_id: 0,
+                start: 11,
+                end: 12
+            }
         );
 
-        // 测试泛型
-        let n6 = parse_from_string(&trim_left_margin(
-            "function bar<T, E>(T a)type E which {
-                T: limit Display+Eq
-                E: String}{
-                let b=conv(a)
-                b++'.'
-            }",
+        let e3 = parse_from_string("let Int a 1").unwrap_err();
+        assert_eq!(e3.code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(e3.location.start, 10);
+
+        let e4 = parse_from_string("a.\"b\"").unwrap_err();
+        assert_eq!(e4.code, DiagnosticCode::InvalidPropertyName);
+        assert_eq!(e4.location.start, 2);
+
+        // 词法分析的错误
+        let e5 = parse_from_string("let a = \"foo").unwrap_err();
+        assert_eq!(e5.code, DiagnosticCode::UnterminatedString);
+        assert_eq!(e5.code.as_str(), "E0106");
+    }
+
+    #[test]
+    fn test_disable_structural_comment() {
+        // 注释掉整个 if 表达式
+        let n1 = parse_from_string(&trim_left_margin(
+            "a
+            #disable
+            if b then
+                c
+            else
+                d
+            e",
         ))
         .unwrap();
-        assert_eq!(
-            n6.to_string(),
-            trim_left_margin(
-                "function bar <T, E> (T a) type E which {
-                    T: limit Display + Eq
-                    E: String
-                } {
-                    let b = (conv)(a)
-                    (b ++ '.')
+        assert_eq!(n1.to_string(), "a\ne\n");
+
+        if let Node::Program(Program { disabled, .. }) = &n1 {
+            assert_eq!(disabled.len(), 1);
+            assert_eq!(
+                disabled[0].location,
+                Location {
+                    file_id: 0,
+                    start: 2,
+                    end: 29
                 }
-                "
-            )
+            );
+            assert_eq!(disabled[0].token_details[0].token, Token::Disable);
+            assert_eq!(disabled[0].token_details.len(), 11);
+        } else {
+            panic!("expected a program");
+        }
+
+        // 注释掉声明语句
+        let n2 = parse_from_string("#disable function foo()=1\nbar").unwrap();
+        assert_eq!(n2.to_string(), "bar\n");
+
+        // 注释掉带有数据验证约束的结构体
+        let n21 = parse_from_string("#disable\n@validate(a > 0)\nstruct A {Int a}\nbar").unwrap();
+        assert_eq!(n21.to_string(), "bar\n");
+        if let Node::Program(Program { disabled, .. }) = &n21 {
+            assert_eq!(disabled.len(), 1);
+            assert_eq!(disabled[0].token_details.len(), 16);
+        }
+
+        // 注释掉表达式块里的表达式
+        let n3 = parse_from_string("do {\na\n#disable b+1\nc\n}").unwrap();
+        assert_eq!(
+            n3.to_string(),
+            parse_from_string("do {\na\nc\n}").unwrap().to_string()
+        );
+
+        // 注释掉参数列表里的一项
+        let n4 = parse_from_string("foo(1, #disable 2, 3)").unwrap();
+        assert_eq!(n4.to_string(), "(foo)(1, 3)\n");
+
+        // 嵌套的结构注释合并到外层
+        let n5 = parse_from_string("#disable do {\n#disable a\nb\n}\nc").unwrap();
+        assert_eq!(n5.to_string(), "c\n");
+        if let Node::Program(Program { disabled, .. }) = &n5 {
+            assert_eq!(disabled.len(), 1);
+            assert_eq!(disabled[0].token_details.len(), 10);
+        }
+
+        // 中文形式
+        let n6 = parse_from_string("#禁用 a\nb").unwrap();
+        assert_eq!(n6.to_string(), "b\n");
+
+        // 缺少被注释的代码
+        let e1 = parse_from_string("a\n#disable").unwrap_err();
+        assert_eq!(e1.code, DiagnosticCode::UnexpectedEndOfFile);
+    }
+
+    #[test]
+    fn test_region_comment() {
+        let text = trim_left_margin(
+            "a
+            // 计算
+            // 第一步
+            // ===
+            b
+            c
+            // ---
+            d",
         );
+        let n1 = parse_from_string(&text).unwrap();
+        assert_eq!(n1.to_string(), "a\nb\nc\nd\n");
+
+        if let Node::Program(Program { regions, .. }) = &n1 {
+            assert_eq!(
+                regions,
+                &vec![Region {
+                    title: "计算".to_string(),
+                    description: "第一步".to_string(),
+                    range: Range {
+                        file_id: 0,
+                        start: text.find('b').unwrap(),
+                        end: text.find('c').unwrap() + 1
+                    }
+                }]
+            );
+        } else {
+            panic!("expected a program");
+        }
+
+        // 嵌套的范围，以及缺少结束横线的范围
+        let text = "// x\n// ===\na\n// ===\nb\n// ---\nc";
+        let n2 = parse_from_string(text).unwrap();
+        if let Node::Program(Program { regions, .. }) = &n2 {
+            assert_eq!(regions.len(), 2);
+            assert_eq!(regions[0].title, "x");
+            assert_eq!(regions[0].range.start, text.find('a').unwrap());
+            assert_eq!(regions[0].range.end, text.len());
+            assert_eq!(regions[1].title, "");
+            assert_eq!(regions[1].range.start, text.find('b').unwrap());
+            assert_eq!(regions[1].range.end, text.find('b').unwrap() + 1);
+        } else {
+            panic!("expected a program");
+        }
     }
 }
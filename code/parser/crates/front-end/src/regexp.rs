@@ -0,0 +1,766 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+// 正则表达式字面量 `~/.../flags` 的语法检查
+//
+// 在编译期把正则表达式解析为语法树，以便尽早发现错误的正则表达式，
+// 比如未闭合的分组、无效的转义字符以及无效的重复次数等。
+// 语法大致跟 Rust 的 regex 相同，但不支持内联标志 `(?i)` 以及 Unicode 类别 `\p{...}`。
+
+// 正则表达式可用的标志
+//
+// - i 忽略大小写
+// - m 多行模式，`^` 和 `$` 匹配每一行的开始和结束
+// - s 允许 `.` 匹配换行符
+//
+// 注：源文件头部的版权声明使用 `/** ... */` 注释，它不是这个常量的文档注释
+#[allow(clippy::empty_line_after_doc_comments)]
+pub const REGEXP_FLAGS: &str = "ims";
+
+// 单次重复的最大次数
+const MAX_REPETITION: u32 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegexpNode {
+    Empty,
+    Char(char),
+    AnyChar,                      // .
+    StartOfLine,                  // ^
+    EndOfLine,                    // $
+    WordBoundary(bool),           // \b 以及 \B（false）
+    PerlClass(PerlClass),         // \d \w \s 以及 \D \W \S
+    CharClass(CharClass),         // [...]
+    Group(Group),                 // (...)
+    Repetition(Repetition),       // a* a+ a? a{n,m}
+    Concat(Vec<RegexpNode>),      // ab
+    Alternation(Vec<RegexpNode>), // a|b
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PerlClassKind {
+    Digit, // \d
+    Word,  // \w
+    Space, // \s
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerlClass {
+    pub kind: PerlClassKind,
+    pub is_negated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharClass {
+    pub is_negated: bool,
+    pub items: Vec<CharClassItem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CharClassItem {
+    Char(char),
+    Range(char, char), // a-z
+    PerlClass(PerlClass),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub is_capturing: bool,
+    pub name: Option<String>, // 命名分组 `(?<name>...)` 或者 `(?P<name>...)`
+    pub body: Box<RegexpNode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repetition {
+    pub node: Box<RegexpNode>,
+    pub min: u32,
+    pub max: Option<u32>, // None 表示不限次数
+    pub is_greedy: bool,  // 后面加上 `?` 表示非贪婪
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regexp {
+    pub root: RegexpNode,
+    pub group_count: usize, // 捕获分组的数量（不包括整个匹配）
+}
+
+// 正则表达式的错误，start 和 end 为相对于正则表达式文本开始位置的偏移量（字节数）
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexpError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn parse_regexp(pattern: &str) -> Result<Regexp, RegexpError> {
+    let mut parser = RegexpParser {
+        chars: pattern.char_indices().collect(),
+        pos: 0,
+        length: pattern.len(),
+        group_count: 0,
+    };
+
+    let root = parser.parse_alternation()?;
+
+    // 所有字符都应该已经消耗，剩余的只可能是多余的 `)`
+    if let Some(&(offset, c)) = parser.chars.get(parser.pos) {
+        return Err(new_regexp_error(
+            "unopened group",
+            offset,
+            offset + c.len_utf8(),
+        ));
+    }
+
+    Ok(Regexp {
+        root,
+        group_count: parser.group_count,
+    })
+}
+
+// 检查正则表达式的标志，返回 Err 时的值为无效标志相对于标志文本开始位置的偏移量
+pub fn check_regexp_flags(flags: &str) -> Result<(), RegexpError> {
+    for (offset, c) in flags.char_indices() {
+        if !REGEXP_FLAGS.contains(c) {
+            return Err(new_regexp_error(
+                &format!("invalid regular expression flag '{}'", c),
+                offset,
+                offset + c.len_utf8(),
+            ));
+        }
+
+        if flags[..offset].contains(c) {
+            return Err(new_regexp_error(
+                &format!("duplicate regular expression flag '{}'", c),
+                offset,
+                offset + c.len_utf8(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn new_regexp_error(message: &str, start: usize, end: usize) -> RegexpError {
+    RegexpError {
+        message: message.to_string(),
+        start,
+        end,
+    }
+}
+
+struct RegexpParser {
+    chars: Vec<(usize, char)>,
+    pos: usize,    // 当前字符在 chars 里的索引
+    length: usize, // 正则表达式文本的长度（字节数）
+    group_count: usize,
+}
+
+impl RegexpParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|(_, c)| *c)
+    }
+
+    // 当前字符的偏移量（字节数）
+    fn offset(&self) -> usize {
+        self.chars
+            .get(self.pos)
+            .map_or(self.length, |(offset, _)| *offset)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn is_char(&self, expected: char) -> bool {
+        self.peek() == Some(expected)
+    }
+
+    fn parse_alternation(&mut self) -> Result<RegexpNode, RegexpError> {
+        let mut branches = vec![self.parse_concat()?];
+
+        while self.is_char('|') {
+            self.next();
+            branches.push(self.parse_concat()?);
+        }
+
+        if branches.len() == 1 {
+            Ok(branches.remove(0))
+        } else {
+            Ok(RegexpNode::Alternation(branches))
+        }
+    }
+
+    fn parse_concat(&mut self) -> Result<RegexpNode, RegexpError> {
+        let mut nodes = vec![];
+
+        while let Some(c) = self.peek() {
+            match c {
+                '|' | ')' => break,
+                '*' | '+' | '?' | '{' => {
+                    // 前面的节点已经消耗了它的重复符号，所以这里的重复符号缺少被重复的表达式
+                    let start = self.offset();
+                    return Err(new_regexp_error(
+                        "repetition operator missing expression",
+                        start,
+                        start + 1,
+                    ));
+                }
+                _ => {
+                    let atom = self.parse_atom()?;
+                    nodes.push(self.parse_repetition(atom)?);
+                }
+            }
+        }
+
+        match nodes.len() {
+            0 => Ok(RegexpNode::Empty),
+            1 => Ok(nodes.remove(0)),
+            _ => Ok(RegexpNode::Concat(nodes)),
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<RegexpNode, RegexpError> {
+        let start = self.offset();
+
+        match self.next() {
+            Some('.') => Ok(RegexpNode::AnyChar),
+            Some('^') => Ok(RegexpNode::StartOfLine),
+            Some('$') => Ok(RegexpNode::EndOfLine),
+            Some('(') => self.continue_parse_group(start),
+            Some('[') => self.continue_parse_char_class(start),
+            Some('\\') => self.continue_parse_escape(start),
+            Some(c) => Ok(RegexpNode::Char(c)),
+            None => unreachable!(),
+        }
+    }
+
+    fn parse_repetition(&mut self, atom: RegexpNode) -> Result<RegexpNode, RegexpError> {
+        let start = self.offset();
+
+        let (min, max) = match self.peek() {
+            Some('*') => {
+                self.next();
+                (0, None)
+            }
+            Some('+') => {
+                self.next();
+                (1, None)
+            }
+            Some('?') => {
+                self.next();
+                (0, Some(1))
+            }
+            Some('{') => {
+                self.next();
+                self.continue_parse_counted_repetition(start)?
+            }
+            _ => return Ok(atom),
+        };
+
+        // 非贪婪重复 `*?`、`+?` 等
+        let is_greedy = if self.is_char('?') {
+            self.next();
+            false
+        } else {
+            true
+        };
+
+        Ok(RegexpNode::Repetition(Repetition {
+            node: Box::new(atom),
+            min,
+            max,
+            is_greedy,
+        }))
+    }
+
+    fn continue_parse_counted_repetition(
+        &mut self,
+        start: usize,
+    ) -> Result<(u32, Option<u32>), RegexpError> {
+        // {n} {n,} {n,m}
+        //  ^--- 当前所处的位置
+
+        let min = self.parse_repetition_count(start)?;
+
+        let max = if self.is_char(',') {
+            self.next();
+            if self.is_char('}') {
+                None
+            } else {
+                Some(self.parse_repetition_count(start)?)
+            }
+        } else {
+            Some(min)
+        };
+
+        if !self.is_char('}') {
+            return Err(self.new_unclosed_repetition_error(start));
+        }
+        self.next();
+
+        let end = self.offset();
+
+        if matches!(max, Some(m) if m < min) {
+            return Err(new_regexp_error(
+                "invalid repetition range, the minimum is greater than the maximum",
+                start,
+                end,
+            ));
+        }
+
+        if min > MAX_REPETITION || matches!(max, Some(m) if m > MAX_REPETITION) {
+            return Err(new_regexp_error(
+                &format!("the repetition count exceeds the limit {}", MAX_REPETITION),
+                start,
+                end,
+            ));
+        }
+
+        Ok((min, max))
+    }
+
+    fn parse_repetition_count(&mut self, start: usize) -> Result<u32, RegexpError> {
+        let mut value: Option<u32> = None;
+
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            self.next();
+            // 超出范围的数值由调用者的上限检查报告
+            value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+
+        value.ok_or_else(|| self.new_unclosed_repetition_error(start))
+    }
+
+    fn new_unclosed_repetition_error(&self, start: usize) -> RegexpError {
+        new_regexp_error(
+            "invalid repetition, expected `{n}`, `{n,}` or `{n,m}`",
+            start,
+            self.offset().max(start + 1),
+        )
+    }
+
+    fn continue_parse_group(&mut self, start: usize) -> Result<RegexpNode, RegexpError> {
+        // (...)
+        //  ^--- 当前所处的位置
+
+        let (is_capturing, name) = if self.is_char('?') {
+            self.next();
+            match self.next() {
+                Some(':') => (false, None),
+                Some('<') => (true, Some(self.continue_parse_group_name(start)?)),
+                Some('P') if self.is_char('<') => {
+                    self.next();
+                    (true, Some(self.continue_parse_group_name(start)?))
+                }
+                _ => {
+                    return Err(new_regexp_error(
+                        "unsupported group syntax, expected `(?:...)` or `(?<name>...)`",
+                        start,
+                        self.offset(),
+                    ));
+                }
+            }
+        } else {
+            (true, None)
+        };
+
+        if is_capturing {
+            self.group_count += 1;
+        }
+
+        let body = self.parse_alternation()?;
+
+        if !self.is_char(')') {
+            return Err(new_regexp_error("unclosed group", start, start + 1));
+        }
+        self.next();
+
+        Ok(RegexpNode::Group(Group {
+            is_capturing,
+            name,
+            body: Box::new(body),
+        }))
+    }
+
+    fn continue_parse_group_name(&mut self, start: usize) -> Result<String, RegexpError> {
+        // (?<name>...)
+        //     ^--- 当前所处的位置
+
+        let mut name = String::new();
+
+        loop {
+            match self.next() {
+                Some('>') => break,
+                Some(c) if c == '_' || c.is_alphanumeric() => name.push(c),
+                _ => {
+                    return Err(new_regexp_error(
+                        "invalid group name, expected `(?<name>...)`",
+                        start,
+                        self.offset(),
+                    ))
+                }
+            }
+        }
+
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(new_regexp_error("invalid group name", start, self.offset()));
+        }
+
+        Ok(name)
+    }
+
+    fn continue_parse_char_class(&mut self, start: usize) -> Result<RegexpNode, RegexpError> {
+        // [...]
+        //  ^--- 当前所处的位置
+
+        let is_negated = if self.is_char('^') {
+            self.next();
+            true
+        } else {
+            false
+        };
+
+        let mut items = vec![];
+
+        loop {
+            let item_start = self.offset();
+
+            let item = match self.next() {
+                Some(']') => break,
+                Some('\\') => self.continue_parse_class_escape(item_start)?,
+                Some(c) => CharClassItem::Char(c),
+                None => {
+                    return Err(new_regexp_error(
+                        "unclosed character class",
+                        start,
+                        start + 1,
+                    ))
+                }
+            };
+
+            // 字符范围 `a-z`，但结尾的 `-`（比如 `[a-]`）是普通字符
+            let is_range =
+                self.is_char('-') && !matches!(self.chars.get(self.pos + 1), Some((_, ']')) | None);
+
+            if let (true, CharClassItem::Char(from)) = (is_range, &item) {
+                self.next();
+                let to_start = self.offset();
+                let to = match self.next() {
+                    Some('\\') => self.continue_parse_class_escape(to_start)?,
+                    Some(c) => CharClassItem::Char(c),
+                    None => unreachable!(),
+                };
+
+                match to {
+                    CharClassItem::Char(to) if to >= *from => {
+                        items.push(CharClassItem::Range(*from, to));
+                    }
+                    CharClassItem::Char(_) => {
+                        return Err(new_regexp_error(
+                            "invalid character class range, the start is greater than the end",
+                            item_start,
+                            self.offset(),
+                        ));
+                    }
+                    _ => {
+                        return Err(new_regexp_error(
+                            "invalid character class range",
+                            item_start,
+                            self.offset(),
+                        ));
+                    }
+                }
+            } else {
+                items.push(item);
+            }
+        }
+
+        if items.is_empty() {
+            return Err(new_regexp_error(
+                "empty character class",
+                start,
+                self.offset(),
+            ));
+        }
+
+        Ok(RegexpNode::CharClass(CharClass { is_negated, items }))
+    }
+
+    fn continue_parse_class_escape(&mut self, start: usize) -> Result<CharClassItem, RegexpError> {
+        match self.continue_parse_escape(start)? {
+            RegexpNode::Char(c) => Ok(CharClassItem::Char(c)),
+            RegexpNode::PerlClass(p) => Ok(CharClassItem::PerlClass(p)),
+            _ => Err(new_regexp_error(
+                "invalid escape in character class",
+                start,
+                self.offset(),
+            )),
+        }
+    }
+
+    fn continue_parse_escape(&mut self, start: usize) -> Result<RegexpNode, RegexpError> {
+        // \x
+        //  ^--- 当前所处的位置
+
+        let c = match self.next() {
+            Some(c) => c,
+            None => {
+                return Err(new_regexp_error("incomplete escape", start, start + 1));
+            }
+        };
+
+        let node = match c {
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' => {
+                let kind = match c.to_ascii_lowercase() {
+                    'd' => PerlClassKind::Digit,
+                    'w' => PerlClassKind::Word,
+                    _ => PerlClassKind::Space,
+                };
+                RegexpNode::PerlClass(PerlClass {
+                    kind,
+                    is_negated: c.is_ascii_uppercase(),
+                })
+            }
+            'b' => RegexpNode::WordBoundary(true),
+            'B' => RegexpNode::WordBoundary(false),
+            'n' => RegexpNode::Char('\n'),
+            'r' => RegexpNode::Char('\r'),
+            't' => RegexpNode::Char('\t'),
+            'f' => RegexpNode::Char('\x0c'),
+            'v' => RegexpNode::Char('\x0b'),
+            '0' => RegexpNode::Char('\0'),
+            'x' => RegexpNode::Char(self.continue_parse_hex_escape(start, 2)?),
+            'u' => RegexpNode::Char(self.continue_parse_hex_escape(start, 4)?),
+            // 元字符以及其他 ASCII 标点符号均可以转义
+            c if c.is_ascii_punctuation() => RegexpNode::Char(c),
+            _ => {
+                return Err(new_regexp_error(
+                    &format!("invalid escape '\\{}'", c),
+                    start,
+                    self.offset(),
+                ));
+            }
+        };
+
+        Ok(node)
+    }
+
+    fn continue_parse_hex_escape(
+        &mut self,
+        start: usize,
+        length: usize,
+    ) -> Result<char, RegexpError> {
+        // \xHH \x{H...} \uHHHH \u{H...}
+        //   ^--- 当前所处的位置
+
+        let is_braced = self.is_char('{');
+        if is_braced {
+            self.next();
+        }
+
+        let mut hex = String::new();
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_hexdigit() || (!is_braced && hex.len() == length) {
+                break;
+            }
+            hex.push(c);
+            self.next();
+        }
+
+        let is_valid_length = if is_braced {
+            !hex.is_empty() && hex.len() <= 6 && self.next() == Some('}')
+        } else {
+            hex.len() == length
+        };
+
+        let value = if is_valid_length {
+            u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+        } else {
+            None
+        };
+
+        value.ok_or_else(|| new_regexp_error("invalid hexadecimal escape", start, self.offset()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_regexp_flags, parse_regexp, CharClass, CharClassItem, Group, PerlClass,
+        PerlClassKind, RegexpError, RegexpNode, Repetition,
+    };
+
+    fn parse_error(pattern: &str) -> (String, usize, usize) {
+        let RegexpError {
+            message,
+            start,
+            end,
+        } = parse_regexp(pattern).unwrap_err();
+        (message, start, end)
+    }
+
+    #[test]
+    fn test_parse_regexp() {
+        let r1 = parse_regexp("a|bc").unwrap();
+        assert_eq!(
+            r1.root,
+            RegexpNode::Alternation(vec![
+                RegexpNode::Char('a'),
+                RegexpNode::Concat(vec![RegexpNode::Char('b'), RegexpNode::Char('c')])
+            ])
+        );
+        assert_eq!(r1.group_count, 0);
+
+        let r2 = parse_regexp("(\\d+)?").unwrap();
+        assert_eq!(
+            r2.root,
+            RegexpNode::Repetition(Repetition {
+                node: Box::new(RegexpNode::Group(Group {
+                    is_capturing: true,
+                    name: None,
+                    body: Box::new(RegexpNode::Repetition(Repetition {
+                        node: Box::new(RegexpNode::PerlClass(PerlClass {
+                            kind: PerlClassKind::Digit,
+                            is_negated: false
+                        })),
+                        min: 1,
+                        max: None,
+                        is_greedy: true
+                    }))
+                })),
+                min: 0,
+                max: Some(1),
+                is_greedy: true
+            })
+        );
+
+        let r3 = parse_regexp("[^a-z_\\W]{2,}?").unwrap();
+        assert_eq!(
+            r3.root,
+            RegexpNode::Repetition(Repetition {
+                node: Box::new(RegexpNode::CharClass(CharClass {
+                    is_negated: true,
+                    items: vec![
+                        CharClassItem::Range('a', 'z'),
+                        CharClassItem::Char('_'),
+                        CharClassItem::PerlClass(PerlClass {
+                            kind: PerlClassKind::Word,
+                            is_negated: true
+                        }),
+                    ]
+                })),
+                min: 2,
+                max: None,
+                is_greedy: false
+            })
+        );
+
+        // 分组数量
+        assert_eq!(parse_regexp("^(.+)@(.+)$").unwrap().group_count, 2);
+        assert_eq!(
+            parse_regexp("(?:a)(?<b>b)(?P<c>(c))").unwrap().group_count,
+            3
+        );
+        assert_eq!(parse_regexp("\\(a\\)[(]").unwrap().group_count, 0);
+
+        // 转义字符
+        assert_eq!(
+            parse_regexp("\\/\\x41\\u{4e2d}").unwrap().root,
+            RegexpNode::Concat(vec![
+                RegexpNode::Char('/'),
+                RegexpNode::Char('A'),
+                RegexpNode::Char('中')
+            ])
+        );
+
+        // 空白的正则表达式以及结尾的 `-`
+        assert_eq!(parse_regexp("").unwrap().root, RegexpNode::Empty);
+        assert!(parse_regexp("[a-]|()").is_ok());
+    }
+
+    #[test]
+    fn test_parse_regexp_error() {
+        // 分组
+        assert_eq!(parse_error("a(b"), ("unclosed group".to_string(), 1, 2));
+        assert_eq!(parse_error("ab)c"), ("unopened group".to_string(), 2, 3));
+        assert_eq!(parse_error("(?=a)").1, 0);
+        assert_eq!(parse_error("(?<1a>b)").0, "invalid group name");
+
+        // 字符类
+        assert_eq!(
+            parse_error("x[abc"),
+            ("unclosed character class".to_string(), 1, 2)
+        );
+        assert_eq!(parse_error("[]").0, "empty character class");
+        assert_eq!(
+            parse_error("[z-a]"),
+            (
+                "invalid character class range, the start is greater than the end".to_string(),
+                1,
+                4
+            )
+        );
+
+        // 转义
+        assert_eq!(
+            parse_error("ab\\q"),
+            ("invalid escape '\\q'".to_string(), 2, 4)
+        );
+        assert_eq!(parse_error("a\\"), ("incomplete escape".to_string(), 1, 2));
+        assert_eq!(
+            parse_error("\\x4"),
+            ("invalid hexadecimal escape".to_string(), 0, 3)
+        );
+        assert_eq!(parse_error("[\\b]").0, "invalid escape in character class");
+
+        // 重复
+        assert_eq!(
+            parse_error("*a"),
+            ("repetition operator missing expression".to_string(), 0, 1)
+        );
+        assert_eq!(parse_error("a|+").1, 2);
+        assert_eq!(parse_error("a**").1, 2);
+        assert_eq!(
+            parse_error("a{3,1}"),
+            (
+                "invalid repetition range, the minimum is greater than the maximum".to_string(),
+                1,
+                6
+            )
+        );
+        assert_eq!(parse_error("a{x}").1, 1);
+        assert_eq!(
+            parse_error("a{1001}").0,
+            "the repetition count exceeds the limit 1000"
+        );
+
+        // 多字节字符的位置
+        assert_eq!(parse_error("中文)"), ("unopened group".to_string(), 6, 7));
+    }
+
+    #[test]
+    fn test_check_regexp_flags() {
+        assert!(check_regexp_flags("").is_ok());
+        assert!(check_regexp_flags("ims").is_ok());
+
+        let e1 = check_regexp_flags("ig").unwrap_err();
+        assert_eq!(
+            (e1.message.as_str(), e1.start, e1.end),
+            ("invalid regular expression flag 'g'", 1, 2)
+        );
+
+        let e2 = check_regexp_flags("mim").unwrap_err();
+        assert_eq!(
+            (e2.message.as_str(), e2.start, e2.end),
+            ("duplicate regular expression flag 'm'", 2, 3)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+use std::fmt;
+
+use crate::token::Location;
+
+// 源文件注册表
+//
+// 负责分配 file_id，保存源文件的内容，
+// 以及把 Location 的字节偏移量转换为行号和列号。
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+#[derive(Debug)]
+pub struct SourceFile {
+    pub file_id: usize,
+    pub path: String,
+    pub content: String,
+    line_starts: Vec<usize>, // 每一行的开始位置（字节偏移量）
+}
+
+// 行号和列号均从 0 开始，
+// 列号分别以字节、字符（Unicode scalar value）以及 UTF-16 代码单元计数。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineColumn {
+    pub line: usize,
+    pub byte_column: usize,
+    pub char_column: usize,
+    pub utf16_column: usize,
+}
+
+// 以 `path:line:col` 的格式显示 Location，
+// 其中行号和列号（以字符计数）均从 1 开始。
+pub struct LocationDisplay<'a> {
+    source_map: &'a SourceMap,
+    location: &'a Location,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap { files: vec![] }
+    }
+
+    // 添加源文件，返回新分配的 file_id
+    pub fn add_file(&mut self, path: &str, content: &str) -> usize {
+        let file_id = self.files.len();
+        self.files.push(SourceFile::new(file_id, path, content));
+        file_id
+    }
+
+    pub fn get_file(&self, file_id: usize) -> Option<&SourceFile> {
+        self.files.get(file_id)
+    }
+
+    pub fn line_column(&self, file_id: usize, offset: usize) -> Option<LineColumn> {
+        self.get_file(file_id)
+            .and_then(|source_file| source_file.line_column(offset))
+    }
+
+    pub fn display_location<'a>(&'a self, location: &'a Location) -> LocationDisplay<'a> {
+        LocationDisplay {
+            source_map: self,
+            location,
+        }
+    }
+}
+
+impl SourceFile {
+    fn new(file_id: usize, path: &str, content: &str) -> Self {
+        SourceFile {
+            file_id,
+            path: path.to_string(),
+            content: content.to_string(),
+            line_starts: get_line_starts(content),
+        }
+    }
+
+    // 把字节偏移量转换为行号和列号
+    //
+    // 当偏移量超出源文件范围，或者不在字符边界上时，返回 None。
+    pub fn line_column(&self, offset: usize) -> Option<LineColumn> {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+
+        let line_start = self.line_starts[line];
+        let text = self.content.get(line_start..offset)?;
+
+        Some(LineColumn {
+            line,
+            byte_column: offset - line_start,
+            char_column: text.chars().count(),
+            utf16_column: text.chars().map(char::len_utf16).sum(),
+        })
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+}
+
+// 获取每一行的开始位置（字节偏移量）
+pub fn get_line_starts(text: &str) -> Vec<usize> {
+    // 换行符 '\r\n' 的下一行从 '\n' 之后开始，所以只需检查 '\n'。
+    // 单独的 '\r' 也视为换行符（跟 lexer 保持一致）。
+    let bytes = text.as_bytes();
+    let mut line_starts = vec![0];
+    for (index, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\n' => line_starts.push(index + 1),
+            b'\r' if bytes.get(index + 1) != Some(&b'\n') => line_starts.push(index + 1),
+            _ => {}
+        }
+    }
+    line_starts
+}
+
+impl fmt::Display for LocationDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source_file = self.source_map.get_file(self.location.file_id);
+        let line_column = source_file.and_then(|s| s.line_column(self.location.start));
+
+        match (source_file, line_column) {
+            (Some(s), Some(lc)) => write!(f, "{}:{}:{}", s.path, lc.line + 1, lc.char_column + 1),
+            _ => write!(f, "{}", self.location),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token::Location;
+
+    use super::{LineColumn, SourceMap};
+
+    #[test]
+    fn test_add_file() {
+        let mut source_map = SourceMap::new();
+        let id1 = source_map.add_file("foo.xuan", "let a = 1");
+        let id2 = source_map.add_file("bar.xuan", "let b = 2");
+
+        assert_eq!(id1, 0);
+        assert_eq!(id2, 1);
+        assert_eq!(source_map.get_file(id2).unwrap().path, "bar.xuan");
+        assert_eq!(source_map.get_file(id2).unwrap().content, "let b = 2");
+        assert!(source_map.get_file(2).is_none());
+    }
+
+    #[test]
+    fn test_line_column() {
+        let mut source_map = SourceMap::new();
+        let id = source_map.add_file("foo.xuan", "ab\r\n文字😀x\rc\n");
+
+        assert_eq!(source_map.get_file(id).unwrap().line_count(), 4);
+
+        assert_eq!(
+            source_map.line_column(id, 1),
+            Some(LineColumn {
+                line: 0,
+                byte_column: 1,
+                char_column: 1,
+                utf16_column: 1
+            })
+        );
+
+        // `x` 位于 "文字😀" 之后
+        assert_eq!(
+            source_map.line_column(id, 14),
+            Some(LineColumn {
+                line: 1,
+                byte_column: 10,
+                char_column: 3,
+                utf16_column: 4
+            })
+        );
+
+        // 单独的 '\r' 也是换行符
+        assert_eq!(
+            source_map.line_column(id, 16),
+            Some(LineColumn {
+                line: 2,
+                byte_column: 0,
+                char_column: 0,
+                utf16_column: 0
+            })
+        );
+
+        // 不在字符边界上
+        assert_eq!(source_map.line_column(id, 5), None);
+
+        // 超出范围
+        assert_eq!(source_map.line_column(id, 100), None);
+    }
+
+    #[test]
+    fn test_display_location() {
+        let mut source_map = SourceMap::new();
+        let id = source_map.add_file("src/foo.xuan", "let a = 1\nlet 文字 = 2");
+
+        let l1 = Location {
+            file_id: id,
+            start: 14,
+            end: 20,
+        };
+        assert_eq!(
+            source_map.display_location(&l1).to_string(),
+            "src/foo.xuan:2:5"
+        );
+
+        // 未注册的文件
+        let l2 = Location {
+            file_id: 9,
+            start: 1,
+            end: 2,
+        };
+        assert_eq!(
+            source_map.display_location(&l2).to_string(),
+            "file id: 9, start: 1, end: 2"
+        );
+    }
+}
@@ -0,0 +1,49 @@
+/**
+ * Copyright (c) 2022 Hemashushu <hippospark@gmail.com>, All rights reserved.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+// 简单的伪随机数生成器（xorshift），以便随机测试的结果可以重现
+pub struct Random(u64);
+
+impl Random {
+    // 参数 seed 不能为 0
+    pub fn new(seed: u64) -> Self {
+        Random(seed)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // 返回 0..n 范围之内的随机数
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    pub fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())].clone()
+    }
+
+    // 生成一个非负的有限浮点数
+    //
+    // 词法分析器不接受超出范围的浮点数，所以这里不生成无穷大以及 NaN：
+    // 指数位全为 1 时表示无穷大或者 NaN，因此指数位的取值范围是 0 到 0x7fe。
+    pub fn finite_float(&mut self) -> f64 {
+        match self.below(4) {
+            0 => self.below(1000) as f64 / 8.0,
+            1 => self.below(1000) as f64 * 1e-5,
+            2 => self.pick(&[f64::MAX, f64::MIN_POSITIVE, 5e-324, 0.0]),
+            _ => {
+                let exponent = self.below(0x7ff) as u64;
+                let mantissa = self.next() & ((1 << 52) - 1);
+                f64::from_bits((exponent << 52) | mantissa)
+            }
+        }
+    }
+}
@@ -6,7 +6,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
 use core::fmt;
-use std::fmt::Write;
+use std::rc::Rc;
 
 // 记录 Token 在源文件中的位置
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -18,22 +18,27 @@ pub struct Location {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
-    NewLine,            // 换行符号，包括 '\r\n', '\n'， '\r' 以及 ';'
-    Identifier(String), // 标识符
+    NewLine,             // 换行符号，包括 '\r\n', '\n'， '\r' 以及 ';'
+    Identifier(Rc<str>), // 标识符，同名的标识符共享同一个字符串
+    Unknown(String),     // 无法识别的源文本，仅在恢复模式（recovering mode）下产生
 
     // 字面量
-    Integer(i64),   // 123, 1_001, 0xab, 0b1001
-    Float(f64),     // 3.14, 1.6e-23。考虑将指数（指数只支持整数）分离出来
-    Imaginary(f64), // 3i, 9.9i。考虑将指数（指数只支持整数）分离出来
-
-    Bit(usize, Vec<u8>),    // 4'b1010, 8'xff, 8'd10
-    Boolean(bool),          // true, false
-    Char(char),             // 'a', '\x41', '\u{6587}'
-    GeneralString(String),  // "foo"
-    TemplateString(String), // `foo`
-    HashString(String),     // #foo
-    Regexp(String),   // ~/foo/
-    Attribute(String),      // #[test]
+    Integer(IntegerLiteral), // 123, 1_001, 0xab, 0b1001, 255u8, 1_000n
+    Float(f64),              // 3.14, 1.6e-23。考虑将指数（指数只支持整数）分离出来
+    Imaginary(f64),          // 3i, 9.9i。考虑将指数（指数只支持整数）分离出来
+
+    Bit(BitLiteral),               // 4'b1010, 8'xff, 8'd10, 6'o17
+    Boolean(bool),                 // true, false
+    Char(char),                    // 'a', '\x41', '\u{6587}'
+    GeneralString(String),         // "foo"
+    TemplateString(String),        // `foo`
+    HashString(String),            // #foo
+    Regexp(String, String, usize), // ~/foo/i，正则表达式、标志以及捕获分组的数量
+    Attribute(String),             // #[test]
+    DocComment(String),            // '''foo'''
+    Disable,             // #disable 或者 #禁用，结构注释，注释掉紧随其后的一个语句或者表达式
+    RegionStart(String), // `// ===`，范围注释的开始，值为范围的标题和描述（多行）
+    RegionEnd,           // `// ---`，范围注释的结束
 
     // 符号
     //
@@ -85,6 +90,7 @@ pub enum Token {
     Ellipsis,          // ...
     Separator,         // ::
     Colon,             // :
+    ColonAssign,       // :=
     Comma,             // ,
 
     // 关键字
@@ -132,10 +138,395 @@ pub enum Token {
     Alias,
 }
 
+// 整数字面量
+//
+// 保留源代码里的原始数字文本（包括进制前缀以及下划线），数值则按需计算，
+// 以便表示任意大小的整数（比如带后缀 `n` 的大整数）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegerLiteral {
+    pub digits: String, // 原始数字文本，比如 `1_000`、`0xff`，不包括类型后缀
+    pub integer_type: Option<IntegerType>, // 由类型后缀指定的数据类型，None 表示默认类型（Int64）
+}
+
+// 整数字面量的数据类型（对应类型后缀）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerType {
+    Int8,       // i8
+    Int16,      // i16
+    Int32,      // i32
+    Int64,      // i64
+    Int128,     // i128
+    Natural8,   // u8
+    Natural16,  // u16
+    Natural32,  // u32
+    Natural64,  // u64
+    Natural128, // u128
+    BigInt,     // n，任意大小的整数
+}
+
+impl IntegerLiteral {
+    pub fn new(digits: &str, integer_type: Option<IntegerType>) -> Self {
+        IntegerLiteral {
+            digits: digits.to_string(),
+            integer_type,
+        }
+    }
+
+    pub fn radix(&self) -> u32 {
+        if self.digits.starts_with("0x") {
+            16
+        } else if self.digits.starts_with("0b") {
+            2
+        } else {
+            10
+        }
+    }
+
+    // 数值（即数字的绝对值），超出 u128 范围时返回 None
+    pub fn to_u128(&self) -> Option<u128> {
+        let radix = self.radix();
+        let digits = if radix == 10 {
+            &self.digits
+        } else {
+            &self.digits[2..] // 跳过进制前缀
+        };
+
+        let value_string = digits.replace('_', "");
+        u128::from_str_radix(&value_string, radix).ok()
+    }
+
+    // 转换为浮点数，超出 u128 范围的十进制大整数会损失精度
+    pub fn to_f64(&self) -> f64 {
+        match self.to_u128() {
+            Some(value) => value as f64,
+            None => self
+                .digits
+                .replace('_', "")
+                .parse()
+                .unwrap_or(f64::INFINITY),
+        }
+    }
+}
+
+impl IntegerType {
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        let integer_type = match suffix {
+            "i8" => IntegerType::Int8,
+            "i16" => IntegerType::Int16,
+            "i32" => IntegerType::Int32,
+            "i64" => IntegerType::Int64,
+            "i128" => IntegerType::Int128,
+            "u8" => IntegerType::Natural8,
+            "u16" => IntegerType::Natural16,
+            "u32" => IntegerType::Natural32,
+            "u64" => IntegerType::Natural64,
+            "u128" => IntegerType::Natural128,
+            "n" => IntegerType::BigInt,
+            _ => return None,
+        };
+        Some(integer_type)
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            IntegerType::Int8 => "i8",
+            IntegerType::Int16 => "i16",
+            IntegerType::Int32 => "i32",
+            IntegerType::Int64 => "i64",
+            IntegerType::Int128 => "i128",
+            IntegerType::Natural8 => "u8",
+            IntegerType::Natural16 => "u16",
+            IntegerType::Natural32 => "u32",
+            IntegerType::Natural64 => "u64",
+            IntegerType::Natural128 => "u128",
+            IntegerType::BigInt => "n",
+        }
+    }
+
+    // 数据类型的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegerType::Int8 => "Int8",
+            IntegerType::Int16 => "Int16",
+            IntegerType::Int32 => "Int32",
+            IntegerType::Int64 => "Int64",
+            IntegerType::Int128 => "Int128",
+            IntegerType::Natural8 => "Natural8",
+            IntegerType::Natural16 => "Natural16",
+            IntegerType::Natural32 => "Natural32",
+            IntegerType::Natural64 => "Natural64",
+            IntegerType::Natural128 => "Natural128",
+            IntegerType::BigInt => "BigInt",
+        }
+    }
+
+    // 位宽，大整数返回 None
+    pub fn bit_width(&self) -> Option<u32> {
+        match self {
+            IntegerType::Int8 | IntegerType::Natural8 => Some(8),
+            IntegerType::Int16 | IntegerType::Natural16 => Some(16),
+            IntegerType::Int32 | IntegerType::Natural32 => Some(32),
+            IntegerType::Int64 | IntegerType::Natural64 => Some(64),
+            IntegerType::Int128 | IntegerType::Natural128 => Some(128),
+            IntegerType::BigInt => None,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        !matches!(
+            self,
+            IntegerType::Natural8
+                | IntegerType::Natural16
+                | IntegerType::Natural32
+                | IntegerType::Natural64
+                | IntegerType::Natural128
+        )
+    }
+
+    // 最大值，大整数返回 None
+    pub fn max_value(&self) -> Option<u128> {
+        let bit_width = self.bit_width()?;
+        let value_bits = if self.is_signed() {
+            bit_width - 1
+        } else {
+            bit_width
+        };
+        Some(u128::MAX >> (128 - value_bits))
+    }
+
+    // 最小值的绝对值，无符号整数为 0，大整数返回 None
+    pub fn min_magnitude(&self) -> Option<u128> {
+        let max_value = self.max_value()?;
+        if self.is_signed() {
+            Some(max_value + 1)
+        } else {
+            Some(0)
+        }
+    }
+}
+
+impl fmt::Display for IntegerLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.integer_type {
+            Some(integer_type) => write!(f, "{}{}", self.digits, integer_type.suffix()),
+            None => write!(f, "{}", self.digits),
+        }
+    }
+}
+
+// 比特字面量
+//
+// 比如 `4'b1010`、`6'o17`、`8'd10` 和 `8'xff`，单引号前面的数字是比特数（位宽），
+// 单引号后面是进制以及数值。数值按大端序存储为 `ceil(width / 8)` 个字节，
+// 超出位宽的高位总是 0，所以相同位宽以及数值的字面量存储的内容总是相同的。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitLiteral {
+    pub width: usize,   // 比特数
+    pub radix: u32,     // 源代码里使用的进制：2、8、10 或者 16
+    pub bytes: Vec<u8>, // 大端序
+}
+
+impl BitLiteral {
+    pub fn new(width: usize, radix: u32, bytes: Vec<u8>) -> Self {
+        BitLiteral {
+            width,
+            radix,
+            bytes,
+        }
+    }
+
+    // 从数字文本（不包括下划线，且每个数字均符合进制）构建比特字面量，
+    // 数值超出位宽时返回 None
+    pub fn from_digits(width: usize, radix: u32, digits: &str) -> Option<Self> {
+        let mut bytes = vec![0u8; width.div_ceil(8)];
+
+        for c in digits.chars() {
+            // bytes = bytes * radix + digit
+            let mut carry = c.to_digit(radix)?;
+            for byte in bytes.iter_mut().rev() {
+                let value = (*byte as u32) * radix + carry;
+                *byte = value as u8;
+                carry = value >> 8;
+            }
+
+            if carry != 0 {
+                return None;
+            }
+        }
+
+        // 检查首个字节里超出位宽的高位
+        let remainder = width % 8;
+        if remainder != 0 && bytes[0] >> remainder != 0 {
+            return None;
+        }
+
+        Some(BitLiteral::new(width, radix, bytes))
+    }
+
+    // 比特数
+    pub fn bit_length(&self) -> usize {
+        self.width
+    }
+
+    // 第 index 位（从最低位 0 开始）的值
+    pub fn bit(&self, index: usize) -> bool {
+        let byte_index = self.bytes.len() - 1 - index / 8;
+        self.bytes[byte_index] >> (index % 8) & 1 == 1
+    }
+
+    // 无符号整数值，数值超出 u128 范围时返回 None
+    pub fn to_u128(&self) -> Option<u128> {
+        let split_pos = self.bytes.len().saturating_sub(16);
+        if self.bytes[..split_pos].iter().any(|b| *b != 0) {
+            return None;
+        }
+
+        Some(
+            self.bytes[split_pos..]
+                .iter()
+                .fold(0u128, |acc, b| acc << 8 | *b as u128),
+        )
+    }
+
+    // 以补码解释的有符号整数值（即最高位为符号位），位宽超过 128 时返回 None
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.width == 0 || self.width > 128 {
+            return None;
+        }
+
+        let value = self.to_u128()? as i128;
+        let shift = 128 - self.width as u32;
+        Some(value << shift >> shift)
+    }
+
+    pub fn radix_char(&self) -> char {
+        match self.radix {
+            2 => 'b',
+            8 => 'o',
+            10 => 'd',
+            _ => 'x',
+        }
+    }
+
+    // 按原始进制输出的规范数字文本
+    //
+    // 二进制、八进制和十六进制按位宽补齐前导 0，比如 `8'o7` 输出为 `8'o007`，
+    // 十进制则不补齐。
+    pub fn digits(&self) -> String {
+        if self.radix == 10 {
+            return self.to_decimal_digits();
+        }
+
+        let bits_per_digit = self.radix.trailing_zeros() as usize;
+        let digit_count = self.width.div_ceil(bits_per_digit).max(1);
+
+        (0..digit_count)
+            .rev()
+            .map(|digit_index| {
+                let start = digit_index * bits_per_digit;
+                let value = (start..(start + bits_per_digit).min(self.width))
+                    .rev()
+                    .fold(0, |acc, index| acc << 1 | self.bit(index) as u32);
+                char::from_digit(value, self.radix).unwrap()
+            })
+            .collect()
+    }
+
+    fn to_decimal_digits(&self) -> String {
+        // 反复除以 10 取余数
+        let mut bytes = self.bytes.clone();
+        let mut digits = vec![];
+
+        loop {
+            let mut remainder = 0u32;
+            for byte in bytes.iter_mut() {
+                let value = remainder << 8 | *byte as u32;
+                *byte = (value / 10) as u8;
+                remainder = value % 10;
+            }
+            digits.push(char::from_digit(remainder, 10).unwrap());
+
+            if bytes.iter().all(|b| *b == 0) {
+                break;
+            }
+        }
+
+        digits.iter().rev().collect()
+    }
+}
+
+impl fmt::Display for BitLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}'{}{}", self.width, self.radix_char(), self.digits())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TokenDetail {
     pub location: Location,
     pub token: Token,
+    pub trivia: Option<Box<TokenTrivia>>, // 仅在无损模式（lossless mode）下才有值
+}
+
+// 无损模式下 token 附带的源文本信息
+//
+// - 跟随在一个非换行符 token 之后（直到下一个 token）的空白和注释，
+//   作为该 token 的 trailing trivia；
+// - 跟随在换行符 token 之后（或者位于源文件开头）的空白和注释，
+//   作为下一个 token 的 leading trivia。
+//
+// 因为换行符本身是 token，所以 trailing trivia 不会跨越多行（块注释除外）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenTrivia {
+    pub leading: Vec<Trivia>,
+    pub lexeme: String, // token 的原始文本
+    pub trailing: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String, // 原始文本，包括注释符号
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,     // 空格和制表符
+    LineComment,    // `// ...`，不包括行尾的换行符
+    BlockComment,   // `/* ... */`
+    LanguageHeader, // `#lang=zh_CN`，源文件首行的语言标记
+}
+
+// 无损模式的词法分析结果
+//
+// 位于最后一个换行符 token 之后的空白和注释（即源文件末尾的 trivia）
+// 没有可以附着的 token，所以单独存放在 end_of_file_trivia。
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessTokens {
+    pub token_details: Vec<TokenDetail>,
+    pub end_of_file_trivia: Vec<Trivia>,
+}
+
+impl LosslessTokens {
+    // 还原源文本
+    pub fn to_source_text(&self) -> String {
+        let mut text = String::new();
+
+        for token_detail in &self.token_details {
+            if let Some(trivia) = &token_detail.trivia {
+                trivia.leading.iter().for_each(|t| text.push_str(&t.text));
+                text.push_str(&trivia.lexeme);
+                trivia.trailing.iter().for_each(|t| text.push_str(&t.text));
+            }
+        }
+
+        self.end_of_file_trivia
+            .iter()
+            .for_each(|t| text.push_str(&t.text));
+
+        text
+    }
 }
 
 impl fmt::Display for Location {
@@ -151,26 +542,31 @@ impl fmt::Display for Location {
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Token::NewLine => write!(f, "\n"),
+            Token::NewLine => writeln!(f),
             Token::Identifier(value) => write!(f, "{}", value),
+            Token::Unknown(value) => write!(f, "{}", value),
 
-            Token::Integer(value) => write!(f, "{}", value),
-            Token::Float(value) => write!(f, "{}", value),
-            Token::Imaginary(value) => write!(f, "{}i", value),
-            Token::Bit(width, bytes) => {
-                let mut hex = String::new();
-                for byte in bytes {
-                    write!(hex, "{:02x}", byte)?;
-                }
-                write!(f, "{}'x{}", width, hex)
-            }
+            Token::Integer(literal) => write!(f, "{}", literal),
+            Token::Float(value) => write!(f, "{}", format_float(*value)),
+            Token::Imaginary(value) => write!(f, "{}i", format_complex_part(*value)),
+            Token::Bit(literal) => write!(f, "{}", literal),
             Token::Boolean(value) => write!(f, "{}", value),
-            Token::Char(value) => write!(f, "'{}'", value),
-            Token::GeneralString(value) => write!(f, "\"{}\"", value),
+            Token::Char(value) => write!(f, "'{}'", escape_char(*value)),
+            Token::GeneralString(value) => write!(f, "\"{}\"", escape_string(value)),
             Token::TemplateString(value) => write!(f, "`{}`", value),
             Token::HashString(value) => write!(f, "#{}", value),
-            Token::Regexp(value) => write!(f, "~/{}/", value),
+            Token::Regexp(value, flags, _) => write!(f, "~/{}/{}", value, flags),
             Token::Attribute(value) => write!(f, "#[{}]", value),
+            Token::DocComment(value) => write!(f, "'''{}'''", value),
+            Token::Disable => write!(f, "#disable"),
+            Token::RegionStart(value) => {
+                // 空的标题不输出标题行，以免跟只有一个空行的标题混淆（两者的值相同）
+                for line in value.split('\n').filter(|_| !value.is_empty()) {
+                    writeln!(f, "// {}", line)?;
+                }
+                write!(f, "// ===")
+            }
+            Token::RegionEnd => write!(f, "// ---"),
 
             Token::LeftBrace => write!(f, "{{"),  // {
             Token::RightBrace => write!(f, "}}"), // }
@@ -215,6 +611,7 @@ impl fmt::Display for Token {
             Token::Ellipsis => write!(f, "..."),          // ...
             Token::Separator => write!(f, "::"),          // ::
             Token::Colon => write!(f, ":"),               // :
+            Token::ColonAssign => write!(f, ":="),        // :=
             Token::Comma => write!(f, ","),               // ,
 
             // 关键字
@@ -264,6 +661,83 @@ impl fmt::Display for Token {
     }
 }
 
+// 把 token 序列输出为源文本，token 之间以空格分隔（换行符前后除外）
+//
+// 输出的源文本经过词法分析之后得到的 token 序列跟原序列相同，前提是每个 token 的值
+// 都可以由词法分析器产生，比如：
+//
+// - 数字字面量的值不能是负数、NaN 以及无穷大（负号是单独的 token）；
+// - 标识符、哈希字符串以及命名操作符的值是 NFC 规范化的合法标识符，且标识符不是关键字；
+// - 模板字符串、正则表达式、属性以及文档注释的值是源文本里的原始文本（未转义），
+//   比如正则表达式里的 `/` 必须写成 `\/`；
+// - 范围注释之后紧随换行符（或者位于序列末尾）。
+pub fn format_tokens(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    let mut is_line_start = true;
+
+    for token in tokens {
+        let is_new_line = *token == Token::NewLine;
+        if !is_line_start && !is_new_line {
+            text.push(' ');
+        }
+        text.push_str(&token.to_string());
+        is_line_start = is_new_line;
+    }
+
+    text
+}
+
+// 输出浮点数，结果总是包含小数点或者指数，以免被当作整数，
+// 比如 `1.0`、`0.016`、`1e300`
+pub fn format_float(value: f64) -> String {
+    format!("{:?}", value)
+}
+
+// 输出复数的实部或者虚部，整数值省略小数部分，比如 `3+4i`；
+// 数值很大或者很小时使用指数形式，以免实部被当作超出范围的整数，比如 `1e300+1i`
+pub fn format_complex_part(value: f64) -> String {
+    let text = format_float(value);
+    match text.strip_suffix(".0") {
+        Some(integer_text) => integer_text.to_string(),
+        None => text,
+    }
+}
+
+// 转义字符串字面量的内容，换行符以及制表符保持原样（字符串允许多行）
+pub fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\n' | '\t' => escaped.push(c),
+            _ => push_escaped_char(&mut escaped, c),
+        }
+    }
+    escaped
+}
+
+// 转义字符字面量的内容
+pub fn escape_char(value: char) -> String {
+    let mut escaped = String::new();
+    match value {
+        '\'' => escaped.push_str("\\'"),
+        '\n' => escaped.push_str("\\n"),
+        '\t' => escaped.push_str("\\t"),
+        _ => push_escaped_char(&mut escaped, value),
+    }
+    escaped
+}
+
+fn push_escaped_char(escaped: &mut String, c: char) {
+    match c {
+        '\\' => escaped.push_str("\\\\"),
+        '\r' => escaped.push_str("\\r"),
+        '\0' => escaped.push_str("\\0"),
+        _ if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        _ => escaped.push(c),
+    }
+}
+
 impl fmt::Display for TokenDetail {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[{}] {}", &self.location, &self.token)
@@ -272,7 +746,109 @@ impl fmt::Display for TokenDetail {
 
 #[cfg(test)]
 mod tests {
-    use super::{Location, Token, TokenDetail};
+    use crate::{lexer::tokenize, testing::Random};
+
+    use super::{
+        format_tokens, BitLiteral, IntegerLiteral, IntegerType, Location, Token, TokenDetail,
+    };
+
+    #[test]
+    fn test_bit_literal() {
+        let b1 = BitLiteral::from_digits(4, 2, "1010").unwrap();
+        assert_eq!(b1.bytes, vec![0b1010]);
+        assert_eq!(b1.bit_length(), 4);
+        assert_eq!(b1.to_u128(), Some(10));
+        assert_eq!(b1.to_i128(), Some(-6));
+        assert_eq!(b1.to_string(), "4'b1010");
+
+        // 补齐前导 0
+        let b2 = BitLiteral::from_digits(8, 8, "7").unwrap();
+        assert_eq!(b2.to_string(), "8'o007");
+        assert_eq!(
+            BitLiteral::from_digits(12, 16, "ab").unwrap().to_string(),
+            "12'x0ab"
+        );
+        assert_eq!(
+            BitLiteral::from_digits(8, 10, "010").unwrap().to_string(),
+            "8'd10"
+        );
+        assert_eq!(
+            BitLiteral::from_digits(8, 10, "0").unwrap().to_string(),
+            "8'd0"
+        );
+
+        // 相同的数值使用不同的进制，存储的内容相同
+        assert_eq!(
+            BitLiteral::from_digits(8, 16, "ff").unwrap().bytes,
+            BitLiteral::from_digits(8, 10, "255").unwrap().bytes
+        );
+
+        // 超出位宽
+        assert_eq!(BitLiteral::from_digits(4, 16, "ff"), None);
+        assert_eq!(BitLiteral::from_digits(8, 10, "256"), None);
+        assert_eq!(BitLiteral::from_digits(3, 8, "10"), None);
+
+        // 超过 64 位以及 128 位的位宽
+        let b3 =
+            BitLiteral::from_digits(72, 16, "80_0000_0000_0000_0001".replace('_', "").as_str())
+                .unwrap();
+        assert_eq!(b3.bytes.len(), 9);
+        assert_eq!(b3.to_u128(), Some(1 << 71 | 1));
+        assert_eq!(b3.to_i128(), Some(-(1 << 71) + 1));
+        assert_eq!(b3.to_string(), "72'x800000000000000001");
+
+        let b4 =
+            BitLiteral::from_digits(130, 10, "340282366920938463463374607431768211456").unwrap();
+        assert_eq!(b4.to_u128(), None);
+        assert_eq!(b4.to_i128(), None);
+        assert_eq!(
+            b4.to_string(),
+            "130'd340282366920938463463374607431768211456"
+        );
+        assert_eq!(
+            BitLiteral::new(b4.width, 16, b4.bytes.clone()).to_string(),
+            "130'x100000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_integer_literal() {
+        let i1 = IntegerLiteral::new("1_000", None);
+        assert_eq!(i1.radix(), 10);
+        assert_eq!(i1.to_u128(), Some(1000));
+        assert_eq!(i1.to_string(), "1_000");
+
+        let i2 = IntegerLiteral::new("0xff", Some(IntegerType::Natural8));
+        assert_eq!(i2.radix(), 16);
+        assert_eq!(i2.to_u128(), Some(255));
+        assert_eq!(i2.to_string(), "0xffu8");
+
+        let i3 = IntegerLiteral::new("0b1010", None);
+        assert_eq!(i3.to_u128(), Some(10));
+
+        // 超出 u128 范围
+        let i4 = IntegerLiteral::new(
+            "1_000_000_000_000_000_000_000_000_000_000_000_000_000",
+            None,
+        );
+        assert_eq!(i4.to_u128(), None);
+        assert_eq!(i4.to_f64(), 1e39);
+    }
+
+    #[test]
+    fn test_integer_type() {
+        assert_eq!(
+            IntegerType::from_suffix("u64"),
+            Some(IntegerType::Natural64)
+        );
+        assert_eq!(IntegerType::from_suffix("i7"), None);
+        assert_eq!(IntegerType::Int8.max_value(), Some(127));
+        assert_eq!(IntegerType::Int8.min_magnitude(), Some(128));
+        assert_eq!(IntegerType::Natural64.max_value(), Some(u64::MAX as u128));
+        assert_eq!(IntegerType::Int128.max_value(), Some(i128::MAX as u128));
+        assert_eq!(IntegerType::Natural128.max_value(), Some(u128::MAX));
+        assert_eq!(IntegerType::BigInt.max_value(), None);
+    }
 
     #[test]
     fn test_location_display() {
@@ -310,12 +886,18 @@ mod tests {
     #[test]
     fn test_token_display() {
         assert_eq!(Token::NewLine.to_string(), "\n");
-        assert_eq!(Token::Identifier("foo".to_string()).to_string(), "foo");
+        assert_eq!(Token::Identifier("foo".into()).to_string(), "foo");
 
-        assert_eq!(Token::Integer(123).to_string(), "123");
+        assert_eq!(
+            Token::Integer(IntegerLiteral::new("123", None)).to_string(),
+            "123"
+        );
         assert_eq!(Token::Float(6.626).to_string(), "6.626");
         assert_eq!(Token::Imaginary(0.618).to_string(), "0.618i");
-        assert_eq!(Token::Bit(8, vec![0xab, 0x4]).to_string(), "8'xab04");
+        assert_eq!(
+            Token::Bit(BitLiteral::new(16, 16, vec![0xab, 0x4])).to_string(),
+            "16'xab04"
+        );
         assert_eq!(Token::Boolean(true).to_string(), "true");
         assert_eq!(Token::Char('a').to_string(), "'a'");
         assert_eq!(
@@ -327,7 +909,14 @@ mod tests {
             "`foo`"
         );
         assert_eq!(Token::HashString("foo".to_string()).to_string(), "#foo");
-        assert_eq!(Token::Regexp("foo".to_string()).to_string(), "~/foo/");
+        assert_eq!(
+            Token::Regexp("foo".to_string(), "".to_string(), 0).to_string(),
+            "~/foo/"
+        );
+        assert_eq!(
+            Token::Regexp("foo".to_string(), "im".to_string(), 0).to_string(),
+            "~/foo/im"
+        );
         assert_eq!(Token::Attribute("test".to_string()).to_string(), "#[test]");
 
         assert_eq!(Token::NamedOperator("foo".to_string()).to_string(), ":foo:");
@@ -419,9 +1008,9 @@ mod tests {
 
     #[test]
     fn test_token_eq() {
-        let tt1 = Token::Identifier("foo".to_string());
+        let tt1 = Token::Identifier("foo".into());
         let tt2 = Token::GeneralString("foo".to_string());
-        let tt3 = Token::Identifier("foo".to_string());
+        let tt3 = Token::Identifier("foo".into());
 
         assert_ne!(tt1, tt2);
         assert_eq!(tt1, tt3);
@@ -439,6 +1028,7 @@ mod tests {
                 end: 3,
             },
             token: Token::Plus, // Add
+            trivia: None,
         };
 
         let tk2 = TokenDetail {
@@ -448,9 +1038,216 @@ mod tests {
                 end: 3,
             },
             token: Token::Minus, // Subtract
+            trivia: None,
         };
 
         assert_eq!(tk1.to_string(), "[file id: 1, start: 2, end: 3] +");
         assert_ne!(tk1, tk2);
     }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            Token::GeneralString("a\"b".to_string()).to_string(),
+            r#""a\"b""#
+        );
+        assert_eq!(
+            Token::GeneralString("\\\r\n\t\0\u{1b}文".to_string()).to_string(),
+            "\"\\\\\\r\n\t\\0\\u{1b}文\""
+        );
+        assert_eq!(Token::Char('\'').to_string(), r"'\''");
+        assert_eq!(Token::Char('"').to_string(), "'\"'");
+        assert_eq!(Token::Char('\n').to_string(), r"'\n'");
+        assert_eq!(Token::Float(1.0).to_string(), "1.0");
+        assert_eq!(Token::Float(1e300).to_string(), "1e300");
+        assert_eq!(Token::RegionStart("".to_string()).to_string(), "// ===");
+        assert_eq!(
+            Token::RegionStart("\nb".to_string()).to_string(),
+            "// \n// b\n// ==="
+        );
+
+        assert_eq!(
+            format_tokens(&[
+                Token::Let,
+                Token::Identifier("a".into()),
+                Token::Assign,
+                Token::Minus,
+                Token::GreaterThan,
+                Token::NewLine,
+                Token::Identifier("a".into()),
+            ]),
+            "let a = - >\na"
+        );
+    }
+
+    fn random_char(random: &mut Random) -> char {
+        const CHARS: [char; 16] = [
+            'a', 'Z', '0', ' ', '中', '🐱', '"', '\'', '\\', '`', '{', '\n', '\t', '\r', '\0',
+            '\u{7f}',
+        ];
+        match random.below(4) {
+            0 => loop {
+                if let Some(c) = char::from_u32(random.below(0x11_0000) as u32) {
+                    return c;
+                }
+            },
+            _ => random.pick(&CHARS),
+        }
+    }
+
+    fn random_token(random: &mut Random) -> Token {
+        let symbols = [
+            Token::LeftBrace,
+            Token::RightBrace,
+            Token::Assign,
+            Token::Pipe,
+            Token::LogicOr,
+            Token::LogicAnd,
+            Token::Equal,
+            Token::NotEqual,
+            Token::GreaterThan,
+            Token::GreaterThanOrEqual,
+            Token::LessThan,
+            Token::LessThanOrEqual,
+            Token::Concat,
+            Token::Plus,
+            Token::Minus,
+            Token::Asterisk,
+            Token::Slash,
+            Token::OptionalOr,
+            Token::OptionalAnd,
+            Token::Combine,
+            Token::Cast,
+            Token::Unwrap,
+            Token::Dot,
+            Token::LeftBracket,
+            Token::RightBracket,
+            Token::Exclamation,
+            Token::LeftParen,
+            Token::RightParen,
+            Token::At,
+            Token::Interval,
+            Token::IntervalInclusive,
+            Token::Ellipsis,
+            Token::Separator,
+            Token::Colon,
+            Token::ColonAssign,
+            Token::Comma,
+            Token::Do,
+            Token::Let,
+            Token::Fn,
+            Token::If,
+            Token::Match,
+            Token::Function,
+            Token::Struct,
+            Token::Disable,
+            Token::NewLine,
+        ];
+        let names = ["a", "foo_bar", "x1", "中文", "caf\u{e9}", "名称Id"];
+
+        match random.below(16) {
+            0 => Token::Identifier(random.pick(&names).into()),
+            1 => {
+                let (digits, integer_type) = random.pick(&[
+                    ("0", None),
+                    ("123", None),
+                    ("1_000", None),
+                    ("0xff", Some(IntegerType::Natural8)),
+                    ("0b1010", None),
+                    ("127", Some(IntegerType::Int8)),
+                    ("99999999999999999999999", Some(IntegerType::BigInt)),
+                ]);
+                Token::Integer(IntegerLiteral::new(digits, integer_type))
+            }
+            2 => Token::Float(random.finite_float()),
+            3 => Token::Imaginary(random.finite_float()),
+            4 => {
+                let width = 1 + random.below(130);
+                let mut bytes: Vec<u8> = (0..width.div_ceil(8))
+                    .map(|_| random.next() as u8)
+                    .collect();
+                if !width.is_multiple_of(8) {
+                    bytes[0] &= (1u8 << (width % 8)) - 1;
+                }
+                Token::Bit(BitLiteral::new(width, random.pick(&[2, 8, 10, 16]), bytes))
+            }
+            5 => Token::Boolean(random.below(2) == 0),
+            6 => Token::Char(random_char(random)),
+            7 => {
+                let length = random.below(6);
+                Token::GeneralString((0..length).map(|_| random_char(random)).collect())
+            }
+            8 => Token::TemplateString(
+                random
+                    .pick(&["", "a {b} c", r"\`\{", "{ {x: `y{z}`} }"])
+                    .to_string(),
+            ),
+            9 => Token::HashString(random.pick(&names).to_string()),
+            10 => Token::NamedOperator(random.pick(&names).to_string()),
+            11 => {
+                let (body, flags, group_count) =
+                    random.pick(&[("a", "", 0), (r"^\d+\/x$", "im", 0), ("(a)|b", "s", 1)]);
+                Token::Regexp(body.to_string(), flags.to_string(), group_count)
+            }
+            12 => Token::Attribute(random.pick(&["test", "cfg(a, b)"]).to_string()),
+            13 => Token::DocComment(
+                random
+                    .pick(&["", " doc ", "\nline 1\nline 2\n"])
+                    .to_string(),
+            ),
+            14 => Token::RegionStart(
+                random
+                    .pick(&["", "title", "title\ndescription", "\n"])
+                    .to_string(),
+            ),
+            _ => random.pick(&symbols),
+        }
+    }
+
+    #[test]
+    fn test_format_tokens_round_trip() {
+        // 随机的 token 序列输出为源文本之后，重新分析得到的 token 序列应该跟原序列相同
+        let mut random = Random::new(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..3000 {
+            let mut tokens: Vec<Token> = vec![];
+            let length = random.below(12);
+
+            while tokens.len() < length {
+                let token = if random.below(16) == 0 {
+                    Token::RegionEnd
+                } else {
+                    random_token(&mut random)
+                };
+
+                let is_region = matches!(token, Token::RegionStart(_) | Token::RegionEnd);
+
+                // 两个范围注释的开始之间只有换行符时，它们会被合并为一个范围注释
+                if matches!(token, Token::RegionStart(_))
+                    && tokens.len() >= 2
+                    && tokens[tokens.len() - 1] == Token::NewLine
+                    && matches!(tokens[tokens.len() - 2], Token::RegionStart(_))
+                {
+                    continue;
+                }
+
+                tokens.push(token);
+
+                // 范围注释之后总是换行符
+                if is_region {
+                    tokens.push(Token::NewLine);
+                }
+            }
+
+            let text = format_tokens(&tokens);
+            let result = tokenize(&text).map(|details| {
+                details
+                    .into_iter()
+                    .map(|detail| detail.token)
+                    .collect::<Vec<Token>>()
+            });
+
+            assert_eq!(result, Ok(tokens), "text: {:?}", text);
+        }
+    }
 }
@@ -5,8 +5,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
-use std::{env, fs, process};
-use front_end::lexer;
+use std::env;
 
 fn main() -> std::io::Result<()> {
     let path = env::current_dir()?;